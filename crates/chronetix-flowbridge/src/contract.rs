@@ -9,6 +9,16 @@ pub struct CompileOutput {
     pub schemas: Vec<SchemaDescriptor>,
 }
 
+impl CompileOutput {
+    /// 规范化 JSON：字段按结构体声明顺序、对象键按字典序，带缩进与结尾换行，
+    /// 同一输入始终得到逐字节一致的输出（便于审阅与 diff）
+    pub fn to_canonical_json(&self) -> anyhow::Result<String> {
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        Ok(json)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct PluginManifest {
     #[serde(rename = "plugin_id")]
@@ -56,7 +56,8 @@ impl From<&t::ChannelKind> for String {
     fn from(k: &t::ChannelKind) -> Self {
         match k {
             t::ChannelKind::EventBus => "control".to_string(),
-            _ => "data".to_string(),
+            t::ChannelKind::Stream => "data".to_string(),
+            t::ChannelKind::BlobRef => "blob".to_string(),
         }
     }
 }
@@ -4,19 +4,20 @@ pub mod contract;
 pub mod convert;
 #[cfg(feature = "inproc")]
 pub mod inproc;
+pub mod qos;
 pub mod types;
 
 use anyhow::Result;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 /// Minimal DSL for Chronetix example (docs/Chronetix/examples/minimal_dag.yaml)
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ExampleDag {
     pub nodes: Vec<ExampleNode>,
     pub routes: Vec<ExampleRoute>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ExampleNode {
     pub id: String,
     pub origin: Option<String>, // internal | external
@@ -27,44 +28,44 @@ pub struct ExampleNode {
     pub bindings: Option<ExampleBindings>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ExamplePlugin {
     pub kind: String,             // wasm
     pub artifact: Option<String>, // uri
     pub r#type: Option<String>,   // for system plugin like timer-source
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ExampleIo {
     pub inputs: Option<Vec<IoType>>,
     pub outputs: Option<Vec<IoType>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct IoType {
     pub content_type: String,
     pub schema_ver: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ExampleBindings {
     pub timer: Option<Vec<ExampleTimerBinding>>, // timers list
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ExampleTimerBinding {
     pub id: String,
     pub schedule: ExampleTimerSchedule,
     pub miss_policy: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ExampleTimerSchedule {
     pub interval_ms: Option<u64>,
     pub align_to: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ExampleRoute {
     pub from: String,
     pub to: String,
@@ -78,14 +79,56 @@ pub struct ExampleRoute {
 }
 
 impl ExampleDag {
+    /// role 推断
+    ///
+    /// - Resource → EnvProvider；System 且 plugin.type=timer-source → Timer
+    /// - Business 按 IO 与入向数据面路由数判断（空列表视为未声明）：
+    ///   - 有输入无输出 → Sink
+    ///   - 有输入输出且无数据面入边（由控制面驱动）→ SourceTransform
+    ///   - 有输入输出且恰有 1 条数据面入边 → Transform
+    ///   - 有输入输出且有多条数据面入边（fan-in）→ Aggregator
+    fn infer_role(&self, n: &ExampleNode) -> Option<String> {
+        let non_empty =
+            |v: &Option<Vec<IoType>>| v.as_ref().is_some_and(|v| !v.is_empty());
+        let (has_inputs, has_outputs) =
+            n.io.as_ref()
+                .map(|io| (non_empty(&io.inputs), non_empty(&io.outputs)))
+                .unwrap_or((false, false));
+        let data_in = self
+            .routes
+            .iter()
+            .filter(|r| r.to == n.id && r.plane == "data")
+            .count();
+
+        let role = match n.category.as_deref() {
+            Some("Resource") => "EnvProvider",
+            Some("System")
+                if n.plugin.r#type.as_deref() == Some("timer-source") =>
+            {
+                "Timer"
+            }
+            Some("Business") => match (has_inputs, has_outputs, data_in) {
+                (true, false, _) => "Sink",
+                (true, true, 0) => "SourceTransform",
+                (true, true, 1) => "Transform",
+                (true, true, _) => "Aggregator",
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(role.to_string())
+    }
+
     /// Convert ExampleDag to contract::CompileOutput (for Chronetix adapter output)
-    pub fn to_contract(&self) -> contract::CompileOutput {
+    ///
+    /// 节点 qos 按 [`qos::Qos`] 解析、校验并补全默认值；非法 qos 返回编译错误
+    pub fn to_contract(&self) -> Result<contract::CompileOutput> {
         use contract as c;
 
-        let manifests: Vec<c::PluginManifest> = self
+        let manifests = self
             .nodes
             .iter()
-            .map(|n| {
+            .map(|n| -> Result<c::PluginManifest> {
                 // plugin_id 规范化
                 let plugin_id = if n.category.as_deref() == Some("System")
                     && n.plugin.r#type.as_deref() == Some("timer-source")
@@ -106,35 +149,7 @@ impl ExampleDag {
                     n.id.clone()
                 };
 
-                // role 推断
-                let role = match n.category.as_deref() {
-                    Some("Resource") => Some("EnvProvider".to_string()),
-                    Some("System")
-                        if n.plugin.r#type.as_deref()
-                            == Some("timer-source") =>
-                    {
-                        Some("Timer".to_string())
-                    }
-                    Some("Business")
-                        if n.io
-                            .as_ref()
-                            .map(|io| {
-                                io.inputs.is_some() && io.outputs.is_some()
-                            })
-                            .unwrap_or(false) =>
-                    {
-                        Some("SourceTransform".to_string())
-                    }
-                    Some("Business")
-                        if n.io
-                            .as_ref()
-                            .map(|io| io.inputs.is_some())
-                            .unwrap_or(false) =>
-                    {
-                        Some("Sink".to_string())
-                    }
-                    _ => None,
-                };
+                let role = self.infer_role(n);
 
                 // artifact 归一
                 let artifact = if n.category.as_deref() == Some("System")
@@ -190,7 +205,13 @@ impl ExampleDag {
                         },
                     );
 
-                c::PluginManifest {
+                let qos = qos::Qos::from_value(
+                    &n.id,
+                    role.as_deref(),
+                    n.qos.as_ref(),
+                )?;
+
+                Ok(c::PluginManifest {
                     plugin_id,
                     version: Some("0.1.0".to_string()),
                     category: n.category.clone(),
@@ -198,13 +219,13 @@ impl ExampleDag {
                     origin: n.origin.clone(),
                     artifact,
                     io,
-                    qos: n.qos.clone(),
+                    qos: Some(qos.to_value()),
                     timers,
                     features: None,
                     annotations: None,
-                }
+                })
             })
-            .collect();
+            .collect::<Result<Vec<_>>>()?;
 
         let routes = self
             .routes
@@ -253,11 +274,92 @@ impl ExampleDag {
             schema_ref: "registry://schemas/resource/netinfo@v1".to_string(),
         });
 
-        c::CompileOutput {
+        Ok(c::CompileOutput {
             manifests,
             routes,
             schemas,
-        }
+        })
+    }
+}
+
+impl contract::CompileOutput {
+    /// 反编译为可编辑的 ExampleDag（decompile → edit → recompile）
+    ///
+    /// 节点 id 取 plugin_id；io 的 schema_ref、features、annotations
+    /// 等无法在 DSL 中表达的字段会丢失，重新编译时按默认规则补全
+    pub fn to_example_dag(&self) -> ExampleDag {
+        let io_types = |v: &Option<Vec<contract::IoType>>| {
+            v.as_ref().map(|v| {
+                v.iter()
+                    .map(|t| IoType {
+                        content_type: t.content_type.clone(),
+                        schema_ver: t.schema_ver.clone(),
+                    })
+                    .collect()
+            })
+        };
+
+        let nodes = self
+            .manifests
+            .iter()
+            .map(|m| {
+                let kind = match m.artifact.as_ref().map(|a| a.kind.as_str()) {
+                    Some("Dylib") => "dylib",
+                    Some("Process") => "process",
+                    _ => "wasm",
+                };
+                let r#type = (m.role.as_deref() == Some("Timer"))
+                    .then(|| "timer-source".to_string());
+
+                ExampleNode {
+                    id: m.plugin_id.clone(),
+                    origin: m.origin.clone(),
+                    category: m.category.clone(),
+                    plugin: ExamplePlugin {
+                        kind: kind.to_string(),
+                        artifact: m.artifact.as_ref().map(|a| a.uri.clone()),
+                        r#type,
+                    },
+                    io: m.io.as_ref().map(|io| ExampleIo {
+                        inputs: io_types(&io.inputs),
+                        outputs: io_types(&io.outputs),
+                    }),
+                    qos: m.qos.clone(),
+                    bindings: m.timers.as_ref().map(|tt| ExampleBindings {
+                        timer: Some(
+                            tt.iter()
+                                .map(|t| ExampleTimerBinding {
+                                    id: t.id.clone(),
+                                    schedule: ExampleTimerSchedule {
+                                        interval_ms: t.schedule.interval_ms,
+                                        align_to: t.schedule.align_to.clone(),
+                                    },
+                                    miss_policy: t.miss_policy.clone(),
+                                })
+                                .collect(),
+                        ),
+                    }),
+                }
+            })
+            .collect();
+
+        let routes = self
+            .routes
+            .iter()
+            .map(|r| ExampleRoute {
+                from: r.from.clone(),
+                to: r.to.clone(),
+                plane: r.plane.clone(),
+                topic: r.topic.clone(),
+                port: r.port.clone(),
+                content_type: Some(r.content_type.clone()),
+                schema_ver: Some(r.schema_ver.clone()),
+                buffer: r.buffer,
+                watermark: r.watermark,
+            })
+            .collect();
+
+        ExampleDag { nodes, routes }
     }
 }
 
@@ -266,6 +368,32 @@ mod tests {
     use super::*;
     use std::fs;
 
+    #[test]
+    fn simple_graph_blob_ref_maps_to_blob_plane() {
+        let graph: SimpleGraph = serde_yaml::from_str(
+            r#"
+nodes:
+  - { id: producer, kind: source, impl_kind: wasm, entry: "file://p.wasm" }
+  - { id: consumer, kind: sink, impl_kind: process, entry: "bin/consumer" }
+edges:
+  - { from: producer, to: consumer, channel: event-bus, label: ready }
+  - { from: producer, to: consumer, channel: stream, label: frames }
+  - { from: producer, to: consumer, channel: blob-ref, label: snapshot }
+"#,
+        )
+        .expect("parse graph");
+
+        let out = graph.to_contract().expect("compile");
+        let planes: Vec<_> =
+            out.routes.iter().map(|r| r.plane.as_str()).collect();
+        assert_eq!(planes, vec!["control", "data", "blob"]);
+
+        let blob = &out.routes[2];
+        assert_eq!(blob.topic, "snapshot");
+        assert_eq!(blob.port.as_deref(), Some("blobport/producer-consumer"));
+        assert_eq!(blob.content_type, "application/octet-stream");
+    }
+
     #[test]
     fn example_dag_to_contract_matches_json() {
         let yaml_path = "../../docs/Chronetix/examples/minimal_dag.yaml";
@@ -275,7 +403,7 @@ mod tests {
         let yaml_str = fs::read_to_string(yaml_path).expect("read yaml");
         let dag: ExampleDag =
             serde_yaml::from_str(&yaml_str).expect("parse yaml");
-        let got = dag.to_contract();
+        let got = dag.to_contract().expect("compile");
 
         let json_str = fs::read_to_string(json_path).expect("read json");
         let expected: contract::CompileOutput =
@@ -317,8 +445,203 @@ mod tests {
                 "origin mismatch for {}",
                 m.plugin_id
             );
+            assert_eq!(found.role, m.role, "role mismatch for {}", m.plugin_id);
+
+            // qos 的 deadline/priority 应与示例一致（显式值或默认补全）
+            let field = |q: &Option<serde_json::Value>, k: &str| {
+                q.as_ref().and_then(|q| q.get(k)).cloned()
+            };
+            for key in ["deadline_ns", "priority"] {
+                assert_eq!(
+                    field(&found.qos, key),
+                    field(&m.qos, key),
+                    "qos.{key} mismatch for {}",
+                    m.plugin_id
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn compile_output_round_trips_through_example_dag() {
+        let json_path =
+            "../../docs/Chronetix/examples/minimal_compile_output.json";
+        let json_str = fs::read_to_string(json_path).expect("read json");
+        let golden: contract::CompileOutput =
+            serde_json::from_str(&json_str).expect("parse json");
+
+        // 反编译结果应可序列化为 YAML 并重新解析
+        let yaml = serde_yaml::to_string(&golden.to_example_dag())
+            .expect("serialize dag");
+        let dag: ExampleDag = serde_yaml::from_str(&yaml).expect("parse dag");
+        let got = dag.to_contract().expect("recompile");
+
+        assert_eq!(got.routes, golden.routes, "routes mismatch");
+        assert_eq!(got.schemas, golden.schemas, "schemas mismatch");
+        assert_eq!(got.manifests.len(), golden.manifests.len());
+        for (g, e) in got.manifests.iter().zip(&golden.manifests) {
+            assert_eq!(g.plugin_id, e.plugin_id);
+            assert_eq!(g.category, e.category, "{}", e.plugin_id);
+            assert_eq!(g.origin, e.origin, "{}", e.plugin_id);
+            assert_eq!(g.role, e.role, "{}", e.plugin_id);
+            assert_eq!(g.artifact, e.artifact, "{}", e.plugin_id);
+            assert_eq!(g.qos, e.qos, "{}", e.plugin_id);
+            assert_eq!(g.timers, e.timers, "{}", e.plugin_id);
+        }
+    }
+
+    struct DagAdapter;
+
+    impl FlowAdapter for DagAdapter {
+        type GraphDef = ExampleDag;
+
+        fn compile(graph: &ExampleDag) -> Result<contract::CompileOutput> {
+            graph.to_contract()
         }
     }
+
+    #[test]
+    fn flow_adapter_compile_to_json_and_file() {
+        let yaml_str = fs::read_to_string(
+            "../../docs/Chronetix/examples/minimal_dag.yaml",
+        )
+        .expect("read yaml");
+        let dag: ExampleDag =
+            serde_yaml::from_str(&yaml_str).expect("parse yaml");
+
+        let json = DagAdapter::compile_to_json(&dag).expect("compile");
+        // 输出稳定：两次编译结果逐字节一致
+        assert_eq!(json, DagAdapter::compile_to_json(&dag).unwrap());
+        let parsed: contract::CompileOutput =
+            serde_json::from_str(&json).expect("parse json");
+        assert_eq!(parsed, dag.to_contract().unwrap());
+
+        let path = std::env::temp_dir()
+            .join(format!("flowbridge-compile-{}.json", std::process::id()));
+        DagAdapter::compile_to_file(&dag, &path).expect("write file");
+        assert_eq!(fs::read_to_string(&path).unwrap(), json);
+        fs::remove_file(&path).ok();
+    }
+
+    fn roles_of(yaml: &str) -> Vec<(String, Option<String>)> {
+        let dag: ExampleDag = serde_yaml::from_str(yaml).expect("parse yaml");
+        let out = dag.to_contract().expect("compile");
+        out.manifests
+            .into_iter()
+            .map(|m| (m.plugin_id, m.role))
+            .collect()
+    }
+
+    fn role<'a>(
+        roles: &'a [(String, Option<String>)],
+        id: &str,
+    ) -> Option<&'a str> {
+        roles
+            .iter()
+            .find(|(pid, _)| pid == id)
+            .and_then(|(_, r)| r.as_deref())
+    }
+
+    const ROLE_DAG: &str = r#"
+nodes:
+  - id: env
+    category: Resource
+    plugin: { kind: wasm, artifact: "builtin://chronetix/resource/netinfo" }
+  - id: tick
+    category: System
+    plugin: { kind: wasm, type: timer-source }
+  - id: fetch-a
+    category: Business
+    plugin: { kind: wasm }
+    io:
+      inputs: [{ content_type: application/json, schema_ver: v1 }]
+      outputs: [{ content_type: application/json, schema_ver: v1 }]
+  - id: fetch-b
+    category: Business
+    plugin: { kind: wasm }
+    io:
+      inputs: [{ content_type: application/json, schema_ver: v1 }]
+      outputs: [{ content_type: application/json, schema_ver: v1 }]
+  - id: map
+    category: Business
+    plugin: { kind: wasm }
+    io:
+      inputs: [{ content_type: application/json, schema_ver: v1 }]
+      outputs: [{ content_type: application/json, schema_ver: v1 }]
+  - id: merge
+    category: Business
+    plugin: { kind: wasm }
+    io:
+      inputs: [{ content_type: application/json, schema_ver: v1 }]
+      outputs: [{ content_type: application/json, schema_ver: v1 }]
+  - id: sink
+    category: Business
+    plugin: { kind: wasm }
+    io:
+      inputs: [{ content_type: application/json, schema_ver: v1 }]
+      outputs: []
+  - id: producer
+    category: Business
+    plugin: { kind: wasm }
+    io:
+      outputs: [{ content_type: application/json, schema_ver: v1 }]
+routes:
+  - { from: tick, to: fetch-a, plane: control, topic: control/tick/a }
+  - { from: tick, to: fetch-b, plane: control, topic: control/tick/b }
+  - { from: fetch-a, to: map, plane: data, topic: flow/edge/a-map }
+  - { from: map, to: merge, plane: data, topic: flow/edge/map-merge }
+  - { from: fetch-b, to: merge, plane: data, topic: flow/edge/b-merge }
+  - { from: merge, to: sink, plane: data, topic: flow/edge/merge-sink }
+"#;
+
+    #[test]
+    fn role_inference_system_and_resource() {
+        let roles = roles_of(ROLE_DAG);
+        assert_eq!(role(&roles, "resource-netinfo"), Some("EnvProvider"));
+        assert_eq!(role(&roles, "timer-source"), Some("Timer"));
+    }
+
+    #[test]
+    fn role_inference_control_driven_is_source_transform() {
+        let roles = roles_of(ROLE_DAG);
+        assert_eq!(role(&roles, "fetch-a"), Some("SourceTransform"));
+        assert_eq!(role(&roles, "fetch-b"), Some("SourceTransform"));
+    }
+
+    #[test]
+    fn role_inference_transform_and_aggregator() {
+        let roles = roles_of(ROLE_DAG);
+        assert_eq!(role(&roles, "map"), Some("Transform"));
+        assert_eq!(role(&roles, "merge"), Some("Aggregator"));
+    }
+
+    #[test]
+    fn role_inference_sink_ignores_empty_outputs() {
+        let roles = roles_of(ROLE_DAG);
+        // outputs: [] 视为无输出，不应被误判为 SourceTransform
+        assert_eq!(role(&roles, "sink"), Some("Sink"));
+        // 仅有输出的业务节点无法推断
+        assert_eq!(role(&roles, "producer"), None);
+    }
+
+    #[test]
+    fn example_dag_rejects_invalid_qos() {
+        let dag: ExampleDag = serde_yaml::from_str(
+            r#"
+nodes:
+  - id: http-client
+    category: Business
+    plugin: { kind: wasm, artifact: "file://plugins/http.wasm" }
+    qos: { deadline_ns: 5000000, priority: 42 }
+routes: []
+"#,
+        )
+        .expect("parse yaml");
+
+        let err = dag.to_contract().unwrap_err().to_string();
+        assert!(err.contains("http-client"), "{err}");
+        assert!(err.contains("qos.priority"), "{err}");
+    }
 }
 
 /// FlowAdapter: 从 Flowbuilder 的图/DSL 编译出 Chronetix 可执行描述
@@ -326,6 +649,23 @@ pub trait FlowAdapter {
     type GraphDef;
 
     fn compile(graph: &Self::GraphDef) -> Result<contract::CompileOutput>;
+
+    /// 编译并输出规范化 JSON（见 [`contract::CompileOutput::to_canonical_json`]）
+    fn compile_to_json(graph: &Self::GraphDef) -> Result<String> {
+        Self::compile(graph)?.to_canonical_json()
+    }
+
+    /// 编译并将规范化 JSON 写入文件
+    fn compile_to_file<P: AsRef<std::path::Path>>(
+        graph: &Self::GraphDef,
+        path: P,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        let json = Self::compile_to_json(graph)?;
+        std::fs::write(path, json).map_err(|e| {
+            anyhow::anyhow!("failed to write compile output to {path:?}: {e}")
+        })
+    }
 }
 
 /// NodeRunner: 在 Chronetix 执行器中的节点运行抽象
@@ -365,3 +705,108 @@ pub struct SimpleEdge {
     pub channel: String, // event-bus/stream/blob-ref
     pub label: String,   // topic 或 stream label
 }
+
+impl SimpleGraph {
+    /// Convert SimpleGraph to contract::CompileOutput
+    ///
+    /// 通道映射：event-bus → control 平面，stream → data 平面，
+    /// blob-ref → blob 平面（仅传递内容寻址引用）
+    pub fn to_contract(&self) -> Result<contract::CompileOutput> {
+        use contract as c;
+        use types::{ChannelKind, PluginKind};
+
+        if self.nodes.is_empty() {
+            return Err(anyhow::anyhow!("no nodes in graph"));
+        }
+
+        // 1) Manifests
+        let manifests = self
+            .nodes
+            .iter()
+            .map(|n| {
+                let kind = match n.impl_kind.as_str() {
+                    "wasm" => PluginKind::Wasm,
+                    "dylib" => PluginKind::Dylib,
+                    "process" => PluginKind::Process,
+                    other => {
+                        tracing::warn!(
+                            "unknown impl_kind: {other}, fallback to process"
+                        );
+                        PluginKind::Process
+                    }
+                };
+                c::PluginManifest {
+                    plugin_id: n.id.clone(),
+                    version: Some("0.1.0".to_string()),
+                    category: Some("Business".to_string()),
+                    role: Some("Transform".to_string()),
+                    origin: Some("external".to_string()),
+                    artifact: Some(c::Artifact {
+                        kind: String::from(&kind),
+                        uri: n.entry.clone(),
+                    }),
+                    io: None,
+                    qos: n.qos.clone().map(|q| serde_json::json!({ "qos": q })),
+                    timers: None,
+                    features: None,
+                    annotations: None,
+                }
+            })
+            .collect();
+
+        // 2) Routes
+        let routes = self
+            .edges
+            .iter()
+            .map(|e| {
+                let channel = match e.channel.as_str() {
+                    "event-bus" => ChannelKind::EventBus,
+                    "stream" => ChannelKind::Stream,
+                    "blob-ref" => ChannelKind::BlobRef,
+                    other => {
+                        tracing::warn!(
+                            "unknown channel: {other}, fallback to stream"
+                        );
+                        ChannelKind::Stream
+                    }
+                };
+                let (port, content_type) = match channel {
+                    ChannelKind::EventBus => (None, "application/cbor"),
+                    ChannelKind::Stream => (
+                        Some(format!("dataport/{}-{}", e.from, e.to)),
+                        "application/cbor",
+                    ),
+                    // blob 平面只传引用，大对象本身走内容寻址存储
+                    ChannelKind::BlobRef => (
+                        Some(format!("blobport/{}-{}", e.from, e.to)),
+                        "application/octet-stream",
+                    ),
+                };
+                c::Route {
+                    from: e.from.clone(),
+                    to: e.to.clone(),
+                    plane: String::from(&channel),
+                    topic: e.label.clone(),
+                    port,
+                    content_type: content_type.to_string(),
+                    schema_ver: "v1".to_string(),
+                    buffer: None,
+                    watermark: None,
+                }
+            })
+            .collect();
+
+        // 3) Schemas（最简：占位示例）
+        let schemas = vec![c::SchemaDescriptor {
+            content_type: "application/cbor".into(),
+            schema_ver: "v1".into(),
+            schema_ref: "registry://schemas/default@v1".into(),
+        }];
+
+        Ok(c::CompileOutput {
+            manifests,
+            routes,
+            schemas,
+        })
+    }
+}
@@ -0,0 +1,251 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Typed QoS for plugin manifests: parsing, validation and defaults.
+
+use serde::{Deserialize, Serialize};
+
+/// 优先级上限（0 最低，9 最高）
+pub const MAX_PRIORITY: u8 = 9;
+
+/// 合法的 QoS 等级
+pub const QOS_CLASSES: [&str; 4] =
+    ["best-effort", "normal", "high", "realtime"];
+
+/// QoS 解析/校验错误（均带节点 id，便于定位）
+#[derive(Debug, thiserror::Error)]
+pub enum QosError {
+    #[error("node `{node}`: qos must be an object, got {found}")]
+    NotAnObject { node: String, found: String },
+    #[error("node `{node}`: invalid qos: {source}")]
+    Malformed {
+        node: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("node `{node}`: qos.{field} {reason}")]
+    Invalid {
+        node: String,
+        field: &'static str,
+        reason: String,
+    },
+}
+
+/// 节点 QoS（序列化后写入 manifest.qos）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Qos {
+    pub deadline_ns: u64,
+    pub priority: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_inflight: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credit_high: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credit_low: Option<u32>,
+}
+
+/// DSL 中的原始 QoS（字段均可省略；deadline 可用 ns 或 ms 表示）
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawQos {
+    deadline_ns: Option<u64>,
+    deadline_ms: Option<u64>,
+    priority: Option<i64>,
+    class: Option<String>,
+    retry: Option<u32>,
+    max_inflight: Option<u32>,
+    credit_high: Option<u32>,
+    credit_low: Option<u32>,
+}
+
+impl Qos {
+    /// 按角色给出默认 QoS（系统定时器最紧，资源提供者次之）
+    pub fn default_for_role(role: Option<&str>) -> Self {
+        let (deadline_ns, priority) = match role {
+            Some("Timer") => (1_000_000, 6),
+            Some("EnvProvider") => (2_000_000, 5),
+            _ => (5_000_000, 5),
+        };
+        Self {
+            deadline_ns,
+            priority,
+            class: None,
+            retry: None,
+            max_inflight: None,
+            credit_high: None,
+            credit_low: None,
+        }
+    }
+
+    /// 解析节点的 qos 字段，缺省项按角色默认值补全，并做校验
+    pub fn from_value(
+        node: &str,
+        role: Option<&str>,
+        value: Option<&serde_json::Value>,
+    ) -> Result<Self, QosError> {
+        let raw = match value {
+            None | Some(serde_json::Value::Null) => RawQos::default(),
+            Some(v @ serde_json::Value::Object(_)) => {
+                serde_json::from_value(v.clone()).map_err(|source| {
+                    QosError::Malformed {
+                        node: node.to_string(),
+                        source,
+                    }
+                })?
+            }
+            Some(other) => {
+                return Err(QosError::NotAnObject {
+                    node: node.to_string(),
+                    found: other.to_string(),
+                })
+            }
+        };
+
+        let invalid = |field, reason: String| QosError::Invalid {
+            node: node.to_string(),
+            field,
+            reason,
+        };
+
+        let defaults = Self::default_for_role(role);
+        let deadline_ns = match (raw.deadline_ns, raw.deadline_ms) {
+            (Some(_), Some(_)) => {
+                return Err(invalid(
+                    "deadline_ns",
+                    "conflicts with deadline_ms; specify only one".into(),
+                ))
+            }
+            (Some(ns), None) => ns,
+            (None, Some(ms)) => ms.checked_mul(1_000_000).ok_or_else(|| {
+                invalid("deadline_ms", format!("{ms} is too large"))
+            })?,
+            (None, None) => defaults.deadline_ns,
+        };
+        let priority = match raw.priority {
+            Some(p) if (0..=MAX_PRIORITY as i64).contains(&p) => p as u8,
+            Some(p) => {
+                return Err(invalid(
+                    "priority",
+                    format!("must be within 0..={MAX_PRIORITY}, got {p}"),
+                ))
+            }
+            None => defaults.priority,
+        };
+
+        let qos = Self {
+            deadline_ns,
+            priority,
+            class: raw.class,
+            retry: raw.retry,
+            max_inflight: raw.max_inflight,
+            credit_high: raw.credit_high,
+            credit_low: raw.credit_low,
+        };
+        qos.validate(node)?;
+        Ok(qos)
+    }
+
+    /// 校验取值范围
+    pub fn validate(&self, node: &str) -> Result<(), QosError> {
+        let invalid = |field, reason: String| QosError::Invalid {
+            node: node.to_string(),
+            field,
+            reason,
+        };
+
+        if self.deadline_ns == 0 {
+            return Err(invalid("deadline_ns", "must be positive".into()));
+        }
+        if self.priority > MAX_PRIORITY {
+            return Err(invalid(
+                "priority",
+                format!(
+                    "must be within 0..={MAX_PRIORITY}, got {}",
+                    self.priority
+                ),
+            ));
+        }
+        if let Some(class) = &self.class {
+            if !QOS_CLASSES.contains(&class.as_str()) {
+                return Err(invalid(
+                    "class",
+                    format!("must be one of {QOS_CLASSES:?}, got `{class}`"),
+                ));
+            }
+        }
+        if self.max_inflight == Some(0) {
+            return Err(invalid("max_inflight", "must be positive".into()));
+        }
+        if self.credit_high == Some(0) {
+            return Err(invalid("credit_high", "must be positive".into()));
+        }
+        if let (Some(low), Some(high)) = (self.credit_low, self.credit_high) {
+            if low > high {
+                return Err(invalid(
+                    "credit_low",
+                    format!("({low}) must not exceed credit_high ({high})"),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// 序列化为 manifest 中的 JSON
+    pub fn to_value(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("qos is always serializable")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn qos_defaults_by_role() {
+        let q = Qos::from_value("t", Some("Timer"), None).unwrap();
+        assert_eq!((q.deadline_ns, q.priority), (1_000_000, 6));
+        let q = Qos::from_value("s", Some("Sink"), None).unwrap();
+        assert_eq!((q.deadline_ns, q.priority), (5_000_000, 5));
+    }
+
+    #[test]
+    fn qos_parses_and_normalizes() {
+        let q = Qos::from_value(
+            "n",
+            None,
+            Some(
+                &json!({ "deadline_ms": 3, "priority": 7, "max_inflight": 32 }),
+            ),
+        )
+        .unwrap();
+        assert_eq!(q.deadline_ns, 3_000_000);
+        assert_eq!(q.priority, 7);
+        assert_eq!(
+            q.to_value(),
+            json!({ "deadline_ns": 3_000_000, "priority": 7, "max_inflight": 32 })
+        );
+    }
+
+    #[test]
+    fn qos_rejects_invalid_values() {
+        let err = |v: serde_json::Value| {
+            Qos::from_value("n", None, Some(&v))
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(err(json!({ "priority": 12 })).contains("qos.priority"));
+        assert!(err(json!({ "priority": -1 })).contains("qos.priority"));
+        assert!(err(json!({ "deadline_ns": 0 })).contains("qos.deadline_ns"));
+        assert!(err(json!({ "deadline_ns": 1, "deadline_ms": 1 }))
+            .contains("conflicts"));
+        assert!(err(json!({ "class": "turbo" })).contains("qos.class"));
+        assert!(err(json!({ "credit_high": 8, "credit_low": 16 }))
+            .contains("qos.credit_low"));
+        assert!(err(json!({ "dedline_ns": 5 })).contains("node `n`"));
+        assert!(err(json!("fast")).contains("must be an object"));
+    }
+}
@@ -2,47 +2,228 @@
 //!
 //! Context management and shared state for FlowBuilder
 
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{broadcast, Mutex, Notify};
 use uuid::Uuid;
 
-#[derive(Debug, Clone)]
+/// 变量存储使用的映射类型（标准库 `HashMap`，启用 `im` 特性时为持久化映射）
+#[cfg(not(feature = "im"))]
+pub type VarMap<V> = std::collections::HashMap<String, V>;
+
+/// 变量存储使用的映射类型
+///
+/// `im` 持久化映射：克隆只复制根节点，快照与上下文共享未修改的部分，
+/// 创建快照与回滚均为 O(1)
+#[cfg(feature = "im")]
+pub type VarMap<V> = im::HashMap<String, V>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlowContext {
     pub trace_id: String,
     pub ok: bool,
     pub errors: Vec<String>,
     pub step_logs: Vec<StepLog>,
-    pub variables: std::collections::HashMap<String, String>,
+    pub variables: VarMap<String>,
+    /// 结构化变量（与 `variables` 并存，保存可往返的 JSON 值）
+    pub typed_variables: VarMap<serde_json::Value>,
     pub snapshots: std::collections::HashMap<String, ContextSnapshot>,
+    /// 保存点栈（嵌套事务，后创建的在栈顶）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub savepoints: Vec<ContextSnapshot>,
+    /// 作用域子流程中标记为导出的变量，结束时合并回父上下文
+    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
+    pub exports: HashSet<String>,
+    /// 外部信号（不参与序列化，克隆间共享）
+    #[serde(skip)]
+    pub signals: SignalRegistry,
+    /// 对外事件总线（不参与序列化，克隆间共享）
+    #[serde(skip)]
+    pub events: EventBus,
+}
+
+/// 流程对外发布的事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowEvent {
+    pub trace_id: String,
+    pub name: String,
+    pub payload: serde_json::Value,
+    /// 事件发布时间（墙钟时间，可序列化）
+    pub timestamp: SystemTime,
 }
 
+/// 基于 broadcast 的事件总线，嵌入方通过 `subscribe` 接收事件
 #[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<FlowEvent>,
+}
+
+impl EventBus {
+    /// 默认缓冲的事件数，订阅方落后更多时会丢失最早的事件
+    pub const DEFAULT_CAPACITY: usize = 64;
+
+    /// 订阅之后发布的事件
+    pub fn subscribe(&self) -> broadcast::Receiver<FlowEvent> {
+        self.sender.subscribe()
+    }
+
+    /// 发布事件，返回接收到事件的订阅方数量（无订阅方时为 0）
+    pub fn publish(&self, event: FlowEvent) -> usize {
+        self.sender.send(event).unwrap_or(0)
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(Self::DEFAULT_CAPACITY).0,
+        }
+    }
+}
+
+/// 按 key 管理的外部信号，用于审批等人工介入场景
+///
+/// 先于等待方到达的信号会被保留，等待方随后立即返回。
+#[derive(Debug, Clone, Default)]
+pub struct SignalRegistry {
+    notifiers:
+        Arc<std::sync::Mutex<std::collections::HashMap<String, Arc<Notify>>>>,
+}
+
+impl SignalRegistry {
+    /// 获取 `key` 对应的通知器（不存在时创建）
+    pub fn notifier(&self, key: &str) -> Arc<Notify> {
+        let mut notifiers =
+            self.notifiers.lock().unwrap_or_else(|e| e.into_inner());
+        notifiers.entry(key.to_string()).or_default().clone()
+    }
+
+    /// 发送信号，唤醒一个等待方
+    pub fn signal(&self, key: &str) {
+        self.notifier(key).notify_one();
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContextSnapshot {
     pub snapshot_id: String,
-    pub timestamp: std::time::Instant,
-    pub variables: std::collections::HashMap<String, String>,
+    /// 快照创建时间（墙钟时间，可序列化）
+    pub timestamp: SystemTime,
+    pub variables: VarMap<String>,
+    pub typed_variables: VarMap<serde_json::Value>,
     pub ok: bool,
     pub errors: Vec<String>,
     pub description: String,
 }
 
-#[derive(Debug, Clone)]
+impl ContextSnapshot {
+    /// 快照与 `context` 当前变量的差异
+    ///
+    /// 字符串变量与结构化变量合并比较，同名时以结构化变量为准
+    pub fn diff(&self, context: &FlowContext) -> ContextDiff {
+        let before = variable_view(&self.variables, &self.typed_variables);
+        let after = variable_view(&context.variables, &context.typed_variables);
+        let mut diff = ContextDiff::default();
+        for (key, old) in &before {
+            match after.get(key) {
+                None => {
+                    diff.removed.insert(key.to_string(), old.clone());
+                }
+                Some(new) if new != old => {
+                    diff.changed
+                        .insert(key.to_string(), (old.clone(), new.clone()));
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, new) in after {
+            if !before.contains_key(key) {
+                diff.added.insert(key.to_string(), new);
+            }
+        }
+        diff
+    }
+}
+
+fn variable_view<'a>(
+    variables: &'a VarMap<String>,
+    typed_variables: &'a VarMap<serde_json::Value>,
+) -> BTreeMap<&'a str, serde_json::Value> {
+    let mut view: BTreeMap<_, _> = variables
+        .iter()
+        .map(|(k, v)| (k.as_str(), serde_json::Value::String(v.clone())))
+        .collect();
+    view.extend(typed_variables.iter().map(|(k, v)| (k.as_str(), v.clone())));
+    view
+}
+
+/// 两个时间点之间的变量差异
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContextDiff {
+    /// 新增的变量（键 → 新值）
+    pub added: BTreeMap<String, serde_json::Value>,
+    /// 删除的变量（键 → 原值）
+    pub removed: BTreeMap<String, serde_json::Value>,
+    /// 修改的变量（键 → (原值, 新值)）
+    pub changed: BTreeMap<String, (serde_json::Value, serde_json::Value)>,
+}
+
+impl ContextDiff {
+    /// 是否没有任何变化
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StepLog {
     pub step_name: String,
-    pub start_time: std::time::Instant,
-    pub end_time: Option<std::time::Instant>,
+    /// 步骤开始时间（墙钟时间，可序列化）
+    pub start_time: SystemTime,
+    pub end_time: Option<SystemTime>,
     pub status: StepStatus,
     pub error_message: Option<String>,
     pub trace_id: String,
+    /// 单调计时起点，仅用于计算耗时，不参与序列化
+    #[serde(skip)]
+    started_at: Option<Instant>,
 }
 
-#[derive(Debug, Clone)]
+impl StepLog {
+    /// 步骤耗时（尚未结束时为 `None`）
+    pub fn duration(&self) -> Option<Duration> {
+        let end_time = self.end_time?;
+        Some(end_time.duration_since(self.start_time).unwrap_or_default())
+    }
+
+    /// 结束步骤并返回耗时
+    fn finish(&mut self, status: StepStatus) -> Duration {
+        let end_time = SystemTime::now();
+        self.end_time = Some(end_time);
+        self.status = status;
+        // 反序列化后的日志没有单调计时起点，退回墙钟时间差
+        match self.started_at {
+            Some(started_at) => started_at.elapsed(),
+            None => {
+                end_time.duration_since(self.start_time).unwrap_or_default()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum StepStatus {
     Running,
     Success,
     Failed,
     Skipped,
     Timeout,
+    /// 执行中被外部取消
+    Cancelled,
 }
 
 impl Default for FlowContext {
@@ -52,8 +233,13 @@ impl Default for FlowContext {
             ok: true,
             errors: Vec::new(),
             step_logs: Vec::new(),
-            variables: std::collections::HashMap::new(),
+            variables: VarMap::new(),
+            typed_variables: VarMap::new(),
             snapshots: std::collections::HashMap::new(),
+            savepoints: Vec::new(),
+            exports: HashSet::new(),
+            signals: SignalRegistry::default(),
+            events: EventBus::default(),
         }
     }
 }
@@ -65,8 +251,13 @@ impl FlowContext {
             ok: true,
             errors: Vec::new(),
             step_logs: Vec::new(),
-            variables: std::collections::HashMap::new(),
+            variables: VarMap::new(),
+            typed_variables: VarMap::new(),
             snapshots: std::collections::HashMap::new(),
+            savepoints: Vec::new(),
+            exports: HashSet::new(),
+            signals: SignalRegistry::default(),
+            events: EventBus::default(),
         }
     }
 
@@ -82,14 +273,7 @@ impl FlowContext {
             ));
         }
 
-        let snapshot = ContextSnapshot {
-            snapshot_id: snapshot_id.clone(),
-            timestamp: std::time::Instant::now(),
-            variables: self.variables.clone(),
-            ok: self.ok,
-            errors: self.errors.clone(),
-            description,
-        };
+        let snapshot = self.capture(snapshot_id.clone(), description);
 
         self.snapshots.insert(snapshot_id.clone(), snapshot);
 
@@ -109,26 +293,92 @@ impl FlowContext {
             .ok_or_else(|| format!("Snapshot '{snapshot_id}' not found"))?
             .clone();
 
-        // 保留 trace_id 和快照信息，回滚其他状态
+        self.restore(snapshot, "Rolled back to snapshot");
+
+        Ok(())
+    }
+
+    /// 创建保存点，压入保存点栈
+    ///
+    /// 与平铺的快照不同，保存点按创建顺序嵌套：回滚到某个保存点时，
+    /// 在它之后创建的保存点一并丢弃，之前的保存点保留
+    pub fn savepoint(&mut self, id: impl Into<String>) -> Result<(), String> {
+        let id = id.into();
+        if self.savepoints.iter().any(|s| s.snapshot_id == id) {
+            return Err(format!("Savepoint with id '{id}' already exists"));
+        }
+        let savepoint = self.capture(id.clone(), String::new());
+        self.savepoints.push(savepoint);
+
+        tracing::info!(trace_id = %self.trace_id, savepoint = %id, depth = self.savepoints.len(), "Created savepoint");
+
+        Ok(())
+    }
+
+    /// 回滚到保存点，丢弃其后创建的保存点（该保存点本身保留，可再次回滚）
+    pub fn rollback_to_savepoint(&mut self, id: &str) -> Result<(), String> {
+        let position = self.savepoint_position(id)?;
+        self.savepoints.truncate(position + 1);
+        let savepoint = self.savepoints[position].clone();
+        self.restore(savepoint, "Rolled back to savepoint");
+        Ok(())
+    }
+
+    /// 释放保存点及其后创建的保存点，保留当前状态
+    pub fn release_savepoint(&mut self, id: &str) -> Result<(), String> {
+        let position = self.savepoint_position(id)?;
+        self.savepoints.truncate(position);
+
+        tracing::info!(trace_id = %self.trace_id, savepoint = %id, "Released savepoint");
+
+        Ok(())
+    }
+
+    fn savepoint_position(&self, id: &str) -> Result<usize, String> {
+        self.savepoints
+            .iter()
+            .rposition(|s| s.snapshot_id == id)
+            .ok_or_else(|| format!("Savepoint '{id}' not found"))
+    }
+
+    /// 记录当前可回滚的状态（不登记到快照表）
+    pub fn capture(
+        &self,
+        snapshot_id: String,
+        description: String,
+    ) -> ContextSnapshot {
+        ContextSnapshot {
+            snapshot_id,
+            timestamp: SystemTime::now(),
+            variables: self.variables.clone(),
+            typed_variables: self.typed_variables.clone(),
+            ok: self.ok,
+            errors: self.errors.clone(),
+            description,
+        }
+    }
+
+    /// 恢复快照中的状态，保留 trace_id、步骤日志与快照信息
+    fn restore(&mut self, snapshot: ContextSnapshot, message: &str) {
         let old_variables_count = self.variables.len();
         let old_errors_count = self.errors.len();
 
         self.variables = snapshot.variables;
+        self.typed_variables = snapshot.typed_variables;
         self.ok = snapshot.ok;
         self.errors = snapshot.errors;
 
         tracing::info!(
             trace_id = %self.trace_id,
-            snapshot = %snapshot_id,
+            snapshot = %snapshot.snapshot_id,
             description = %snapshot.description,
             old_variables = old_variables_count,
             new_variables = self.variables.len(),
             old_errors = old_errors_count,
             new_errors = self.errors.len(),
-            "Rolled back to snapshot"
+            "{}",
+            message
         );
-
-        Ok(())
     }
 
     /// 删除快照
@@ -150,11 +400,12 @@ impl FlowContext {
     pub fn start_step(&mut self, step_name: String) {
         let step_log = StepLog {
             step_name: step_name.clone(),
-            start_time: std::time::Instant::now(),
+            start_time: SystemTime::now(),
             end_time: None,
             status: StepStatus::Running,
             error_message: None,
             trace_id: self.trace_id.clone(),
+            started_at: Some(Instant::now()),
         };
         self.step_logs.push(step_log);
 
@@ -168,9 +419,7 @@ impl FlowContext {
             .rev()
             .find(|log| log.step_name == step_name)
         {
-            log.end_time = Some(std::time::Instant::now());
-            log.status = StepStatus::Success;
-            let duration = log.end_time.unwrap().duration_since(log.start_time);
+            let duration = log.finish(StepStatus::Success);
 
             tracing::info!(trace_id = %self.trace_id, step = %step_name, duration_ms = ?duration, "step success");
         }
@@ -183,10 +432,8 @@ impl FlowContext {
             .rev()
             .find(|log| log.step_name == step_name)
         {
-            log.end_time = Some(std::time::Instant::now());
-            log.status = StepStatus::Failed;
+            let duration = log.finish(StepStatus::Failed);
             log.error_message = Some(error.to_string());
-            let duration = log.end_time.unwrap().duration_since(log.start_time);
 
             tracing::error!(trace_id = %self.trace_id, step = %step_name, duration_ms = ?duration, error = %error, "step failed");
         }
@@ -201,14 +448,26 @@ impl FlowContext {
             .rev()
             .find(|log| log.step_name == step_name)
         {
-            log.end_time = Some(std::time::Instant::now());
-            log.status = StepStatus::Skipped;
-            let duration = log.end_time.unwrap().duration_since(log.start_time);
+            let duration = log.finish(StepStatus::Skipped);
 
             tracing::warn!(trace_id = %self.trace_id, step = %step_name, duration_ms = ?duration, reason = %reason, "step skipped");
         }
     }
 
+    /// 将步骤标记为已取消
+    pub fn end_step_cancelled(&mut self, step_name: &str) {
+        if let Some(log) = self
+            .step_logs
+            .iter_mut()
+            .rev()
+            .find(|log| log.step_name == step_name)
+        {
+            let duration = log.finish(StepStatus::Cancelled);
+
+            tracing::warn!(trace_id = %self.trace_id, step = %step_name, duration_ms = ?duration, "step cancelled");
+        }
+    }
+
     pub fn end_step_timeout(&mut self, step_name: &str) {
         if let Some(log) = self
             .step_logs
@@ -216,9 +475,7 @@ impl FlowContext {
             .rev()
             .find(|log| log.step_name == step_name)
         {
-            log.end_time = Some(std::time::Instant::now());
-            log.status = StepStatus::Timeout;
-            let duration = log.end_time.unwrap().duration_since(log.start_time);
+            let duration = log.finish(StepStatus::Timeout);
 
             tracing::error!(trace_id = %self.trace_id, step = %step_name, duration_ms = ?duration, "step timeout");
         }
@@ -236,6 +493,75 @@ impl FlowContext {
         self.variables.get(key)
     }
 
+    /// 写入结构化变量
+    pub fn set_typed(&mut self, key: String, value: serde_json::Value) {
+        tracing::debug!(trace_id = %self.trace_id, key = %key, value = %value, "set typed variable");
+
+        self.typed_variables.insert(key, value);
+    }
+
+    /// 读取结构化变量
+    pub fn get_typed(&self, key: &str) -> Option<&serde_json::Value> {
+        self.typed_variables.get(key)
+    }
+
+    /// 将变量标记为导出：作用域子流程结束时只有导出的变量写回父上下文
+    pub fn export(&mut self, key: impl Into<String>) {
+        self.exports.insert(key.into());
+    }
+
+    /// 创建作用域子上下文
+    ///
+    /// 变量为当前变量的副本，子上下文中的写入不影响本上下文；trace_id、
+    /// 信号与事件总线保持共享，日志、错误与快照从空开始
+    pub fn scoped(&self) -> Self {
+        Self {
+            variables: self.variables.clone(),
+            typed_variables: self.typed_variables.clone(),
+            signals: self.signals.clone(),
+            events: self.events.clone(),
+            ..Self::new_with_trace_id(self.trace_id.clone())
+        }
+    }
+
+    /// 发送外部信号，唤醒 `step_await_signal` 中等待 `key` 的步骤
+    pub fn signal(&self, key: &str) {
+        tracing::debug!(trace_id = %self.trace_id, key = %key, "signal");
+        self.signals.signal(key);
+    }
+
+    /// 向事件总线发布事件（携带 trace_id 与时间戳）
+    pub fn emit_event(
+        &self,
+        name: impl Into<String>,
+        payload: serde_json::Value,
+    ) -> usize {
+        let name = name.into();
+        tracing::debug!(trace_id = %self.trace_id, event = %name, "emit event");
+        self.events.publish(FlowEvent {
+            trace_id: self.trace_id.clone(),
+            name,
+            payload,
+            timestamp: SystemTime::now(),
+        })
+    }
+
+    /// 订阅流程事件
+    pub fn subscribe_events(&self) -> broadcast::Receiver<FlowEvent> {
+        self.events.subscribe()
+    }
+
+    /// 序列化为 JSON（包含快照，用于崩溃恢复）
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self)
+            .expect("FlowContext contains only serializable fields")
+    }
+
+    /// 从 JSON 恢复上下文
+    pub fn from_json(s: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(s)
+    }
+
     pub fn print_summary(&self) {
         let summary =
             format!("\n=== Flow Summary [trace_id: {}] ===", self.trace_id);
@@ -263,12 +589,18 @@ impl FlowContext {
             .iter()
             .filter(|log| matches!(log.status, StepStatus::Timeout))
             .count();
+        let cancelled_count = self
+            .step_logs
+            .iter()
+            .filter(|log| matches!(log.status, StepStatus::Cancelled))
+            .count();
 
         tracing::info!(
             success = success_count,
             failed = failed_count,
             skipped = skipped_count,
-            timeout = timeout_count
+            timeout = timeout_count,
+            cancelled = cancelled_count
         );
 
         if !self.errors.is_empty() {
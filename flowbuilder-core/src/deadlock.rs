@@ -0,0 +1,120 @@
+//! # FlowBuilder Core - 死锁检测
+//!
+//! 调试用的启发式检测：等待步骤在窗口期内未观察到上下文有任何进展时，
+//! 判定为疑似死锁并报告所有正在等待的条件
+
+use flowbuilder_context::{FlowContext, VarMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// 死锁检测器，由一个流程及其并行子流程共享
+#[derive(Debug, Default)]
+pub(crate) struct DeadlockDetector {
+    /// 无进展的容忍窗口，`None` 表示未启用
+    window: Mutex<Option<Duration>>,
+    /// 所属父流程的检测器（并行子流程）
+    parent: Mutex<Option<Arc<DeadlockDetector>>>,
+    /// 正在等待的条件描述（仅根检测器使用）
+    waiting: Mutex<Vec<(u64, String)>>,
+    next_id: AtomicU64,
+}
+
+impl DeadlockDetector {
+    pub(crate) fn set_window(&self, window: Duration) {
+        *self.window.lock().unwrap_or_else(|e| e.into_inner()) = Some(window);
+    }
+
+    /// 将子流程的检测器挂到当前检测器下，共享等待列表与窗口
+    pub(crate) fn adopt(self: &Arc<Self>, child: &Arc<Self>) {
+        *child.parent.lock().unwrap_or_else(|e| e.into_inner()) =
+            Some(self.clone());
+    }
+
+    fn parent(&self) -> Option<Arc<Self>> {
+        self.parent
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn root(self: &Arc<Self>) -> Arc<Self> {
+        let mut current = self.clone();
+        while let Some(parent) = current.parent() {
+            current = parent;
+        }
+        current
+    }
+
+    /// 生效的窗口：自身未设置时沿父检测器查找
+    pub(crate) fn window(&self) -> Option<Duration> {
+        let own = *self.window.lock().unwrap_or_else(|e| e.into_inner());
+        own.or_else(|| self.parent()?.window())
+    }
+
+    /// 登记一个等待中的条件，返回的守卫在丢弃时注销
+    pub(crate) fn enter_wait(self: &Arc<Self>, description: &str) -> WaitGuard {
+        let root = self.root();
+        let id = root.next_id.fetch_add(1, Ordering::Relaxed);
+        root.waiting
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((id, description.to_string()));
+        WaitGuard { root, id }
+    }
+}
+
+/// 等待登记守卫
+pub(crate) struct WaitGuard {
+    root: Arc<DeadlockDetector>,
+    id: u64,
+}
+
+impl WaitGuard {
+    /// 当前所有正在等待的条件描述
+    pub(crate) fn waiting(&self) -> Vec<String> {
+        self.root
+            .waiting
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(_, description)| description.clone())
+            .collect()
+    }
+}
+
+impl Drop for WaitGuard {
+    fn drop(&mut self) {
+        self.root
+            .waiting
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|(id, _)| *id != self.id);
+    }
+}
+
+/// 上下文的进展标记：变量、错误与步骤日志任一变化都视为有进展
+#[derive(Debug, PartialEq)]
+pub(crate) struct Progress {
+    variables: VarMap<String>,
+    typed_variables: VarMap<serde_json::Value>,
+    errors: usize,
+    steps_started: usize,
+    steps_finished: usize,
+}
+
+impl Progress {
+    pub(crate) fn of(context: &FlowContext) -> Self {
+        Self {
+            variables: context.variables.clone(),
+            typed_variables: context.typed_variables.clone(),
+            errors: context.errors.len(),
+            steps_started: context.step_logs.len(),
+            steps_finished: context
+                .step_logs
+                .iter()
+                .filter(|log| log.end_time.is_some())
+                .count(),
+        }
+    }
+}
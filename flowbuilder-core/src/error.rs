@@ -0,0 +1,65 @@
+//! # FlowBuilder Core - 流程错误
+//!
+//! 需要调用方区分处理的流程错误；以 `anyhow::Error` 传递，可用
+//! `downcast_ref::<FlowError>()` 识别
+
+use std::fmt;
+use std::time::Duration;
+
+/// 流程错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlowError {
+    /// 步骤执行超时
+    StepTimeout {
+        /// 步骤名称
+        name: String,
+        /// 超时时长
+        after: Duration,
+    },
+    /// 流程在检查点被中止
+    Aborted {
+        /// 中止时上下文中累计的错误数
+        error_count: usize,
+    },
+    /// 流程被外部取消
+    Cancelled,
+    /// 整个流程未在限定时间内完成
+    FlowTimeout {
+        /// 超时时长
+        after: Duration,
+    },
+    /// 等待步骤在窗口期内未观察到任何进展，疑似死锁
+    Deadlock {
+        /// 仍在等待的条件描述
+        waiting: Vec<String>,
+        /// 无进展的持续时长
+        stalled_for: Duration,
+    },
+}
+
+impl fmt::Display for FlowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlowError::StepTimeout { name, after } => {
+                write!(f, "step '{name}' timed out after {after:?}")
+            }
+            FlowError::Aborted { error_count } => {
+                write!(f, "flow aborted with {error_count} recorded errors")
+            }
+            FlowError::Cancelled => write!(f, "flow cancelled"),
+            FlowError::FlowTimeout { after } => {
+                write!(f, "flow timed out after {after:?}")
+            }
+            FlowError::Deadlock {
+                waiting,
+                stalled_for,
+            } => write!(
+                f,
+                "likely deadlock: no progress for {stalled_for:?} while waiting on [{}]",
+                waiting.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FlowError {}
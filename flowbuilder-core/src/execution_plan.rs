@@ -2,8 +2,12 @@
 //!
 //! 定义流程执行的核心数据结构和接口
 
+use crate::StepFuture;
 use anyhow::Result;
+use flowbuilder_context::SharedContext;
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// 执行计划 - 编排器生成的执行顺序
 #[derive(Debug, Clone)]
@@ -45,7 +49,11 @@ pub enum PhaseExecutionMode {
 }
 
 /// 执行节点 - 最小的执行单元
+///
+/// 字段会随功能增加，crate 外通过 [`ExecutionNode::new`] 与 `with_*`
+/// 方法构造
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct ExecutionNode {
     /// 节点ID
     pub id: String,
@@ -65,6 +73,52 @@ pub struct ExecutionNode {
     pub retry_config: Option<RetryConfig>,
     /// 超时配置
     pub timeout_config: Option<TimeoutConfig>,
+    /// 节点标签
+    pub tags: Vec<String>,
+    /// 循环配置（循环节点使用）
+    pub loop_config: Option<LoopConfig>,
+    /// 入边条件：依赖节点ID → 条件（依赖完成后求值，不成立时跳过本节点）
+    pub edge_conditions: HashMap<String, String>,
+    /// 原生动作（设置时代替 `action_spec` 执行）
+    pub native: Option<NativeAction>,
+    /// 显式阶段：按首次出现的顺序排列，之前阶段的节点全部完成后才执行
+    pub phase: Option<String>,
+    /// 互斥组：同组节点同一时刻至多执行一个
+    pub mutex_group: Option<String>,
+}
+
+/// 原生动作 - 包装 Rust 闭包，使代码定义的节点与 YAML 节点共存于同一计划
+#[derive(Clone)]
+pub struct NativeAction(Arc<dyn Fn(SharedContext) -> StepFuture + Send + Sync>);
+
+impl NativeAction {
+    /// 原生节点的动作类型
+    pub const ACTION_TYPE: &'static str = "native";
+
+    /// 包装闭包
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(SharedContext) -> StepFuture + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    pub(crate) fn from_arc(
+        f: Arc<dyn Fn(SharedContext) -> StepFuture + Send + Sync>,
+    ) -> Self {
+        Self(f)
+    }
+
+    /// 执行闭包（可重复调用）
+    pub fn call(&self, context: SharedContext) -> StepFuture {
+        (self.0)(context)
+    }
+}
+
+impl std::fmt::Debug for NativeAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("NativeAction(..)")
+    }
 }
 
 /// 节点类型
@@ -115,6 +169,23 @@ pub enum RetryStrategy {
     Linear { increment: u64 },
 }
 
+impl RetryStrategy {
+    /// 第 `retry` 次重试（从 1 开始）前的等待时长
+    pub fn delay_for(&self, base: Duration, retry: u32) -> Duration {
+        match self {
+            RetryStrategy::Fixed => base,
+            RetryStrategy::Exponential { multiplier } => {
+                let nanos =
+                    base.as_nanos() as f64 * multiplier.powi(retry as i32);
+                Duration::from_nanos(nanos as u64)
+            }
+            RetryStrategy::Linear { increment } => {
+                base + Duration::from_millis(increment * retry as u64)
+            }
+        }
+    }
+}
+
 /// 超时配置
 #[derive(Debug, Clone)]
 pub struct TimeoutConfig {
@@ -122,6 +193,17 @@ pub struct TimeoutConfig {
     pub duration: u64,
     /// 超时处理动作
     pub on_timeout: Option<String>,
+    /// 超时后执行的处理动作（由 `on_timeout` 解析得到）
+    pub on_timeout_action: Option<ActionSpec>,
+}
+
+/// 循环配置
+#[derive(Debug, Clone)]
+pub struct LoopConfig {
+    /// 继续循环的条件（每次迭代后求值）
+    pub condition: String,
+    /// 最大迭代次数
+    pub max_iterations: u32,
 }
 
 /// 计划元数据
@@ -253,19 +335,67 @@ impl ExecutionPlan {
         self.metadata.total_phases = self.phases.len();
     }
 
+    /// 计划是否不含任何节点
+    pub fn is_empty(&self) -> bool {
+        self.phases.iter().all(|phase| phase.nodes.is_empty())
+    }
+
     /// 获取总执行时间估计
     pub fn estimated_duration(&self) -> std::time::Duration {
         // 简化的估计逻辑
-        let total_nodes = self.metadata.total_nodes;
-        std::time::Duration::from_millis((total_nodes * 100) as u64)
+        let total_nodes = self.metadata.total_nodes as u32;
+        ExecutionNode::DEFAULT_ESTIMATE * total_nodes
     }
 
-    /// 验证计划的有效性
-    pub fn validate(&self) -> Result<(), String> {
-        if self.phases.is_empty() {
-            return Err("执行计划不能为空".to_string());
+    /// 关键路径：估计耗时最长的依赖链上的节点 ID（按执行顺序）
+    pub fn critical_path(&self) -> Vec<String> {
+        self.critical_chain().0
+    }
+
+    /// 关键路径的估计总耗时
+    pub fn critical_path_duration(&self) -> std::time::Duration {
+        self.critical_chain().1
+    }
+
+    /// 计算关键路径及其耗时
+    fn critical_chain(&self) -> (Vec<String>, std::time::Duration) {
+        // 阶段按依赖顺序排列，依赖总在之前的阶段（或同阶段之前）出现
+        let mut chains: HashMap<&str, (std::time::Duration, Option<&str>)> =
+            HashMap::new();
+        let mut tail: Option<(&str, std::time::Duration)> = None;
+        for node in self.phases.iter().flat_map(|p| &p.nodes) {
+            let previous = node
+                .dependencies
+                .iter()
+                .filter_map(|dep| chains.get_key_value(dep.as_str()))
+                .map(|(id, &(duration, _))| (*id, duration))
+                .max_by_key(|&(_, duration)| duration);
+            let duration = previous.map_or(std::time::Duration::ZERO, |p| p.1)
+                + node.estimated_duration();
+            chains.insert(&node.id, (duration, previous.map(|p| p.0)));
+            match tail {
+                Some((_, longest)) if longest >= duration => {}
+                _ => tail = Some((&node.id, duration)),
+            }
         }
 
+        let Some((last, duration)) = tail else {
+            return (Vec::new(), std::time::Duration::ZERO);
+        };
+        let mut path = Vec::new();
+        let mut current = Some(last);
+        while let Some(id) = current {
+            path.push(id.to_string());
+            current = chains.get(id).and_then(|&(_, previous)| previous);
+        }
+        path.reverse();
+        (path, duration)
+    }
+
+    /// 验证计划的有效性
+    ///
+    /// 没有任何阶段的空计划是合法的（执行时直接成功）
+    pub fn validate(&self) -> Result<(), String> {
         // 验证每个阶段
         for phase in &self.phases {
             if phase.nodes.is_empty() {
@@ -273,6 +403,12 @@ impl ExecutionPlan {
             }
         }
 
+        // 依赖不能成环
+        let nodes = self.phases.iter().flat_map(|p| &p.nodes);
+        if let Some(cycle) = find_dependency_cycle(nodes) {
+            return Err(format!("检测到循环依赖: {}", cycle.join(" -> ")));
+        }
+
         Ok(())
     }
 
@@ -298,6 +434,16 @@ impl ExecutionPlan {
         writeln!(s, "- 阶段数: {}", self.metadata.total_phases).ok();
         writeln!(s, "- 节点数: {}", self.metadata.total_nodes).ok();
         writeln!(s, "- 预计总时长: {:?}", self.estimated_duration()).ok();
+        let (critical_path, critical_duration) = self.critical_chain();
+        if !critical_path.is_empty() {
+            writeln!(
+                s,
+                "- 关键路径: {} ({:?})",
+                critical_path.join(" -> "),
+                critical_duration
+            )
+            .ok();
+        }
 
         // 变量统计
         writeln!(s, "- 环境变量: {} 项", self.env_vars.len()).ok();
@@ -333,6 +479,12 @@ impl ExecutionPlan {
                     .ok();
                 writeln!(s, "    • 类型: {:?}", node.node_type).ok();
                 writeln!(s, "    • 优先级: {}", node.priority).ok();
+                if let Some(phase) = &node.phase {
+                    writeln!(s, "    • 显式阶段: {}", phase).ok();
+                }
+                if let Some(group) = &node.mutex_group {
+                    writeln!(s, "    • 互斥组: {}", group).ok();
+                }
                 if let Some(cond) = &node.condition {
                     writeln!(s, "    • 条件: {}", cond).ok();
                 }
@@ -393,9 +545,27 @@ impl ExecutionNode {
             priority: 100,
             retry_config: None,
             timeout_config: None,
+            tags: Vec::new(),
+            loop_config: None,
+            edge_conditions: HashMap::new(),
+            native: None,
+            phase: None,
+            mutex_group: None,
         }
     }
 
+    /// 设置显式阶段
+    pub fn with_phase(mut self, phase: impl Into<String>) -> Self {
+        self.phase = Some(phase.into());
+        self
+    }
+
+    /// 设置互斥组
+    pub fn with_mutex_group(mut self, group: impl Into<String>) -> Self {
+        self.mutex_group = Some(group.into());
+        self
+    }
+
     /// 添加依赖
     pub fn add_dependency(mut self, dependency: String) -> Self {
         self.dependencies.push(dependency);
@@ -425,4 +595,115 @@ impl ExecutionNode {
         self.timeout_config = Some(timeout_config);
         self
     }
+
+    /// 设置标签
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// 添加依赖及其入边条件
+    pub fn with_edge_condition(
+        mut self,
+        dependency: String,
+        condition: String,
+    ) -> Self {
+        if !self.dependencies.contains(&dependency) {
+            self.dependencies.push(dependency.clone());
+        }
+        self.edge_conditions.insert(dependency, condition);
+        self
+    }
+
+    /// 创建原生动作节点
+    pub fn native(id: String, name: String, action: NativeAction) -> Self {
+        let action_spec = ActionSpec {
+            action_type: NativeAction::ACTION_TYPE.to_string(),
+            parameters: HashMap::new(),
+            outputs: HashMap::new(),
+        };
+        Self::new(id, name, action_spec).with_native(action)
+    }
+
+    /// 未知耗时时单个节点的估计执行时间
+    pub const DEFAULT_ESTIMATE: std::time::Duration =
+        std::time::Duration::from_millis(100);
+
+    /// 估计执行时间（循环节点按迭代上限计）
+    pub fn estimated_duration(&self) -> std::time::Duration {
+        let iterations = self
+            .loop_config
+            .as_ref()
+            .map_or(1, |l| l.max_iterations.max(1));
+        Self::DEFAULT_ESTIMATE * iterations
+    }
+
+    /// 设置原生动作
+    pub fn with_native(mut self, action: NativeAction) -> Self {
+        self.native = Some(action);
+        self
+    }
+
+    /// 设置循环配置
+    pub fn with_loop(mut self, loop_config: LoopConfig) -> Self {
+        self.loop_config = Some(loop_config);
+        self
+    }
+}
+
+/// 对节点依赖做拓扑排序，存在循环时返回其中一个循环（首尾为同一节点）
+///
+/// 依赖了不在 `nodes` 中的节点时视为已满足
+pub(crate) fn find_dependency_cycle<'a>(
+    nodes: impl IntoIterator<Item = &'a ExecutionNode>,
+) -> Option<Vec<String>> {
+    let nodes: Vec<&ExecutionNode> = nodes.into_iter().collect();
+    let known: HashMap<&str, &ExecutionNode> =
+        nodes.iter().map(|n| (n.id.as_str(), *n)).collect();
+    let deps_of = |node: &'a ExecutionNode| {
+        node.dependencies
+            .iter()
+            .map(String::as_str)
+            .filter(|dep| known.contains_key(dep))
+    };
+
+    // Kahn 算法：不断移除依赖均已就位的节点
+    let mut in_degree: HashMap<&str, usize> = nodes
+        .iter()
+        .map(|n| (n.id.as_str(), deps_of(n).count()))
+        .collect();
+    let mut ready: Vec<&str> = in_degree
+        .iter()
+        .filter(|(_, &d)| d == 0)
+        .map(|(&id, _)| id)
+        .collect();
+    while let Some(done) = ready.pop() {
+        in_degree.remove(done);
+        for node in &nodes {
+            if let Some(degree) = in_degree.get_mut(node.id.as_str()) {
+                let hits = deps_of(node).filter(|dep| *dep == done).count();
+                *degree -= hits;
+                if hits > 0 && *degree == 0 {
+                    ready.push(node.id.as_str());
+                }
+            }
+        }
+    }
+
+    // 剩余节点都依赖另一个剩余节点：沿依赖走下去必然回到走过的节点
+    let start = nodes
+        .iter()
+        .find(|n| in_degree.contains_key(n.id.as_str()))?;
+    let mut path = vec![start.id.as_str()];
+    loop {
+        let current = known[path[path.len() - 1]];
+        let next = deps_of(current).find(|dep| in_degree.contains_key(dep))?;
+        if let Some(pos) = path.iter().position(|id| *id == next) {
+            let mut cycle: Vec<String> =
+                path[pos..].iter().map(|id| id.to_string()).collect();
+            cycle.push(next.to_string());
+            return Some(cycle);
+        }
+        path.push(next);
+    }
 }
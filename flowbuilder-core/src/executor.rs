@@ -1,6 +1,8 @@
-use crate::Step;
+use crate::{FlowError, Step};
 use anyhow::Result;
-use flowbuilder_context::SharedContext;
+use flowbuilder_context::{FlowContext, SharedContext, StepStatus};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 
 /// Executes flow steps
 pub struct FlowExecutor;
@@ -21,6 +23,89 @@ impl FlowExecutor {
         }
         Ok(())
     }
+
+    /// Execute steps sequentially until `cancel` fires
+    ///
+    /// The token is checked before each step, and a running step is dropped
+    /// as soon as it is cancelled. Steps still running are then marked
+    /// [`StepStatus::Cancelled`] and [`FlowError::Cancelled`] is returned.
+    pub async fn execute_steps_with_cancel(
+        &self,
+        steps: Vec<Step>,
+        context: SharedContext,
+        cancel: &CancellationToken,
+    ) -> Result<()> {
+        for step in steps {
+            if cancel.is_cancelled() {
+                return Err(Self::cancelled(&context).await);
+            }
+            tokio::select! {
+                biased;
+                _ = cancel.cancelled() => {
+                    return Err(Self::cancelled(&context).await);
+                }
+                result = step(context.clone()) => result?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Execute steps sequentially, giving the whole run `after` to finish
+    ///
+    /// Once the deadline passes no further step starts and the running one
+    /// is dropped. Steps still running are then marked
+    /// [`StepStatus::Timeout`] and [`FlowError::FlowTimeout`] is returned.
+    pub async fn execute_steps_with_timeout(
+        &self,
+        steps: Vec<Step>,
+        context: SharedContext,
+        after: Duration,
+    ) -> Result<()> {
+        let deadline = tokio::time::Instant::now() + after;
+        for step in steps {
+            if tokio::time::Instant::now() >= deadline {
+                return Err(Self::timed_out(&context, after).await);
+            }
+            match tokio::time::timeout_at(deadline, step(context.clone())).await
+            {
+                Ok(result) => result?,
+                Err(_) => return Err(Self::timed_out(&context, after).await),
+            }
+        }
+        Ok(())
+    }
+
+    async fn cancelled(context: &SharedContext) -> anyhow::Error {
+        Self::interrupted(context, FlowContext::end_step_cancelled).await;
+        FlowError::Cancelled.into()
+    }
+
+    async fn timed_out(
+        context: &SharedContext,
+        after: Duration,
+    ) -> anyhow::Error {
+        Self::interrupted(context, FlowContext::end_step_timeout).await;
+        FlowError::FlowTimeout { after }.into()
+    }
+
+    /// Closes the logs of steps that were still running and prints the
+    /// summary
+    async fn interrupted(
+        context: &SharedContext,
+        end_step: fn(&mut FlowContext, &str),
+    ) {
+        let mut guard = context.lock().await;
+        let running: Vec<String> = guard
+            .step_logs
+            .iter()
+            .filter(|log| matches!(log.status, StepStatus::Running))
+            .map(|log| log.step_name.clone())
+            .collect();
+        for name in &running {
+            end_step(&mut guard, name);
+        }
+        guard.print_summary();
+    }
 }
 
 impl Default for FlowExecutor {
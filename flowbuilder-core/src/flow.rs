@@ -1,17 +1,70 @@
-use crate::{FlowExecutor, Step};
+use crate::flow_builder::{CompensationStack, RetryBudget};
+use crate::{FlowExecutor, FlowHooks, FlowMetrics, ProgressStore, Step};
 use anyhow::Result;
-use flowbuilder_context::FlowContext;
+use flowbuilder_context::{FlowContext, SharedContext};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 
 /// Represents a flow that can be executed
 pub struct Flow {
     steps: Vec<Step>,
+    retry_budget: Option<Arc<RetryBudget>>,
+    hooks: Arc<[Arc<dyn FlowHooks>]>,
+    transactional: bool,
+    compensations: Arc<CompensationStack>,
+    progress: Option<Arc<dyn ProgressStore>>,
 }
 
 impl Flow {
     pub(crate) fn new(steps: Vec<Step>) -> Self {
-        Self { steps }
+        Self {
+            steps,
+            retry_budget: None,
+            hooks: Arc::new([]),
+            transactional: false,
+            compensations: Arc::default(),
+            progress: None,
+        }
+    }
+
+    pub(crate) fn with_retry_budget(
+        mut self,
+        budget: Arc<RetryBudget>,
+    ) -> Self {
+        self.retry_budget = Some(budget);
+        self
+    }
+
+    pub(crate) fn with_hooks(
+        mut self,
+        hooks: Arc<[Arc<dyn FlowHooks>]>,
+    ) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    pub(crate) fn with_transactional(mut self, transactional: bool) -> Self {
+        self.transactional = transactional;
+        self
+    }
+
+    pub(crate) fn with_compensations(
+        mut self,
+        compensations: Arc<CompensationStack>,
+    ) -> Self {
+        self.compensations = compensations;
+        self
+    }
+
+    pub(crate) fn with_progress_store(
+        mut self,
+        store: Arc<dyn ProgressStore>,
+    ) -> Self {
+        self.progress = Some(store);
+        self
     }
 
     /// Execute the flow with a default context
@@ -26,16 +79,165 @@ impl Flow {
         context: FlowContext,
     ) -> Result<FlowContext> {
         let shared_context = Arc::new(Mutex::new(context));
-        let executor = FlowExecutor::new();
+        self.run(shared_context.clone(), Interrupt::None).await?;
+        into_context(shared_context)
+    }
+
+    /// Execute the flow on a context the caller keeps a handle to
+    ///
+    /// Unlike the entry points that take a [`FlowContext`], the context
+    /// stays reachable when the flow fails, e.g. to read the restored state
+    /// of a [transactional](crate::FlowBuilder::transactional) flow.
+    pub async fn execute_shared(self, context: SharedContext) -> Result<()> {
+        self.run(context, Interrupt::None).await
+    }
 
-        executor
-            .execute_steps(self.steps, shared_context.clone())
+    /// Execute the flow until it completes or `cancel` fires
+    ///
+    /// On cancellation later steps do not run, the interrupted named step is
+    /// logged as cancelled and [`crate::FlowError::Cancelled`] is returned.
+    pub async fn execute_with_cancel(
+        self,
+        context: FlowContext,
+        cancel: CancellationToken,
+    ) -> Result<FlowContext> {
+        let shared_context = Arc::new(Mutex::new(context));
+        self.run(shared_context.clone(), Interrupt::Cancel(&cancel))
             .await?;
+        into_context(shared_context)
+    }
 
-        let final_context = Arc::try_unwrap(shared_context)
-            .map_err(|_| anyhow::anyhow!("Failed to unwrap shared context"))?
-            .into_inner();
+    /// Execute the flow, giving the whole run `after` to finish
+    ///
+    /// On timeout the running step is dropped and logged as
+    /// [`StepStatus::Timeout`](flowbuilder_context::StepStatus::Timeout),
+    /// the context summary is printed and
+    /// [`crate::FlowError::FlowTimeout`] is returned.
+    pub async fn execute_with_timeout(
+        self,
+        context: FlowContext,
+        after: Duration,
+    ) -> Result<FlowContext> {
+        let shared_context = Arc::new(Mutex::new(context));
+        self.run(shared_context.clone(), Interrupt::Timeout(after))
+            .await?;
+        into_context(shared_context)
+    }
+
+    /// Execute the flow and collect [`FlowMetrics`] from its step logs
+    ///
+    /// Metrics are returned even when the flow fails. `total_duration` is
+    /// the wall time of the whole run, including unnamed steps.
+    pub async fn execute_with_metrics(
+        self,
+        context: FlowContext,
+    ) -> (Result<FlowContext>, FlowMetrics) {
+        let started = Instant::now();
+        let shared_context = Arc::new(Mutex::new(context));
+        let retry_budget = self.retry_budget.clone();
+        let result = self.run(shared_context.clone(), Interrupt::None).await;
+
+        let mut metrics =
+            FlowMetrics::from_context(&*shared_context.lock().await);
+        metrics.total_duration = started.elapsed();
+        metrics.retry_budget_remaining =
+            retry_budget.and_then(|budget| budget.remaining());
+
+        let result = result.and_then(|()| into_context(shared_context));
+        (result, metrics)
+    }
+
+    async fn run(
+        self,
+        context: SharedContext,
+        interrupt: Interrupt<'_>,
+    ) -> Result<()> {
+        let savepoint = format!("transaction-{}", uuid::Uuid::new_v4());
+        let span = {
+            let mut guard = context.lock().await;
+            if self.transactional {
+                guard
+                    .savepoint(savepoint.clone())
+                    .map_err(anyhow::Error::msg)?;
+            }
+            tracing::info_span!("flow", trace_id = %guard.trace_id)
+        };
+        let executor = FlowExecutor::new();
+        let result = match interrupt {
+            Interrupt::None => {
+                executor
+                    .execute_steps(self.steps, context.clone())
+                    .instrument(span)
+                    .await
+            }
+            Interrupt::Cancel(cancel) => {
+                executor
+                    .execute_steps_with_cancel(
+                        self.steps,
+                        context.clone(),
+                        cancel,
+                    )
+                    .instrument(span)
+                    .await
+            }
+            Interrupt::Timeout(after) => {
+                executor
+                    .execute_steps_with_timeout(
+                        self.steps,
+                        context.clone(),
+                        after,
+                    )
+                    .instrument(span)
+                    .await
+            }
+        };
+
+        match (&result, &self.progress) {
+            (Err(_), _) => self.compensations.unwind(&context).await,
+            (Ok(()), Some(store)) => {
+                if let Err(e) = store.clear() {
+                    tracing::warn!(error = %e, "[progress] failed to clear progress");
+                }
+            }
+            (Ok(()), None) => {}
+        }
 
-        Ok(final_context)
+        if self.transactional {
+            let mut guard = context.lock().await;
+            let restored = if result.is_err() {
+                tracing::warn!(trace_id = %guard.trace_id, "flow failed, rolling back transaction");
+                guard.rollback_to_savepoint(&savepoint)
+            } else {
+                Ok(())
+            };
+            // A step rolling back to an earlier savepoint drops this one
+            if let Err(e) =
+                restored.and_then(|()| guard.release_savepoint(&savepoint))
+            {
+                tracing::warn!(trace_id = %guard.trace_id, error = %e, "transaction savepoint lost");
+            }
+        }
+
+        if !self.hooks.is_empty() {
+            let guard = context.lock().await;
+            for hook in self.hooks.iter() {
+                hook.on_flow_complete(&guard);
+            }
+        }
+        result
     }
 }
+
+/// What may end a run before every step has finished
+enum Interrupt<'a> {
+    None,
+    Cancel(&'a CancellationToken),
+    Timeout(Duration),
+}
+
+/// Takes the context back once every step has released it
+fn into_context(context: SharedContext) -> Result<FlowContext> {
+    Ok(Arc::try_unwrap(context)
+        .map_err(|_| anyhow::anyhow!("Failed to unwrap shared context"))?
+        .into_inner())
+}
@@ -1,16 +1,320 @@
-use crate::Flow;
+#[cfg(feature = "deadlock-detector")]
+use crate::deadlock::{DeadlockDetector, Progress};
+use crate::{
+    ExecutionNode, ExecutionPhase, ExecutionPlan, Flow, FlowError,
+    FlowExecutor, FlowHooks, FlowMetrics, FlowProgress, NativeAction,
+    PhaseExecutionMode, ProgressStore, RetryStrategy,
+};
 use anyhow::Result;
-use flowbuilder_context::{FlowContext, SharedContext};
-use std::{future::Future, pin::Pin, time::Duration};
-use tracing::{info, warn};
+use flowbuilder_context::{FlowContext, SharedContext, StepStatus, VarMap};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use std::{fmt::Debug, future::Future, pin::Pin, time::Duration};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn, Instrument};
 
 /// Type alias for step functions
 pub type StepFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
 pub type Step = Box<dyn FnOnce(SharedContext) -> StepFuture + Send>;
 
+/// Condition guarding a step, readable without running the step
+type StepCondition = Arc<dyn Fn(&FlowContext) -> bool + Send + Sync>;
+
+/// A registered step; re-callable so compiled plans can retry it
+struct BuilderStep {
+    name: Option<&'static str>,
+    run: Arc<dyn Fn(SharedContext) -> StepFuture + Send + Sync>,
+    condition: Option<StepCondition>,
+    kind: StepKind,
+    metadata: BTreeMap<String, String>,
+}
+
+/// Kind of a step, as reported by [`FlowBuilder::describe`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StepKind {
+    /// A plain step, optionally with retries or a timeout
+    Step,
+    /// A step guarded by a condition ([`FlowBuilder::step_if`])
+    If,
+    /// A loop over an accumulator ([`FlowBuilder::step_fold`])
+    While,
+    /// Subflows run side by side ([`FlowBuilder::parallel_group`])
+    Parallel {
+        /// Steps of each subflow, in the order given
+        branches: Vec<Vec<StepDescriptor>>,
+    },
+    /// A subflow run once per item ([`FlowBuilder::step_foreach`])
+    Subflow,
+    /// Waits for a condition or signal
+    Wait,
+    /// Aborts the flow when a condition holds ([`FlowBuilder::abort_if`])
+    Abort,
+    /// Publishes an event ([`FlowBuilder::step_emit`])
+    Emit,
+}
+
+/// Static description of a step, for visualizing or validating a flow
+/// before it runs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StepDescriptor {
+    /// Position of the step in the flow
+    pub index: usize,
+    /// Step name, or `step_<index>` for unnamed steps
+    pub name: String,
+    /// What the step does
+    pub kind: StepKind,
+    /// Static settings such as retries, timeouts or signal keys
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// A step as previewed by [`FlowBuilder::plan`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedStep {
+    /// Position of the step in the flow
+    pub index: usize,
+    /// Step name, or `step_<index>` for unnamed steps
+    pub name: String,
+    /// Whether the step is guarded by a condition
+    pub conditional: bool,
+    /// Whether the step would run against the given context
+    pub will_run: bool,
+}
+
+/// Calls a `FnMut` shared behind a mutex, releasing the lock before the
+/// returned future is polled
+fn call_shared<F, Fut>(f: &Mutex<F>, ctx: SharedContext) -> Fut
+where
+    F: FnMut(SharedContext) -> Fut,
+{
+    let mut f = f.lock().unwrap_or_else(|e| e.into_inner());
+    f(ctx)
+}
+
+/// Settings for [`FlowBuilder::parallel_group`]
+#[derive(Debug, Clone)]
+pub struct ParallelConfig {
+    /// Maximum number of subflows running at the same time
+    pub max_concurrency: usize,
+    /// Time limit for each subflow
+    pub timeout: Option<Duration>,
+    /// Abort the remaining subflows on the first failure
+    pub fail_fast: bool,
+}
+
+impl Default for ParallelConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 10,
+            timeout: None,
+            fail_fast: true,
+        }
+    }
+}
+
+/// Opens the span for a nested flow run by step `step`
+///
+/// Created inside the parent step, so it nests under the current flow span;
+/// each subflow gets its own `sub_id` next to the shared `trace_id`.
+fn subflow_span(step: &str, index: usize, trace_id: &str) -> tracing::Span {
+    tracing::info_span!(
+        "subflow",
+        step,
+        index,
+        sub_id = %uuid::Uuid::new_v4(),
+        trace_id
+    )
+}
+
+/// Writes the variables exported by a scoped subflow back into `parent`
+///
+/// `base` holds the variables the child started from. An exported key
+/// whose parent value changed since then is overwritten anyway (last
+/// writer wins) and a warning is logged.
+fn merge_scope(
+    parent: &mut FlowContext,
+    child: FlowContext,
+    base: &(VarMap<String>, VarMap<serde_json::Value>),
+) {
+    for key in &child.exports {
+        let conflict = parent.variables.get(key) != base.0.get(key)
+            || parent.typed_variables.get(key) != base.1.get(key);
+        if conflict {
+            warn!(
+                trace_id = %parent.trace_id,
+                key = %key,
+                "[subflow_scoped] exported variable changed concurrently, overwriting"
+            );
+        }
+        if let Some(value) = child.variables.get(key) {
+            parent.set_variable(key.clone(), value.clone());
+        }
+        if let Some(value) = child.typed_variables.get(key) {
+            parent.set_typed(key.clone(), value.clone());
+        }
+    }
+}
+
+/// Wraps every step so that the variables it changed are logged
+fn with_diff_logging(steps: Vec<Step>, names: Vec<String>) -> Vec<Step> {
+    steps
+        .into_iter()
+        .zip(names)
+        .map(|(step, name)| {
+            Box::new(move |ctx: SharedContext| -> StepFuture {
+                Box::pin(async move {
+                    let before =
+                        ctx.lock().await.capture(name.clone(), String::new());
+                    let result = step(ctx.clone()).await;
+                    let guard = ctx.lock().await;
+                    let diff = before.diff(&guard);
+                    if !diff.is_empty() {
+                        debug!(
+                            trace_id = %guard.trace_id,
+                            step = %name,
+                            added = ?diff.added,
+                            removed = ?diff.removed,
+                            changed = ?diff.changed,
+                            "[diff] step changed context"
+                        );
+                    }
+                    result
+                })
+            }) as Step
+        })
+        .collect()
+}
+
+/// Wraps every step so that the progress is saved once it completes
+fn with_progress(
+    steps: Vec<Step>,
+    names: Vec<String>,
+    store: Arc<dyn ProgressStore>,
+) -> Vec<Step> {
+    steps
+        .into_iter()
+        .zip(names)
+        .enumerate()
+        .map(|(index, (step, name))| {
+            let store = store.clone();
+            Box::new(move |ctx: SharedContext| -> StepFuture {
+                Box::pin(async move {
+                    step(ctx.clone()).await?;
+                    let progress = FlowProgress {
+                        completed_steps: index + 1,
+                        last_step: name,
+                        context: ctx.lock().await.clone(),
+                    };
+                    if let Err(e) = store.save(&progress) {
+                        warn!(
+                            trace_id = %progress.context.trace_id,
+                            step = %progress.last_step,
+                            error = %e,
+                            "[progress] failed to save progress"
+                        );
+                    }
+                    Ok(())
+                })
+            }) as Step
+        })
+        .collect()
+}
+
+/// Whether `error` is a step timeout raised by this builder
+fn is_step_timeout(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<FlowError>(),
+        Some(FlowError::StepTimeout { .. })
+    )
+}
+
+/// Retries left for a whole flow, shared by its retrying steps
+#[derive(Debug, Default)]
+pub(crate) struct RetryBudget {
+    /// `None` means unlimited
+    remaining: Mutex<Option<u32>>,
+}
+
+impl RetryBudget {
+    fn set(&self, limit: u32) {
+        *self.remaining.lock().unwrap_or_else(|e| e.into_inner()) = Some(limit);
+    }
+
+    /// Takes one retry from the budget; `false` once it is exhausted
+    fn try_acquire(&self) -> bool {
+        let mut remaining =
+            self.remaining.lock().unwrap_or_else(|e| e.into_inner());
+        match remaining.as_mut() {
+            None => true,
+            Some(0) => false,
+            Some(n) => {
+                *n -= 1;
+                true
+            }
+        }
+    }
+
+    /// Retries left, `None` when unlimited
+    pub(crate) fn remaining(&self) -> Option<u32> {
+        *self.remaining.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Undo action registered by a completed step
+type Compensation = Arc<dyn Fn(SharedContext) -> StepFuture + Send + Sync>;
+
+/// Compensations registered by the steps of one flow, most recent last
+#[derive(Default)]
+pub(crate) struct CompensationStack {
+    entries: Mutex<Vec<(&'static str, Compensation)>>,
+}
+
+impl CompensationStack {
+    fn push(&self, name: &'static str, undo: Compensation) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((name, undo));
+    }
+
+    fn pop(&self) -> Option<(&'static str, Compensation)> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).pop()
+    }
+
+    /// Runs and removes the registered compensations, most recent first
+    ///
+    /// Each one is logged as a `<name>.compensate` step. A failing
+    /// compensation is recorded and does not stop the remaining ones.
+    pub(crate) async fn unwind(&self, context: &SharedContext) {
+        while let Some((name, undo)) = self.pop() {
+            let step_name = format!("{name}.compensate");
+            context.lock().await.start_step(step_name.clone());
+            let result = undo(context.clone()).await;
+            let mut guard = context.lock().await;
+            match result {
+                Ok(()) => guard.end_step_success(&step_name),
+                Err(e) => {
+                    warn!(step = name, error = %e, "[compensate] compensation failed");
+                    guard.end_step_failed(&step_name, &e.to_string());
+                }
+            }
+        }
+    }
+}
+
 /// Builder for creating flows with a fluent API
 pub struct FlowBuilder {
-    steps: Vec<Step>,
+    steps: Vec<BuilderStep>,
+    retry_budget: Arc<RetryBudget>,
+    hooks: Vec<Arc<dyn FlowHooks>>,
+    transactional: bool,
+    diff_logging: bool,
+    compensations: Arc<CompensationStack>,
+    progress: Option<Arc<dyn ProgressStore>>,
+    #[cfg(feature = "deadlock-detector")]
+    deadlock: Arc<DeadlockDetector>,
 }
 
 impl Default for FlowBuilder {
@@ -22,27 +326,132 @@ impl Default for FlowBuilder {
 impl FlowBuilder {
     /// Creates a new FlowBuilder
     pub fn new() -> Self {
-        Self { steps: Vec::new() }
+        Self {
+            steps: Vec::new(),
+            retry_budget: Arc::default(),
+            hooks: Vec::new(),
+            transactional: false,
+            diff_logging: false,
+            compensations: Arc::default(),
+            progress: None,
+            #[cfg(feature = "deadlock-detector")]
+            deadlock: Arc::default(),
+        }
+    }
+
+    /// Caps the number of retries across all retrying steps of this flow
+    ///
+    /// Once the budget is used up, a failing step is not retried again and
+    /// fails with its last error. The budget belongs to the built flow and
+    /// applies whether this is called before or after adding the steps.
+    pub fn with_retry_budget(self, retries: u32) -> Self {
+        self.retry_budget.set(retries);
+        self
+    }
+
+    /// Makes the built flow all-or-nothing
+    ///
+    /// The context is captured as a savepoint before the first step. If the
+    /// flow fails or is cancelled, variables, typed variables and errors are
+    /// restored from it before [`FlowHooks::on_flow_complete`] runs, so the
+    /// context looks as if the flow never ran; step logs are kept. Run it
+    /// with [`execute_shared`](Self::execute_shared) to read the restored
+    /// context after a failure.
+    pub fn transactional(mut self) -> Self {
+        self.transactional = true;
+        self
+    }
+
+    /// Logs, at debug level, what each step of the built flow changed in
+    /// the context variables
+    ///
+    /// The variables are captured before every step and compared
+    /// afterwards; steps that change nothing are not logged.
+    pub fn with_diff_logging(mut self) -> Self {
+        self.diff_logging = true;
+        self
+    }
+
+    /// Saves a [`FlowProgress`] to `store` each time a top-level step of
+    /// the built flow completes, and clears it once the flow succeeds
+    ///
+    /// Use [`Self::resume_from_store`] to continue a flow that stopped
+    /// part-way. A failed save is logged and does not fail the flow.
+    pub fn with_progress_store(
+        mut self,
+        store: Arc<dyn ProgressStore>,
+    ) -> Self {
+        self.progress = Some(store);
+        self
+    }
+
+    /// Registers lifecycle hooks called around every step of the built
+    /// flow; may be called several times to install multiple hooks
+    pub fn with_hooks(mut self, hooks: Arc<dyn FlowHooks>) -> Self {
+        self.hooks.push(hooks);
+        self
+    }
+
+    fn push<R>(mut self, name: Option<&'static str>, run: R) -> Self
+    where
+        R: Fn(SharedContext) -> StepFuture + Send + Sync + 'static,
+    {
+        self.steps.push(BuilderStep {
+            name,
+            run: Arc::new(run),
+            condition: None,
+            kind: StepKind::Step,
+            metadata: BTreeMap::new(),
+        });
+        self
+    }
+
+    /// Records the kind and static settings of the most recently added
+    /// step, as reported by [`Self::describe`]
+    fn described<const N: usize>(
+        mut self,
+        kind: StepKind,
+        metadata: [(&str, String); N],
+    ) -> Self {
+        if let Some(step) = self.steps.last_mut() {
+            step.kind = kind;
+            step.metadata = metadata
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect();
+        }
+        self
+    }
+
+    /// Attaches `condition` to the most recently added step, so that
+    /// [`Self::plan`] can evaluate it without running the step
+    fn guarded_by(mut self, condition: StepCondition) -> Self {
+        if let Some(step) = self.steps.last_mut() {
+            step.condition = Some(condition);
+        }
+        self
     }
 
     /// Adds a simple step to the flow
-    pub fn step<Fut, F>(mut self, mut f: F) -> Self
+    pub fn step<Fut, F>(self, f: F) -> Self
     where
         F: FnMut(SharedContext) -> Fut + Send + 'static,
         Fut: Future<Output = Result<()>> + Send + 'static,
     {
-        self.steps.push(Box::new(move |ctx| Box::pin(f(ctx))));
-        self
+        let f = Mutex::new(f);
+        self.push(None, move |ctx| Box::pin(call_shared(&f, ctx)))
     }
 
     /// Adds a named step to the flow with automatic logging
-    pub fn named_step<Fut, F>(mut self, name: &'static str, mut f: F) -> Self
+    pub fn named_step<Fut, F>(self, name: &'static str, f: F) -> Self
     where
         F: FnMut(SharedContext) -> Fut + Send + 'static,
         Fut: Future<Output = Result<()>> + Send + 'static,
     {
-        self.steps.push(Box::new(move |ctx| {
+        let f = Arc::new(Mutex::new(f));
+        self.push(Some(name), move |ctx| {
             let ctx2 = ctx.clone();
+            let f = f.clone();
             Box::pin(async move {
                 // Start step logging
                 {
@@ -50,7 +459,7 @@ impl FlowBuilder {
                     guard.start_step(name.to_string());
                 }
 
-                let result = f(ctx2.clone()).await;
+                let result = call_shared(&f, ctx2.clone()).await;
 
                 // End step logging
                 {
@@ -63,24 +472,358 @@ impl FlowBuilder {
 
                 result
             })
-        }));
-        self
+        })
+    }
+
+    /// Adds a named step that is retried up to `max_retries` times on error,
+    /// waiting `delay` between attempts
+    pub fn step_with_retry<Fut, F>(
+        self,
+        name: &'static str,
+        max_retries: u32,
+        delay: Duration,
+        f: F,
+    ) -> Self
+    where
+        F: FnMut(SharedContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.step_with_retry_if(name, max_retries, delay, f, |_| true, None)
+    }
+
+    /// Adds a named step that is retried only while `should_retry` accepts
+    /// the error
+    ///
+    /// Errors rejected by the predicate fail the step immediately. The wait
+    /// before the n-th retry follows `strategy` with `delay` as its base
+    /// (fixed delay when `None`).
+    pub fn step_with_retry_if<Fut, F, P>(
+        self,
+        name: &'static str,
+        max_retries: u32,
+        delay: Duration,
+        f: F,
+        should_retry: P,
+        strategy: Option<RetryStrategy>,
+    ) -> Self
+    where
+        F: FnMut(SharedContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+        P: Fn(&anyhow::Error) -> bool + Send + Sync + 'static,
+    {
+        let f = Arc::new(Mutex::new(f));
+        let should_retry = Arc::new(should_retry);
+        let strategy = Arc::new(strategy.unwrap_or(RetryStrategy::Fixed));
+        let budget = self.retry_budget.clone();
+        self.push(Some(name), move |ctx| {
+            let f = f.clone();
+            let should_retry = should_retry.clone();
+            let strategy = strategy.clone();
+            let budget = budget.clone();
+            Box::pin(async move {
+                ctx.lock().await.start_step(name.to_string());
+
+                let mut retries = 0;
+                let result = loop {
+                    match call_shared(&f, ctx.clone()).await {
+                        Ok(()) => break Ok(()),
+                        Err(e) if retries < max_retries && should_retry(&e) => {
+                            if !budget.try_acquire() {
+                                warn!(
+                                    step = name,
+                                    "[step_with_retry] flow retry budget exhausted"
+                                );
+                                break Err(e.context("flow retry budget exhausted"));
+                            }
+                            retries += 1;
+                            warn!(
+                                step = name,
+                                retries,
+                                max_retries,
+                                error = %e,
+                                "[step_with_retry] attempt failed, retrying"
+                            );
+                            tokio::time::sleep(
+                                strategy.delay_for(delay, retries),
+                            )
+                            .await;
+                        }
+                        Err(e) => break Err(e),
+                    }
+                };
+
+                let mut guard = ctx.lock().await;
+                match &result {
+                    Ok(()) => guard.end_step_success(name),
+                    Err(e) => guard.end_step_failed(name, &e.to_string()),
+                }
+                result
+            })
+        })
+        .described(
+            StepKind::Step,
+            [
+                ("max_retries", max_retries.to_string()),
+                ("delay_ms", delay.as_millis().to_string()),
+            ],
+        )
+    }
+
+    /// Adds a named step that rolls the context back to `snapshot_id` when
+    /// it fails
+    ///
+    /// The snapshot is created when the step starts unless it already
+    /// exists. The failure still propagates: the flow stops with the step
+    /// error, on a context that no longer holds the step's partial writes.
+    /// Use [`Self::step_with_rollback_continue`] to roll back and carry on.
+    pub fn step_with_rollback<Fut, F>(
+        self,
+        name: &'static str,
+        snapshot_id: &'static str,
+        f: F,
+    ) -> Self
+    where
+        F: FnMut(SharedContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.rollback_step(name, snapshot_id, 0, true, f)
+    }
+
+    /// Like [`Self::step_with_rollback`], but a rolled-back failure is
+    /// suppressed: it is recorded in the step log and context errors, and
+    /// the flow moves on to the next step
+    ///
+    /// The flow result no longer reflects the failure, so callers that need
+    /// to know must inspect the context errors.
+    pub fn step_with_rollback_continue<Fut, F>(
+        self,
+        name: &'static str,
+        snapshot_id: &'static str,
+        f: F,
+    ) -> Self
+    where
+        F: FnMut(SharedContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.rollback_step(name, snapshot_id, 0, false, f)
+    }
+
+    /// Adds a named step that rolls the context back to `snapshot_id` after
+    /// every failed attempt and retries it up to `max_retries` times
+    ///
+    /// The snapshot is created when the step starts unless it already
+    /// exists. Retries draw from the flow retry budget; once retries are
+    /// exhausted the context is left rolled back and the last error is
+    /// returned.
+    pub fn step_with_rollback_retry<Fut, F>(
+        self,
+        name: &'static str,
+        snapshot_id: &'static str,
+        max_retries: u32,
+        f: F,
+    ) -> Self
+    where
+        F: FnMut(SharedContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.rollback_step(name, snapshot_id, max_retries, true, f)
+    }
+
+    fn rollback_step<Fut, F>(
+        self,
+        name: &'static str,
+        snapshot_id: &'static str,
+        max_retries: u32,
+        propagate: bool,
+        f: F,
+    ) -> Self
+    where
+        F: FnMut(SharedContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let f = Arc::new(Mutex::new(f));
+        let budget = self.retry_budget.clone();
+        self.push(Some(name), move |ctx| {
+            let f = f.clone();
+            let budget = budget.clone();
+            Box::pin(async move {
+                {
+                    let mut guard = ctx.lock().await;
+                    guard.start_step(name.to_string());
+                    if !guard.snapshots.contains_key(snapshot_id) {
+                        guard
+                            .create_snapshot(
+                                snapshot_id.to_string(),
+                                format!("before {name}"),
+                            )
+                            .map_err(anyhow::Error::msg)?;
+                    }
+                }
+
+                let mut retries = 0;
+                let result = loop {
+                    let Err(e) = call_shared(&f, ctx.clone()).await else {
+                        break Ok(());
+                    };
+                    if let Err(msg) =
+                        ctx.lock().await.rollback_to_snapshot(snapshot_id)
+                    {
+                        break Err(e.context(msg));
+                    }
+                    if retries >= max_retries {
+                        break Err(e);
+                    }
+                    if !budget.try_acquire() {
+                        warn!(
+                            step = name,
+                            "[step_with_rollback] flow retry budget exhausted"
+                        );
+                        break Err(e.context("flow retry budget exhausted"));
+                    }
+                    retries += 1;
+                    warn!(
+                        step = name,
+                        snapshot = snapshot_id,
+                        retries,
+                        max_retries,
+                        error = %e,
+                        "[step_with_rollback] attempt failed, rolled back and retrying"
+                    );
+                };
+
+                let mut guard = ctx.lock().await;
+                match result {
+                    Ok(()) => {
+                        guard.end_step_success(name);
+                        Ok(())
+                    }
+                    Err(e) => {
+                        guard.end_step_failed(name, &e.to_string());
+                        if propagate {
+                            return Err(e);
+                        }
+                        warn!(step = name, snapshot = snapshot_id, error = %e, "[step_with_rollback] rolled back, continuing");
+                        Ok(())
+                    }
+                }
+            })
+        })
+        .described(
+            StepKind::Step,
+            [
+                ("max_retries", max_retries.to_string()),
+                ("snapshot", snapshot_id.to_string()),
+                ("propagate", propagate.to_string()),
+            ],
+        )
+    }
+
+    /// Adds a named step whose side effects can be undone by `undo`
+    ///
+    /// Once `f` succeeds, `undo` is registered with the built flow. If a
+    /// later step fails (or the flow is cancelled or times out), the
+    /// registered compensations run in reverse order before the error is
+    /// returned, each logged as a `<name>.compensate` step. Compensations
+    /// of nested subflows are not registered with the parent flow.
+    pub fn step_with_compensation<Fut, F, UFut, U>(
+        self,
+        name: &'static str,
+        mut f: F,
+        undo: U,
+    ) -> Self
+    where
+        F: FnMut(SharedContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+        U: FnMut(SharedContext) -> UFut + Send + 'static,
+        UFut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let stack = self.compensations.clone();
+        let undo = Arc::new(Mutex::new(undo));
+        self.named_step(name, move |ctx| {
+            let run = f(ctx);
+            let stack = stack.clone();
+            let undo = undo.clone();
+            async move {
+                run.await?;
+                stack.push(
+                    name,
+                    Arc::new(move |ctx| -> StepFuture {
+                        Box::pin(call_shared(&undo, ctx))
+                    }),
+                );
+                Ok(())
+            }
+        })
+    }
+
+    /// Adds a named step that fails with [`FlowError::StepTimeout`] when it
+    /// runs longer than `timeout`
+    ///
+    /// The step log is marked as timed out rather than failed, so callers can
+    /// tell a timeout from an error returned by the step itself.
+    pub fn step_with_timeout<Fut, F>(
+        self,
+        name: &'static str,
+        timeout: Duration,
+        f: F,
+    ) -> Self
+    where
+        F: FnMut(SharedContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let f = Arc::new(Mutex::new(f));
+        self.push(Some(name), move |ctx| {
+            let f = f.clone();
+            Box::pin(async move {
+                ctx.lock().await.start_step(name.to_string());
+
+                let step = call_shared(&f, ctx.clone());
+                let result = tokio::time::timeout(timeout, step)
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(FlowError::StepTimeout {
+                            name: name.to_string(),
+                            after: timeout,
+                        }
+                        .into())
+                    });
+
+                let mut guard = ctx.lock().await;
+                match &result {
+                    Ok(()) => guard.end_step_success(name),
+                    Err(e) if is_step_timeout(e) => {
+                        guard.end_step_timeout(name)
+                    }
+                    Err(e) => guard.end_step_failed(name, &e.to_string()),
+                }
+                result
+            })
+        })
+        .described(
+            StepKind::Step,
+            [("timeout_ms", timeout.as_millis().to_string())],
+        )
     }
 
     /// Adds a conditional step that only executes if the condition is met
-    pub fn step_if<Fut, F, Cond>(mut self, cond: Cond, mut f: F) -> Self
+    pub fn step_if<Fut, F, Cond>(self, cond: Cond, f: F) -> Self
     where
         Cond: Fn(&FlowContext) -> bool + Send + Sync + 'static,
         F: FnMut(SharedContext) -> Fut + Send + 'static,
         Fut: Future<Output = Result<()>> + Send + 'static,
     {
-        self.steps.push(Box::new(move |ctx| {
+        let cond = Arc::new(cond);
+        let f = Arc::new(Mutex::new(f));
+        let condition: StepCondition = cond.clone();
+        self.push(None, move |ctx| {
             let ctx2 = ctx.clone();
+            let cond = cond.clone();
+            let f = f.clone();
             Box::pin(async move {
                 let guard = ctx2.lock().await;
                 if cond(&guard) {
                     drop(guard);
-                    f(ctx2).await
+                    call_shared(&f, ctx2).await
                 } else {
                     let trace_id = guard.trace_id.clone();
                     drop(guard);
@@ -88,13 +831,43 @@ impl FlowBuilder {
                     Ok(())
                 }
             })
-        }));
-        self
+        })
+        .described(StepKind::If, [])
+        .guarded_by(condition)
+    }
+
+    /// Adds a checkpoint that aborts the rest of the flow with
+    /// [`FlowError::Aborted`] when `cond` holds
+    ///
+    /// Meant for best-effort flows that record errors instead of failing,
+    /// e.g. `abort_if(|ctx| ctx.errors.len() > 3)`.
+    pub fn abort_if<Cond>(self, cond: Cond) -> Self
+    where
+        Cond: Fn(&FlowContext) -> bool + Send + Sync + 'static,
+    {
+        let cond = Arc::new(cond);
+        self.push(None, move |ctx| {
+            let cond = cond.clone();
+            Box::pin(async move {
+                let guard = ctx.lock().await;
+                if !cond(&guard) {
+                    return Ok(());
+                }
+                let error_count = guard.errors.len();
+                warn!(
+                    trace_id = %guard.trace_id,
+                    error_count,
+                    "[abort_if] condition met, aborting flow"
+                );
+                Err(FlowError::Aborted { error_count }.into())
+            })
+        })
+        .described(StepKind::Abort, [])
     }
 
     /// Adds a wait step that waits until a condition is met
     pub fn wait_until<Cond>(
-        mut self,
+        self,
         cond: Cond,
         interval: Duration,
         max_retry: usize,
@@ -102,18 +875,75 @@ impl FlowBuilder {
     where
         Cond: Fn(&FlowContext) -> bool + Send + Sync + 'static,
     {
-        self.steps.push(Box::new(move |ctx| {
+        self.named_wait_until("wait_until", cond, interval, max_retry)
+    }
+
+    /// Like [`Self::wait_until`], with `description` naming the awaited
+    /// condition in logs, errors and deadlock reports
+    pub fn named_wait_until<Cond>(
+        self,
+        description: &'static str,
+        cond: Cond,
+        interval: Duration,
+        max_retry: usize,
+    ) -> Self
+    where
+        Cond: Fn(&FlowContext) -> bool + Send + Sync + 'static,
+    {
+        let cond = Arc::new(cond);
+        #[cfg(feature = "deadlock-detector")]
+        let detector = self.deadlock.clone();
+        self.push(None, move |ctx| {
+            let cond = cond.clone();
+            #[cfg(feature = "deadlock-detector")]
+            let detector = detector.clone();
             Box::pin(async move {
+                #[cfg(feature = "deadlock-detector")]
+                let wait = detector.enter_wait(description);
+                #[cfg(feature = "deadlock-detector")]
+                let mut last_progress: Option<(
+                    Progress,
+                    Instant,
+                )> = None;
+
                 for attempt in 0..max_retry {
                     {
                         let guard = ctx.lock().await;
                         if cond(&guard) {
                             info!(
                                 attempt = attempt + 1,
-                                "[wait_until] condition met"
+                                "[{}] condition met", description
                             );
                             return Ok(());
                         }
+
+                        #[cfg(feature = "deadlock-detector")]
+                        if let Some(window) = detector.window() {
+                            let progress = Progress::of(&guard);
+                            match &last_progress {
+                                Some((last, since)) if *last == progress => {
+                                    let stalled_for = since.elapsed();
+                                    if stalled_for >= window {
+                                        let waiting = wait.waiting();
+                                        warn!(
+                                            ?waiting,
+                                            ?stalled_for,
+                                            "[{}] likely deadlock",
+                                            description
+                                        );
+                                        return Err(FlowError::Deadlock {
+                                            waiting,
+                                            stalled_for,
+                                        }
+                                        .into());
+                                    }
+                                }
+                                _ => {
+                                    last_progress =
+                                        Some((progress, Instant::now()))
+                                }
+                            }
+                        }
                     }
 
                     if attempt < max_retry - 1 {
@@ -122,22 +952,668 @@ impl FlowBuilder {
                 }
 
                 anyhow::bail!(
-                    "[wait_until] condition not met after {} attempts",
+                    "[{}] condition not met after {} attempts",
+                    description,
                     max_retry
                 )
             })
-        }));
+        })
+        .described(
+            StepKind::Wait,
+            [
+                ("description", description.to_string()),
+                ("interval_ms", interval.as_millis().to_string()),
+                ("max_retry", max_retry.to_string()),
+            ],
+        )
+    }
+
+    /// Reports a likely deadlock instead of waiting out `max_retry` when a
+    /// wait step sees no progress in the context for `window`
+    ///
+    /// Progress means any change to variables, errors or step logs. The
+    /// check is a debugging heuristic: a long step that touches none of
+    /// these also looks stalled. Waits inside [`Self::parallel_group`]
+    /// subflows share the detector, so the failing wait reports every
+    /// condition still being waited on as [`FlowError::Deadlock`].
+    #[cfg(feature = "deadlock-detector")]
+    pub fn with_deadlock_detection(self, window: Duration) -> Self {
+        self.deadlock.set_window(window);
         self
     }
 
+    /// Adds a named step that pauses until `signal_key` is raised via
+    /// [`FlowContext::signal`], failing with [`FlowError::StepTimeout`]
+    /// after `timeout`
+    ///
+    /// The context lock is released while waiting, so another task can
+    /// signal through the shared context. A signal raised before the step
+    /// starts waiting is kept and consumed immediately.
+    pub fn step_await_signal(
+        self,
+        name: &'static str,
+        signal_key: impl Into<String>,
+        timeout: Duration,
+    ) -> Self {
+        let signal_key = signal_key.into();
+        let metadata = [
+            ("signal", signal_key.clone()),
+            ("timeout_ms", timeout.as_millis().to_string()),
+        ];
+        self.push(Some(name), move |ctx| {
+            let signal_key = signal_key.clone();
+            Box::pin(async move {
+                let notifier = {
+                    let mut guard = ctx.lock().await;
+                    guard.start_step(name.to_string());
+                    guard.signals.notifier(&signal_key)
+                };
+                info!(signal = %signal_key, "[{}] awaiting signal", name);
+
+                let waited =
+                    tokio::time::timeout(timeout, notifier.notified()).await;
+
+                let mut guard = ctx.lock().await;
+                if waited.is_err() {
+                    guard.end_step_timeout(name);
+                    return Err(FlowError::StepTimeout {
+                        name: name.to_string(),
+                        after: timeout,
+                    }
+                    .into());
+                }
+                guard.end_step_success(name);
+                Ok(())
+            })
+        })
+        .described(StepKind::Wait, metadata)
+    }
+
+    /// Adds a step that publishes `event` with `payload` to the context's
+    /// event bus (see [`FlowContext::subscribe_events`])
+    ///
+    /// Emitting never fails; events are dropped when nobody subscribes.
+    pub fn step_emit(
+        self,
+        event: impl Into<String>,
+        payload: serde_json::Value,
+    ) -> Self {
+        let event = event.into();
+        let metadata = [("event", event.clone())];
+        self.push(None, move |ctx| {
+            let event = event.clone();
+            let payload = payload.clone();
+            Box::pin(async move {
+                let receivers = ctx.lock().await.emit_event(&event, payload);
+                info!(event = %event, receivers, "[step_emit] event emitted");
+                Ok(())
+            })
+        })
+        .described(StepKind::Emit, metadata)
+    }
+
+    /// Adds a loop step that threads an accumulator through iterations
+    ///
+    /// `f` runs while `cond` holds, receiving the current accumulator and
+    /// returning the next one. The final accumulator is stored as the typed
+    /// variable `name` (see [`FlowContext::get_typed`]) and, Debug
+    /// formatted, as the context variable `name`. The initial accumulator is
+    /// consumed by the first run, so the step cannot be retried.
+    pub fn step_fold<S, Cond, F, Fut>(
+        self,
+        name: &'static str,
+        init: S,
+        cond: Cond,
+        f: F,
+    ) -> Self
+    where
+        S: Debug + Serialize + Send + 'static,
+        Cond: Fn(&S, &FlowContext) -> bool + Send + Sync + 'static,
+        F: Fn(S, SharedContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<S>> + Send + 'static,
+    {
+        let init = Arc::new(Mutex::new(Some(init)));
+        let cond = Arc::new(cond);
+        let f = Arc::new(Mutex::new(f));
+        self.push(Some(name), move |ctx| {
+            let init = init.lock().unwrap_or_else(|e| e.into_inner()).take();
+            let cond = cond.clone();
+            let f = f.clone();
+            Box::pin(async move {
+                let Some(init) = init else {
+                    anyhow::bail!("[step_fold] step '{}' has already run", name)
+                };
+                {
+                    let mut guard = ctx.lock().await;
+                    guard.start_step(name.to_string());
+                }
+
+                let mut acc = init;
+                let mut iterations = 0usize;
+                loop {
+                    {
+                        let guard = ctx.lock().await;
+                        if !cond(&acc, &guard) {
+                            break;
+                        }
+                    }
+
+                    let next = {
+                        let f = f.lock().unwrap_or_else(|e| e.into_inner());
+                        f(acc, ctx.clone())
+                    };
+                    match next.await {
+                        Ok(next) => acc = next,
+                        Err(e) => {
+                            let mut guard = ctx.lock().await;
+                            guard.end_step_failed(name, &e.to_string());
+                            return Err(e);
+                        }
+                    }
+                    iterations += 1;
+                }
+
+                info!(step = name, iterations, "[step_fold] loop finished");
+                let mut guard = ctx.lock().await;
+                let typed = match serde_json::to_value(&acc) {
+                    Ok(typed) => typed,
+                    Err(e) => {
+                        let e = anyhow::anyhow!(
+                            "[step_fold] step '{}' accumulator is not serializable: {}",
+                            name,
+                            e
+                        );
+                        guard.end_step_failed(name, &e.to_string());
+                        return Err(e);
+                    }
+                };
+                guard.set_typed(name.to_string(), typed);
+                guard.set_variable(name.to_string(), format!("{acc:?}"));
+                guard.end_step_success(name);
+                Ok(())
+            })
+        })
+        .described(StepKind::While, [])
+    }
+
+    /// Adds a step that runs a subflow for every item of a list variable
+    ///
+    /// The context variable `items_key` must hold a JSON array. For each
+    /// item, `body` receives the index and the item (strings unquoted, other
+    /// values as JSON text) and returns the subflow to run on the shared
+    /// context. The item is also stored in `{name}_current_item`, and each
+    /// iteration is logged as step `{name}[index]`. The first failing
+    /// iteration aborts the loop; see [`Self::step_foreach_continue`].
+    pub fn step_foreach<G>(
+        self,
+        name: &'static str,
+        items_key: &'static str,
+        body: G,
+    ) -> Self
+    where
+        G: Fn(usize, &str) -> FlowBuilder + Send + Sync + 'static,
+    {
+        self.foreach(name, items_key, false, body)
+    }
+
+    /// Like [`Self::step_foreach`], but failing iterations are recorded in
+    /// the context errors and the loop moves on to the next item
+    pub fn step_foreach_continue<G>(
+        self,
+        name: &'static str,
+        items_key: &'static str,
+        body: G,
+    ) -> Self
+    where
+        G: Fn(usize, &str) -> FlowBuilder + Send + Sync + 'static,
+    {
+        self.foreach(name, items_key, true, body)
+    }
+
+    fn foreach<G>(
+        self,
+        name: &'static str,
+        items_key: &'static str,
+        continue_on_error: bool,
+        body: G,
+    ) -> Self
+    where
+        G: Fn(usize, &str) -> FlowBuilder + Send + Sync + 'static,
+    {
+        let body = Arc::new(body);
+        self.push(Some(name), move |ctx| {
+            let body = body.clone();
+            Box::pin(async move {
+                let items = {
+                    let guard = ctx.lock().await;
+                    let raw = guard.get_variable(items_key).ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "[step_foreach] variable '{}' not found",
+                            items_key
+                        )
+                    })?;
+                    match serde_json::from_str(raw) {
+                        Ok(serde_json::Value::Array(items)) => items,
+                        _ => anyhow::bail!(
+                            "[step_foreach] variable '{}' is not a JSON array",
+                            items_key
+                        ),
+                    }
+                };
+
+                let mut failed = 0usize;
+                for (index, item) in items.iter().enumerate() {
+                    let item = match item {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    let step_name = format!("{name}[{index}]");
+                    let span = {
+                        let mut guard = ctx.lock().await;
+                        guard.set_variable(
+                            format!("{name}_current_item"),
+                            item.clone(),
+                        );
+                        guard.start_step(step_name.clone());
+                        subflow_span(name, index, &guard.trace_id)
+                    };
+
+                    let result = FlowExecutor::new()
+                        .execute_steps(body(index, &item).into_steps(), ctx.clone())
+                        .instrument(span)
+                        .await;
+
+                    let mut guard = ctx.lock().await;
+                    match result {
+                        Ok(()) => guard.end_step_success(&step_name),
+                        Err(e) => {
+                            guard.end_step_failed(&step_name, &e.to_string());
+                            if !continue_on_error {
+                                return Err(e);
+                            }
+                            warn!(step = name, index, error = %e, "[step_foreach] iteration failed, continuing");
+                            failed += 1;
+                        }
+                    }
+                }
+
+                info!(
+                    step = name,
+                    iterations = items.len(),
+                    failed,
+                    "[step_foreach] loop finished"
+                );
+                Ok(())
+            })
+        })
+        .described(
+            StepKind::Subflow,
+            [
+                ("items", items_key.to_string()),
+                ("continue_on_error", continue_on_error.to_string()),
+            ],
+        )
+    }
+
+    /// Adds a subflow that runs in its own variable scope when `cond` holds
+    ///
+    /// The subflow generated by `gen` runs against [`FlowContext::scoped`],
+    /// a copy of the variables, so its writes do not leak into the parent
+    /// or into sibling subflows. Only keys marked with
+    /// [`FlowContext::export`] are written back once the subflow succeeds;
+    /// when another subflow changed an exported key in the meantime, the
+    /// last writer wins and a warning is logged. Step logs and errors of
+    /// the subflow are always appended to the parent context.
+    pub fn subflow_scoped<Cond, G>(self, cond: Cond, gen: G) -> Self
+    where
+        Cond: Fn(&FlowContext) -> bool + Send + Sync + 'static,
+        G: Fn() -> FlowBuilder + Send + Sync + 'static,
+    {
+        let cond = Arc::new(cond);
+        let gen = Arc::new(gen);
+        let condition: StepCondition = cond.clone();
+        self.push(None, move |ctx| {
+            let cond = cond.clone();
+            let gen = gen.clone();
+            Box::pin(async move {
+                let (child, base, span) = {
+                    let guard = ctx.lock().await;
+                    if !cond(&guard) {
+                        warn!(trace_id = %guard.trace_id, "[subflow_scoped] condition not met, skipping subflow");
+                        return Ok(());
+                    }
+                    let child = guard.scoped();
+                    let base = (
+                        child.variables.clone(),
+                        child.typed_variables.clone(),
+                    );
+                    let span = subflow_span("subflow_scoped", 0, &guard.trace_id);
+                    (Arc::new(tokio::sync::Mutex::new(child)), base, span)
+                };
+
+                let result = FlowExecutor::new()
+                    .execute_steps(gen().into_steps(), child.clone())
+                    .instrument(span)
+                    .await;
+
+                let child = std::mem::take(&mut *child.lock().await);
+                let mut guard = ctx.lock().await;
+                guard.ok &= child.ok;
+                guard.errors.extend(child.errors.iter().cloned());
+                guard.step_logs.extend(child.step_logs.iter().cloned());
+                if result.is_ok() {
+                    merge_scope(&mut guard, child, &base);
+                }
+                result
+            })
+        })
+        .described(StepKind::Subflow, [("scoped", true.to_string())])
+        .guarded_by(condition)
+    }
+
+    /// Adds a step that runs subflows concurrently on the shared context
+    ///
+    /// At most `config.max_concurrency` subflows run at once. The outcome is
+    /// stored in `{name}_success`, `{name}_failed` and `{name}_duration_ms`.
+    /// With `fail_fast` the remaining subflows are aborted and the first
+    /// error is returned; otherwise failures are recorded in the context
+    /// errors and the step succeeds.
+    pub fn parallel_group(
+        self,
+        name: &'static str,
+        config: ParallelConfig,
+        subflows: Vec<FlowBuilder>,
+    ) -> Self {
+        #[cfg(feature = "deadlock-detector")]
+        for flow in &subflows {
+            self.deadlock.adopt(&flow.deadlock);
+        }
+        let metadata = [
+            ("max_concurrency", config.max_concurrency.to_string()),
+            ("fail_fast", config.fail_fast.to_string()),
+        ];
+        let kind = StepKind::Parallel {
+            branches: subflows.iter().map(FlowBuilder::describe).collect(),
+        };
+        let subflows: Arc<Vec<Vec<_>>> = Arc::new(
+            subflows
+                .into_iter()
+                .map(|flow| {
+                    flow.steps.into_iter().map(|s| s.run).collect::<Vec<_>>()
+                })
+                .collect(),
+        );
+        let semaphore = Arc::new(tokio::sync::Semaphore::new(
+            config.max_concurrency.max(1),
+        ));
+        self.push(Some(name), move |ctx| {
+            let subflows = subflows.clone();
+            let semaphore = semaphore.clone();
+            let config = config.clone();
+            Box::pin(async move {
+                let trace_id = {
+                    let mut guard = ctx.lock().await;
+                    guard.start_step(name.to_string());
+                    guard.trace_id.clone()
+                };
+                let started = std::time::Instant::now();
+
+                let mut tasks = tokio::task::JoinSet::new();
+                for (index, steps) in subflows.iter().cloned().enumerate() {
+                    let ctx = ctx.clone();
+                    let semaphore = semaphore.clone();
+                    let span = subflow_span(name, index, &trace_id);
+                    let subflow = async move {
+                        let _permit = semaphore.acquire_owned().await?;
+                        let run = async {
+                            for step in &steps {
+                                step(ctx.clone()).await?;
+                            }
+                            Ok::<(), anyhow::Error>(())
+                        };
+                        let result = match config.timeout {
+                            Some(limit) => tokio::time::timeout(limit, run)
+                                .await
+                                .unwrap_or_else(|_| {
+                                    Err(anyhow::anyhow!(
+                                        "subflow timed out after {:?}",
+                                        limit
+                                    ))
+                                }),
+                            None => run.await,
+                        };
+                        result.map_err(|e| {
+                            anyhow::anyhow!("subflow {}: {}", index, e)
+                        })
+                    };
+                    tasks.spawn(subflow.instrument(span));
+                }
+
+                let mut succeeded = 0usize;
+                let mut failures = Vec::new();
+                while let Some(joined) = tasks.join_next().await {
+                    let result = joined.unwrap_or_else(|e| {
+                        Err(anyhow::anyhow!("subflow task failed: {}", e))
+                    });
+                    match result {
+                        Ok(()) => succeeded += 1,
+                        Err(e) => {
+                            failures.push(e);
+                            if config.fail_fast {
+                                tasks.abort_all();
+                                break;
+                            }
+                        }
+                    }
+                }
+
+                let mut guard = ctx.lock().await;
+                guard.set_variable(
+                    format!("{name}_success"),
+                    succeeded.to_string(),
+                );
+                guard.set_variable(
+                    format!("{name}_failed"),
+                    failures.len().to_string(),
+                );
+                guard.set_variable(
+                    format!("{name}_duration_ms"),
+                    started.elapsed().as_millis().to_string(),
+                );
+                info!(
+                    step = name,
+                    succeeded,
+                    failed = failures.len(),
+                    "[parallel_group] group finished"
+                );
+
+                if config.fail_fast {
+                    if let Some(error) = failures.into_iter().next() {
+                        guard.end_step_failed(name, &error.to_string());
+                        return Err(error);
+                    }
+                } else {
+                    for error in &failures {
+                        warn!(step = name, error = %error, "[parallel_group] subflow failed");
+                        let message =
+                            format!("[{}] {}: {}", guard.trace_id, name, error);
+                        guard.errors.push(message);
+                    }
+                }
+                guard.end_step_success(name);
+                Ok(())
+            })
+        })
+        .described(kind, metadata)
+    }
+
+    /// Previews which steps would run against `context` without running
+    /// any step body
+    ///
+    /// Conditions are evaluated against `context` as given; changes the
+    /// steps themselves would make to the context are not simulated.
+    pub fn plan(&self, context: &FlowContext) -> Vec<PlannedStep> {
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(index, step)| PlannedStep {
+                index,
+                name: step
+                    .name
+                    .map_or_else(|| format!("step_{index}"), str::to_string),
+                conditional: step.condition.is_some(),
+                will_run: step.condition.as_ref().is_none_or(|c| c(context)),
+            })
+            .collect()
+    }
+
+    /// Describes the structure of the flow without running it
+    ///
+    /// Unlike [`Self::plan`], no condition is evaluated; parallel groups
+    /// include the steps of each of their subflows.
+    pub fn describe(&self) -> Vec<StepDescriptor> {
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(index, step)| StepDescriptor {
+                index,
+                name: step
+                    .name
+                    .map_or_else(|| format!("step_{index}"), str::to_string),
+                kind: step.kind.clone(),
+                metadata: step.metadata.clone(),
+            })
+            .collect()
+    }
+
     /// Builds the flow
     pub fn build(self) -> Flow {
-        Flow::new(self.steps)
+        self.build_from(0)
+    }
+
+    /// Builds the flow without its first `skip` steps
+    fn build_from(self, skip: usize) -> Flow {
+        let retry_budget = self.retry_budget.clone();
+        let hooks: Arc<[Arc<dyn FlowHooks>]> = self.hooks.clone().into();
+        let transactional = self.transactional;
+        let compensations = self.compensations.clone();
+        let progress = self.progress.clone();
+        let names: Vec<String> = if self.diff_logging || progress.is_some() {
+            self.describe().into_iter().map(|step| step.name).collect()
+        } else {
+            Vec::new()
+        };
+        let diff_logging = self.diff_logging;
+        let mut steps = if hooks.is_empty() {
+            self.into_steps()
+        } else {
+            self.into_hooked_steps(&hooks)
+        };
+        if diff_logging {
+            steps = with_diff_logging(steps, names.clone());
+        }
+        if let Some(store) = &progress {
+            steps = with_progress(steps, names, store.clone());
+        }
+        steps.drain(..skip.min(steps.len()));
+        let flow = Flow::new(steps)
+            .with_retry_budget(retry_budget)
+            .with_hooks(hooks)
+            .with_transactional(transactional)
+            .with_compensations(compensations);
+        match progress {
+            Some(store) => flow.with_progress_store(store),
+            None => flow,
+        }
+    }
+
+    /// Wraps every step so that `hooks` see its start and end
+    fn into_hooked_steps(self, hooks: &Arc<[Arc<dyn FlowHooks>]>) -> Vec<Step> {
+        self.steps
+            .into_iter()
+            .enumerate()
+            .map(|(index, step)| {
+                let name = step
+                    .name
+                    .map_or_else(|| format!("step_{index}"), str::to_string);
+                let hooks = hooks.clone();
+                Box::new(move |ctx: SharedContext| -> StepFuture {
+                    Box::pin(async move {
+                        let will_run = {
+                            let guard = ctx.lock().await;
+                            for hook in hooks.iter() {
+                                hook.on_step_start(&name, &guard);
+                            }
+                            step.condition.as_ref().is_none_or(|c| c(&guard))
+                        };
+
+                        let started = Instant::now();
+                        let result = (step.run)(ctx).await;
+                        let status = match &result {
+                            Ok(()) if will_run => StepStatus::Success,
+                            Ok(()) => StepStatus::Skipped,
+                            Err(e) if is_step_timeout(e) => StepStatus::Timeout,
+                            Err(_) => StepStatus::Failed,
+                        };
+                        let duration = started.elapsed();
+                        for hook in hooks.iter() {
+                            hook.on_step_end(&name, &status, duration);
+                        }
+                        result
+                    })
+                }) as Step
+            })
+            .collect()
     }
 
     /// Access steps for runtime extensions
     pub fn into_steps(self) -> Vec<Step> {
         self.steps
+            .into_iter()
+            .map(|step| {
+                let run = step.run;
+                Box::new(move |ctx| run(ctx)) as Step
+            })
+            .collect()
+    }
+
+    /// Compiles the flow into an [`ExecutionPlan`] for the plan executor
+    ///
+    /// Each step becomes a native-action node (`step_<n>`, named after the
+    /// step when it has a name) in its own sequential phase, depending on
+    /// the previous step. Retry and timeout settings can then be set on the
+    /// nodes like on any other plan.
+    pub fn compile(self) -> ExecutionPlan {
+        let mut plan = ExecutionPlan::new(
+            "flowbuilder".to_string(),
+            env!("CARGO_PKG_VERSION").to_string(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+
+        let mut previous: Option<String> = None;
+        for (index, step) in self.steps.into_iter().enumerate() {
+            let id = format!("step_{index}");
+            let name = step.name.map_or_else(|| id.clone(), str::to_string);
+            let mut node = ExecutionNode::native(
+                id.clone(),
+                name,
+                NativeAction::from_arc(step.run),
+            );
+            if let Some(previous) = previous.replace(id) {
+                node = node.add_dependency(previous);
+            }
+
+            plan.add_phase(ExecutionPhase {
+                id: format!("phase_{index}"),
+                name: format!("执行阶段 {}", index + 1),
+                execution_mode: PhaseExecutionMode::Sequential,
+                nodes: vec![node],
+                condition: None,
+            });
+        }
+
+        plan
     }
 
     /// Builds and executes the flow immediately
@@ -145,6 +1621,38 @@ impl FlowBuilder {
         self.build().execute().await
     }
 
+    /// Executes the flow, resuming from the progress saved in the store
+    /// set by [`Self::with_progress_store`]
+    ///
+    /// Steps that already completed are skipped and the saved context is
+    /// restored; without saved progress the flow runs from the start. The
+    /// flow must have the same steps, in the same order, as the run that
+    /// saved the progress.
+    pub async fn resume_from_store(self) -> Result<FlowContext> {
+        let store = self.progress.clone().ok_or_else(|| {
+            anyhow::anyhow!("resume_from_store requires with_progress_store")
+        })?;
+        let Some(progress) = store.load()? else {
+            return self.execute().await;
+        };
+        if progress.completed_steps > self.steps.len() {
+            anyhow::bail!(
+                "saved progress has {} completed steps but the flow only has {}",
+                progress.completed_steps,
+                self.steps.len()
+            );
+        }
+        info!(
+            trace_id = %progress.context.trace_id,
+            step = %progress.last_step,
+            completed = progress.completed_steps,
+            "[progress] resuming flow"
+        );
+        self.build_from(progress.completed_steps)
+            .execute_with_context(progress.context)
+            .await
+    }
+
     /// Builds and executes the flow with a custom context
     pub async fn execute_with_context(
         self,
@@ -152,4 +1660,37 @@ impl FlowBuilder {
     ) -> Result<FlowContext> {
         self.build().execute_with_context(context).await
     }
+
+    /// Builds and executes the flow on a context the caller keeps a handle
+    /// to, which stays reachable when the flow fails
+    pub async fn execute_shared(self, context: SharedContext) -> Result<()> {
+        self.build().execute_shared(context).await
+    }
+
+    /// Builds and executes the flow until it completes or `cancel` fires
+    pub async fn execute_with_cancel(
+        self,
+        context: FlowContext,
+        cancel: CancellationToken,
+    ) -> Result<FlowContext> {
+        self.build().execute_with_cancel(context, cancel).await
+    }
+
+    /// Builds and executes the flow, giving the whole run `after` to finish
+    pub async fn execute_with_timeout(
+        self,
+        context: FlowContext,
+        after: Duration,
+    ) -> Result<FlowContext> {
+        self.build().execute_with_timeout(context, after).await
+    }
+
+    /// Builds and executes the flow, returning [`FlowMetrics`] alongside
+    /// the result
+    pub async fn execute_with_metrics(
+        self,
+        context: FlowContext,
+    ) -> (Result<FlowContext>, FlowMetrics) {
+        self.build().execute_with_metrics(context).await
+    }
 }
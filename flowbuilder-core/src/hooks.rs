@@ -0,0 +1,25 @@
+//! # FlowBuilder Core - 生命周期钩子
+//!
+//! 在每个步骤前后及流程结束时回调，用于上报指标等横切逻辑，
+//! 无需包装每个步骤闭包
+
+use flowbuilder_context::{FlowContext, StepStatus};
+use std::time::Duration;
+
+/// 构建器流程的生命周期钩子，方法均有空的默认实现
+pub trait FlowHooks: Send + Sync {
+    /// 步骤开始前（`name` 为步骤名，未命名步骤为 `step_<index>`）
+    fn on_step_start(&self, _name: &str, _ctx: &FlowContext) {}
+
+    /// 步骤结束后（条件不成立的步骤报告为 [`StepStatus::Skipped`]）
+    fn on_step_end(
+        &self,
+        _name: &str,
+        _status: &StepStatus,
+        _duration: Duration,
+    ) {
+    }
+
+    /// 流程结束后（无论成功或失败）
+    fn on_flow_complete(&self, _ctx: &FlowContext) {}
+}
@@ -0,0 +1,81 @@
+//! # FlowBuilder Core - jq 路径
+//!
+//! jq 风格的 JSON 路径程序，供 `${jq:...}` 与运行时动作共用
+
+use anyhow::Result;
+use serde_json::Value as JsonValue;
+
+/// 路径片段
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// 预编译的 jq 表达式：按管道拆分的路径阶段
+#[derive(Debug, Clone, PartialEq)]
+pub struct JqProgram {
+    stages: Vec<(String, Vec<Segment>)>,
+}
+
+impl JqProgram {
+    /// 编译表达式（支持 `a.b[0]`、`.[1].c` 与 `|` 管道）
+    pub fn compile(expr: &str) -> Result<Self> {
+        let mut stages = Vec::new();
+        for stage in expr.split('|').map(|s| s.trim()) {
+            if stage.is_empty() {
+                continue;
+            }
+            // 以 `.` 开头表示相对当前值，与全局路径的解析方式一致
+            let path = stage.strip_prefix('.').unwrap_or(stage);
+            let segments = Self::parse_path(path).ok_or_else(|| {
+                anyhow::anyhow!("jq expression failed at: {}", stage)
+            })?;
+            stages.push((stage.to_string(), segments));
+        }
+        Ok(Self { stages })
+    }
+
+    fn parse_path(path: &str) -> Option<Vec<Segment>> {
+        let mut segments = Vec::new();
+        for seg in path.trim().split('.') {
+            if seg.is_empty() {
+                continue;
+            }
+            let (base, mut rest) = match seg.find('[') {
+                Some(pos) => seg.split_at(pos),
+                None => (seg, ""),
+            };
+            if !base.is_empty() {
+                segments.push(Segment::Key(base.to_string()));
+            }
+            // 逐个消费索引，如 [0][1]
+            while !rest.is_empty() {
+                let end = rest.find(']')?;
+                if !rest.starts_with('[') {
+                    return None;
+                }
+                segments.push(Segment::Index(rest[1..end].parse().ok()?));
+                rest = &rest[end + 1..];
+            }
+        }
+        Some(segments)
+    }
+
+    /// 在根对象上执行，仅在最终结果处克隆
+    pub fn run(&self, root: &JsonValue) -> Result<JsonValue> {
+        let mut cur = root;
+        for (stage, segments) in &self.stages {
+            cur = segments
+                .iter()
+                .try_fold(cur, |value, segment| match segment {
+                    Segment::Key(key) => value.get(key.as_str()),
+                    Segment::Index(idx) => value.as_array()?.get(*idx),
+                })
+                .ok_or_else(|| {
+                    anyhow::anyhow!("jq expression failed at: {}", stage)
+                })?;
+        }
+        Ok(cur.clone())
+    }
+}
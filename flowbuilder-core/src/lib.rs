@@ -4,36 +4,62 @@
 
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+#[cfg(feature = "deadlock-detector")]
+mod deadlock;
+mod error;
 mod execution_plan;
 mod executor;
 mod flow;
 mod flow_builder;
+mod hooks;
+mod jq;
+mod metrics;
+mod orchestrator;
+mod progress;
 
 #[cfg(test)]
 mod tests;
 
 // 原有的公共接口
+pub use error::FlowError;
 pub use executor::FlowExecutor;
 pub use flow::Flow;
-pub use flow_builder::{FlowBuilder, Step, StepFuture};
+pub use flow_builder::{
+    FlowBuilder, ParallelConfig, PlannedStep, Step, StepDescriptor, StepFuture,
+    StepKind,
+};
+pub use hooks::FlowHooks;
+pub use jq::JqProgram;
+pub use metrics::FlowMetrics;
+pub use progress::{
+    FlowProgress, FsProgressStore, MemoryProgressStore, ProgressStore,
+};
+pub use tokio_util::sync::CancellationToken;
 
 // 新架构的公共接口
 pub use execution_plan::{
     ActionSpec, ConfigParser, ExecutionNode, ExecutionPhase, ExecutionPlan,
-    Executor, ExecutorStatus, ExpressionEvaluator, FlowPlanner, NodeType,
-    PhaseExecutionMode, PlanMetadata, RetryConfig, RetryStrategy,
-    TimeoutConfig,
+    Executor, ExecutorStatus, ExpressionEvaluator, FlowPlanner, LoopConfig,
+    NativeAction, NodeType, PhaseExecutionMode, PlanMetadata, RetryConfig,
+    RetryStrategy, TimeoutConfig,
+};
+pub use orchestrator::{
+    EnhancedFlowOrchestrator, ExecutionComplexity, OrchestratorConfig,
+    ResourcePacking,
 };
 
 /// 预导入模块
 pub mod prelude {
     // 原有接口
-    pub use crate::{Flow, FlowBuilder, FlowExecutor, Step, StepFuture};
-    pub use flowbuilder_context::{FlowContext, SharedContext};
+    pub use crate::{
+        Flow, FlowBuilder, FlowError, FlowExecutor, FlowHooks, Step, StepFuture,
+    };
+    pub use flowbuilder_context::{FlowContext, FlowEvent, SharedContext};
 
     // 新架构接口
     pub use crate::{
-        ConfigParser, ExecutionNode, ExecutionPhase, ExecutionPlan, Executor,
-        ExecutorStatus, ExpressionEvaluator, FlowPlanner, PhaseExecutionMode,
+        ConfigParser, EnhancedFlowOrchestrator, ExecutionNode, ExecutionPhase,
+        ExecutionPlan, Executor, ExecutorStatus, ExpressionEvaluator,
+        FlowPlanner, PhaseExecutionMode,
     };
 }
@@ -0,0 +1,75 @@
+//! # FlowBuilder Core - 流程指标
+//!
+//! 由上下文已记录的步骤日志汇总构建器流程的执行指标
+
+use flowbuilder_context::{FlowContext, StepStatus};
+use std::time::{Duration, SystemTime};
+
+/// 构建器流程的执行指标
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlowMetrics {
+    /// 记录了日志的步骤数
+    pub total_steps: usize,
+    /// 成功步骤数
+    pub succeeded: usize,
+    /// 失败步骤数
+    pub failed: usize,
+    /// 跳过步骤数
+    pub skipped: usize,
+    /// 超时步骤数
+    pub timed_out: usize,
+    /// 被取消的步骤数
+    pub cancelled: usize,
+    /// 总执行时间
+    pub total_duration: Duration,
+    /// 耗时最长的步骤
+    pub slowest_step: Option<(String, Duration)>,
+    /// 各步骤耗时（按开始顺序，未结束的步骤不计入）
+    pub step_durations: Vec<(String, Duration)>,
+    /// 流程剩余的重试预算（未设置预算时为 `None`）
+    pub retry_budget_remaining: Option<u32>,
+}
+
+impl FlowMetrics {
+    /// 从上下文的步骤日志汇总指标
+    ///
+    /// 总执行时间取最早开始到最晚结束之间的跨度
+    pub fn from_context(context: &FlowContext) -> Self {
+        let mut metrics = Self::default();
+        let mut first_start: Option<SystemTime> = None;
+        let mut last_end: Option<SystemTime> = None;
+
+        for log in &context.step_logs {
+            metrics.total_steps += 1;
+            match log.status {
+                StepStatus::Success => metrics.succeeded += 1,
+                StepStatus::Failed => metrics.failed += 1,
+                StepStatus::Skipped => metrics.skipped += 1,
+                StepStatus::Timeout => metrics.timed_out += 1,
+                StepStatus::Cancelled => metrics.cancelled += 1,
+                StepStatus::Running => {}
+            }
+
+            first_start = Some(
+                first_start.map_or(log.start_time, |t| t.min(log.start_time)),
+            );
+            last_end = last_end.max(log.end_time);
+            if let Some(duration) = log.duration() {
+                metrics
+                    .step_durations
+                    .push((log.step_name.clone(), duration));
+            }
+        }
+
+        if let (Some(start), Some(end)) = (first_start, last_end) {
+            metrics.total_duration =
+                end.duration_since(start).unwrap_or_default();
+        }
+        metrics.slowest_step = metrics
+            .step_durations
+            .iter()
+            .max_by_key(|(_, duration)| *duration)
+            .cloned();
+        metrics
+    }
+}
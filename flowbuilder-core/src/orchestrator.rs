@@ -0,0 +1,993 @@
+//! # FlowBuilder Core - 增强的流程编排器
+//!
+//! 基于执行计划的流程编排器，负责生成和优化执行计划；
+//! 纯数据转换，不依赖运行时
+
+use crate::execution_plan::find_dependency_cycle;
+use crate::{
+    ActionSpec, ExecutionNode, ExecutionPhase, ExecutionPlan, FlowPlanner,
+    NodeType, PhaseExecutionMode,
+};
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use tracing::info;
+
+/// 增强的流程编排器
+pub struct EnhancedFlowOrchestrator {
+    /// 优化配置
+    config: OrchestratorConfig,
+}
+
+/// 编排器配置
+#[derive(Debug, Clone)]
+pub struct OrchestratorConfig {
+    /// 是否启用并行优化
+    pub enable_parallel_optimization: bool,
+    /// 最大并行度
+    pub max_parallelism: usize,
+    /// 是否启用依赖分析
+    pub enable_dependency_analysis: bool,
+    /// 是否启用条件优化
+    pub enable_condition_optimization: bool,
+    /// 计划ID由计划内容哈希生成（相同输入得到相同ID），默认使用随机ID
+    pub deterministic_ids: bool,
+    /// 按资源权重拆分过宽的阶段，默认不启用
+    pub resource_packing: Option<ResourcePacking>,
+    /// 计划允许的最大节点数，超过时拒绝创建计划
+    pub max_nodes: usize,
+}
+
+impl OrchestratorConfig {
+    /// 默认的最大节点数
+    pub const DEFAULT_MAX_NODES: usize = 10_000;
+}
+
+/// 资源打包配置：阶段内节点的资源权重之和不超过上限
+#[derive(Debug, Clone, Default)]
+pub struct ResourcePacking {
+    /// 每个子阶段允许的最大总权重
+    pub capacity: u64,
+    /// 节点资源权重（按节点ID）
+    pub weights: HashMap<String, u64>,
+    /// 未配置权重的节点使用的默认权重
+    pub default_weight: u64,
+}
+
+impl ResourcePacking {
+    /// 创建指定上限的打包配置
+    pub fn new(capacity: u64) -> Self {
+        Self {
+            capacity,
+            ..Self::default()
+        }
+    }
+
+    /// 设置节点的资源权重
+    pub fn with_weight(
+        mut self,
+        node_id: impl Into<String>,
+        weight: u64,
+    ) -> Self {
+        self.weights.insert(node_id.into(), weight);
+        self
+    }
+
+    /// 设置默认权重
+    pub fn with_default_weight(mut self, weight: u64) -> Self {
+        self.default_weight = weight;
+        self
+    }
+
+    /// 获取节点的资源权重
+    pub fn weight_of(&self, node_id: &str) -> u64 {
+        self.weights
+            .get(node_id)
+            .copied()
+            .unwrap_or(self.default_weight)
+    }
+
+    /// 将同一层的节点装箱（首次适应递减），每个箱子的总权重不超过上限；
+    /// 超过上限的单个节点独占一个箱子
+    fn pack(&self, layer: Vec<ExecutionNode>) -> Vec<Vec<ExecutionNode>> {
+        let mut order: Vec<(usize, u64)> = layer
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (i, self.weight_of(&node.id)))
+            .collect();
+        order.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let mut bins: Vec<(u64, Vec<usize>)> = Vec::new();
+        for (index, weight) in order {
+            match bins
+                .iter_mut()
+                .find(|(load, _)| load + weight <= self.capacity)
+            {
+                Some((load, members)) => {
+                    *load += weight;
+                    members.push(index);
+                }
+                None => bins.push((weight, vec![index])),
+            }
+        }
+
+        // 子阶段内保持声明顺序
+        let mut slots: Vec<Option<ExecutionNode>> =
+            layer.into_iter().map(Some).collect();
+        bins.into_iter()
+            .map(|(_, mut members)| {
+                members.sort_unstable();
+                members
+                    .into_iter()
+                    .filter_map(|i| slots[i].take())
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl Default for OrchestratorConfig {
+    fn default() -> Self {
+        Self {
+            enable_parallel_optimization: true,
+            max_parallelism: 10,
+            enable_dependency_analysis: true,
+            enable_condition_optimization: true,
+            deterministic_ids: false,
+            resource_packing: None,
+            max_nodes: Self::DEFAULT_MAX_NODES,
+        }
+    }
+}
+
+impl Default for EnhancedFlowOrchestrator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EnhancedFlowOrchestrator {
+    /// 创建新的编排器
+    pub fn new() -> Self {
+        Self {
+            config: OrchestratorConfig::default(),
+        }
+    }
+
+    /// 使用配置创建编排器
+    pub fn with_config(config: OrchestratorConfig) -> Self {
+        Self { config }
+    }
+
+    /// 当前配置
+    pub fn config(&self) -> &OrchestratorConfig {
+        &self.config
+    }
+
+    /// 从节点列表创建执行计划
+    #[tracing::instrument(level = "info", skip(self, env_vars, flow_vars), fields(workflow = %workflow_name, version = %workflow_version, nodes = nodes.len()))]
+    pub fn create_execution_plan(
+        &self,
+        nodes: Vec<ExecutionNode>,
+        env_vars: HashMap<String, serde_yaml::Value>,
+        flow_vars: HashMap<String, serde_yaml::Value>,
+        workflow_name: String,
+        workflow_version: String,
+    ) -> Result<ExecutionPlan> {
+        if nodes.len() > self.config.max_nodes {
+            return Err(anyhow::anyhow!(
+                "节点数 {} 超过上限 {}（max_nodes）",
+                nodes.len(),
+                self.config.max_nodes
+            ));
+        }
+
+        let mut plan = ExecutionPlan::new(
+            workflow_name,
+            workflow_version,
+            env_vars,
+            flow_vars,
+        );
+
+        // 1. 构建依赖图
+        let dependency_graph = self.build_dependency_graph(&nodes)?;
+
+        // 2. 执行拓扑排序
+        let sorted_layers = self.topological_sort(&nodes, &dependency_graph)?;
+
+        // 3. 生成执行阶段
+        let phases = self.create_execution_phases(sorted_layers)?;
+
+        // 4. 添加阶段到计划
+        for phase in phases {
+            plan.add_phase(phase);
+        }
+
+        // 5. 优化计划
+        if self.config.enable_parallel_optimization {
+            self.optimize_for_parallelism(&mut plan)?;
+        }
+
+        // 6. 验证计划
+        plan.validate()
+            .map_err(|e| anyhow::anyhow!("执行计划验证失败: {}", e))?;
+
+        if self.config.deterministic_ids {
+            plan.metadata.plan_id = content_plan_id(&plan);
+        }
+
+        Ok(plan)
+    }
+
+    /// 构建依赖图
+    #[tracing::instrument(level = "debug", skip(self, nodes), fields(nodes = nodes.len()))]
+    fn build_dependency_graph(
+        &self,
+        nodes: &[ExecutionNode],
+    ) -> Result<HashMap<String, Vec<String>>> {
+        let mut graph = HashMap::new();
+
+        for node in nodes {
+            graph.insert(node.id.clone(), node.dependencies.clone());
+        }
+
+        // 验证依赖的有效性
+        for (node_id, deps) in &graph {
+            for dep in deps {
+                if !graph.contains_key(dep) {
+                    return Err(anyhow::anyhow!(
+                        "节点 {} 依赖的节点 {} 不存在",
+                        node_id,
+                        dep
+                    ));
+                }
+            }
+        }
+
+        Ok(graph)
+    }
+
+    /// 拓扑排序，生成执行层次
+    #[tracing::instrument(level = "debug", skip(self, nodes, _graph), fields(nodes = nodes.len()))]
+    fn topological_sort(
+        &self,
+        nodes: &[ExecutionNode],
+        _graph: &HashMap<String, Vec<String>>,
+    ) -> Result<Vec<Vec<ExecutionNode>>> {
+        let mut layers = Vec::new();
+        let mut remaining_nodes: HashMap<String, ExecutionNode> =
+            nodes.iter().map(|n| (n.id.clone(), n.clone())).collect();
+        let mut in_degree = HashMap::new();
+
+        // 计算入度
+        for node in nodes {
+            in_degree.insert(node.id.clone(), node.dependencies.len());
+        }
+
+        // 显式阶段按首次出现的顺序编号；节点需等之前阶段的节点全部就位
+        let mut phase_ranks: HashMap<&str, usize> = HashMap::new();
+        for phase in nodes.iter().filter_map(|n| n.phase.as_deref()) {
+            let next = phase_ranks.len();
+            phase_ranks.entry(phase).or_insert(next);
+        }
+        let rank_of = |node: &ExecutionNode| {
+            node.phase
+                .as_deref()
+                .and_then(|p| phase_ranks.get(p))
+                .copied()
+        };
+        let mut pending_per_rank = vec![0usize; phase_ranks.len()];
+        for rank in nodes.iter().filter_map(rank_of) {
+            pending_per_rank[rank] += 1;
+        }
+
+        // 分层处理
+        while !remaining_nodes.is_empty() {
+            let mut current_layer = Vec::new();
+
+            // 找出当前层可以执行的节点（入度为0），按声明顺序排列
+            let barrier_open = |node: &ExecutionNode| {
+                rank_of(node).is_none_or(|rank| {
+                    pending_per_rank[..rank].iter().all(|&n| n == 0)
+                })
+            };
+            let ready_nodes: Vec<String> = nodes
+                .iter()
+                .filter(|n| in_degree.get(&n.id) == Some(&0))
+                .filter(|n| barrier_open(n))
+                .map(|n| n.id.clone())
+                .collect();
+
+            if ready_nodes.is_empty() {
+                // 依赖已满足却仍无法执行，说明显式阶段与数据依赖矛盾
+                if let Some(blocked) = nodes.iter().find(|n| {
+                    in_degree.get(&n.id) == Some(&0) && !barrier_open(n)
+                }) {
+                    return Err(anyhow::anyhow!(
+                        "节点 {} 的阶段 {} 与数据依赖冲突：之前阶段的节点依赖了后续阶段的节点",
+                        blocked.id,
+                        blocked.phase.as_deref().unwrap_or_default()
+                    ));
+                }
+                let cycle = find_dependency_cycle(
+                    nodes
+                        .iter()
+                        .filter(|n| remaining_nodes.contains_key(&n.id)),
+                )
+                .unwrap_or_default();
+                return Err(anyhow::anyhow!(
+                    "检测到循环依赖: {}",
+                    cycle.join(" -> ")
+                ));
+            }
+
+            // 添加到当前层
+            for node_id in ready_nodes {
+                if let Some(node) = remaining_nodes.remove(&node_id) {
+                    if let Some(rank) = rank_of(&node) {
+                        pending_per_rank[rank] -= 1;
+                    }
+                    current_layer.push(node);
+                    in_degree.remove(&node_id);
+                }
+            }
+
+            // 更新剩余节点的入度
+            for node in &current_layer {
+                for other_node in remaining_nodes.values() {
+                    if other_node.dependencies.contains(&node.id) {
+                        if let Some(degree) = in_degree.get_mut(&other_node.id)
+                        {
+                            *degree -= 1;
+                        }
+                    }
+                }
+            }
+
+            layers.push(current_layer);
+        }
+
+        Ok(layers)
+    }
+
+    /// 创建执行阶段
+    #[tracing::instrument(level = "debug", skip(self, layers), fields(layers = layers.len()))]
+    fn create_execution_phases(
+        &self,
+        layers: Vec<Vec<ExecutionNode>>,
+    ) -> Result<Vec<ExecutionPhase>> {
+        let mut phases = Vec::new();
+
+        for (index, layer) in layers.into_iter().enumerate() {
+            let id = format!("phase_{index}");
+            let name = format!("执行阶段 {}", index + 1);
+
+            // 同层节点互不依赖，可按资源权重安全拆分为连续的子阶段
+            let parts = match &self.config.resource_packing {
+                Some(packing) if layer.len() > 1 => packing.pack(layer),
+                _ => vec![layer],
+            };
+            if parts.len() > 1 {
+                info!(phase = %name, sub_phases = parts.len(), "阶段按资源权重拆分为子阶段");
+                for (i, part) in parts.into_iter().enumerate() {
+                    phases.push(self.layer_phase(
+                        format!("{id}_sub_{i}"),
+                        format!("{name} - 子阶段 {}", i + 1),
+                        part,
+                    ));
+                }
+            } else if let Some(layer) = parts.into_iter().next() {
+                phases.push(self.layer_phase(id, name, layer));
+            }
+        }
+
+        Ok(phases)
+    }
+
+    /// 由一层节点创建执行阶段
+    fn layer_phase(
+        &self,
+        id: String,
+        name: String,
+        layer: Vec<ExecutionNode>,
+    ) -> ExecutionPhase {
+        let execution_mode = if layer.len() == 1 {
+            PhaseExecutionMode::Sequential
+        } else if layer.len() <= self.config.max_parallelism {
+            PhaseExecutionMode::Parallel
+        } else {
+            // 如果节点数超过最大并行度，分批处理
+            PhaseExecutionMode::Parallel
+        };
+
+        ExecutionPhase {
+            id,
+            name,
+            execution_mode,
+            nodes: layer,
+            condition: None,
+        }
+    }
+
+    /// 优化并行执行
+    #[tracing::instrument(level = "debug", skip(self, plan), fields(phases = plan.phases.len()))]
+    fn optimize_for_parallelism(&self, plan: &mut ExecutionPlan) -> Result<()> {
+        if !self.config.enable_parallel_optimization {
+            return Ok(());
+        }
+
+        // 分析每个阶段的并行可能性
+        for phase in &mut plan.phases {
+            if phase.nodes.len() > self.config.max_parallelism {
+                // 如果节点数量超过最大并行度，需要分批
+                let chunks: Vec<Vec<ExecutionNode>> = phase
+                    .nodes
+                    .chunks(self.config.max_parallelism)
+                    .map(|chunk| chunk.to_vec())
+                    .collect();
+
+                // 创建子阶段
+                let mut sub_phases = Vec::new();
+                for (i, chunk) in chunks.into_iter().enumerate() {
+                    let sub_phase = ExecutionPhase {
+                        id: format!("{}_sub_{}", phase.id, i),
+                        name: format!("{} - 子阶段 {}", phase.name, i + 1),
+                        execution_mode: PhaseExecutionMode::Parallel,
+                        nodes: chunk,
+                        condition: phase.condition.clone(),
+                    };
+                    sub_phases.push(sub_phase);
+                }
+
+                // 注意：这里需要重新构建计划结构来支持子阶段
+                // 为了简化，这里只是记录优化信息
+                info!(phase = %phase.name, sub_phases = sub_phases.len(), "阶段被优化为子阶段");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 分析执行计划的复杂度
+    #[tracing::instrument(level = "debug", skip(self, plan), fields(phases = plan.phases.len(), nodes = plan.metadata.total_nodes))]
+    pub fn analyze_complexity(
+        &self,
+        plan: &ExecutionPlan,
+    ) -> ExecutionComplexity {
+        let mut total_nodes = 0;
+        let mut max_parallel_nodes = 0;
+        let mut total_dependencies = 0;
+        let mut conditional_nodes = 0;
+        let mut phase_widths = Vec::with_capacity(plan.phases.len());
+
+        for phase in &plan.phases {
+            total_nodes += phase.nodes.len();
+            phase_widths.push(phase.nodes.len());
+
+            if matches!(phase.execution_mode, PhaseExecutionMode::Parallel) {
+                max_parallel_nodes = max_parallel_nodes.max(phase.nodes.len());
+            }
+
+            for node in &phase.nodes {
+                total_dependencies += node.dependencies.len();
+                if node.condition.is_some()
+                    || matches!(node.node_type, NodeType::Condition)
+                {
+                    conditional_nodes += 1;
+                }
+            }
+        }
+
+        // 阶段按依赖顺序排列，依赖总在之前的阶段（或同阶段之前）出现
+        let mut chains: HashMap<&str, usize> = HashMap::new();
+        let mut dependents: HashMap<&str, usize> = HashMap::new();
+        for node in plan.phases.iter().flat_map(|p| &p.nodes) {
+            let length = node
+                .dependencies
+                .iter()
+                .filter_map(|dep| chains.get(dep.as_str()))
+                .max()
+                .copied()
+                .unwrap_or(0);
+            chains.insert(&node.id, length + 1);
+            for dep in &node.dependencies {
+                *dependents.entry(dep.as_str()).or_default() += 1;
+            }
+        }
+        let critical_path_length = chains.values().max().copied().unwrap_or(0);
+        let branching_factor = if dependents.is_empty() {
+            0.0
+        } else {
+            dependents.values().sum::<usize>() as f64 / dependents.len() as f64
+        };
+
+        ExecutionComplexity {
+            total_nodes,
+            total_phases: plan.phases.len(),
+            max_parallel_nodes,
+            total_dependencies,
+            conditional_nodes,
+            complexity_score: self.calculate_complexity_score(
+                total_nodes,
+                total_dependencies,
+                conditional_nodes,
+                max_parallel_nodes,
+            ),
+            critical_path_length,
+            critical_path_duration: plan.critical_path_duration(),
+            branching_factor,
+            phase_widths,
+        }
+    }
+
+    /// 计算复杂度分数
+    fn calculate_complexity_score(
+        &self,
+        total_nodes: usize,
+        total_dependencies: usize,
+        conditional_nodes: usize,
+        max_parallel_nodes: usize,
+    ) -> f64 {
+        let base_score = total_nodes as f64;
+        let dependency_penalty = (total_dependencies as f64) * 0.5;
+        let condition_penalty = (conditional_nodes as f64) * 0.3;
+        let parallel_bonus = (max_parallel_nodes as f64) * 0.2;
+
+        base_score + dependency_penalty + condition_penalty - parallel_bonus
+    }
+}
+
+/// 执行复杂度分析结果
+#[derive(Debug, Clone)]
+pub struct ExecutionComplexity {
+    /// 总节点数
+    pub total_nodes: usize,
+    /// 总阶段数
+    pub total_phases: usize,
+    /// 最大并行节点数
+    pub max_parallel_nodes: usize,
+    /// 总依赖关系数
+    pub total_dependencies: usize,
+    /// 条件节点数
+    pub conditional_nodes: usize,
+    /// 复杂度分数
+    pub complexity_score: f64,
+    /// 关键路径长度（最长依赖链上的节点数）
+    pub critical_path_length: usize,
+    /// 关键路径的估计耗时（估计耗时最长的依赖链）
+    pub critical_path_duration: Duration,
+    /// 分支因子（有下游的节点平均拥有的下游数）
+    pub branching_factor: f64,
+    /// 各阶段宽度（节点数），按阶段顺序
+    pub phase_widths: Vec<usize>,
+}
+
+impl FlowPlanner for EnhancedFlowOrchestrator {
+    type Input = (
+        Vec<ExecutionNode>,
+        HashMap<String, serde_yaml::Value>,
+        HashMap<String, serde_yaml::Value>,
+        String,
+        String,
+    );
+    type Output = ExecutionPlan;
+    type Error = anyhow::Error;
+
+    fn create_execution_plan(
+        &self,
+        input: Self::Input,
+    ) -> Result<Self::Output, Self::Error> {
+        let (nodes, env_vars, flow_vars, workflow_name, workflow_version) =
+            input;
+        self.create_execution_plan(
+            nodes,
+            env_vars,
+            flow_vars,
+            workflow_name,
+            workflow_version,
+        )
+    }
+
+    fn optimize_plan(
+        &self,
+        mut plan: Self::Output,
+    ) -> Result<Self::Output, Self::Error> {
+        self.optimize_for_parallelism(&mut plan)?;
+        Ok(plan)
+    }
+}
+
+/// 由计划内容生成稳定的计划ID（UUID 格式）
+///
+/// 哈希覆盖工作流名称与版本、变量以及各阶段的节点定义；映射按键排序，
+/// 保证与 `HashMap` 的迭代顺序无关
+fn content_plan_id(plan: &ExecutionPlan) -> String {
+    let mut hasher = Sha256::new();
+    let mut feed = |part: &str| {
+        hasher.update(part.len().to_le_bytes());
+        hasher.update(part.as_bytes());
+    };
+
+    feed(&plan.metadata.workflow_name);
+    feed(&plan.metadata.workflow_version);
+    feed(&canonical_values(&plan.env_vars));
+    feed(&canonical_values(&plan.flow_vars));
+    for (index, phase) in plan.phases.iter().enumerate() {
+        feed(&format!(
+            "{index}:{:?}:{:?}",
+            phase.execution_mode, phase.condition
+        ));
+        for node in &phase.nodes {
+            feed(&node.id);
+            feed(&node.name);
+            feed(&format!(
+                "{:?}|{:?}|{:?}|{}|{:?}|{:?}|{:?}",
+                node.node_type,
+                node.dependencies,
+                node.condition,
+                node.priority,
+                node.retry_config,
+                node.tags,
+                node.loop_config,
+            ));
+            feed(&canonical_action(&node.action_spec));
+            if let Some(timeout) = &node.timeout_config {
+                feed(&format!("{}|{:?}", timeout.duration, timeout.on_timeout));
+                if let Some(handler) = &timeout.on_timeout_action {
+                    feed(&canonical_action(handler));
+                }
+            }
+            let edges: BTreeMap<_, _> = node.edge_conditions.iter().collect();
+            feed(&format!("{edges:?}"));
+        }
+    }
+
+    let digest = hasher.finalize();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Uuid::from_bytes(bytes).to_string()
+}
+
+/// 动作规格的规范化表示
+fn canonical_action(spec: &ActionSpec) -> String {
+    format!(
+        "{}|{}|{}",
+        spec.action_type,
+        canonical_values(&spec.parameters),
+        canonical_values(&spec.outputs)
+    )
+}
+
+/// 按键排序后的变量表示
+fn canonical_values(values: &HashMap<String, serde_yaml::Value>) -> String {
+    let sorted: BTreeMap<_, _> = values
+        .iter()
+        .map(|(k, v)| (k, serde_yaml::to_string(v).unwrap_or_default()))
+        .collect();
+    format!("{sorted:?}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orchestrator_creation() {
+        let orchestrator = EnhancedFlowOrchestrator::new();
+        assert!(orchestrator.config.enable_parallel_optimization);
+    }
+
+    #[test]
+    fn test_dependency_graph_build() {
+        let orchestrator = EnhancedFlowOrchestrator::new();
+
+        let node1 = ExecutionNode::new(
+            "node1".to_string(),
+            "Node 1".to_string(),
+            ActionSpec {
+                action_type: "test".to_string(),
+                parameters: HashMap::new(),
+                outputs: HashMap::new(),
+            },
+        );
+
+        let node2 = ExecutionNode::new(
+            "node2".to_string(),
+            "Node 2".to_string(),
+            ActionSpec {
+                action_type: "test".to_string(),
+                parameters: HashMap::new(),
+                outputs: HashMap::new(),
+            },
+        )
+        .add_dependency("node1".to_string());
+
+        let nodes = vec![node1, node2];
+        let graph = orchestrator.build_dependency_graph(&nodes).unwrap();
+
+        assert_eq!(graph.len(), 2);
+        assert_eq!(graph.get("node1").unwrap().len(), 0);
+        assert_eq!(graph.get("node2").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_complexity_structure_metrics() {
+        let node = |id: &str, deps: &[&str]| {
+            deps.iter().fold(
+                ExecutionNode::new(
+                    id.to_string(),
+                    id.to_string(),
+                    ActionSpec {
+                        action_type: "builtin".to_string(),
+                        parameters: HashMap::new(),
+                        outputs: HashMap::new(),
+                    },
+                ),
+                |node, dep| node.add_dependency(dep.to_string()),
+            )
+        };
+        let nodes = vec![
+            node("a", &[]),
+            node("b", &["a"]),
+            node("c", &["a"]),
+            node("d", &["b", "c"]),
+            node("e", &[]),
+        ];
+        let orchestrator = EnhancedFlowOrchestrator::new();
+        let plan = orchestrator
+            .create_execution_plan(
+                nodes,
+                HashMap::new(),
+                HashMap::new(),
+                "wf".to_string(),
+                "1.0".to_string(),
+            )
+            .unwrap();
+
+        let complexity = orchestrator.analyze_complexity(&plan);
+        assert_eq!(complexity.critical_path_length, 3);
+        assert_eq!(
+            complexity.critical_path_duration,
+            ExecutionNode::DEFAULT_ESTIMATE * 3
+        );
+        assert!((complexity.branching_factor - 4.0 / 3.0).abs() < 1e-9);
+        assert_eq!(complexity.phase_widths, vec![2, 2, 1]);
+    }
+
+    #[test]
+    fn test_critical_path_follows_longest_chain() {
+        let node = |id: &str, deps: &[&str], iterations: Option<u32>| {
+            let node = deps.iter().fold(
+                ExecutionNode::new(
+                    id.to_string(),
+                    id.to_string(),
+                    ActionSpec {
+                        action_type: "builtin".to_string(),
+                        parameters: HashMap::new(),
+                        outputs: HashMap::new(),
+                    },
+                ),
+                |node, dep| node.add_dependency(dep.to_string()),
+            );
+            match iterations {
+                Some(max_iterations) => node.with_loop(crate::LoopConfig {
+                    condition: "true".to_string(),
+                    max_iterations,
+                }),
+                None => node,
+            }
+        };
+        let nodes = vec![
+            node("fetch", &[], None),
+            node("quick", &["fetch"], None),
+            node("crawl", &["fetch"], Some(5)),
+            node("report", &["quick", "crawl"], None),
+            node("notify", &[], None),
+        ];
+        let plan = EnhancedFlowOrchestrator::new()
+            .create_execution_plan(
+                nodes,
+                HashMap::new(),
+                HashMap::new(),
+                "wf".to_string(),
+                "1.0".to_string(),
+            )
+            .unwrap();
+
+        assert_eq!(plan.critical_path(), vec!["fetch", "crawl", "report"]);
+        assert_eq!(
+            plan.critical_path_duration(),
+            ExecutionNode::DEFAULT_ESTIMATE * 7
+        );
+        assert!(plan
+            .to_pretty_string()
+            .contains("关键路径: fetch -> crawl -> report"));
+    }
+
+    #[test]
+    fn test_resource_packing_splits_wide_phases() {
+        let node = |id: &str, deps: &[&str]| {
+            deps.iter().fold(
+                ExecutionNode::new(
+                    id.to_string(),
+                    id.to_string(),
+                    ActionSpec {
+                        action_type: "builtin".to_string(),
+                        parameters: HashMap::new(),
+                        outputs: HashMap::new(),
+                    },
+                ),
+                |node, dep| node.add_dependency(dep.to_string()),
+            )
+        };
+        let nodes = vec![
+            node("load", &[]),
+            node("a", &["load"]),
+            node("b", &["load"]),
+            node("c", &["load"]),
+            node("d", &["load"]),
+            node("merge", &["a", "b", "c", "d"]),
+        ];
+        let packing = ResourcePacking::new(8)
+            .with_default_weight(2)
+            .with_weight("a", 6)
+            .with_weight("c", 5);
+        let plan = EnhancedFlowOrchestrator::with_config(OrchestratorConfig {
+            resource_packing: Some(packing.clone()),
+            ..OrchestratorConfig::default()
+        })
+        .create_execution_plan(
+            nodes,
+            HashMap::new(),
+            HashMap::new(),
+            "wf".to_string(),
+            "1.0".to_string(),
+        )
+        .unwrap();
+
+        let layout: Vec<(String, Vec<String>)> = plan
+            .phases
+            .iter()
+            .map(|p| {
+                (p.id.clone(), p.nodes.iter().map(|n| n.id.clone()).collect())
+            })
+            .collect();
+        let phase = |id: &str, nodes: &[&str]| {
+            (
+                id.to_string(),
+                nodes.iter().map(|n| n.to_string()).collect::<Vec<_>>(),
+            )
+        };
+        assert_eq!(
+            layout,
+            vec![
+                phase("phase_0", &["load"]),
+                phase("phase_1_sub_0", &["a", "b"]),
+                phase("phase_1_sub_1", &["c", "d"]),
+                phase("phase_2", &["merge"]),
+            ]
+        );
+        for phase in &plan.phases {
+            let load: u64 =
+                phase.nodes.iter().map(|n| packing.weight_of(&n.id)).sum();
+            assert!(load <= 8);
+        }
+    }
+
+    #[test]
+    fn test_max_nodes_guard() {
+        let nodes: Vec<_> = (0..5)
+            .map(|i| {
+                ExecutionNode::new(
+                    format!("n{i}"),
+                    format!("n{i}"),
+                    ActionSpec {
+                        action_type: "builtin".to_string(),
+                        parameters: HashMap::new(),
+                        outputs: HashMap::new(),
+                    },
+                )
+            })
+            .collect();
+        let orchestrator = |max_nodes| {
+            EnhancedFlowOrchestrator::with_config(OrchestratorConfig {
+                max_nodes,
+                ..OrchestratorConfig::default()
+            })
+        };
+        let plan = |max_nodes| {
+            orchestrator(max_nodes).create_execution_plan(
+                nodes.clone(),
+                HashMap::new(),
+                HashMap::new(),
+                "wf".to_string(),
+                "1.0".to_string(),
+            )
+        };
+
+        assert!(plan(5).is_ok());
+        let error = plan(4).unwrap_err().to_string();
+        assert!(error.contains("节点数 5 超过上限 4"), "{error}");
+    }
+
+    #[test]
+    fn test_deterministic_plan_ids() {
+        let nodes = || {
+            let mut nodes = Vec::new();
+            for id in ["fetch", "parse", "lint", "report"] {
+                let mut parameters = HashMap::new();
+                for key in ["a", "b", "c", "d"] {
+                    parameters.insert(key.to_string(), id.into());
+                }
+                let mut node = ExecutionNode::new(
+                    id.to_string(),
+                    id.to_string(),
+                    ActionSpec {
+                        action_type: "builtin".to_string(),
+                        parameters,
+                        outputs: HashMap::new(),
+                    },
+                );
+                if id != "fetch" {
+                    node = node.add_dependency("fetch".to_string());
+                }
+                nodes.push(node);
+            }
+            nodes
+        };
+        let plan = |orchestrator: &EnhancedFlowOrchestrator| {
+            orchestrator
+                .create_execution_plan(
+                    nodes(),
+                    HashMap::new(),
+                    HashMap::new(),
+                    "wf".to_string(),
+                    "1.0".to_string(),
+                )
+                .unwrap()
+        };
+        let order = |plan: &ExecutionPlan| -> Vec<Vec<String>> {
+            plan.phases
+                .iter()
+                .map(|p| p.nodes.iter().map(|n| n.id.clone()).collect())
+                .collect()
+        };
+
+        let deterministic =
+            EnhancedFlowOrchestrator::with_config(OrchestratorConfig {
+                deterministic_ids: true,
+                ..OrchestratorConfig::default()
+            });
+        let first = plan(&deterministic);
+        let second = plan(&deterministic);
+        assert_eq!(first.metadata.plan_id, second.metadata.plan_id);
+        // 同层节点保持声明顺序
+        assert_eq!(
+            order(&first),
+            vec![vec!["fetch"], vec!["parse", "lint", "report"]]
+        );
+        assert_eq!(order(&first), order(&second));
+
+        // 内容变化时ID随之变化
+        let mut changed = nodes();
+        changed[1].priority = 9;
+        let other = deterministic
+            .create_execution_plan(
+                changed,
+                HashMap::new(),
+                HashMap::new(),
+                "wf".to_string(),
+                "1.0".to_string(),
+            )
+            .unwrap();
+        assert_ne!(first.metadata.plan_id, other.metadata.plan_id);
+
+        // 默认使用随机ID
+        let random = EnhancedFlowOrchestrator::new();
+        assert_ne!(
+            plan(&random).metadata.plan_id,
+            plan(&random).metadata.plan_id
+        );
+    }
+}
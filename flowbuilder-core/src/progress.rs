@@ -0,0 +1,113 @@
+//! # FlowBuilder Core - 进度持久化
+//!
+//! 构建器流程每完成一个步骤即保存进度（已完成的步骤数与上下文），
+//! 进程崩溃后可从最后保存的进度继续执行
+
+use anyhow::{Context, Result};
+use flowbuilder_context::FlowContext;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// 已保存的流程进度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowProgress {
+    /// 已完成的顶层步骤数，恢复时跳过这些步骤
+    pub completed_steps: usize,
+    /// 最后完成的步骤名（未命名步骤为 `step_<index>`）
+    pub last_step: String,
+    /// 该步骤完成时的上下文
+    pub context: FlowContext,
+}
+
+/// 流程进度存储，保存单个流程的最新进度
+pub trait ProgressStore: fmt::Debug + Send + Sync {
+    /// 保存进度（覆盖之前的进度）
+    fn save(&self, progress: &FlowProgress) -> Result<()>;
+    /// 读取进度，没有保存过时返回 `None`
+    fn load(&self) -> Result<Option<FlowProgress>>;
+    /// 清除进度（流程成功完成后调用）
+    fn clear(&self) -> Result<()>;
+}
+
+/// 内存进度存储
+#[derive(Debug, Default)]
+pub struct MemoryProgressStore {
+    progress: Mutex<Option<FlowProgress>>,
+}
+
+impl MemoryProgressStore {
+    /// 创建空存储
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ProgressStore for MemoryProgressStore {
+    fn save(&self, progress: &FlowProgress) -> Result<()> {
+        *self.progress.lock().unwrap_or_else(|e| e.into_inner()) =
+            Some(progress.clone());
+        Ok(())
+    }
+
+    fn load(&self) -> Result<Option<FlowProgress>> {
+        Ok(self
+            .progress
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone())
+    }
+
+    fn clear(&self) -> Result<()> {
+        *self.progress.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        Ok(())
+    }
+}
+
+/// 文件进度存储（JSON）
+#[derive(Debug, Clone)]
+pub struct FsProgressStore {
+    path: PathBuf,
+}
+
+impl FsProgressStore {
+    /// 以指定文件保存进度
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl ProgressStore for FsProgressStore {
+    fn save(&self, progress: &FlowProgress) -> Result<()> {
+        let json = serde_json::to_vec(progress)?;
+        // 先写临时文件再重命名，崩溃时不会留下写了一半的进度
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(format!(".{}", uuid::Uuid::new_v4()));
+        std::fs::write(&tmp, json)
+            .with_context(|| format!("写入进度失败: {:?}", self.path))?;
+        std::fs::rename(&tmp, &self.path)
+            .with_context(|| format!("写入进度失败: {:?}", self.path))
+    }
+
+    fn load(&self) -> Result<Option<FlowProgress>> {
+        match std::fs::read(&self.path) {
+            Ok(json) => serde_json::from_slice(&json)
+                .map(Some)
+                .with_context(|| format!("解析进度失败: {:?}", self.path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => {
+                Err(e).with_context(|| format!("读取进度失败: {:?}", self.path))
+            }
+        }
+    }
+
+    fn clear(&self) -> Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("删除进度失败: {:?}", self.path))
+            }
+            _ => Ok(()),
+        }
+    }
+}
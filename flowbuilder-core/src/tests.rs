@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod unit_tests {
     use crate::*;
+    use std::collections::HashMap;
 
     #[tokio::test]
     async fn test_basic_flow() {
@@ -35,4 +36,1303 @@ mod unit_tests {
         let result = flow.execute().await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_step_fold_accumulates() {
+        let context = FlowBuilder::new()
+            .step_fold(
+                "total_items",
+                0usize,
+                |acc, _ctx| *acc < 25,
+                |acc, ctx| async move {
+                    // 模拟分页：每页 10 条
+                    let mut guard = ctx.lock().await;
+                    let page = guard
+                        .get_variable("pages")
+                        .and_then(|v| v.parse::<usize>().ok())
+                        .unwrap_or(0);
+                    guard.set_variable("pages".into(), (page + 1).to_string());
+                    Ok(acc + 10)
+                },
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            context.get_variable("total_items"),
+            Some(&"30".to_string())
+        );
+        assert_eq!(
+            context.get_typed("total_items"),
+            Some(&serde_json::json!(30))
+        );
+        assert_eq!(context.get_variable("pages"), Some(&"3".to_string()));
+        assert_eq!(context.step_logs[0].step_name, "total_items");
+    }
+
+    #[tokio::test]
+    async fn test_step_foreach_runs_subflow_per_item() {
+        let build = |continue_on_error: bool| {
+            let body = |index: usize, item: &str| {
+                let item = item.to_string();
+                FlowBuilder::new().step(move |ctx| {
+                    let item = item.clone();
+                    async move {
+                        let mut guard = ctx.lock().await;
+                        if item == "bad" {
+                            anyhow::bail!("item {} rejected", index);
+                        }
+                        let seen = guard
+                            .get_variable("seen")
+                            .cloned()
+                            .unwrap_or_default();
+                        let current =
+                            guard.get_variable("users_current_item").cloned();
+                        assert_eq!(current.as_deref(), Some(item.as_str()));
+                        guard.set_variable("seen".into(), seen + &item);
+                        Ok(())
+                    }
+                })
+            };
+            let builder = FlowBuilder::new().step(|ctx| async move {
+                ctx.lock().await.set_variable(
+                    "user_ids".into(),
+                    r#"["a", "bad", 7]"#.into(),
+                );
+                Ok(())
+            });
+            if continue_on_error {
+                builder.step_foreach_continue("users", "user_ids", body)
+            } else {
+                builder.step_foreach("users", "user_ids", body)
+            }
+        };
+
+        let context = build(true).execute().await.unwrap();
+        assert_eq!(context.get_variable("seen"), Some(&"a7".to_string()));
+        let names: Vec<_> = context
+            .step_logs
+            .iter()
+            .map(|l| l.step_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["users[0]", "users[1]", "users[2]"]);
+        assert_eq!(context.errors.len(), 1);
+
+        let error = build(false).execute().await.unwrap_err();
+        assert!(error.to_string().contains("item 1 rejected"));
+    }
+
+    #[tokio::test]
+    async fn test_subflow_scoped_isolates_variables() {
+        let scoped = |value: &'static str| {
+            move || {
+                FlowBuilder::new().named_step("scoped", move |ctx| async move {
+                    let mut guard = ctx.lock().await;
+                    guard.set_variable("result".into(), value.into());
+                    guard.set_variable("winner".into(), value.into());
+                    guard.export("winner");
+                    Ok(())
+                })
+            }
+        };
+
+        let context = FlowBuilder::new()
+            .step(|ctx| async move {
+                ctx.lock()
+                    .await
+                    .set_variable("result".into(), "parent".into());
+                Ok(())
+            })
+            .parallel_group(
+                "fanout",
+                ParallelConfig::default(),
+                vec![
+                    FlowBuilder::new().subflow_scoped(|_| true, scoped("a")),
+                    FlowBuilder::new().subflow_scoped(|_| true, scoped("b")),
+                ],
+            )
+            .subflow_scoped(|_| false, scoped("skipped"))
+            .execute()
+            .await
+            .unwrap();
+
+        // 未导出的变量不回写，导出的变量以最后写入者为准
+        assert_eq!(context.get_variable("result"), Some(&"parent".into()));
+        let winner = context.get_variable("winner").unwrap();
+        assert!(winner == "a" || winner == "b");
+        assert!(context.exports.is_empty());
+        let scoped_logs = context
+            .step_logs
+            .iter()
+            .filter(|l| l.step_name == "scoped")
+            .count();
+        assert_eq!(scoped_logs, 2);
+    }
+
+    #[tokio::test]
+    async fn test_parallel_group_bounds_concurrency() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let subflow = |fail: bool| {
+            let running = running.clone();
+            let peak = peak.clone();
+            FlowBuilder::new().step(move |_ctx| {
+                let running = running.clone();
+                let peak = peak.clone();
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    if fail {
+                        anyhow::bail!("boom");
+                    }
+                    Ok(())
+                }
+            })
+        };
+        let config = ParallelConfig {
+            max_concurrency: 2,
+            fail_fast: false,
+            ..ParallelConfig::default()
+        };
+
+        let context = FlowBuilder::new()
+            .parallel_group(
+                "fanout",
+                config.clone(),
+                vec![subflow(false), subflow(true), subflow(false)],
+            )
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert_eq!(context.get_variable("fanout_success"), Some(&"2".into()));
+        assert_eq!(context.get_variable("fanout_failed"), Some(&"1".into()));
+        assert!(context.get_variable("fanout_duration_ms").is_some());
+        assert_eq!(context.errors.len(), 1);
+
+        let error = FlowBuilder::new()
+            .parallel_group(
+                "fanout",
+                ParallelConfig {
+                    fail_fast: true,
+                    ..config
+                },
+                vec![subflow(true), subflow(false)],
+            )
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("boom"));
+    }
+
+    #[test]
+    fn test_describe_reports_step_kinds() {
+        let flow = FlowBuilder::new()
+            .step_with_timeout(
+                "fetch",
+                std::time::Duration::from_millis(250),
+                |_ctx| async move { Ok(()) },
+            )
+            .step_if(|_ctx| true, |_ctx| async move { Ok(()) })
+            .parallel_group(
+                "fan_out",
+                ParallelConfig::default(),
+                vec![
+                    FlowBuilder::new()
+                        .named_step("a", |_ctx| async move { Ok(()) }),
+                    FlowBuilder::new()
+                        .step_emit("done", serde_json::Value::Null),
+                ],
+            );
+
+        let described = flow.describe();
+        assert_eq!(described.len(), 3);
+        assert_eq!(described[0].kind, StepKind::Step);
+        assert_eq!(described[0].metadata["timeout_ms"], "250");
+        assert_eq!(
+            (described[1].name.as_str(), &described[1].kind),
+            ("step_1", &StepKind::If)
+        );
+        let StepKind::Parallel { branches } = &described[2].kind else {
+            panic!("expected a parallel group, got {:?}", described[2].kind);
+        };
+        assert_eq!(branches[0][0].name, "a");
+        assert_eq!(branches[1][0].kind, StepKind::Emit);
+        assert_eq!(branches[1][0].metadata["event"], "done");
+        assert_eq!(described[2].metadata["max_concurrency"], "10");
+    }
+
+    #[test]
+    fn test_plan_previews_conditional_steps() {
+        let flow = FlowBuilder::new()
+            .named_step("load", |_ctx| async move { Ok(()) })
+            .step_if(
+                |ctx| {
+                    ctx.get_variable("mode").map(String::as_str) == Some("full")
+                },
+                |_ctx| async move { anyhow::bail!("body must not run") },
+            )
+            .step_if(
+                |ctx| ctx.get_variable("mode").is_none(),
+                |_ctx| async move { anyhow::bail!("body must not run") },
+            );
+
+        let mut context = flowbuilder_context::FlowContext::default();
+        context.set_variable("mode".to_string(), "full".to_string());
+        let planned = flow.plan(&context);
+        assert_eq!(
+            planned
+                .iter()
+                .map(|p| (p.name.as_str(), p.conditional, p.will_run))
+                .collect::<Vec<_>>(),
+            vec![
+                ("load", false, true),
+                ("step_1", true, true),
+                ("step_2", true, false),
+            ]
+        );
+
+        let planned = flow.plan(&flowbuilder_context::FlowContext::default());
+        assert!(!planned[1].will_run && planned[2].will_run);
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_metrics_counts_steps() {
+        use std::time::Duration;
+
+        let (result, metrics) = FlowBuilder::new()
+            .named_step("fast", |_ctx| async move { Ok(()) })
+            .named_step("slow", |_ctx| async move {
+                tokio::time::sleep(Duration::from_millis(30)).await;
+                Ok(())
+            })
+            .step_with_timeout(
+                "stuck",
+                Duration::from_millis(10),
+                |_ctx| async move {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    Ok(())
+                },
+            )
+            .execute_with_metrics(flowbuilder_context::FlowContext::default())
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(metrics.total_steps, 3);
+        assert_eq!((metrics.succeeded, metrics.timed_out), (2, 1));
+        assert_eq!(metrics.step_durations.len(), 3);
+        assert_eq!(metrics.slowest_step.unwrap().0, "slow");
+        assert!(metrics.total_duration >= Duration::from_millis(40));
+    }
+
+    #[test]
+    fn test_validate_reports_dependency_cycle() {
+        let node = |id: &str, dep: &str| {
+            ExecutionNode::new(
+                id.to_string(),
+                id.to_string(),
+                ActionSpec {
+                    action_type: "builtin".to_string(),
+                    parameters: HashMap::new(),
+                    outputs: HashMap::new(),
+                },
+            )
+            .add_dependency(dep.to_string())
+        };
+        let mut plan = ExecutionPlan::new(
+            "cycle".to_string(),
+            "1.0".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+        plan.add_phase(ExecutionPhase {
+            id: "phase_0".to_string(),
+            name: "phase_0".to_string(),
+            execution_mode: PhaseExecutionMode::Sequential,
+            nodes: vec![node("a", "b"), node("b", "a")],
+            condition: None,
+        });
+
+        let error = plan.validate().unwrap_err();
+        assert!(error.contains("a -> b -> a"), "{error}");
+    }
+
+    /// 记录每个 span 的名称及其父 span 名称
+    #[derive(Default)]
+    struct SpanRecorder {
+        next_id: std::sync::atomic::AtomicU64,
+        names: std::sync::Mutex<HashMap<u64, &'static str>>,
+        entered: std::sync::Mutex<Vec<u64>>,
+        edges: std::sync::Mutex<Vec<(&'static str, Option<&'static str>)>>,
+    }
+
+    impl tracing::Subscriber for SpanRecorder {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+        ) -> tracing::span::Id {
+            let id = self
+                .next_id
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst)
+                + 1;
+            let parent = match attrs.parent() {
+                Some(parent) => Some(parent.into_u64()),
+                None if attrs.is_contextual() => {
+                    self.entered.lock().unwrap().last().copied()
+                }
+                None => None,
+            };
+            let mut names = self.names.lock().unwrap();
+            names.insert(id, attrs.metadata().name());
+            let parent = parent.and_then(|p| names.get(&p).copied());
+            self.edges
+                .lock()
+                .unwrap()
+                .push((attrs.metadata().name(), parent));
+            tracing::span::Id::from_u64(id)
+        }
+
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {
+        }
+
+        fn record_follows_from(
+            &self,
+            _: &tracing::span::Id,
+            _: &tracing::span::Id,
+        ) {
+        }
+
+        fn event(&self, _: &tracing::Event<'_>) {}
+
+        fn enter(&self, span: &tracing::span::Id) {
+            self.entered.lock().unwrap().push(span.into_u64());
+        }
+
+        fn exit(&self, span: &tracing::span::Id) {
+            let mut entered = self.entered.lock().unwrap();
+            if let Some(pos) =
+                entered.iter().rposition(|id| *id == span.into_u64())
+            {
+                entered.remove(pos);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subflow_spans_nest_under_flow_span() {
+        let recorder = std::sync::Arc::new(SpanRecorder::default());
+        let _guard = tracing::subscriber::set_default(recorder.clone());
+
+        let subflow = || FlowBuilder::new().step(|_ctx| async move { Ok(()) });
+        FlowBuilder::new()
+            .parallel_group(
+                "group",
+                ParallelConfig::default(),
+                vec![subflow(), subflow()],
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        let edges = recorder.edges.lock().unwrap().clone();
+        assert_eq!(edges[0], ("flow", None));
+        assert_eq!(
+            edges
+                .iter()
+                .filter(|e| **e == ("subflow", Some("flow")))
+                .count(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_step_with_retry_if_stops_on_rejected_error() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let attempts = Arc::new(AtomicU32::new(0));
+        let counter = attempts.clone();
+        let result = FlowBuilder::new()
+            .step_with_retry_if(
+                "validate",
+                3,
+                Duration::from_millis(1),
+                move |_ctx| {
+                    let counter = counter.clone();
+                    async move {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        anyhow::bail!("400 bad request")
+                    }
+                },
+                |e| !e.to_string().starts_with("4"),
+                Some(RetryStrategy::Exponential { multiplier: 2.0 }),
+            )
+            .execute()
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        // 可重试错误按次数重试，直至成功
+        let attempts = Arc::new(AtomicU32::new(0));
+        let counter = attempts.clone();
+        FlowBuilder::new()
+            .step_with_retry_if(
+                "fetch",
+                3,
+                Duration::from_millis(1),
+                move |_ctx| {
+                    let counter = counter.clone();
+                    async move {
+                        if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                            anyhow::bail!("503 unavailable")
+                        }
+                        Ok(())
+                    }
+                },
+                |e| !e.to_string().starts_with("4"),
+                None,
+            )
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_budget_shared_across_steps() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let first_attempts = Arc::new(AtomicU32::new(0));
+        let second_attempts = Arc::new(AtomicU32::new(0));
+        let (first, second) = (first_attempts.clone(), second_attempts.clone());
+
+        // 每步最多重试 5 次，但整个流程只允许 3 次：
+        // 第一步用掉 2 次后成功，第二步只剩 1 次
+        let (result, metrics) = FlowBuilder::new()
+            .with_retry_budget(3)
+            .step_with_retry(
+                "first",
+                5,
+                Duration::from_millis(1),
+                move |_ctx| {
+                    let first = first.clone();
+                    async move {
+                        if first.fetch_add(1, Ordering::SeqCst) < 2 {
+                            anyhow::bail!("unavailable")
+                        }
+                        Ok(())
+                    }
+                },
+            )
+            .step_with_retry(
+                "second",
+                5,
+                Duration::from_millis(1),
+                move |_ctx| {
+                    let second = second.clone();
+                    async move {
+                        second.fetch_add(1, Ordering::SeqCst);
+                        anyhow::bail!("unavailable")
+                    }
+                },
+            )
+            .execute_with_metrics(flowbuilder_context::FlowContext::default())
+            .await;
+
+        let error = result.unwrap_err();
+        assert!(format!("{error:#}").contains("retry budget exhausted"));
+        assert_eq!(first_attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(second_attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(metrics.retry_budget_remaining, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_abort_if_stops_after_error_threshold() {
+        let mut context = flowbuilder_context::FlowContext::default();
+        context.set_variable("items".to_string(), "[1, 2, 3]".to_string());
+
+        let (result, metrics) = FlowBuilder::new()
+            .step_foreach_continue("each", "items", |_index, _item| {
+                FlowBuilder::new()
+                    .step(|_ctx| async move { anyhow::bail!("item failed") })
+            })
+            .abort_if(|ctx| ctx.errors.len() > 5)
+            .named_step("still_runs", |_ctx| async move { Ok(()) })
+            .abort_if(|ctx| ctx.errors.len() > 2)
+            .named_step("never_runs", |_ctx| async move { Ok(()) })
+            .execute_with_metrics(context)
+            .await;
+
+        let error = result.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<FlowError>(),
+            Some(&FlowError::Aborted { error_count: 3 })
+        );
+        let steps: Vec<_> = metrics
+            .step_durations
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert!(steps.contains(&"still_runs"));
+        assert!(!steps.contains(&"never_runs"));
+    }
+
+    #[tokio::test]
+    async fn test_step_with_timeout_returns_typed_error() {
+        use std::time::Duration;
+
+        let error = FlowBuilder::new()
+            .step_with_timeout(
+                "slow",
+                Duration::from_millis(20),
+                |_ctx| async move {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    Ok(())
+                },
+            )
+            .execute()
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<FlowError>(),
+            Some(&FlowError::StepTimeout {
+                name: "slow".to_string(),
+                after: Duration::from_millis(20),
+            })
+        );
+
+        // 业务错误即使包含 "timed out" 也不视为超时
+        let context = std::sync::Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        let steps = FlowBuilder::new()
+            .step_with_timeout(
+                "remote",
+                Duration::from_secs(5),
+                |_ctx| async move { anyhow::bail!("upstream timed out") },
+            )
+            .into_steps();
+        let error = FlowExecutor::new()
+            .execute_steps(steps, context.clone())
+            .await
+            .unwrap_err();
+        assert!(error.downcast_ref::<FlowError>().is_none());
+        let guard = context.lock().await;
+        assert!(matches!(
+            guard.step_logs[0].status,
+            flowbuilder_context::StepStatus::Failed
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_step_await_signal_resumes_on_signal() {
+        use std::time::Duration;
+
+        let context = std::sync::Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        let approver = context.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            approver.lock().await.signal("approval");
+        });
+
+        let steps = FlowBuilder::new()
+            .step_await_signal("approve", "approval", Duration::from_secs(5))
+            .named_step("deploy", |ctx| async move {
+                ctx.lock()
+                    .await
+                    .set_variable("deployed".into(), "true".into());
+                Ok(())
+            })
+            .into_steps();
+        FlowExecutor::new()
+            .execute_steps(steps, context.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            context.lock().await.get_variable("deployed"),
+            Some(&"true".to_string())
+        );
+
+        // 无信号时等待受超时约束
+        let error = FlowBuilder::new()
+            .step_await_signal("approve", "never", Duration::from_millis(20))
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<FlowError>(),
+            Some(FlowError::StepTimeout { .. })
+        ));
+    }
+
+    #[cfg(feature = "deadlock-detector")]
+    #[tokio::test]
+    async fn test_deadlock_detector_reports_stalled_waits() {
+        use std::time::{Duration, Instant};
+
+        let waiter = |description: &'static str, key: &'static str| {
+            FlowBuilder::new().named_wait_until(
+                description,
+                move |ctx| ctx.get_variable(key).is_some(),
+                Duration::from_millis(5),
+                2000,
+            )
+        };
+        let started = Instant::now();
+        let error = FlowBuilder::new()
+            .with_deadlock_detection(Duration::from_millis(50))
+            .parallel_group(
+                "approvals",
+                ParallelConfig::default(),
+                vec![
+                    waiter("manager approval", "a"),
+                    waiter("qa sign-off", "b"),
+                ],
+            )
+            .execute()
+            .await
+            .unwrap_err()
+            .to_string();
+
+        // 远早于 2000 次轮询结束就报告，并列出所有等待中的条件
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(error.contains("likely deadlock"), "{error}");
+        assert!(error.contains("manager approval"), "{error}");
+        assert!(error.contains("qa sign-off"), "{error}");
+    }
+
+    /// 统计钩子调用次数
+    #[derive(Default)]
+    struct CountingHooks {
+        started: std::sync::atomic::AtomicUsize,
+        ended: std::sync::Mutex<Vec<(String, String)>>,
+        completed: std::sync::atomic::AtomicUsize,
+    }
+
+    impl FlowHooks for CountingHooks {
+        fn on_step_start(
+            &self,
+            _name: &str,
+            _ctx: &flowbuilder_context::FlowContext,
+        ) {
+            self.started
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
+
+        fn on_step_end(
+            &self,
+            name: &str,
+            status: &flowbuilder_context::StepStatus,
+            _duration: std::time::Duration,
+        ) {
+            self.ended
+                .lock()
+                .unwrap()
+                .push((name.to_string(), format!("{status:?}")));
+        }
+
+        fn on_flow_complete(&self, _ctx: &flowbuilder_context::FlowContext) {
+            self.completed
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hooks_see_every_step() {
+        use std::sync::atomic::Ordering;
+        use std::sync::Arc;
+
+        let first = Arc::new(CountingHooks::default());
+        let second = Arc::new(CountingHooks::default());
+        FlowBuilder::new()
+            .with_hooks(first.clone())
+            .with_hooks(second.clone())
+            .named_step("load", |_ctx| async move { Ok(()) })
+            .step(|_ctx| async move { Ok(()) })
+            .step_if(|_ctx| false, |_ctx| async move { Ok(()) })
+            .execute()
+            .await
+            .unwrap();
+
+        for hooks in [&first, &second] {
+            assert_eq!(hooks.started.load(Ordering::SeqCst), 3);
+            assert_eq!(
+                *hooks.ended.lock().unwrap(),
+                vec![
+                    ("load".to_string(), "Success".to_string()),
+                    ("step_1".to_string(), "Success".to_string()),
+                    ("step_2".to_string(), "Skipped".to_string()),
+                ]
+            );
+            assert_eq!(hooks.completed.load(Ordering::SeqCst), 1);
+        }
+
+        // 失败的流程同样回调结束
+        let hooks = Arc::new(CountingHooks::default());
+        let result = FlowBuilder::new()
+            .with_hooks(hooks.clone())
+            .step(|_ctx| async move { anyhow::bail!("boom") })
+            .step(|_ctx| async move { Ok(()) })
+            .execute()
+            .await;
+        assert!(result.is_err());
+        assert_eq!(hooks.started.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            *hooks.ended.lock().unwrap(),
+            vec![("step_0".to_string(), "Failed".to_string())]
+        );
+        assert_eq!(hooks.completed.load(Ordering::SeqCst), 1);
+    }
+
+    /// 记录流程结束时的上下文
+    #[derive(Default)]
+    struct FinalContext(
+        std::sync::Mutex<Option<flowbuilder_context::FlowContext>>,
+    );
+
+    impl FlowHooks for FinalContext {
+        fn on_flow_complete(&self, ctx: &flowbuilder_context::FlowContext) {
+            *self.0.lock().unwrap() = Some(ctx.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transactional_flow_rolls_back_on_failure() {
+        use std::sync::Arc;
+
+        let build = |fail: bool| {
+            FlowBuilder::new()
+                .transactional()
+                .named_step("reserve", |ctx| async move {
+                    ctx.lock()
+                        .await
+                        .set_variable("reserved".into(), "yes".into());
+                    Ok(())
+                })
+                .named_step("charge", move |_ctx| async move {
+                    if fail {
+                        anyhow::bail!("card declined");
+                    }
+                    Ok(())
+                })
+        };
+
+        let mut initial = flowbuilder_context::FlowContext::default();
+        initial.set_variable("order".into(), "42".into());
+        let shared = Arc::new(tokio::sync::Mutex::new(initial));
+        let error = build(true)
+            .execute_shared(shared.clone())
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("card declined"));
+
+        let context = shared.lock().await;
+        assert_eq!(context.get_variable("order"), Some(&"42".into()));
+        assert!(context.get_variable("reserved").is_none());
+        assert!(context.errors.is_empty() && context.ok);
+        assert!(context.savepoints.is_empty());
+        // 步骤日志保留
+        assert_eq!(context.step_logs.len(), 2);
+
+        let context = build(false).execute().await.unwrap();
+        assert_eq!(context.get_variable("reserved"), Some(&"yes".into()));
+        assert!(context.savepoints.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_step_with_compensation_unwinds_in_reverse() {
+        use std::sync::{Arc, Mutex};
+
+        let undone = Arc::new(Mutex::new(Vec::new()));
+        let build = |fail: bool| {
+            let undo = |name: &'static str| {
+                let undone = undone.clone();
+                move |_ctx| {
+                    undone.lock().unwrap().push(name);
+                    async move { Ok(()) }
+                }
+            };
+            FlowBuilder::new()
+                .step_with_compensation(
+                    "reserve",
+                    |_ctx| async move { Ok(()) },
+                    undo("reserve"),
+                )
+                .step_with_compensation(
+                    "charge",
+                    |_ctx| async move { Ok(()) },
+                    |_ctx| async move { anyhow::bail!("refund rejected") },
+                )
+                .step_with_compensation(
+                    "notify",
+                    |_ctx| async move { Ok(()) },
+                    undo("notify"),
+                )
+                .step_with_compensation(
+                    "ship",
+                    move |_ctx| async move {
+                        if fail {
+                            anyhow::bail!("carrier unavailable");
+                        }
+                        Ok(())
+                    },
+                    undo("ship"),
+                )
+        };
+
+        let context = build(false).execute().await.unwrap();
+        assert!(undone.lock().unwrap().is_empty());
+        assert_eq!(context.step_logs.len(), 4);
+
+        let hooks = Arc::new(FinalContext::default());
+        let error = build(true)
+            .with_hooks(hooks.clone())
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("carrier unavailable"));
+        // 失败步骤不补偿，失败的补偿不影响其余补偿
+        assert_eq!(*undone.lock().unwrap(), ["notify", "reserve"]);
+
+        let context = hooks.0.lock().unwrap().take().unwrap();
+        let compensations: Vec<_> = context
+            .step_logs
+            .iter()
+            .skip(4)
+            .map(|log| log.step_name.as_str())
+            .collect();
+        assert_eq!(
+            compensations,
+            [
+                "notify.compensate",
+                "charge.compensate",
+                "reserve.compensate"
+            ]
+        );
+        assert!(context.errors[1].contains("refund rejected"));
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_store_skips_completed_steps() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let path = std::env::temp_dir()
+            .join(format!("flowbuilder-progress-{}.json", std::process::id()));
+        let store: Arc<dyn ProgressStore> =
+            Arc::new(FsProgressStore::new(&path));
+        let copied = Arc::new(AtomicUsize::new(0));
+        let build = |crash: bool| {
+            let copied = copied.clone();
+            FlowBuilder::new()
+                .with_progress_store(store.clone())
+                .named_step("copy", move |ctx| {
+                    copied.fetch_add(1, Ordering::SeqCst);
+                    async move {
+                        ctx.lock()
+                            .await
+                            .set_variable("rows".into(), "100".into());
+                        Ok(())
+                    }
+                })
+                .named_step("verify", move |ctx| async move {
+                    if crash {
+                        anyhow::bail!("worker crashed");
+                    }
+                    let mut guard = ctx.lock().await;
+                    assert_eq!(guard.get_variable("rows"), Some(&"100".into()));
+                    guard.set_variable("verified".into(), "yes".into());
+                    Ok(())
+                })
+        };
+
+        assert!(build(true).execute().await.is_err());
+        let progress = store.load().unwrap().unwrap();
+        assert_eq!(progress.completed_steps, 1);
+        assert_eq!(progress.last_step, "copy");
+
+        let context = build(false).resume_from_store().await.unwrap();
+        assert_eq!(copied.load(Ordering::SeqCst), 1);
+        assert_eq!(context.trace_id, progress.context.trace_id);
+        assert_eq!(context.get_variable("verified"), Some(&"yes".into()));
+        // 成功完成后清除进度，再次恢复从头执行
+        assert!(store.load().unwrap().is_none());
+        build(false).resume_from_store().await.unwrap();
+        assert_eq!(copied.load(Ordering::SeqCst), 2);
+        assert!(!path.exists());
+
+        assert!(FlowBuilder::new().resume_from_store().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_cancel_stops_mid_flow() {
+        use flowbuilder_context::StepStatus;
+        use std::time::Duration;
+
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        let context = std::sync::Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        let steps = FlowBuilder::new()
+            .named_step("first", |_ctx| async move { Ok(()) })
+            .named_step("slow", move |_ctx| {
+                let trigger = trigger.clone();
+                async move {
+                    trigger.cancel();
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    Ok(())
+                }
+            })
+            .named_step("last", |_ctx| async move {
+                panic!("steps after cancellation must not run")
+            })
+            .into_steps();
+        let error = FlowExecutor::new()
+            .execute_steps_with_cancel(steps, context.clone(), &cancel)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<FlowError>(),
+            Some(&FlowError::Cancelled)
+        );
+        let guard = context.lock().await;
+        let statuses: Vec<_> = guard
+            .step_logs
+            .iter()
+            .map(|log| (log.step_name.as_str(), log.status.clone()))
+            .collect();
+        assert!(matches!(
+            statuses.as_slice(),
+            [
+                ("first", StepStatus::Success),
+                ("slow", StepStatus::Cancelled)
+            ]
+        ));
+
+        // 已取消的令牌：一个步骤也不执行
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let error = FlowBuilder::new()
+            .named_step("first", |_ctx| async move {
+                panic!("steps after cancellation must not run")
+            })
+            .execute_with_cancel(
+                flowbuilder_context::FlowContext::default(),
+                cancel,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<FlowError>(),
+            Some(&FlowError::Cancelled)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_timeout_marks_running_step() {
+        use flowbuilder_context::StepStatus;
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let hooks = Arc::new(FinalContext::default());
+        let error = FlowBuilder::new()
+            .named_step("first", |_ctx| async move { Ok(()) })
+            .named_step("slow", |ctx| async move {
+                // 超时时持有上下文锁的步骤被丢弃，锁随之释放
+                let _guard = ctx.lock().await;
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(())
+            })
+            .named_step("last", |_ctx| async move {
+                panic!("steps after the timeout must not run")
+            })
+            .with_hooks(hooks.clone())
+            .execute_with_timeout(
+                flowbuilder_context::FlowContext::default(),
+                Duration::from_millis(50),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<FlowError>(),
+            Some(&FlowError::FlowTimeout {
+                after: Duration::from_millis(50)
+            })
+        );
+
+        let context = hooks.0.lock().unwrap().take().unwrap();
+        let statuses: Vec<_> = context
+            .step_logs
+            .iter()
+            .map(|log| (log.step_name.as_str(), log.status.clone()))
+            .collect();
+        assert!(matches!(
+            statuses.as_slice(),
+            [
+                ("first", StepStatus::Success),
+                ("slow", StepStatus::Timeout)
+            ]
+        ));
+        assert!(context.errors[0].ends_with("slow: timeout"));
+    }
+
+    #[tokio::test]
+    async fn test_step_emit_publishes_to_subscribers() {
+        let context = std::sync::Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::new_with_trace_id(
+                "trace-1".into(),
+            ),
+        ));
+        let mut events = context.lock().await.subscribe_events();
+
+        let steps = FlowBuilder::new()
+            .step_emit("stage_done", serde_json::json!({ "stage": "build" }))
+            .into_steps();
+        FlowExecutor::new()
+            .execute_steps(steps, context.clone())
+            .await
+            .unwrap();
+
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.name, "stage_done");
+        assert_eq!(event.trace_id, "trace-1");
+        assert_eq!(event.payload["stage"], "build");
+        assert!(event.timestamp <= std::time::SystemTime::now());
+    }
+
+    #[tokio::test]
+    async fn test_context_json_round_trip_keeps_snapshots() {
+        let mut context = FlowBuilder::new()
+            .named_step("load", |ctx| async move {
+                let mut guard = ctx.lock().await;
+                guard.set_variable("cursor".into(), "10".into());
+                Ok(())
+            })
+            .execute()
+            .await
+            .unwrap();
+        context
+            .create_snapshot("checkpoint".into(), "after load".into())
+            .unwrap();
+        context.set_variable("cursor".into(), "20".into());
+
+        let mut restored =
+            flowbuilder_context::FlowContext::from_json(&context.to_json())
+                .unwrap();
+        assert_eq!(restored.trace_id, context.trace_id);
+        assert_eq!(restored.step_logs[0].step_name, "load");
+        assert!(restored.step_logs[0].end_time.is_some());
+
+        restored.rollback_to_snapshot("checkpoint").unwrap();
+        assert_eq!(restored.get_variable("cursor"), Some(&"10".to_string()));
+    }
+
+    #[test]
+    fn test_nested_savepoints_roll_back_in_order() {
+        let mut context = flowbuilder_context::FlowContext::default();
+        context.set_variable("stage".into(), "outer".into());
+        context.savepoint("outer").unwrap();
+        context.set_variable("outer_done".into(), "yes".into());
+        context.savepoint("inner").unwrap();
+        context.set_variable("inner_done".into(), "yes".into());
+        context.savepoint("innermost").unwrap();
+        assert!(context.savepoint("inner").is_err());
+
+        // 内层回滚保留外层进度，并丢弃其后创建的保存点
+        context.rollback_to_savepoint("inner").unwrap();
+        assert_eq!(context.get_variable("outer_done"), Some(&"yes".into()));
+        assert!(context.get_variable("inner_done").is_none());
+        assert!(context.rollback_to_savepoint("innermost").is_err());
+
+        // 保存点回滚后仍可再次回滚
+        context.set_variable("inner_done".into(), "retry".into());
+        context.rollback_to_savepoint("inner").unwrap();
+        assert!(context.get_variable("inner_done").is_none());
+
+        context.release_savepoint("inner").unwrap();
+        assert_eq!(context.savepoints.len(), 1);
+        context.rollback_to_savepoint("outer").unwrap();
+        assert!(context.get_variable("outer_done").is_none());
+        assert_eq!(context.get_variable("stage"), Some(&"outer".into()));
+    }
+
+    #[test]
+    fn test_snapshot_isolated_from_later_writes() {
+        let mut context = flowbuilder_context::FlowContext::default();
+        for i in 0..100 {
+            context.set_variable(format!("key_{i}"), i.to_string());
+        }
+        context
+            .create_snapshot("base".into(), String::new())
+            .unwrap();
+
+        // 快照与上下文共享存储（`im` 特性）时，写入也不得影响快照
+        context.set_variable("key_0".into(), "changed".into());
+        context.variables.remove("key_1");
+        context.set_typed("extra".into(), serde_json::json!(true));
+        let snapshot = &context.snapshots["base"];
+        assert_eq!(snapshot.variables["key_0"], "0");
+        assert_eq!(snapshot.variables.len(), 100);
+        assert!(snapshot.typed_variables.is_empty());
+
+        context.rollback_to_snapshot("base").unwrap();
+        assert_eq!(context.get_variable("key_1"), Some(&"1".to_string()));
+        assert!(context.get_typed("extra").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_context_diff_between_steps() {
+        let mut context = flowbuilder_context::FlowContext::default();
+        context.set_variable("stale".into(), "1".into());
+        context.set_variable("count".into(), "1".into());
+        let before = context.capture("before".into(), String::new());
+
+        let context = FlowBuilder::new()
+            .with_diff_logging()
+            .step(|ctx| async move {
+                let mut guard = ctx.lock().await;
+                guard.variables.remove("stale");
+                guard.set_variable("count".into(), "2".into());
+                guard.set_typed("user".into(), serde_json::json!({"id": 7}));
+                Ok(())
+            })
+            .execute_with_context(context)
+            .await
+            .unwrap();
+
+        let diff = before.diff(&context);
+        assert_eq!(diff.added.keys().collect::<Vec<_>>(), vec!["user"]);
+        assert_eq!(diff.added["user"]["id"], 7);
+        assert_eq!(diff.removed["stale"], "1");
+        assert_eq!(diff.changed["count"], ("1".into(), "2".into()));
+        assert!(context
+            .capture(String::new(), String::new())
+            .diff(&context)
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_step_with_rollback_retry_restores_between_attempts() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+
+        let build = |fail_until: u32| {
+            let attempts = Arc::new(AtomicU32::new(0));
+            FlowBuilder::new().step_with_rollback_retry(
+                "transfer",
+                "before_transfer",
+                2,
+                move |ctx| {
+                    let attempts = attempts.clone();
+                    async move {
+                        let mut guard = ctx.lock().await;
+                        // 回滚后每次尝试都看不到上一次的部分写入
+                        assert!(guard.get_variable("debited").is_none());
+                        guard.set_variable("debited".into(), "yes".into());
+                        let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+                        if attempt < fail_until {
+                            anyhow::bail!("attempt {attempt} failed");
+                        }
+                        Ok(())
+                    }
+                },
+            )
+        };
+
+        let context = build(2).execute().await.unwrap();
+        assert_eq!(context.get_variable("debited"), Some(&"yes".into()));
+
+        let error = build(3).execute().await.unwrap_err();
+        assert!(error.to_string().contains("attempt 2 failed"));
+    }
+
+    #[tokio::test]
+    async fn test_step_with_rollback_propagation() {
+        let failing = |ctx: flowbuilder_context::SharedContext| async move {
+            ctx.lock()
+                .await
+                .set_variable("partial".into(), "written".into());
+            anyhow::bail!("write failed")
+        };
+
+        let error = FlowBuilder::new()
+            .step_with_rollback("write", "before_write", failing)
+            .named_step(
+                "after",
+                |_ctx| async move { anyhow::bail!("must not run") },
+            )
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("write failed"));
+
+        let context = FlowBuilder::new()
+            .step_with_rollback_continue("write", "before_write", failing)
+            .named_step("after", |ctx| async move {
+                ctx.lock().await.set_variable("after".into(), "ran".into());
+                Ok(())
+            })
+            .execute()
+            .await
+            .unwrap();
+        assert!(context.get_variable("partial").is_none());
+        assert_eq!(context.get_variable("after"), Some(&"ran".into()));
+        assert_eq!(context.errors.len(), 1);
+        assert!(matches!(
+            context.step_logs[0].status,
+            flowbuilder_context::StepStatus::Failed
+        ));
+    }
+
+    #[test]
+    fn test_compile_builder_to_plan() {
+        let plan = FlowBuilder::new()
+            .named_step("fetch", |_ctx| async move { Ok(()) })
+            .step(|_ctx| async move { Ok(()) })
+            .compile();
+
+        assert_eq!(plan.phases.len(), 2);
+        let fetch = &plan.phases[0].nodes[0];
+        assert_eq!(fetch.id, "step_0");
+        assert_eq!(fetch.name, "fetch");
+        assert_eq!(fetch.action_spec.action_type, NativeAction::ACTION_TYPE);
+        assert!(fetch.native.is_some());
+
+        let second = &plan.phases[1].nodes[0];
+        assert_eq!(second.name, "step_1");
+        assert_eq!(second.dependencies, vec!["step_0".to_string()]);
+    }
+
+    #[test]
+    fn test_program_matches_relative_and_indexed_paths() {
+        let root = serde_json::json!({
+            "vars": { "matrix": [[1, 2], [3, 4]], "users": [{ "name": "a" }] }
+        });
+        let program = JqProgram::compile("vars.matrix[1][0]").unwrap();
+        assert_eq!(program.run(&root).unwrap(), serde_json::json!(3));
+        let program = JqProgram::compile("vars.users | .[0].name").unwrap();
+        assert_eq!(program.run(&root).unwrap(), serde_json::json!("a"));
+        assert!(JqProgram::compile("vars.users[x]").is_err());
+        assert!(JqProgram::compile("vars.missing")
+            .unwrap()
+            .run(&root)
+            .is_err());
+    }
 }
@@ -3,7 +3,7 @@ use flowbuilder_core::{
     ActionSpec, ExecutionNode, ExecutionPhase, ExecutionPlan,
     PhaseExecutionMode,
 };
-use flowbuilder_runtime::EnhancedTaskExecutor;
+use flowbuilder_runtime::{EnhancedTaskExecutor, RunContext};
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -49,13 +49,16 @@ fn bench_executor(c: &mut Criterion) {
             // Reuse a runtime per bench invocation for lower overhead
             let rt = tokio::runtime::Runtime::new().unwrap();
             b.to_async(&rt).iter(|| async move {
-                let mut exec = EnhancedTaskExecutor::new();
+                let exec = EnhancedTaskExecutor::new();
                 // black_box to avoid compiler optimizing away plan construction
                 let plan = black_box(build_plan(false, nodes));
                 let ctx = Arc::new(tokio::sync::Mutex::new(
                     flowbuilder_context::FlowContext::default(),
                 ));
-                let _ = exec.execute_plan(plan, ctx).await.unwrap();
+                let _ = exec
+                    .execute_plan(plan, ctx, &RunContext::default())
+                    .await
+                    .unwrap();
             });
         });
         // parallel (if feature enabled)
@@ -63,12 +66,15 @@ fn bench_executor(c: &mut Criterion) {
         group.bench_function(format!("par_{nodes}"), |b| {
             let rt = tokio::runtime::Runtime::new().unwrap();
             b.to_async(&rt).iter(|| async move {
-                let mut exec = EnhancedTaskExecutor::new();
+                let exec = EnhancedTaskExecutor::new();
                 let plan = black_box(build_plan(true, nodes));
                 let ctx = Arc::new(tokio::sync::Mutex::new(
                     flowbuilder_context::FlowContext::default(),
                 ));
-                let _ = exec.execute_plan(plan, ctx).await.unwrap();
+                let _ = exec
+                    .execute_plan(plan, ctx, &RunContext::default())
+                    .await
+                    .unwrap();
             });
         });
     }
@@ -0,0 +1,188 @@
+//! # FlowBuilder Runtime - 动作上下文
+//!
+//! 动作处理器通过 [`ActionContext`] 读取输入、访问流程变量并写出输出，
+//! 不直接依赖 `FlowContext` 的内部结构
+
+use anyhow::Result;
+use flowbuilder_context::SharedContext;
+use flowbuilder_core::ActionSpec;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// 动作执行上下文
+#[derive(Debug, Clone)]
+pub struct ActionContext {
+    node_id: Option<String>,
+    inputs: HashMap<String, serde_yaml::Value>,
+    outputs: HashMap<String, String>,
+    shared: SharedContext,
+}
+
+impl ActionContext {
+    /// 以动作参数作为输入创建
+    pub fn new(action_spec: &ActionSpec, shared: SharedContext) -> Self {
+        Self {
+            node_id: None,
+            inputs: action_spec.parameters.clone(),
+            outputs: HashMap::new(),
+            shared,
+        }
+    }
+
+    /// 设置所属节点ID
+    pub fn with_node_id<S: Into<String>>(mut self, node_id: S) -> Self {
+        self.node_id = Some(node_id.into());
+        self
+    }
+
+    /// 所属节点ID（直接执行动作时为空）
+    pub fn node_id(&self) -> Option<&str> {
+        self.node_id.as_deref()
+    }
+
+    /// 全部输入
+    pub fn inputs(&self) -> &HashMap<String, serde_yaml::Value> {
+        &self.inputs
+    }
+
+    /// 获取输入
+    pub fn input(&self, name: &str) -> Option<&serde_yaml::Value> {
+        self.inputs.get(name)
+    }
+
+    /// 获取字符串输入
+    pub fn input_str(&self, name: &str) -> Option<&str> {
+        self.input(name).and_then(|v| v.as_str())
+    }
+
+    /// 获取无符号整数输入
+    pub fn input_u64(&self, name: &str) -> Option<u64> {
+        self.input(name).and_then(|v| v.as_u64())
+    }
+
+    /// 获取布尔输入
+    pub fn input_bool(&self, name: &str) -> Option<bool> {
+        self.input(name).and_then(|v| v.as_bool())
+    }
+
+    /// 获取必填输入
+    pub fn require(&self, name: &str) -> Result<&serde_yaml::Value> {
+        self.input(name)
+            .ok_or_else(|| anyhow::anyhow!("动作缺少 '{}' 参数", name))
+    }
+
+    /// 获取必填字符串输入
+    pub fn require_str(&self, name: &str) -> Result<&str> {
+        self.require(name)?
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("动作参数 '{}' 必须是字符串", name))
+    }
+
+    /// 读取流程变量
+    pub async fn get_var(&self, key: &str) -> Option<String> {
+        self.shared.lock().await.get_variable(key).cloned()
+    }
+
+    /// 读取并解析流程变量（不存在或解析失败时为 `None`）
+    pub async fn get_var_as<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.get_var(key).await.and_then(|v| v.parse().ok())
+    }
+
+    /// 写入流程变量
+    pub async fn set_var<K: Into<String>, V: Into<String>>(
+        &self,
+        key: K,
+        value: V,
+    ) {
+        self.shared
+            .lock()
+            .await
+            .set_variable(key.into(), value.into());
+    }
+
+    /// 写入结构化流程变量
+    pub async fn set_typed_var<K: Into<String>>(
+        &self,
+        key: K,
+        value: serde_json::Value,
+    ) {
+        self.shared.lock().await.set_typed(key.into(), value);
+    }
+
+    /// 记录输出（调用 [`commit_outputs`](Self::commit_outputs) 后写入流程变量）
+    pub fn set_output<K: Into<String>, V: Into<String>>(
+        &mut self,
+        key: K,
+        value: V,
+    ) {
+        self.outputs.insert(key.into(), value.into());
+    }
+
+    /// 已记录的输出
+    pub fn outputs(&self) -> &HashMap<String, String> {
+        &self.outputs
+    }
+
+    /// 把已记录的输出写入流程变量并清空
+    pub async fn commit_outputs(&mut self) {
+        if self.outputs.is_empty() {
+            return;
+        }
+        let mut guard = self.shared.lock().await;
+        for (key, value) in self.outputs.drain() {
+            guard.set_variable(key, value);
+        }
+    }
+
+    /// 底层共享上下文
+    pub fn shared(&self) -> &SharedContext {
+        &self.shared
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flowbuilder_context::FlowContext;
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_action_context_inputs_and_outputs() {
+        let spec = ActionSpec {
+            action_type: "builtin".to_string(),
+            parameters: HashMap::from([
+                ("url".to_string(), "https://example.com".into()),
+                ("retries".to_string(), 3.into()),
+            ]),
+            outputs: HashMap::new(),
+        };
+        let shared = Arc::new(Mutex::new(FlowContext::default()));
+        let mut ctx =
+            ActionContext::new(&spec, shared.clone()).with_node_id("fetch");
+
+        assert_eq!(ctx.node_id(), Some("fetch"));
+        assert_eq!(ctx.input_str("url"), Some("https://example.com"));
+        assert_eq!(ctx.input_u64("retries"), Some(3));
+        assert!(ctx.require_str("retries").is_err());
+        assert!(ctx.require("missing").is_err());
+
+        ctx.set_var("count", "42").await;
+        assert_eq!(ctx.get_var_as::<u32>("count").await, Some(42));
+        assert_eq!(ctx.get_var_as::<u32>("missing").await, None);
+
+        // 输出在提交前不可见
+        ctx.set_output("status", "200");
+        assert!(shared.lock().await.get_variable("status").is_none());
+        ctx.commit_outputs().await;
+        assert!(ctx.outputs().is_empty());
+        assert_eq!(
+            shared
+                .lock()
+                .await
+                .get_variable("status")
+                .map(String::as_str),
+            Some("200")
+        );
+    }
+}
@@ -0,0 +1,62 @@
+//! # FlowBuilder Runtime - 自定义动作
+//!
+//! 通过 [`ActionRegistry`] 注册自定义动作类型（如 `grpc`、`s3`）；分派时
+//! 先查找注册表，未注册的类型再交给内置动作
+
+use anyhow::Result;
+use flowbuilder_context::SharedContext;
+use flowbuilder_core::ActionSpec;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// 自定义动作执行结果的 Future
+pub type ActionFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// 自定义动作处理器
+pub trait ActionHandler: fmt::Debug + Send + Sync {
+    /// 执行动作
+    fn handle<'a>(
+        &'a self,
+        spec: &'a ActionSpec,
+        context: SharedContext,
+    ) -> ActionFuture<'a>;
+}
+
+/// 动作类型 → 处理器
+#[derive(Debug, Clone, Default)]
+pub struct ActionRegistry {
+    handlers: HashMap<String, Arc<dyn ActionHandler>>,
+}
+
+impl ActionRegistry {
+    /// 创建空注册表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册动作类型（同名类型覆盖之前的处理器，也可覆盖内置类型）
+    pub fn register(
+        &mut self,
+        action_type: impl Into<String>,
+        handler: Arc<dyn ActionHandler>,
+    ) {
+        self.handlers.insert(action_type.into(), handler);
+    }
+
+    /// 查找动作类型的处理器
+    pub fn get(&self, action_type: &str) -> Option<&Arc<dyn ActionHandler>> {
+        self.handlers.get(action_type)
+    }
+
+    /// 已注册的动作类型（按名称排序）
+    pub fn action_types(&self) -> Vec<&str> {
+        let mut types: Vec<_> =
+            self.handlers.keys().map(String::as_str).collect();
+        types.sort_unstable();
+        types
+    }
+}
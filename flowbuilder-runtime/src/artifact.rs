@@ -0,0 +1,247 @@
+//! # FlowBuilder Runtime - 制品存储
+//!
+//! 基于内容寻址的制品存储，节点间通过句柄传递大块数据，上下文中只保存句柄
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+/// 制品句柄（`sha256:<hex>`）
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArtifactId(String);
+
+impl ArtifactId {
+    const PREFIX: &'static str = "sha256:";
+
+    /// 计算数据对应的句柄
+    pub fn for_bytes(data: &[u8]) -> Self {
+        Self(format!("{}{:x}", Self::PREFIX, Sha256::digest(data)))
+    }
+
+    /// 解析句柄字符串
+    pub fn parse(s: &str) -> Result<Self> {
+        let hex = s
+            .strip_prefix(Self::PREFIX)
+            .ok_or_else(|| anyhow::anyhow!("无效的制品句柄: {}", s))?;
+        if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow::anyhow!("无效的制品句柄: {}", s));
+        }
+        Ok(Self(s.to_ascii_lowercase()))
+    }
+
+    /// 获取句柄字符串
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// 摘要部分（不含前缀）
+    fn digest(&self) -> &str {
+        &self.0[Self::PREFIX.len()..]
+    }
+}
+
+impl fmt::Display for ArtifactId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// 制品存储
+pub trait ArtifactStore: fmt::Debug + Send + Sync {
+    /// 写入数据，返回句柄（相同内容得到相同句柄）
+    fn put(&self, data: &[u8]) -> Result<ArtifactId>;
+    /// 按句柄读取数据
+    fn get(&self, id: &ArtifactId) -> Result<Vec<u8>>;
+    /// 是否存在
+    fn contains(&self, id: &ArtifactId) -> bool;
+    /// 删除制品
+    fn remove(&self, id: &ArtifactId) -> Result<()>;
+}
+
+/// 内存制品存储
+#[derive(Debug, Default)]
+pub struct MemoryArtifactStore {
+    blobs: RwLock<HashMap<ArtifactId, Arc<[u8]>>>,
+}
+
+impl MemoryArtifactStore {
+    /// 创建空存储
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ArtifactStore for MemoryArtifactStore {
+    fn put(&self, data: &[u8]) -> Result<ArtifactId> {
+        let id = ArtifactId::for_bytes(data);
+        let mut blobs = self
+            .blobs
+            .write()
+            .map_err(|_| anyhow::anyhow!("制品存储锁已损坏"))?;
+        blobs.entry(id.clone()).or_insert_with(|| Arc::from(data));
+        Ok(id)
+    }
+
+    fn get(&self, id: &ArtifactId) -> Result<Vec<u8>> {
+        let blobs = self
+            .blobs
+            .read()
+            .map_err(|_| anyhow::anyhow!("制品存储锁已损坏"))?;
+        blobs
+            .get(id)
+            .map(|data| data.to_vec())
+            .ok_or_else(|| anyhow::anyhow!("制品不存在: {}", id))
+    }
+
+    fn contains(&self, id: &ArtifactId) -> bool {
+        self.blobs
+            .read()
+            .map(|blobs| blobs.contains_key(id))
+            .unwrap_or(false)
+    }
+
+    fn remove(&self, id: &ArtifactId) -> Result<()> {
+        let mut blobs = self
+            .blobs
+            .write()
+            .map_err(|_| anyhow::anyhow!("制品存储锁已损坏"))?;
+        blobs.remove(id);
+        Ok(())
+    }
+}
+
+/// 不可用的制品存储：无共享配置的调用路径使用，读写均报错
+#[derive(Debug, Default)]
+pub(crate) struct UnavailableArtifactStore;
+
+impl UnavailableArtifactStore {
+    fn unavailable() -> anyhow::Error {
+        anyhow::anyhow!(
+            "当前调用未配置制品存储，请使用 execute_action_with_config 传入 ExecutorConfig"
+        )
+    }
+}
+
+impl ArtifactStore for UnavailableArtifactStore {
+    fn put(&self, _data: &[u8]) -> Result<ArtifactId> {
+        Err(Self::unavailable())
+    }
+
+    fn get(&self, _id: &ArtifactId) -> Result<Vec<u8>> {
+        Err(Self::unavailable())
+    }
+
+    fn contains(&self, _id: &ArtifactId) -> bool {
+        false
+    }
+
+    fn remove(&self, _id: &ArtifactId) -> Result<()> {
+        Err(Self::unavailable())
+    }
+}
+
+/// 文件系统制品存储（`<root>/<前两位摘要>/<摘要>`）
+#[derive(Debug, Clone)]
+pub struct FsArtifactStore {
+    root: PathBuf,
+}
+
+impl FsArtifactStore {
+    /// 以指定目录为根创建
+    pub fn new<P: Into<PathBuf>>(root: P) -> Result<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(&root)
+            .with_context(|| format!("创建制品目录失败: {root:?}"))?;
+        Ok(Self { root })
+    }
+
+    fn path_of(&self, id: &ArtifactId) -> PathBuf {
+        let digest = id.digest();
+        self.root.join(&digest[..2]).join(digest)
+    }
+}
+
+impl ArtifactStore for FsArtifactStore {
+    fn put(&self, data: &[u8]) -> Result<ArtifactId> {
+        let id = ArtifactId::for_bytes(data);
+        let path = self.path_of(&id);
+        if path.exists() {
+            return Ok(id);
+        }
+
+        let dir = path.parent().expect("artifact path has parent");
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("创建制品目录失败: {dir:?}"))?;
+        // 先写临时文件再重命名，避免读到写了一半的制品
+        let tmp =
+            dir.join(format!(".{}.{}", id.digest(), uuid::Uuid::new_v4()));
+        std::fs::write(&tmp, data)
+            .with_context(|| format!("写入制品失败: {id}"))?;
+        std::fs::rename(&tmp, &path)
+            .with_context(|| format!("写入制品失败: {id}"))?;
+        Ok(id)
+    }
+
+    fn get(&self, id: &ArtifactId) -> Result<Vec<u8>> {
+        std::fs::read(self.path_of(id))
+            .with_context(|| format!("制品不存在: {id}"))
+    }
+
+    fn contains(&self, id: &ArtifactId) -> bool {
+        self.path_of(id).exists()
+    }
+
+    fn remove(&self, id: &ArtifactId) -> Result<()> {
+        match std::fs::remove_file(self.path_of(id)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("删除制品失败: {id}"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_store(store: &dyn ArtifactStore) {
+        let id = store.put(b"hello artifact").unwrap();
+        assert!(id.as_str().starts_with("sha256:"));
+        assert_eq!(store.put(b"hello artifact").unwrap(), id);
+        assert!(store.contains(&id));
+        assert_eq!(store.get(&id).unwrap(), b"hello artifact");
+
+        store.remove(&id).unwrap();
+        assert!(!store.contains(&id));
+        assert!(store.get(&id).is_err());
+    }
+
+    #[test]
+    fn test_artifact_id() {
+        let id = ArtifactId::for_bytes(b"");
+        assert_eq!(
+            id.as_str(),
+            "sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(ArtifactId::parse(id.as_str()).unwrap(), id);
+        assert!(ArtifactId::parse("md5:abc").is_err());
+        assert!(ArtifactId::parse("sha256:../../etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_memory_store() {
+        check_store(&MemoryArtifactStore::new());
+    }
+
+    #[test]
+    fn test_fs_store() {
+        let root = std::env::temp_dir()
+            .join(format!("flowbuilder-artifacts-{}", std::process::id()));
+        check_store(&FsArtifactStore::new(&root).unwrap());
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
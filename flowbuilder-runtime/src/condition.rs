@@ -0,0 +1,83 @@
+//! # FlowBuilder Runtime - 条件求值
+//!
+//! 运行时不内置表达式语言；条件由配置层注入的 [`ConditionEvaluator`]
+//! 基于当前上下文变量求值
+
+use anyhow::Result;
+use flowbuilder_context::{SharedContext, VarMap};
+use std::fmt;
+use std::sync::Arc;
+
+/// 条件求值器
+pub trait ConditionEvaluator: fmt::Debug + Send + Sync {
+    /// 基于上下文变量求值条件表达式
+    fn evaluate(
+        &self,
+        condition: &str,
+        variables: &VarMap<String>,
+    ) -> Result<bool>;
+}
+
+/// 求值条件；未配置求值器时只接受字面量 `true`/`false`
+pub(crate) async fn evaluate_condition(
+    condition: &str,
+    context: &SharedContext,
+    evaluator: Option<&Arc<dyn ConditionEvaluator>>,
+) -> Result<bool> {
+    match evaluator {
+        Some(evaluator) => {
+            let variables = context.lock().await.variables.clone();
+            evaluator.evaluate(condition, &variables)
+        }
+        None => match condition.trim() {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(anyhow::anyhow!(
+                "未配置条件求值器，无法求值条件: {}",
+                condition
+            )),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flowbuilder_context::FlowContext;
+    use tokio::sync::Mutex;
+
+    /// 条件为变量名，变量值为 "yes" 时成立
+    #[derive(Debug)]
+    struct VarIsYes;
+
+    impl ConditionEvaluator for VarIsYes {
+        fn evaluate(
+            &self,
+            condition: &str,
+            variables: &VarMap<String>,
+        ) -> Result<bool> {
+            Ok(variables.get(condition).map(String::as_str) == Some("yes"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_condition() {
+        let context = Arc::new(Mutex::new(FlowContext::default()));
+        context
+            .lock()
+            .await
+            .set_variable("ready".to_string(), "yes".to_string());
+
+        let evaluator: Arc<dyn ConditionEvaluator> = Arc::new(VarIsYes);
+        assert!(evaluate_condition("ready", &context, Some(&evaluator))
+            .await
+            .unwrap());
+        assert!(!evaluate_condition("missing", &context, Some(&evaluator))
+            .await
+            .unwrap());
+
+        // 未配置求值器时只接受字面量
+        assert!(!evaluate_condition("false", &context, None).await.unwrap());
+        assert!(evaluate_condition("ready", &context, None).await.is_err());
+    }
+}
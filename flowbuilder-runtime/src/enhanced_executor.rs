@@ -2,28 +2,127 @@
 //!
 //! 基于执行计划的任务执行器，负责执行具体的任务
 
+use crate::action_context::ActionContext;
+use crate::action_handler::{ActionHandler, ActionRegistry};
+use crate::artifact::{
+    ArtifactId, ArtifactStore, MemoryArtifactStore, UnavailableArtifactStore,
+};
+use crate::condition::{self, ConditionEvaluator};
+use crate::parameter::{self, ParameterResolver};
+use crate::run_context::RunContext;
+use crate::subflow::{self, WorkflowRunner};
 use anyhow::Result;
 use flowbuilder_context::SharedContext;
 use flowbuilder_core::{
     ActionSpec, ExecutionNode, ExecutionPhase, ExecutionPlan, Executor,
-    ExecutorStatus, PhaseExecutionMode, RetryStrategy,
+    ExecutorStatus, LoopConfig, NativeAction, PhaseExecutionMode,
 };
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::Semaphore;
-// tracing 宏无需显式 use 引入
+use tracing::Instrument;
 
 /// 增强的任务执行器
+///
+/// 配置在创建后不再变化，`execute_plan` 只需 `&self`，同一实例可同时驱动
+/// 多个计划；单次执行的状态与统计随返回的结果（或 [`PlanHandle`]）给出
 pub struct EnhancedTaskExecutor {
     /// 执行器配置
     config: ExecutorConfig,
-    /// 执行器状态
-    status: ExecutorStatus,
+    /// 进行中的执行数（按开始时的停止代数分组）
+    active_runs: std::sync::Mutex<BTreeMap<u64, usize>>,
+    /// 停止代数，每次 `request_stop` 加一，只影响此前已开始的执行
+    stop_generation: AtomicU64,
     /// 并发控制信号量
     semaphore: Arc<Semaphore>,
-    /// 执行统计（可选）
+    /// 互斥组锁（按组名懒创建，跨计划共享）
+    mutex_groups:
+        std::sync::Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    /// 累计执行统计（可选）
     #[allow(dead_code)]
-    stats: ExecutionStats,
+    stats: std::sync::Mutex<ExecutionStats>,
+}
+
+/// 进行中执行的计数守卫（提前返回时同样递减）
+struct ActiveRun<'a> {
+    executor: &'a EnhancedTaskExecutor,
+    /// 开始时的停止代数
+    generation: u64,
+}
+
+impl<'a> ActiveRun<'a> {
+    fn enter(executor: &'a EnhancedTaskExecutor) -> Self {
+        let mut runs = executor
+            .active_runs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let generation = executor.stop_generation.load(Ordering::SeqCst);
+        *runs.entry(generation).or_default() += 1;
+        Self {
+            executor,
+            generation,
+        }
+    }
+
+    /// 开始后是否收到过停止请求
+    fn stop_requested(&self) -> bool {
+        self.executor.stop_generation.load(Ordering::SeqCst) != self.generation
+    }
+}
+
+impl Drop for ActiveRun<'_> {
+    fn drop(&mut self) {
+        let mut runs = self
+            .executor
+            .active_runs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(count) = runs.get_mut(&self.generation) {
+            *count -= 1;
+            if *count == 0 {
+                runs.remove(&self.generation);
+            }
+        }
+    }
+}
+
+/// 后台执行的计划句柄（由 [`EnhancedTaskExecutor::spawn_plan`] 返回）
+#[derive(Debug)]
+pub struct PlanHandle {
+    run: RunContext,
+    task: tokio::task::JoinHandle<Result<ExecutionResult>>,
+}
+
+impl PlanHandle {
+    /// 本次执行的状态
+    pub fn status(&self) -> ExecutorStatus {
+        if self.run.cancel.is_cancelled() {
+            ExecutorStatus::Stopped
+        } else if self.task.is_finished() {
+            ExecutorStatus::Idle
+        } else {
+            ExecutorStatus::Running
+        }
+    }
+
+    /// 是否已结束
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// 取消本次执行（不影响同一执行器上的其他执行）
+    pub fn cancel(&self) {
+        self.run.cancel.cancel();
+    }
+
+    /// 等待执行结束并取得结果
+    pub async fn join(self) -> Result<ExecutionResult> {
+        self.task
+            .await
+            .map_err(|e| anyhow::anyhow!("执行任务异常结束: {}", e))?
+    }
 }
 
 /// 执行器配置
@@ -33,6 +132,22 @@ pub struct ExecutorConfig {
     pub max_concurrent_tasks: usize,
     /// 默认超时时间（毫秒）
     pub default_timeout: u64,
+    /// 制品存储（节点间传递大块数据）
+    pub artifact_store: Arc<dyn ArtifactStore>,
+    /// 子工作流运行器（`workflow` 动作使用）
+    pub workflow_runner: Option<Arc<dyn WorkflowRunner>>,
+    /// 条件求值器（复合子动作的 `condition` 与循环条件使用）
+    pub condition_evaluator: Option<Arc<dyn ConditionEvaluator>>,
+    /// 参数求值器（分派前求值动作参数中的 `${...}` 表达式）
+    pub parameter_resolver: Option<Arc<dyn ParameterResolver>>,
+    /// 循环节点的迭代次数上限（与节点自身的上限取较小值）
+    pub max_loop_iterations: u32,
+    /// 自定义动作类型（分派时优先于内置动作）
+    pub action_handlers: ActionRegistry,
+    /// cmd 动作保留的 stdout/stderr 最大字节数（超出部分截断，`None` 不限制）
+    pub max_output_bytes: Option<usize>,
+    /// 并行阶段按节点优先级（数值越小越优先）启动并分配并发名额
+    pub respect_priority: bool,
 }
 
 impl Default for ExecutorConfig {
@@ -40,10 +155,33 @@ impl Default for ExecutorConfig {
         Self {
             max_concurrent_tasks: 10,
             default_timeout: 30000, // 30秒
+            artifact_store: Arc::new(MemoryArtifactStore::new()),
+            workflow_runner: None,
+            condition_evaluator: None,
+            parameter_resolver: None,
+            max_loop_iterations: 1000,
+            action_handlers: ActionRegistry::default(),
+            max_output_bytes: Some(1024 * 1024), // 1 MiB
+            respect_priority: true,
         }
     }
 }
 
+/// 单次执行的轻量汇总（始终可用，不依赖 perf-metrics 特性）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutionSummary {
+    /// 节点总数
+    pub total: usize,
+    /// 成功节点数
+    pub succeeded: usize,
+    /// 失败节点数
+    pub failed: usize,
+    /// 跳过节点数
+    pub skipped: usize,
+    /// 总执行时间
+    pub total_duration: Duration,
+}
+
 /// 执行统计
 #[derive(Debug, Clone, Default)]
 #[allow(dead_code)]
@@ -76,9 +214,11 @@ impl EnhancedTaskExecutor {
 
         Self {
             config,
-            status: ExecutorStatus::Idle,
+            active_runs: std::sync::Mutex::new(BTreeMap::new()),
+            stop_generation: AtomicU64::new(0),
             semaphore,
-            stats: ExecutionStats::default(),
+            mutex_groups: std::sync::Mutex::new(HashMap::new()),
+            stats: std::sync::Mutex::new(ExecutionStats::default()),
         }
     }
 
@@ -88,20 +228,43 @@ impl EnhancedTaskExecutor {
 
         Self {
             config,
-            status: ExecutorStatus::Idle,
+            active_runs: std::sync::Mutex::new(BTreeMap::new()),
+            stop_generation: AtomicU64::new(0),
             semaphore,
-            stats: ExecutionStats::default(),
+            mutex_groups: std::sync::Mutex::new(HashMap::new()),
+            stats: std::sync::Mutex::new(ExecutionStats::default()),
         }
     }
 
+    /// 在后台执行计划，返回可查询状态、取消与等待的句柄
+    ///
+    /// `run` 的取消令牌由句柄持有，`PlanHandle::cancel` 只取消本次执行
+    pub fn spawn_plan(
+        self: &Arc<Self>,
+        plan: ExecutionPlan,
+        context: SharedContext,
+        run: RunContext,
+    ) -> PlanHandle {
+        let executor = self.clone();
+        let task_run = run.clone();
+        let task = tokio::spawn(async move {
+            executor.execute_plan(plan, context, &task_run).await
+        });
+        PlanHandle { run, task }
+    }
+
     /// 执行执行计划
-    #[tracing::instrument(level = "info", skip(self, context), fields(workflow = %plan.metadata.workflow_name, phases = plan.phases.len()))]
+    ///
+    /// `run` 携带取消令牌、截止时间与父 span；取消或超过截止时间后，
+    /// 进行中的节点被中断，后续阶段不再执行
+    #[tracing::instrument(level = "info", parent = &run.span, skip(self, context, run), fields(workflow = %plan.metadata.workflow_name, plan_id = %plan.metadata.plan_id, phases = plan.phases.len(), trace_id = tracing::field::Empty))]
     pub async fn execute_plan(
-        &mut self,
+        &self,
         plan: ExecutionPlan,
         context: SharedContext,
+        run: &RunContext,
     ) -> Result<ExecutionResult> {
-        self.status = ExecutorStatus::Running;
+        let active = ActiveRun::enter(self);
         let start_time = Instant::now();
 
         #[cfg(feature = "detailed-logging")]
@@ -119,32 +282,53 @@ impl EnhancedTaskExecutor {
             error_message: None,
         };
 
+        tracing::Span::current()
+            .record("trace_id", context.lock().await.trace_id.as_str());
+
         // 设置环境变量和流程变量到上下文
         self.setup_context(&plan, context.clone()).await?;
 
+        if plan.is_empty() {
+            tracing::info!(workflow = %plan.metadata.workflow_name, "执行计划不含节点，直接完成");
+        }
+
+        // 因入边条件不成立（或依赖被跳过）而跳过的节点
+        let mut skipped = HashSet::new();
+
         // 按阶段执行
         #[cfg(feature = "detailed-logging")]
         for (index, phase) in plan.phases.iter().enumerate() {
+            if Self::stop_requested(&active, &mut result) {
+                break;
+            }
             tracing::info!(phase_index = index + 1, phase_name = %phase.name, mode = ?phase.execution_mode, "执行阶段");
             let phase_start = Instant::now();
-            let phase_result =
-                match self.execute_phase(phase, context.clone()).await {
-                    Ok(r) => r,
-                    Err(e) => {
-                        result.success = false;
-                        result.error_message = Some(e.to_string());
-                        PhaseResult {
-                            phase_id: phase.id.clone(),
-                            phase_name: phase.name.clone(),
-                            start_time: phase_start,
-                            end_time: Some(Instant::now()),
-                            duration: phase_start.elapsed(),
-                            success: false,
-                            error_message: Some(e.to_string()),
-                            node_results: Vec::new(),
-                        }
+            let phase_result = match self
+                .execute_phase(
+                    phase,
+                    context.clone(),
+                    run,
+                    &active,
+                    &mut skipped,
+                )
+                .await
+            {
+                Ok(r) => r,
+                Err(e) => {
+                    result.success = false;
+                    result.error_message = Some(e.to_string());
+                    PhaseResult {
+                        phase_id: phase.id.clone(),
+                        phase_name: phase.name.clone(),
+                        start_time: phase_start,
+                        end_time: Some(Instant::now()),
+                        duration: phase_start.elapsed(),
+                        success: false,
+                        error_message: Some(e.to_string()),
+                        node_results: Vec::new(),
                     }
-                };
+                }
+            };
             result.phase_results.push(phase_result);
             if !result.success {
                 break;
@@ -152,25 +336,36 @@ impl EnhancedTaskExecutor {
         }
         #[cfg(not(feature = "detailed-logging"))]
         for phase in plan.phases.iter() {
+            if Self::stop_requested(&active, &mut result) {
+                break;
+            }
             let phase_start = Instant::now();
-            let phase_result =
-                match self.execute_phase(phase, context.clone()).await {
-                    Ok(r) => r,
-                    Err(e) => {
-                        result.success = false;
-                        result.error_message = Some(e.to_string());
-                        PhaseResult {
-                            phase_id: phase.id.clone(),
-                            phase_name: phase.name.clone(),
-                            start_time: phase_start,
-                            end_time: Some(Instant::now()),
-                            duration: phase_start.elapsed(),
-                            success: false,
-                            error_message: Some(e.to_string()),
-                            node_results: Vec::new(),
-                        }
+            let phase_result = match self
+                .execute_phase(
+                    phase,
+                    context.clone(),
+                    run,
+                    &active,
+                    &mut skipped,
+                )
+                .await
+            {
+                Ok(r) => r,
+                Err(e) => {
+                    result.success = false;
+                    result.error_message = Some(e.to_string());
+                    PhaseResult {
+                        phase_id: phase.id.clone(),
+                        phase_name: phase.name.clone(),
+                        start_time: phase_start,
+                        end_time: Some(Instant::now()),
+                        duration: phase_start.elapsed(),
+                        success: false,
+                        error_message: Some(e.to_string()),
+                        node_results: Vec::new(),
                     }
-                };
+                }
+            };
             result.phase_results.push(phase_result);
             if !result.success {
                 break;
@@ -186,8 +381,6 @@ impl EnhancedTaskExecutor {
             self.update_stats(&result);
         }
 
-        self.status = ExecutorStatus::Idle;
-
         #[cfg(feature = "detailed-logging")]
         {
             tracing::info!(total_duration_ms = ?result.total_duration, "执行计划完成");
@@ -196,13 +389,37 @@ impl EnhancedTaskExecutor {
         Ok(result)
     }
 
+    /// 请求停止：进行中的执行在当前阶段结束后停止，并行阶段不再派发新
+    /// 节点，已派发的节点执行完毕后返回；之后开始的执行不受影响
+    pub fn request_stop(&self) {
+        self.stop_generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// 检查是否已请求停止，是则将执行结果标记为失败
+    fn stop_requested(
+        active: &ActiveRun<'_>,
+        result: &mut ExecutionResult,
+    ) -> bool {
+        if !active.stop_requested() {
+            return false;
+        }
+        tracing::info!(plan_id = %result.plan_id, "收到停止请求，不再执行后续阶段");
+        result.success = false;
+        result.error_message = Some("stopped by request".to_string());
+        true
+    }
+
     /// 执行阶段
-    #[tracing::instrument(level = "info", skip(self, context), fields(phase = %phase.name, mode = ?phase.execution_mode))]
+    #[tracing::instrument(level = "info", skip(self, context, run, active, skipped), fields(phase = %phase.name, phase_id = %phase.id, mode = ?phase.execution_mode))]
     async fn execute_phase(
-        &mut self,
+        &self,
         phase: &ExecutionPhase,
         context: SharedContext,
+        run: &RunContext,
+        active: &ActiveRun<'_>,
+        skipped: &mut HashSet<String>,
     ) -> Result<PhaseResult> {
+        run.check()?;
         let start_time = Instant::now();
         let mut phase_result = PhaseResult {
             phase_id: phase.id.clone(),
@@ -215,31 +432,55 @@ impl EnhancedTaskExecutor {
             node_results: Vec::new(),
         };
 
-        // 检查阶段条件
-        if let Some(_condition) = &phase.condition {
-            // 这里应该使用表达式评估器检查条件
-            // 为了简化，这里假设条件总是满足
-            #[cfg(feature = "detailed-logging")]
-            {
-                tracing::debug!("检查阶段条件(已省略表达式)");
+        // 空阶段直接成功
+        if phase.nodes.is_empty() {
+            phase_result.end_time = Some(Instant::now());
+            return Ok(phase_result);
+        }
+
+        // 阶段条件（含条件执行模式）不成立时，阶段内节点全部跳过
+        if let Some(reason) = self.phase_skip_reason(phase, &context).await? {
+            tracing::info!(phase = %phase.name, %reason, "跳过阶段");
+            for node in &phase.nodes {
+                skipped.insert(node.id.clone());
+                phase_result.node_results.push(NodeResult::skipped(node));
             }
+            phase_result.end_time = Some(Instant::now());
+            phase_result.duration = start_time.elapsed();
+            return Ok(phase_result);
         }
 
         match phase.execution_mode {
             PhaseExecutionMode::Sequential => {
                 for node in &phase.nodes {
                     let node_result =
-                        self.execute_node(node, context.clone()).await?;
+                        match self.route_node(node, &context, skipped).await? {
+                            Some(result) => result,
+                            None => {
+                                self.execute_node(node, context.clone(), run)
+                                    .await?
+                            }
+                        };
                     phase_result.node_results.push(node_result);
                 }
             }
             PhaseExecutionMode::Parallel => {
+                let nodes = self.by_priority(&phase.nodes);
+
                 #[cfg(not(feature = "parallel"))]
                 {
                     // 并行被禁用时退化为顺序
-                    for node in &phase.nodes {
-                        let node_result =
-                            self.execute_node(node, context.clone()).await?;
+                    for node in nodes {
+                        let node_result = match self
+                            .route_node(node, &context, skipped)
+                            .await?
+                        {
+                            Some(result) => result,
+                            None => {
+                                self.execute_node(node, context.clone(), run)
+                                    .await?
+                            }
+                        };
                         phase_result.node_results.push(node_result);
                     }
                     phase_result.end_time = Some(Instant::now());
@@ -250,21 +491,58 @@ impl EnhancedTaskExecutor {
                 #[cfg(feature = "parallel")]
                 let mut handles = Vec::new();
 
-                for node in &phase.nodes {
+                for node in nodes {
+                    // 请求停止后不再派发，已派发的节点照常等待完成
+                    if active.stop_requested() {
+                        skipped.insert(node.id.clone());
+                        phase_result
+                            .node_results
+                            .push(NodeResult::skipped(node));
+                        continue;
+                    }
+                    if let Some(result) =
+                        self.route_node(node, &context, skipped).await?
+                    {
+                        phase_result.node_results.push(result);
+                        continue;
+                    }
                     let node_clone = node.clone();
                     let context_clone = context.clone();
                     let semaphore = self.semaphore.clone();
+                    let group = self.mutex_group(node);
                     let config = self.config.clone();
-
-                    let handle = tokio::spawn(async move {
-                        let _permit = semaphore.acquire().await.unwrap();
+                    let run = run.clone();
+
+                    // 按优先级启动时在派发前按序取得名额，保证高优先级节点先
+                    // 占用；属于互斥组的节点仍在任务内先取锁再取名额
+                    let permit =
+                        if self.config.respect_priority && group.is_none() {
+                            Some(semaphore.clone().acquire_owned().await?)
+                        } else {
+                            None
+                        };
+
+                    let task = async move {
+                        // 先取得互斥组锁，等待期间不占用并发名额
+                        let _exclusive = match &group {
+                            Some(group) => Some(group.lock().await),
+                            None => None,
+                        };
+                        let _permit = match permit {
+                            Some(permit) => permit,
+                            None => semaphore.acquire_owned().await.unwrap(),
+                        };
                         Self::execute_node_static(
                             &node_clone,
                             context_clone,
                             &config,
+                            &run,
                         )
                         .await
-                    });
+                    }
+                    // 保持节点 span 挂在当前阶段 span 之下
+                    .in_current_span();
+                    let handle = tokio::spawn(task);
 
                     handles.push(handle);
                 }
@@ -291,23 +569,18 @@ impl EnhancedTaskExecutor {
                     }
                 }
             }
-            PhaseExecutionMode::Conditional { condition: _ } => {
-                // 检查条件
-                #[cfg(feature = "detailed-logging")]
-                tracing::debug!("检查条件(已忽略具体表达式)");
-
-                // 简化的条件检查，实际应该使用表达式评估器
-                let condition_met = true; // 假设条件满足
-
-                if condition_met {
-                    for node in &phase.nodes {
-                        let node_result =
-                            self.execute_node(node, context.clone()).await?;
-                        phase_result.node_results.push(node_result);
-                    }
-                } else {
-                    #[cfg(feature = "detailed-logging")]
-                    tracing::info!(phase = %phase.name, "跳过阶段 (条件不满足)");
+            PhaseExecutionMode::Conditional { .. } => {
+                // 条件已在阶段开始时求值，成立时按顺序执行
+                for node in &phase.nodes {
+                    let node_result =
+                        match self.route_node(node, &context, skipped).await? {
+                            Some(result) => result,
+                            None => {
+                                self.execute_node(node, context.clone(), run)
+                                    .await?
+                            }
+                        };
+                    phase_result.node_results.push(node_result);
                 }
             }
         }
@@ -318,23 +591,141 @@ impl EnhancedTaskExecutor {
         Ok(phase_result)
     }
 
+    /// 求值阶段条件与条件执行模式的条件，返回跳过原因
+    async fn phase_skip_reason(
+        &self,
+        phase: &ExecutionPhase,
+        context: &SharedContext,
+    ) -> Result<Option<String>> {
+        let mode_condition = match &phase.execution_mode {
+            PhaseExecutionMode::Conditional { condition } => Some(condition),
+            _ => None,
+        };
+        for cond in phase.condition.iter().chain(mode_condition) {
+            let met = condition::evaluate_condition(
+                cond,
+                context,
+                self.config.condition_evaluator.as_ref(),
+            )
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!("阶段 {} 条件求值失败: {e:#}", phase.id)
+            })?;
+            if !met {
+                return Ok(Some(format!("阶段条件不满足: {cond}")));
+            }
+        }
+        Ok(None)
+    }
+
+    /// 按入边条件与节点条件决定节点是否执行
+    ///
+    /// 依赖被跳过、入边条件或节点条件不成立时，记录并返回跳过结果
+    async fn route_node(
+        &self,
+        node: &ExecutionNode,
+        context: &SharedContext,
+        skipped: &mut HashSet<String>,
+    ) -> Result<Option<NodeResult>> {
+        let mut reason = node
+            .dependencies
+            .iter()
+            .find(|d| skipped.contains(*d))
+            .map(|dep| format!("依赖任务被跳过: {dep}"));
+
+        if reason.is_none() {
+            // 按依赖顺序求值，保证结果确定
+            for dep in &node.dependencies {
+                let Some(cond) = node.edge_conditions.get(dep) else {
+                    continue;
+                };
+                let met = condition::evaluate_condition(
+                    cond,
+                    context,
+                    self.config.condition_evaluator.as_ref(),
+                )
+                .await
+                .map_err(|e| {
+                    anyhow::anyhow!("节点 {} 入边条件求值失败: {e:#}", node.id)
+                })?;
+                if !met {
+                    reason = Some(format!("{dep} 的分支条件不满足: {cond}"));
+                    break;
+                }
+            }
+        }
+
+        if let (None, Some(cond)) = (&reason, &node.condition) {
+            let met = condition::evaluate_condition(
+                cond,
+                context,
+                self.config.condition_evaluator.as_ref(),
+            )
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!("节点 {} 条件求值失败: {e:#}", node.id)
+            })?;
+            if !met {
+                reason = Some(format!("节点条件不满足: {cond}"));
+            }
+        }
+
+        Ok(reason.map(|reason| {
+            tracing::info!(node_id = %node.id, %reason, "跳过节点");
+            skipped.insert(node.id.clone());
+            NodeResult::skipped(node)
+        }))
+    }
+
     /// 执行节点
-    #[tracing::instrument(level = "debug", skip(self, context), fields(node_id = %node.id, node_name = %node.name))]
+    #[tracing::instrument(level = "debug", skip(self, context, run), fields(node_id = %node.id, node_name = %node.name))]
     async fn execute_node(
-        &mut self,
+        &self,
         node: &ExecutionNode,
         context: SharedContext,
+        run: &RunContext,
     ) -> Result<NodeResult> {
-        Self::execute_node_static(node, context, &self.config).await
+        let group = self.mutex_group(node);
+        let _exclusive = match &group {
+            Some(group) => Some(group.lock().await),
+            None => None,
+        };
+        Self::execute_node_static(node, context, &self.config, run).await
+    }
+
+    /// 并行阶段的节点派发顺序：启用 `respect_priority` 时按优先级升序
+    /// （稳定排序，同优先级保持原顺序）
+    fn by_priority<'a>(
+        &self,
+        nodes: &'a [ExecutionNode],
+    ) -> Vec<&'a ExecutionNode> {
+        let mut nodes: Vec<_> = nodes.iter().collect();
+        if self.config.respect_priority {
+            nodes.sort_by_key(|node| node.priority);
+        }
+        nodes
+    }
+
+    /// 节点所属互斥组的锁
+    fn mutex_group(
+        &self,
+        node: &ExecutionNode,
+    ) -> Option<Arc<tokio::sync::Mutex<()>>> {
+        let group = node.mutex_group.as_ref()?;
+        let mut groups =
+            self.mutex_groups.lock().unwrap_or_else(|e| e.into_inner());
+        Some(groups.entry(group.clone()).or_default().clone())
     }
 
     /// 静态执行节点（用于并发执行）
-    #[tracing::instrument(level = "debug", skip(context, config), fields(node_id = %node.id, node_name = %node.name))]
+    #[tracing::instrument(level = "debug", skip(context, config, run), fields(node_id = %node.id, node_name = %node.name))]
     async fn execute_node_static(
         node: &ExecutionNode,
         context: SharedContext,
         config: &ExecutorConfig,
+        run: &RunContext,
     ) -> Result<NodeResult> {
+        run.check()?;
         let start_time = Instant::now();
         let mut result = NodeResult {
             node_id: node.id.clone(),
@@ -345,6 +736,9 @@ impl EnhancedTaskExecutor {
             success: true,
             error_message: None,
             retry_count: 0,
+            iterations: 0,
+            skipped: false,
+            timeout_handler: None,
         };
 
         #[cfg(feature = "detailed-logging")]
@@ -352,25 +746,6 @@ impl EnhancedTaskExecutor {
             tracing::info!(node_id = %node.id, node_name = %node.name, "执行节点");
         }
 
-        // 检查节点条件
-        if let Some(_condition) = &node.condition {
-            #[cfg(feature = "detailed-logging")]
-            {
-                tracing::debug!("检查节点条件(已省略表达式)");
-            }
-            // 简化的条件检查
-            let condition_met = true;
-            if !condition_met {
-                #[cfg(feature = "detailed-logging")]
-                {
-                    tracing::info!(node = %node.name, "跳过节点 (条件不满足)");
-                }
-                result.end_time = Some(Instant::now());
-                result.duration = start_time.elapsed();
-                return Ok(result);
-            }
-        }
-
         // 执行重试逻辑（可关闭）
         #[cfg(feature = "retry")]
         let max_retries = node
@@ -384,15 +759,17 @@ impl EnhancedTaskExecutor {
 
         loop {
             let execute_result =
-                Self::execute_node_action(node, context.clone(), config).await;
+                Self::execute_node_action(node, context.clone(), config, run)
+                    .await;
 
             match execute_result {
-                Ok(()) => {
+                Ok(iterations) => {
+                    result.iterations = iterations;
                     result.success = true;
                     break;
                 }
                 Err(e) => {
-                    if retries < max_retries {
+                    if retries < max_retries && !run.is_stopped() {
                         retries += 1;
                         result.retry_count = retries;
 
@@ -405,26 +782,25 @@ impl EnhancedTaskExecutor {
                             #[cfg(not(feature = "retry"))]
                             { /* 重试功能关闭时不进入延迟逻辑 */ }
                             #[cfg(feature = "retry")]
-                            let delay = match retry_config.strategy {
-                                RetryStrategy::Fixed => retry_config.delay,
-                                RetryStrategy::Exponential { multiplier } => {
-                                    (retry_config.delay as f64
-                                        * multiplier.powi(retries as i32))
-                                        as u64
-                                }
-                                RetryStrategy::Linear { increment } => {
-                                    retry_config.delay
-                                        + (increment * retries as u64)
-                                }
-                            };
-                            #[cfg(feature = "retry")]
-                            tokio::time::sleep(Duration::from_millis(delay))
-                                .await;
+                            tokio::time::sleep(
+                                retry_config.strategy.delay_for(
+                                    Duration::from_millis(retry_config.delay),
+                                    retries,
+                                ),
+                            )
+                            .await;
                         }
                         continue;
                     } else {
                         result.success = false;
                         result.error_message = Some(e.to_string());
+                        // 最终一次执行超时：运行 on_timeout 处理动作并记录结果
+                        if e.is::<NodeTimeout>() {
+                            result.timeout_handler = Self::run_timeout_handler(
+                                node, &context, config,
+                            )
+                            .await;
+                        }
                         break;
                     }
                 }
@@ -438,36 +814,177 @@ impl EnhancedTaskExecutor {
     }
 
     /// 执行节点动作
-    #[tracing::instrument(level = "debug", skip(context, config), fields(node_id = %node.id, node_name = %node.name, action_type = %node.action_spec.action_type))]
+    #[tracing::instrument(level = "debug", skip(context, config, run), fields(node_id = %node.id, node_name = %node.name, action_type = %node.action_spec.action_type))]
     async fn execute_node_action(
         node: &ExecutionNode,
         context: SharedContext,
         config: &ExecutorConfig,
-    ) -> Result<()> {
-        let action_spec = &node.action_spec;
-
-        // 设置超时
+        run: &RunContext,
+    ) -> Result<u32> {
+        // 设置超时（循环节点的超时覆盖全部迭代）
         let timeout_duration = node
             .timeout_config
             .as_ref()
             .map(|c| Duration::from_millis(c.duration))
             .unwrap_or_else(|| Duration::from_millis(config.default_timeout));
 
-        let action_future = Self::execute_action_by_type(action_spec, context);
+        let action_future = async {
+            match &node.loop_config {
+                Some(loop_config) => {
+                    Self::execute_loop(node, loop_config, context, config).await
+                }
+                None => {
+                    Self::run_node_once(node, context, config).await?;
+                    Ok(1)
+                }
+            }
+        };
+
+        // 截止时间早于节点超时时，以截止时间为准
+        let (limit, by_deadline) = match run.remaining() {
+            Some(left) if left < timeout_duration => (left, true),
+            _ => (timeout_duration, false),
+        };
+        let outcome = tokio::select! {
+            biased;
+            _ = run.cancel.cancelled() => {
+                return Err(anyhow::anyhow!("节点 {} 已取消", node.name));
+            }
+            outcome = tokio::time::timeout(limit, action_future) => outcome,
+        };
 
-        match tokio::time::timeout(timeout_duration, action_future).await {
+        match outcome {
             Ok(result) => result,
+            Err(_) if by_deadline => {
+                Err(anyhow::anyhow!("节点 {} 超过执行截止时间", node.name))
+            }
             Err(_) => {
                 #[cfg(feature = "detailed-logging")]
                 {
                     tracing::error!(node = %node.name, "节点执行超时");
                 }
-                Err(anyhow::anyhow!("节点 {} 执行超时", node.name))
+                Err(NodeTimeout {
+                    node_name: node.name.clone(),
+                }
+                .into())
+            }
+        }
+    }
+
+    /// 运行节点的 on_timeout 处理动作；未配置 on_timeout 时返回 `None`
+    async fn run_timeout_handler(
+        node: &ExecutionNode,
+        context: &SharedContext,
+        config: &ExecutorConfig,
+    ) -> Option<TimeoutHandlerResult> {
+        let timeout_config = node.timeout_config.as_ref()?;
+        let target = timeout_config.on_timeout.clone()?;
+        let start_time = Instant::now();
+
+        let outcome = match &timeout_config.on_timeout_action {
+            Some(action_spec) => {
+                tracing::warn!(node_id = %node.id, on_timeout = %target, "执行超时处理动作");
+                let handler = Self::execute_action_with_config(
+                    action_spec,
+                    context.clone(),
+                    config,
+                );
+                match tokio::time::timeout(
+                    Duration::from_millis(config.default_timeout),
+                    handler,
+                )
+                .await
+                {
+                    Ok(outcome) => outcome,
+                    Err(_) => Err(anyhow::anyhow!("超时处理动作执行超时")),
+                }
+            }
+            None => Err(anyhow::anyhow!("未找到超时处理动作: {}", target)),
+        };
+
+        Some(TimeoutHandlerResult {
+            target,
+            success: outcome.is_ok(),
+            error_message: outcome.err().map(|e| format!("{e:#}")),
+            duration: start_time.elapsed(),
+        })
+    }
+
+    /// 执行循环节点，返回迭代次数
+    ///
+    /// 每次迭代后把迭代次数写入上下文变量 `<节点ID>.iteration`，
+    /// 再求值循环条件；条件不成立或达到迭代上限时结束
+    async fn execute_loop(
+        node: &ExecutionNode,
+        loop_config: &LoopConfig,
+        context: SharedContext,
+        config: &ExecutorConfig,
+    ) -> Result<u32> {
+        let max_iterations = loop_config
+            .max_iterations
+            .min(config.max_loop_iterations)
+            .max(1);
+        let mut iterations = 0;
+
+        loop {
+            Self::run_node_once(node, context.clone(), config)
+                .await
+                .map_err(|e| {
+                    anyhow::anyhow!(
+                        "循环第 {} 次迭代失败: {e:#}",
+                        iterations + 1
+                    )
+                })?;
+            iterations += 1;
+            context.lock().await.set_variable(
+                format!("{}.iteration", node.id),
+                iterations.to_string(),
+            );
+            tracing::debug!(node_id = %node.id, iteration = iterations, "循环迭代完成");
+
+            if iterations >= max_iterations {
+                tracing::warn!(node_id = %node.id, max_iterations, "循环达到最大迭代次数");
+                break;
+            }
+            let again = condition::evaluate_condition(
+                &loop_config.condition,
+                &context,
+                config.condition_evaluator.as_ref(),
+            )
+            .await?;
+            if !again {
+                break;
+            }
+        }
+
+        Ok(iterations)
+    }
+
+    /// 执行一次节点动作（原生节点直接调用闭包）
+    async fn run_node_once(
+        node: &ExecutionNode,
+        context: SharedContext,
+        config: &ExecutorConfig,
+    ) -> Result<()> {
+        match &node.native {
+            Some(native) => native.call(context).await,
+            None => {
+                Self::dispatch_action(
+                    &node.action_spec,
+                    context,
+                    config,
+                    Some(&node.id),
+                )
+                .await
             }
         }
     }
 
     /// 根据动作类型执行动作 (Public for demo purposes)
+    ///
+    /// 使用默认配置且不跨调用共享状态，因此不支持制品：`put_artifact`/
+    /// `get_artifact` 会报错。需要在动作间传递制品时请使用
+    /// [`execute_action_with_config`](Self::execute_action_with_config)
     pub fn execute_action_by_type(
         action_spec: &ActionSpec,
         context: SharedContext,
@@ -475,15 +992,176 @@ impl EnhancedTaskExecutor {
         Box<dyn std::future::Future<Output = Result<()>> + Send + '_>,
     > {
         Box::pin(async move {
+            let config = ExecutorConfig {
+                artifact_store: Arc::new(UnavailableArtifactStore),
+                ..ExecutorConfig::default()
+            };
+            Self::execute_action_with_config(action_spec, context, &config)
+                .await
+        })
+    }
+
+    /// 使用指定执行器配置执行动作
+    pub fn execute_action_with_config<'a>(
+        action_spec: &'a ActionSpec,
+        context: SharedContext,
+        config: &'a ExecutorConfig,
+    ) -> std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<()>> + Send + 'a>,
+    > {
+        Self::dispatch_action(action_spec, context, config, None)
+    }
+
+    /// 注册自定义动作类型
+    pub fn register_action(
+        &mut self,
+        name: &str,
+        handler: Arc<dyn ActionHandler>,
+    ) {
+        self.config.action_handlers.register(name, handler);
+    }
+
+    /// 本执行器可执行的动作类型（内置类型与已注册的自定义类型）
+    pub fn action_types(&self) -> Vec<&str> {
+        let mut types = Self::supported_action_types();
+        for action_type in self.config.action_handlers.action_types() {
+            if !types.contains(&action_type) {
+                types.push(action_type);
+            }
+        }
+        types
+    }
+
+    /// 当前构建支持的动作类型（与 `dispatch_action` 的分派一致）
+    pub fn supported_action_types() -> Vec<&'static str> {
+        // 依赖可选特性的类型
+        let optional = [
+            ("http_paginate", cfg!(feature = "http")),
+            ("sql", cfg!(feature = "sqlx")),
+            ("sql_stream", cfg!(feature = "sqlx")),
+        ];
+        [
+            "builtin",
+            "cmd",
+            "http",
+            "wasm",
+            "healthcheck",
+            "wait_for_port",
+            "composite",
+            "workflow",
+            NativeAction::ACTION_TYPE,
+        ]
+        .into_iter()
+        .chain(
+            optional
+                .into_iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(action_type, _)| action_type),
+        )
+        .collect()
+    }
+
+    /// 按动作类型分派执行（`node_id` 为所属节点）
+    fn dispatch_action<'a>(
+        action_spec: &'a ActionSpec,
+        context: SharedContext,
+        config: &'a ExecutorConfig,
+        node_id: Option<&'a str>,
+    ) -> std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<()>> + Send + 'a>,
+    > {
+        Box::pin(async move {
+            // 以当前上下文变量求值参数中的表达式；复合动作的子动作在各自
+            // 分派时求值，以便看到前序子动作写入的变量
+            let resolved = match &config.parameter_resolver {
+                Some(resolver) if action_spec.action_type != "composite" => {
+                    parameter::resolve_parameters(
+                        action_spec,
+                        &context,
+                        resolver,
+                    )
+                    .await?
+                }
+                _ => None,
+            };
+            let action_spec = resolved.as_ref().unwrap_or(action_spec);
+
+            if let Some(handler) =
+                config.action_handlers.get(&action_spec.action_type)
+            {
+                return handler.handle(action_spec, context).await;
+            }
             match action_spec.action_type.as_str() {
                 "builtin" => {
-                    Self::execute_builtin_action(action_spec, context).await
+                    let mut ctx = ActionContext::new(action_spec, context);
+                    if let Some(node_id) = node_id {
+                        ctx = ctx.with_node_id(node_id);
+                    }
+                    Self::execute_builtin_action(action_spec, &mut ctx, config)
+                        .await
+                }
+                "cmd" => {
+                    Self::execute_cmd_action(action_spec, context, config).await
                 }
-                "cmd" => Self::execute_cmd_action(action_spec, context).await,
                 "http" => Self::execute_http_action(action_spec, context).await,
+                #[cfg(feature = "http")]
+                "http_paginate" => {
+                    Self::execute_http_paginate_action(action_spec, context)
+                        .await
+                }
                 "wasm" => Self::execute_wasm_action(action_spec, context).await,
+                "healthcheck" => {
+                    let mut ctx = ActionContext::new(action_spec, context);
+                    if let Some(node_id) = node_id {
+                        ctx = ctx.with_node_id(node_id);
+                    }
+                    Self::execute_healthcheck_action(action_spec, &mut ctx)
+                        .await
+                }
+                "wait_for_port" => {
+                    let mut ctx = ActionContext::new(action_spec, context);
+                    if let Some(node_id) = node_id {
+                        ctx = ctx.with_node_id(node_id);
+                    }
+                    Self::execute_wait_for_port_action(action_spec, &mut ctx)
+                        .await
+                }
+                #[cfg(feature = "sqlx")]
+                "sql" => {
+                    let mut ctx = ActionContext::new(action_spec, context);
+                    if let Some(node_id) = node_id {
+                        ctx = ctx.with_node_id(node_id);
+                    }
+                    crate::sql::execute_sql_action(action_spec, &mut ctx).await
+                }
+                #[cfg(feature = "sqlx")]
+                "sql_stream" => {
+                    let mut ctx = ActionContext::new(action_spec, context);
+                    if let Some(node_id) = node_id {
+                        ctx = ctx.with_node_id(node_id);
+                    }
+                    crate::sql::execute_sql_stream_action(
+                        action_spec,
+                        &mut ctx,
+                        config,
+                    )
+                    .await
+                }
                 "composite" => {
-                    Self::execute_composite_action(action_spec, context).await
+                    Self::execute_composite_action(action_spec, context, config)
+                        .await
+                }
+                // 闭包保存在节点上，由 `run_node_once` 直接调用
+                NativeAction::ACTION_TYPE => {
+                    Err(anyhow::anyhow!("原生动作只能通过携带闭包的节点执行"))
+                }
+                "workflow" => {
+                    subflow::execute_workflow_action(
+                        action_spec,
+                        context,
+                        config.workflow_runner.as_ref(),
+                    )
+                    .await
                 }
                 _ => Err(anyhow::anyhow!(
                     "不支持的动作类型: {}",
@@ -496,63 +1174,57 @@ impl EnhancedTaskExecutor {
     /// 执行内置动作
     async fn execute_builtin_action(
         action_spec: &ActionSpec,
-        context: SharedContext,
+        ctx: &mut ActionContext,
+        config: &ExecutorConfig,
     ) -> Result<()> {
-        tracing::debug!("执行内置动作");
+        tracing::debug!(node_id = ?ctx.node_id(), "执行内置动作");
 
         // 获取操作类型参数
-        let operation = action_spec
-            .parameters
-            .get("operation")
-            .and_then(|v| v.as_str())
+        let operation = ctx
+            .input_str("operation")
             .ok_or_else(|| anyhow::anyhow!("内置动作缺少 'operation' 参数"))?;
 
         match operation {
             "set_variable" => {
-                let key = action_spec
-                    .parameters
-                    .get("key")
-                    .and_then(|v| v.as_str())
-                    .ok_or_else(|| {
-                        anyhow::anyhow!("set_variable 操作缺少 'key' 参数")
-                    })?;
-
-                let value =
-                    action_spec.parameters.get("value").ok_or_else(|| {
-                        anyhow::anyhow!("set_variable 操作缺少 'value' 参数")
+                let key = ctx.input_str("key").ok_or_else(|| {
+                    anyhow::anyhow!("set_variable 操作缺少 'key' 参数")
+                })?;
+
+                let value = ctx.input("value").ok_or_else(|| {
+                    anyhow::anyhow!("set_variable 操作缺少 'value' 参数")
+                })?;
+
+                ctx.set_var(key, format!("{value:?}")).await;
+                // 映射与列表另存为结构化变量，便于按原结构读回
+                if matches!(
+                    value,
+                    serde_yaml::Value::Mapping(_)
+                        | serde_yaml::Value::Sequence(_)
+                ) {
+                    let typed = serde_json::to_value(value).map_err(|e| {
+                        anyhow::anyhow!("变量 {} 无法转换为 JSON: {}", key, e)
                     })?;
-
-                let mut guard = context.lock().await;
-                guard.set_variable(key.to_string(), format!("{value:?}"));
+                    ctx.set_typed_var(key, typed).await;
+                }
                 tracing::debug!("设置变量: {} = {:?}", key, value);
             }
             "get_variable" => {
-                let key = action_spec
-                    .parameters
-                    .get("key")
-                    .and_then(|v| v.as_str())
-                    .ok_or_else(|| {
-                        anyhow::anyhow!("get_variable 操作缺少 'key' 参数")
-                    })?;
+                let key = ctx.input_str("key").ok_or_else(|| {
+                    anyhow::anyhow!("get_variable 操作缺少 'key' 参数")
+                })?;
 
-                let guard = context.lock().await;
-                if let Some(value) = guard.variables.get(key) {
+                if let Some(value) = ctx.get_var(key).await {
                     tracing::debug!("获取变量: {} = {}", key, value);
                 } else {
                     return Err(anyhow::anyhow!("变量 '{}' 不存在", key));
                 }
             }
             "log" => {
-                let message =
-                    action_spec.parameters.get("message").ok_or_else(|| {
-                        anyhow::anyhow!("log 操作缺少 'message' 参数")
-                    })?;
+                let message = ctx.input("message").ok_or_else(|| {
+                    anyhow::anyhow!("log 操作缺少 'message' 参数")
+                })?;
 
-                let level = action_spec
-                    .parameters
-                    .get("level")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("info");
+                let level = ctx.input_str("level").unwrap_or("info");
 
                 match level {
                     "debug" => tracing::debug!("内置日志: {:?}", message),
@@ -563,17 +1235,67 @@ impl EnhancedTaskExecutor {
                 }
             }
             "sleep" => {
-                let duration = action_spec
-                    .parameters
-                    .get("duration")
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        anyhow::anyhow!("sleep 操作缺少有效的 'duration' 参数")
-                    })?;
+                let duration = ctx.input_u64("duration").ok_or_else(|| {
+                    anyhow::anyhow!("sleep 操作缺少有效的 'duration' 参数")
+                })?;
 
                 tracing::debug!("睡眠 {} 毫秒", duration);
                 tokio::time::sleep(Duration::from_millis(duration)).await;
             }
+            "put_artifact" => {
+                let key = ctx.input_str("key").ok_or_else(|| {
+                    anyhow::anyhow!("put_artifact 操作缺少 'key' 参数")
+                })?;
+
+                let data = if let Some(file) = ctx.input_str("file") {
+                    tokio::fs::read(file).await.map_err(|e| {
+                        anyhow::anyhow!("读取制品文件失败: {}", e)
+                    })?
+                } else {
+                    let content = ctx.input("content").ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "put_artifact 操作缺少 'content' 或 'file' 参数"
+                        )
+                    })?;
+                    match content.as_str() {
+                        Some(s) => s.as_bytes().to_vec(),
+                        None => serde_json::to_vec(content)?,
+                    }
+                };
+
+                let id = config.artifact_store.put(&data)?;
+                tracing::debug!("写入制品: {} ({} 字节)", id, data.len());
+                ctx.set_var(key, id.to_string()).await;
+            }
+            "get_artifact" => {
+                let key = ctx.input_str("key").ok_or_else(|| {
+                    anyhow::anyhow!("get_artifact 操作缺少 'key' 参数")
+                })?;
+
+                let handle = ctx
+                    .get_var(key)
+                    .await
+                    .ok_or_else(|| anyhow::anyhow!("变量 '{}' 不存在", key))?;
+                let data =
+                    config.artifact_store.get(&ArtifactId::parse(&handle)?)?;
+
+                if let Some(path) = ctx.input_str("path") {
+                    tokio::fs::write(path, &data).await.map_err(|e| {
+                        anyhow::anyhow!("写入制品文件失败: {}", e)
+                    })?;
+                } else {
+                    let output = ctx.input_str("output").ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "get_artifact 操作缺少 'path' 或 'output' 参数"
+                        )
+                    })?;
+                    ctx.set_var(
+                        output,
+                        String::from_utf8_lossy(&data).into_owned(),
+                    )
+                    .await;
+                }
+            }
             _ => {
                 return Err(anyhow::anyhow!("不支持的内置操作: {}", operation));
             }
@@ -581,17 +1303,25 @@ impl EnhancedTaskExecutor {
 
         // 存储输出到上下文
         for (key, value) in &action_spec.outputs {
-            let mut guard = context.lock().await;
-            guard.set_variable(key.clone(), format!("{value:?}"));
+            ctx.set_output(key.clone(), format!("{value:?}"));
         }
+        ctx.commit_outputs().await;
 
         Ok(())
     }
 
     /// 执行命令动作
+    ///
+    /// `inherit_context_env: true` 将全部上下文变量导出为子进程环境变量，
+    /// `context_env_prefix` 仅导出以该前缀开头的变量；变量名转为大写，
+    /// 非字母数字字符替换为 `_`（如 `flow.user` → `FLOW_USER`）
+    ///
+    /// stdout/stderr 边读边截断到 `max_output_bytes`（截断时追加
+    /// `...[truncated]`）；参数 `stream: true` 时逐行写入 debug 日志
     async fn execute_cmd_action(
         action_spec: &ActionSpec,
         context: SharedContext,
+        config: &ExecutorConfig,
     ) -> Result<()> {
         tracing::debug!("执行命令动作");
 
@@ -627,6 +1357,26 @@ impl EnhancedTaskExecutor {
             cmd.current_dir(dir);
         }
 
+        // 导出上下文变量（显式 env 在后设置，同名时优先）
+        let context_env_prefix = action_spec
+            .parameters
+            .get("context_env_prefix")
+            .and_then(|v| v.as_str());
+        let inherit_context_env = action_spec
+            .parameters
+            .get("inherit_context_env")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        if inherit_context_env || context_env_prefix.is_some() {
+            let guard = context.lock().await;
+            for (key, value) in &guard.variables {
+                if context_env_prefix.is_some_and(|p| !key.starts_with(p)) {
+                    continue;
+                }
+                cmd.env(context_env_name(key), value);
+            }
+        }
+
         // 设置环境变量
         if let Some(env_vars) = action_spec.parameters.get("env") {
             if let Some(env_map) = env_vars.as_mapping() {
@@ -638,18 +1388,30 @@ impl EnhancedTaskExecutor {
             }
         }
 
-        let output = cmd
-            .output()
-            .await
+        let stream = action_spec
+            .parameters
+            .get("stream")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        cmd.stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true);
+        let mut child = cmd
+            .spawn()
             .map_err(|e| anyhow::anyhow!("执行命令失败: {}", e))?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let exit_code = output.status.code().unwrap_or(-1);
+        let limit = config.max_output_bytes;
+        let (stdout, stderr, status) = tokio::try_join!(
+            capture_output(child.stdout.take(), limit, stream, "stdout"),
+            capture_output(child.stderr.take(), limit, stream, "stderr"),
+            child.wait(),
+        )
+        .map_err(|e| anyhow::anyhow!("执行命令失败: {}", e))?;
+        let exit_code = status.code().unwrap_or(-1);
 
         tracing::debug!("命令执行完成，退出码: {}", exit_code);
 
-        if !output.status.success() {
+        if !status.success() {
             return Err(anyhow::anyhow!(
                 "命令执行失败，退出码: {}，错误输出: {}",
                 exit_code,
@@ -660,8 +1422,8 @@ impl EnhancedTaskExecutor {
         // 将命令输出存储到上下文
         {
             let mut guard = context.lock().await;
-            guard.set_variable("cmd_stdout".to_string(), stdout.to_string());
-            guard.set_variable("cmd_stderr".to_string(), stderr.to_string());
+            guard.set_variable("cmd_stdout".to_string(), stdout);
+            guard.set_variable("cmd_stderr".to_string(), stderr);
             guard.set_variable(
                 "cmd_exit_code".to_string(),
                 exit_code.to_string(),
@@ -677,6 +1439,201 @@ impl EnhancedTaskExecutor {
         Ok(())
     }
 
+    /// 执行健康检查动作
+    ///
+    /// 按 `interval`（毫秒，默认 1000）轮询 `url` 或 `tcp`（`host:port`），
+    /// 直到探测成功或超出 `timeout`（毫秒，默认 30000）/`max_attempts`。
+    /// HTTP 探测默认要求 2xx，可用 `expect_status` 与 `body_contains` 指定；
+    /// 结果写入 `healthcheck_attempts` 与 `healthcheck_latency_ms`
+    async fn execute_healthcheck_action(
+        action_spec: &ActionSpec,
+        ctx: &mut ActionContext,
+    ) -> Result<()> {
+        tracing::debug!(node_id = ?ctx.node_id(), "执行健康检查动作");
+
+        let target = match (ctx.input_str("url"), ctx.input_str("tcp")) {
+            #[cfg(feature = "http")]
+            (Some(url), None) => HealthTarget::Http(url),
+            #[cfg(not(feature = "http"))]
+            (Some(_), None) => return Err(anyhow::anyhow!("HTTP 功能未启用")),
+            (None, Some(addr)) => HealthTarget::Tcp(addr),
+            (Some(_), Some(_)) => {
+                return Err(anyhow::anyhow!(
+                    "健康检查动作的 'url' 与 'tcp' 参数只能指定一个"
+                ))
+            }
+            (None, None) => {
+                return Err(anyhow::anyhow!(
+                    "健康检查动作缺少 'url' 或 'tcp' 参数"
+                ))
+            }
+        };
+        let interval =
+            Duration::from_millis(ctx.input_u64("interval").unwrap_or(1000));
+        let budget =
+            Duration::from_millis(ctx.input_u64("timeout").unwrap_or(30_000));
+        let max_attempts = ctx.input_u64("max_attempts");
+        #[cfg(feature = "http")]
+        let expect_status = ctx.input_u64("expect_status");
+        #[cfg(feature = "http")]
+        let body_contains = ctx.input_str("body_contains");
+
+        #[cfg(feature = "http")]
+        let client = reqwest::Client::new();
+        let started = Instant::now();
+        let mut attempts = 0u64;
+
+        let (latency, last_error) = loop {
+            attempts += 1;
+            let probe_started = Instant::now();
+            let remaining = budget.saturating_sub(started.elapsed());
+            let probe = async {
+                match target {
+                    HealthTarget::Tcp(addr) => {
+                        tokio::net::TcpStream::connect(addr)
+                            .await
+                            .map(|_| ())
+                            .map_err(|e| anyhow::anyhow!("连接失败: {}", e))
+                    }
+                    #[cfg(feature = "http")]
+                    HealthTarget::Http(url) => {
+                        let response =
+                            client.get(url).send().await.map_err(|e| {
+                                anyhow::anyhow!("请求失败: {}", e)
+                            })?;
+                        let status = response.status();
+                        let healthy = match expect_status {
+                            Some(code) => u64::from(status.as_u16()) == code,
+                            None => status.is_success(),
+                        };
+                        if !healthy {
+                            return Err(anyhow::anyhow!(
+                                "状态码不符合预期: {}",
+                                status.as_u16()
+                            ));
+                        }
+                        if let Some(needle) = body_contains {
+                            let body = response.text().await.map_err(|e| {
+                                anyhow::anyhow!("读取响应体失败: {}", e)
+                            })?;
+                            if !body.contains(needle) {
+                                return Err(anyhow::anyhow!(
+                                    "响应体不包含 '{}'",
+                                    needle
+                                ));
+                            }
+                        }
+                        Ok(())
+                    }
+                }
+            };
+            let outcome = match tokio::time::timeout(remaining, probe).await {
+                Ok(outcome) => outcome,
+                Err(_) => Err(anyhow::anyhow!("探测超时")),
+            };
+            let latency = probe_started.elapsed();
+
+            match outcome {
+                Ok(()) => break (latency, None),
+                Err(e) => {
+                    tracing::debug!(attempts, "健康检查未通过: {}", e);
+                    let exhausted = max_attempts
+                        .is_some_and(|max| attempts >= max)
+                        || started.elapsed() + interval >= budget;
+                    if exhausted {
+                        break (latency, Some(e));
+                    }
+                }
+            }
+            tokio::time::sleep(interval).await;
+        };
+
+        ctx.set_output("healthcheck_attempts", attempts.to_string());
+        ctx.set_output(
+            "healthcheck_latency_ms",
+            latency.as_millis().to_string(),
+        );
+        if let Some(e) = last_error {
+            ctx.commit_outputs().await;
+            return Err(anyhow::anyhow!(
+                "健康检查失败（尝试 {} 次）: {}",
+                attempts,
+                e
+            ));
+        }
+
+        // 存储输出到上下文
+        for (key, value) in &action_spec.outputs {
+            ctx.set_output(key.clone(), format!("{value:?}"));
+        }
+        ctx.commit_outputs().await;
+
+        Ok(())
+    }
+
+    /// 执行端口等待动作
+    ///
+    /// 按 `interval`（毫秒，默认 500）重复连接 `host:port`，直到连接成功或
+    /// 超出 `timeout`（毫秒，默认 30000）。结果写入 `wait_for_port_success`
+    /// 与 `wait_for_port_elapsed_ms`
+    async fn execute_wait_for_port_action(
+        action_spec: &ActionSpec,
+        ctx: &mut ActionContext,
+    ) -> Result<()> {
+        tracing::debug!(node_id = ?ctx.node_id(), "执行端口等待动作");
+
+        let host = ctx.input_str("host").unwrap_or("127.0.0.1");
+        let port = ctx
+            .input_u64("port")
+            .and_then(|p| u16::try_from(p).ok())
+            .ok_or_else(|| {
+                anyhow::anyhow!("端口等待动作缺少有效的 'port' 参数")
+            })?;
+        let address = format!("{host}:{port}");
+        let interval =
+            Duration::from_millis(ctx.input_u64("interval").unwrap_or(500));
+        let timeout =
+            Duration::from_millis(ctx.input_u64("timeout").unwrap_or(30_000));
+
+        let started = Instant::now();
+        let connected = tokio::time::timeout(timeout, async {
+            loop {
+                match tokio::net::TcpStream::connect(&address).await {
+                    Ok(_) => break,
+                    Err(e) => {
+                        tracing::debug!("端口 {} 尚不可用: {}", address, e);
+                        tokio::time::sleep(interval).await;
+                    }
+                }
+            }
+        })
+        .await
+        .is_ok();
+        let elapsed = started.elapsed();
+
+        ctx.set_output("wait_for_port_success", connected.to_string());
+        ctx.set_output(
+            "wait_for_port_elapsed_ms",
+            elapsed.as_millis().to_string(),
+        );
+        if !connected {
+            ctx.commit_outputs().await;
+            return Err(anyhow::anyhow!(
+                "等待端口 {} 超时（{} 毫秒）",
+                address,
+                timeout.as_millis()
+            ));
+        }
+
+        // 存储输出到上下文
+        for (key, value) in &action_spec.outputs {
+            ctx.set_output(key.clone(), format!("{value:?}"));
+        }
+        ctx.commit_outputs().await;
+
+        Ok(())
+    }
+
     /// 执行HTTP动作
     #[allow(unused_variables)]
     async fn execute_http_action(
@@ -687,7 +1644,7 @@ impl EnhancedTaskExecutor {
         {
             tracing::warn!("HTTP 功能未启用，跳过 HTTP 动作");
             tokio::time::sleep(Duration::from_millis(300)).await;
-            return Ok(());
+            Ok(())
         }
 
         #[cfg(feature = "http")]
@@ -707,62 +1664,7 @@ impl EnhancedTaskExecutor {
                 .unwrap_or("GET");
 
             let client = reqwest::Client::new();
-            let mut request = match method.to_uppercase().as_str() {
-                "GET" => client.get(url),
-                "POST" => client.post(url),
-                "PUT" => client.put(url),
-                "DELETE" => client.delete(url),
-                "PATCH" => client.patch(url),
-                _ => {
-                    return Err(anyhow::anyhow!("不支持的HTTP方法: {}", method))
-                }
-            };
-
-            // 添加请求头
-            if let Some(headers) = action_spec.parameters.get("headers") {
-                if let Some(headers_map) = headers.as_mapping() {
-                    for (key, value) in headers_map {
-                        if let (Some(k), Some(v)) =
-                            (key.as_str(), value.as_str())
-                        {
-                            request = request.header(k, v);
-                        }
-                    }
-                }
-            }
-
-            // 添加请求体
-            if let Some(body) = action_spec.parameters.get("body") {
-                let content_type = action_spec
-                    .parameters
-                    .get("content_type")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("application/json");
-
-                match content_type {
-                    "application/json" => {
-                        let json_body =
-                            serde_json::to_string(body).map_err(|e| {
-                                anyhow::anyhow!("序列化JSON失败: {}", e)
-                            })?;
-                        request = request
-                            .header("Content-Type", "application/json")
-                            .body(json_body);
-                    }
-                    "text/plain" => {
-                        let text_body = body.as_str().unwrap_or("");
-                        request = request
-                            .header("Content-Type", "text/plain")
-                            .body(text_body.to_string());
-                    }
-                    _ => {
-                        return Err(anyhow::anyhow!(
-                            "不支持的Content-Type: {}",
-                            content_type
-                        ));
-                    }
-                }
-            }
+            let request = Self::build_http_request(&client, action_spec, url)?;
 
             tracing::debug!("发送HTTP请求: {} {}", method, url);
 
@@ -771,6 +1673,21 @@ impl EnhancedTaskExecutor {
                 .await
                 .map_err(|e| anyhow::anyhow!("HTTP请求失败: {}", e))?;
 
+            // 下载模式：流式写入文件，上下文中仅记录路径与字节数
+            if let Some(path) = action_spec
+                .parameters
+                .get("download_to")
+                .and_then(|v| v.as_str())
+            {
+                return Self::download_http_response(
+                    response,
+                    path,
+                    action_spec,
+                    context,
+                )
+                .await;
+            }
+
             let status_code = response.status().as_u16();
             let response_headers = response.headers().clone();
             let response_text = response
@@ -825,6 +1742,284 @@ impl EnhancedTaskExecutor {
         }
     }
 
+    /// 将HTTP响应体流式写入文件
+    #[cfg(feature = "http")]
+    async fn download_http_response(
+        mut response: reqwest::Response,
+        path: &str,
+        action_spec: &ActionSpec,
+        context: SharedContext,
+    ) -> Result<()> {
+        let status_code = response.status().as_u16();
+        if !response.status().is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "HTTP请求失败，状态码: {}，响应: {}",
+                status_code,
+                text
+            ));
+        }
+
+        if let Some(parent) = std::path::Path::new(path).parent() {
+            if !parent.as_os_str().is_empty() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("创建下载目录失败: {}", e))?;
+            }
+        }
+        // 先写入同目录临时文件，成功后再重命名，失败时不留下残缺文件
+        let part_path = format!("{path}.part");
+        let bytes = match Self::stream_to_file(&mut response, &part_path).await
+        {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&part_path).await;
+                return Err(e);
+            }
+        };
+        if let Err(e) = tokio::fs::rename(&part_path, path).await {
+            let _ = tokio::fs::remove_file(&part_path).await;
+            return Err(anyhow::anyhow!("写入下载文件失败: {}", e));
+        }
+
+        tracing::debug!(path, bytes, "HTTP下载完成");
+
+        let mut guard = context.lock().await;
+        guard.set_variable(
+            "http_status_code".to_string(),
+            status_code.to_string(),
+        );
+        guard.set_variable("http_download_path".to_string(), path.to_string());
+        guard
+            .set_variable("http_download_bytes".to_string(), bytes.to_string());
+
+        // 存储输出到上下文
+        for (key, value) in &action_spec.outputs {
+            guard.set_variable(key.clone(), format!("{value:?}"));
+        }
+
+        Ok(())
+    }
+
+    /// 将响应体逐块写入文件，返回写入的字节数
+    #[cfg(feature = "http")]
+    async fn stream_to_file(
+        response: &mut reqwest::Response,
+        path: &str,
+    ) -> Result<u64> {
+        use tokio::io::AsyncWriteExt;
+
+        let mut file = tokio::fs::File::create(path)
+            .await
+            .map_err(|e| anyhow::anyhow!("创建下载文件失败: {}", e))?;
+
+        let mut bytes = 0u64;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| anyhow::anyhow!("读取响应体失败: {}", e))?
+        {
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| anyhow::anyhow!("写入下载文件失败: {}", e))?;
+            bytes += chunk.len() as u64;
+        }
+        file.flush()
+            .await
+            .map_err(|e| anyhow::anyhow!("写入下载文件失败: {}", e))?;
+        Ok(bytes)
+    }
+
+    /// 执行HTTP分页动作
+    ///
+    /// 按游标逐页请求并累积条目，游标为空或缺失时停止。参数：
+    /// `url`（可含 `{cursor}` 占位符，否则游标以 `cursor_param` 查询参数追加）、
+    /// `items`/`next_cursor`（jq 路径，与 `${jq:...}` 语法一致，如 `.data.items`）、
+    /// `max_pages`（默认 100）、`output`（默认 `http_paginate_items`）
+    #[cfg(feature = "http")]
+    async fn execute_http_paginate_action(
+        action_spec: &ActionSpec,
+        context: SharedContext,
+    ) -> Result<()> {
+        tracing::debug!("执行HTTP分页动作");
+
+        let params = &action_spec.parameters;
+        let str_param = |key: &str| params.get(key).and_then(|v| v.as_str());
+
+        let url_template = str_param("url")
+            .ok_or_else(|| anyhow::anyhow!("HTTP分页动作缺少 'url' 参数"))?;
+        let items_path = str_param("items")
+            .ok_or_else(|| anyhow::anyhow!("HTTP分页动作缺少 'items' 参数"))?;
+        let cursor_path = str_param("next_cursor").ok_or_else(|| {
+            anyhow::anyhow!("HTTP分页动作缺少 'next_cursor' 参数")
+        })?;
+        let cursor_param = str_param("cursor_param").unwrap_or("cursor");
+        let output = str_param("output").unwrap_or("http_paginate_items");
+        let max_pages = params
+            .get("max_pages")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(100);
+
+        let items_program = flowbuilder_core::JqProgram::compile(items_path)?;
+        let cursor_program = flowbuilder_core::JqProgram::compile(cursor_path)?;
+
+        let client = reqwest::Client::new();
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+        let mut pages = 0u64;
+
+        while pages < max_pages {
+            let url = if url_template.contains("{cursor}") {
+                url_template
+                    .replace("{cursor}", cursor.as_deref().unwrap_or(""))
+            } else {
+                url_template.to_string()
+            };
+            let mut request =
+                Self::build_http_request(&client, action_spec, &url)?;
+            if let (false, Some(c)) =
+                (url_template.contains("{cursor}"), &cursor)
+            {
+                request = request.query(&[(cursor_param, c)]);
+            }
+
+            let response = request
+                .send()
+                .await
+                .map_err(|e| anyhow::anyhow!("HTTP请求失败: {}", e))?;
+            let status = response.status();
+            let body = response
+                .text()
+                .await
+                .map_err(|e| anyhow::anyhow!("读取响应体失败: {}", e))?;
+            if !status.is_success() {
+                return Err(anyhow::anyhow!(
+                    "HTTP请求失败，状态码: {}，响应: {}",
+                    status.as_u16(),
+                    body
+                ));
+            }
+            pages += 1;
+
+            let json: serde_json::Value = serde_json::from_str(&body)
+                .map_err(|e| anyhow::anyhow!("解析响应JSON失败: {}", e))?;
+            // 路径缺失视为空页/无下一页
+            match items_program.run(&json).ok() {
+                Some(serde_json::Value::Array(page_items)) => {
+                    items.extend(page_items)
+                }
+                Some(serde_json::Value::Null) | None => {}
+                Some(other) => {
+                    return Err(anyhow::anyhow!(
+                        "'{}' 不是数组: {}",
+                        items_path,
+                        other
+                    ))
+                }
+            }
+
+            cursor = match cursor_program.run(&json).ok() {
+                Some(serde_json::Value::String(c)) if !c.is_empty() => Some(c),
+                Some(serde_json::Value::Number(n)) => Some(n.to_string()),
+                _ => None,
+            };
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        if cursor.is_some() {
+            tracing::warn!(max_pages, "HTTP分页达到最大页数，停止请求");
+        }
+        tracing::debug!(pages, items = items.len(), "HTTP分页完成");
+
+        {
+            let mut guard = context.lock().await;
+            guard.set_variable(
+                output.to_string(),
+                serde_json::Value::Array(items).to_string(),
+            );
+            guard.set_variable(format!("{output}_pages"), pages.to_string());
+        }
+
+        // 存储输出到上下文
+        for (key, value) in &action_spec.outputs {
+            let mut guard = context.lock().await;
+            guard.set_variable(key.clone(), format!("{value:?}"));
+        }
+
+        Ok(())
+    }
+
+    /// 构造HTTP请求（方法、请求头与请求体）
+    #[cfg(feature = "http")]
+    fn build_http_request(
+        client: &reqwest::Client,
+        action_spec: &ActionSpec,
+        url: &str,
+    ) -> Result<reqwest::RequestBuilder> {
+        let method = action_spec
+            .parameters
+            .get("method")
+            .and_then(|v| v.as_str())
+            .unwrap_or("GET");
+
+        let mut request = match method.to_uppercase().as_str() {
+            "GET" => client.get(url),
+            "POST" => client.post(url),
+            "PUT" => client.put(url),
+            "DELETE" => client.delete(url),
+            "PATCH" => client.patch(url),
+            _ => return Err(anyhow::anyhow!("不支持的HTTP方法: {}", method)),
+        };
+
+        // 添加请求头
+        if let Some(headers) = action_spec.parameters.get("headers") {
+            if let Some(headers_map) = headers.as_mapping() {
+                for (key, value) in headers_map {
+                    if let (Some(k), Some(v)) = (key.as_str(), value.as_str()) {
+                        request = request.header(k, v);
+                    }
+                }
+            }
+        }
+
+        // 添加请求体
+        if let Some(body) = action_spec.parameters.get("body") {
+            let content_type = action_spec
+                .parameters
+                .get("content_type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("application/json");
+
+            match content_type {
+                "application/json" => {
+                    let json_body =
+                        serde_json::to_string(body).map_err(|e| {
+                            anyhow::anyhow!("序列化JSON失败: {}", e)
+                        })?;
+                    request = request
+                        .header("Content-Type", "application/json")
+                        .body(json_body);
+                }
+                "text/plain" => {
+                    let text_body = body.as_str().unwrap_or("");
+                    request = request
+                        .header("Content-Type", "text/plain")
+                        .body(text_body.to_string());
+                }
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "不支持的Content-Type: {}",
+                        content_type
+                    ));
+                }
+            }
+        }
+
+        Ok(request)
+    }
+
     /// 执行WASM动作
     async fn execute_wasm_action(
         action_spec: &ActionSpec,
@@ -864,6 +2059,7 @@ impl EnhancedTaskExecutor {
     async fn execute_composite_action(
         action_spec: &ActionSpec,
         context: SharedContext,
+        config: &ExecutorConfig,
     ) -> Result<()> {
         tracing::debug!("执行复合动作");
 
@@ -919,9 +2115,34 @@ impl EnhancedTaskExecutor {
                     outputs,
                 };
 
-                tracing::debug!("执行子动作 {}: {}", index, action_type);
-                Self::execute_action_by_type(&sub_action_spec, context.clone())
+                // 子动作条件不满足时跳过该子动作
+                if let Some(condition) = action_map
+                    .get(serde_yaml::Value::String("condition".to_string()))
+                    .and_then(|v| v.as_str())
+                {
+                    let met = condition::evaluate_condition(
+                        condition,
+                        &context,
+                        config.condition_evaluator.as_ref(),
+                    )
                     .await?;
+                    if !met {
+                        tracing::debug!(
+                            "跳过子动作 {}: 条件不满足 ({})",
+                            index,
+                            condition
+                        );
+                        continue;
+                    }
+                }
+
+                tracing::debug!("执行子动作 {}: {}", index, action_type);
+                Self::execute_action_with_config(
+                    &sub_action_spec,
+                    context.clone(),
+                    config,
+                )
+                .await?;
             }
         }
 
@@ -945,12 +2166,12 @@ impl EnhancedTaskExecutor {
 
         // 设置环境变量
         for (key, value) in &plan.env_vars {
-            guard.set_variable(format!("env.{key}"), format!("{value:?}"));
+            guard.set_variable(format!("env.{key}"), scalar_text(value));
         }
 
         // 设置流程变量
         for (key, value) in &plan.flow_vars {
-            guard.set_variable(format!("flow.{key}"), format!("{value:?}"));
+            guard.set_variable(format!("flow.{key}"), scalar_text(value));
         }
 
         Ok(())
@@ -958,32 +2179,22 @@ impl EnhancedTaskExecutor {
 
     /// 更新统计信息
     #[cfg(feature = "perf-metrics")]
-    fn update_stats(&mut self, result: &ExecutionResult) {
-        self.stats.total_execution_time = result.total_duration;
-
-        for phase_result in &result.phase_results {
-            for node_result in &phase_result.node_results {
-                self.stats.total_tasks += 1;
-                if node_result.success {
-                    self.stats.successful_tasks += 1;
-                } else {
-                    self.stats.failed_tasks += 1;
-                }
-            }
-        }
-
-        if self.stats.total_tasks > 0 {
-            self.stats.average_execution_time = Duration::from_nanos(
-                self.stats.total_execution_time.as_nanos() as u64
-                    / self.stats.total_tasks as u64,
-            );
-        }
+    fn update_stats(&self, result: &ExecutionResult) {
+        let run_stats = result.stats();
+        let mut stats = self.stats.lock().unwrap_or_else(|e| e.into_inner());
+        stats.total_tasks += run_stats.total_tasks;
+        stats.successful_tasks += run_stats.successful_tasks;
+        stats.failed_tasks += run_stats.failed_tasks;
+        stats.skipped_tasks += run_stats.skipped_tasks;
+        stats.total_execution_time += run_stats.total_execution_time;
+        stats.average_execution_time =
+            average(stats.total_execution_time, stats.total_tasks);
     }
 
-    /// 获取执行统计
+    /// 获取累计执行统计（全部已完成的执行）
     #[cfg(feature = "perf-metrics")]
-    pub fn get_stats(&self) -> &ExecutionStats {
-        &self.stats
+    pub fn get_stats(&self) -> ExecutionStats {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).clone()
     }
 }
 
@@ -997,19 +2208,37 @@ impl Executor for EnhancedTaskExecutor {
         input: Self::Input,
     ) -> Result<Self::Output, Self::Error> {
         let (plan, context) = input;
-        self.execute_plan(plan, context).await
+        self.execute_plan(plan, context, &RunContext::default())
+            .await
     }
 
     fn status(&self) -> ExecutorStatus {
-        self.status.clone()
+        let runs = self.active_runs.lock().unwrap_or_else(|e| e.into_inner());
+        let generation = self.stop_generation.load(Ordering::SeqCst);
+        // 仍有在停止请求之前开始的执行未结束
+        if runs.keys().next().is_some_and(|&g| g < generation) {
+            ExecutorStatus::Stopped
+        } else if !runs.is_empty() {
+            ExecutorStatus::Running
+        } else {
+            ExecutorStatus::Idle
+        }
     }
 
     async fn stop(&mut self) -> Result<(), Self::Error> {
-        self.status = ExecutorStatus::Stopped;
+        self.request_stop();
         Ok(())
     }
 }
 
+/// 平均耗时（任务数为 0 时为 0）
+fn average(total: Duration, count: usize) -> Duration {
+    match count {
+        0 => Duration::default(),
+        n => Duration::from_nanos((total.as_nanos() / n as u128) as u64),
+    }
+}
+
 /// 执行结果
 #[derive(Debug, Clone)]
 pub struct ExecutionResult {
@@ -1069,14 +2298,211 @@ pub struct NodeResult {
     pub error_message: Option<String>,
     /// 重试次数
     pub retry_count: u32,
+    /// 动作执行次数（循环节点为迭代次数）
+    pub iterations: u32,
+    /// 是否被跳过（未执行）
+    pub skipped: bool,
+    /// 超时处理动作的执行结果（节点最终超时且配置了 on_timeout 时）
+    pub timeout_handler: Option<TimeoutHandlerResult>,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use flowbuilder_core::{ActionSpec, ExecutionNode};
-    use std::collections::HashMap;
-
+/// 超时处理动作的执行结果
+#[derive(Debug, Clone)]
+pub struct TimeoutHandlerResult {
+    /// on_timeout 指向的处理目标
+    pub target: String,
+    /// 是否成功
+    pub success: bool,
+    /// 错误信息
+    pub error_message: Option<String>,
+    /// 执行时间
+    pub duration: Duration,
+}
+
+/// 节点执行超时
+#[derive(Debug)]
+struct NodeTimeout {
+    node_name: String,
+}
+
+impl std::fmt::Display for NodeTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "节点 {} 执行超时", self.node_name)
+    }
+}
+
+impl std::error::Error for NodeTimeout {}
+
+/// 读取子进程输出，最多保留 `limit` 字节
+///
+/// 超出部分继续读取并丢弃（避免子进程因管道写满而阻塞），结果末尾追加
+/// `...[truncated]`；`stream` 时逐行写入 debug 日志
+async fn capture_output<R>(
+    reader: Option<R>,
+    limit: Option<usize>,
+    stream: bool,
+    label: &'static str,
+) -> std::io::Result<String>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    use tokio::io::AsyncReadExt;
+
+    let Some(mut reader) = reader else {
+        return Ok(String::new());
+    };
+    let mut captured = Vec::new();
+    let mut truncated = false;
+    let mut pending = Vec::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        let chunk = &buf[..n];
+        let room = limit.map_or(n, |l| l.saturating_sub(captured.len()).min(n));
+        captured.extend_from_slice(&chunk[..room]);
+        truncated |= room < n;
+
+        if stream {
+            pending.extend_from_slice(chunk);
+            log_stream_lines(&mut pending, label);
+        }
+    }
+    if stream && !pending.is_empty() {
+        tracing::debug!(
+            output = label,
+            "{}",
+            String::from_utf8_lossy(&pending)
+        );
+    }
+
+    let mut text = String::from_utf8_lossy(&captured).into_owned();
+    if truncated {
+        text.push_str("...[truncated]");
+    }
+    Ok(text)
+}
+
+/// 流式日志中单行的最大字节数，超出时按不完整的行输出
+const STREAM_LINE_LIMIT: usize = 8 * 1024;
+
+/// 输出 `pending` 中的完整行；剩余部分超过 [`STREAM_LINE_LIMIT`] 时
+/// 一并输出，无换行的输出（进度条、二进制数据）不会无限累积
+fn log_stream_lines(pending: &mut Vec<u8>, label: &'static str) {
+    while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+        let line: Vec<u8> = pending.drain(..=end).collect();
+        tracing::debug!(
+            output = label,
+            "{}",
+            String::from_utf8_lossy(&line).trim_end()
+        );
+    }
+    if pending.len() >= STREAM_LINE_LIMIT {
+        tracing::debug!(output = label, "{}", String::from_utf8_lossy(pending));
+        pending.clear();
+    }
+}
+
+/// 上下文变量对应的环境变量名：大写，非字母数字字符替换为 `_`
+fn context_env_name(key: &str) -> String {
+    key.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// 流程/环境变量写入上下文的文本（标量取其本身，复合值序列化为 YAML）
+fn scalar_text(value: &serde_yaml::Value) -> String {
+    match value {
+        serde_yaml::Value::String(s) => s.clone(),
+        serde_yaml::Value::Number(n) => n.to_string(),
+        serde_yaml::Value::Bool(b) => b.to_string(),
+        serde_yaml::Value::Null => "null".to_string(),
+        _ => serde_yaml::to_string(value)
+            .map(|s| s.trim_end().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+/// 健康检查目标
+#[derive(Debug, Clone, Copy)]
+enum HealthTarget<'a> {
+    #[cfg(feature = "http")]
+    Http(&'a str),
+    Tcp(&'a str),
+}
+
+impl ExecutionResult {
+    /// 按节点结果汇总成功/失败/跳过数量
+    pub fn summary(&self) -> ExecutionSummary {
+        let mut summary = ExecutionSummary {
+            total_duration: self.total_duration,
+            ..ExecutionSummary::default()
+        };
+        let nodes = self.phase_results.iter().flat_map(|p| &p.node_results);
+        for node in nodes {
+            summary.total += 1;
+            if node.skipped {
+                summary.skipped += 1;
+            } else if node.success {
+                summary.succeeded += 1;
+            } else {
+                summary.failed += 1;
+            }
+        }
+        summary
+    }
+
+    /// 本次执行的统计
+    pub fn stats(&self) -> ExecutionStats {
+        let summary = self.summary();
+        ExecutionStats {
+            total_tasks: summary.total,
+            successful_tasks: summary.succeeded,
+            failed_tasks: summary.failed,
+            skipped_tasks: summary.skipped,
+            total_execution_time: summary.total_duration,
+            average_execution_time: average(
+                summary.total_duration,
+                summary.total,
+            ),
+        }
+    }
+}
+
+impl NodeResult {
+    /// 跳过节点的结果
+    fn skipped(node: &ExecutionNode) -> Self {
+        let now = Instant::now();
+        Self {
+            node_id: node.id.clone(),
+            node_name: node.name.clone(),
+            start_time: now,
+            end_time: Some(now),
+            duration: Duration::default(),
+            success: true,
+            error_message: None,
+            retry_count: 0,
+            iterations: 0,
+            skipped: true,
+            timeout_handler: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flowbuilder_core::{ActionSpec, ExecutionNode, RetryStrategy};
+    use std::collections::HashMap;
+
     #[tokio::test]
     async fn test_executor_creation() {
         let executor = EnhancedTaskExecutor::new();
@@ -1110,9 +2536,13 @@ mod tests {
             },
         );
 
-        let result =
-            EnhancedTaskExecutor::execute_node_static(&node, context, &config)
-                .await;
+        let result = EnhancedTaskExecutor::execute_node_static(
+            &node,
+            context,
+            &config,
+            &RunContext::default(),
+        )
+        .await;
         assert!(result.is_ok());
 
         let node_result = result.unwrap();
@@ -1120,6 +2550,942 @@ mod tests {
         assert_eq!(node_result.node_id, "test_node");
     }
 
+    #[test]
+    fn test_supported_action_types() {
+        let types = EnhancedTaskExecutor::supported_action_types();
+        assert!(types.contains(&"builtin"));
+        assert!(types.contains(&NativeAction::ACTION_TYPE));
+        assert_eq!(types.contains(&"sql"), cfg!(feature = "sqlx"));
+    }
+
+    #[tokio::test]
+    async fn test_compiled_builder_flow_retries() {
+        let attempts = Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let counter = attempts.clone();
+        let mut plan = flowbuilder_core::FlowBuilder::new()
+            .named_step("flaky", move |ctx| {
+                let attempt =
+                    counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                async move {
+                    if attempt == 0 {
+                        anyhow::bail!("first attempt fails");
+                    }
+                    ctx.lock()
+                        .await
+                        .set_variable("done".to_string(), "yes".to_string());
+                    Ok(())
+                }
+            })
+            .compile();
+        plan.phases[0].nodes[0].retry_config =
+            Some(flowbuilder_core::RetryConfig {
+                max_retries: 2,
+                delay: 1,
+                strategy: RetryStrategy::Fixed,
+            });
+
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        let result = EnhancedTaskExecutor::new()
+            .execute_plan(plan, context.clone(), &RunContext::default())
+            .await
+            .unwrap();
+
+        assert!(result.success);
+        let node = &result.phase_results[0].node_results[0];
+        assert_eq!(node.node_name, "flaky");
+        assert_eq!(node.retry_count, 1);
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert_eq!(
+            context.lock().await.get_variable("done"),
+            Some(&"yes".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_native_nodes_mix_with_declarative_nodes() {
+        let mut plan = ExecutionPlan::new(
+            "wf".to_string(),
+            "1.0".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+        let declared = ExecutionNode::new(
+            "declared".to_string(),
+            "Declared".to_string(),
+            ActionSpec {
+                action_type: "builtin".to_string(),
+                parameters: HashMap::from([
+                    ("operation".to_string(), "set_variable".into()),
+                    ("key".to_string(), "greeting".into()),
+                    ("value".to_string(), "hi".into()),
+                ]),
+                outputs: HashMap::new(),
+            },
+        );
+        let native = ExecutionNode::native(
+            "native".to_string(),
+            "Native".to_string(),
+            NativeAction::new(|ctx| {
+                Box::pin(async move {
+                    let mut guard = ctx.lock().await;
+                    let greeting = guard
+                        .get_variable("greeting")
+                        .cloned()
+                        .ok_or_else(|| anyhow::anyhow!("missing greeting"))?;
+                    guard.set_variable("echo".to_string(), greeting);
+                    Ok(())
+                })
+            }),
+        )
+        .add_dependency("declared".to_string());
+        let slow = ExecutionNode::native(
+            "slow".to_string(),
+            "Slow".to_string(),
+            NativeAction::new(|_ctx| {
+                Box::pin(async move {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    Ok(())
+                })
+            }),
+        )
+        .with_timeout(flowbuilder_core::TimeoutConfig {
+            duration: 50,
+            on_timeout: None,
+            on_timeout_action: None,
+        });
+        plan.add_phase(ExecutionPhase {
+            id: "phase_0".to_string(),
+            name: "phase".to_string(),
+            execution_mode: PhaseExecutionMode::Sequential,
+            nodes: vec![declared, native, slow],
+            condition: None,
+        });
+
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        let result = EnhancedTaskExecutor::new()
+            .execute_plan(plan, context.clone(), &RunContext::default())
+            .await
+            .unwrap();
+
+        let nodes = &result.phase_results[0].node_results;
+        assert!(nodes[0].success && nodes[1].success);
+        assert_eq!(
+            context.lock().await.get_variable("echo"),
+            Some(&"String(\"hi\")".to_string())
+        );
+        assert!(!nodes[2].success);
+        assert!(nodes[2].error_message.as_deref().unwrap().contains("超时"));
+    }
+
+    #[tokio::test]
+    async fn test_shared_executor_runs_plans_concurrently() {
+        let plan = |id: &str| {
+            let mut plan = ExecutionPlan::new(
+                id.to_string(),
+                "1.0".to_string(),
+                HashMap::new(),
+                HashMap::new(),
+            );
+            plan.add_phase(ExecutionPhase {
+                id: "phase_0".to_string(),
+                name: "phase".to_string(),
+                execution_mode: PhaseExecutionMode::Sequential,
+                nodes: vec![ExecutionNode::native(
+                    "wait".to_string(),
+                    "Wait".to_string(),
+                    NativeAction::new(|_ctx| {
+                        Box::pin(async move {
+                            tokio::time::sleep(Duration::from_millis(200))
+                                .await;
+                            Ok(())
+                        })
+                    }),
+                )],
+                condition: None,
+            });
+            plan
+        };
+        let context = || {
+            Arc::new(tokio::sync::Mutex::new(
+                flowbuilder_context::FlowContext::default(),
+            ))
+        };
+        let executor = Arc::new(EnhancedTaskExecutor::new());
+
+        let started = Instant::now();
+        let first =
+            executor.spawn_plan(plan("a"), context(), RunContext::default());
+        let second =
+            executor.spawn_plan(plan("b"), context(), RunContext::default());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(executor.status(), ExecutorStatus::Running);
+        assert_eq!(first.status(), ExecutorStatus::Running);
+
+        // 取消只影响对应的执行
+        second.cancel();
+        assert_eq!(second.status(), ExecutorStatus::Stopped);
+        let cancelled = second.join().await.unwrap();
+        assert_eq!(cancelled.stats().failed_tasks, 1);
+
+        let result = first.join().await.unwrap();
+        assert!(started.elapsed() < Duration::from_millis(400));
+        assert_eq!(result.stats().successful_tasks, 1);
+        assert_eq!(executor.status(), ExecutorStatus::Idle);
+    }
+
+    #[tokio::test]
+    async fn test_request_stop_drains_current_phase() {
+        use std::sync::atomic::AtomicUsize;
+
+        let finished = Arc::new(AtomicUsize::new(0));
+        let node = |id: &str| {
+            let finished = finished.clone();
+            ExecutionNode::native(
+                id.to_string(),
+                id.to_string(),
+                NativeAction::new(move |_ctx| {
+                    let finished = finished.clone();
+                    Box::pin(async move {
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        finished.fetch_add(1, Ordering::SeqCst);
+                        Ok(())
+                    })
+                }),
+            )
+        };
+        let mut plan = ExecutionPlan::new(
+            "stoppable".to_string(),
+            "1.0".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+        for (index, ids) in [["a", "b"], ["c", "d"]].iter().enumerate() {
+            plan.add_phase(ExecutionPhase {
+                id: format!("phase_{index}"),
+                name: format!("phase {index}"),
+                execution_mode: PhaseExecutionMode::Parallel,
+                nodes: ids.iter().map(|id| node(id)).collect(),
+                condition: None,
+            });
+        }
+
+        let executor = Arc::new(EnhancedTaskExecutor::new());
+        let handle = executor.spawn_plan(
+            plan,
+            Arc::new(tokio::sync::Mutex::new(
+                flowbuilder_context::FlowContext::default(),
+            )),
+            RunContext::default(),
+        );
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        executor.request_stop();
+        assert_eq!(executor.status(), ExecutorStatus::Stopped);
+
+        let result = handle.join().await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error_message.as_deref(), Some("stopped by request"));
+        // 进行中的阶段执行完毕，后续阶段未执行
+        assert_eq!(result.phase_results.len(), 1);
+        assert_eq!(result.stats().successful_tasks, 2);
+        assert_eq!(finished.load(Ordering::SeqCst), 2);
+
+        // 停止只作用于当时进行中的执行，之后的执行照常完成
+        assert_eq!(executor.status(), ExecutorStatus::Idle);
+        let mut next = ExecutionPlan::new(
+            "after_stop".to_string(),
+            "1.0".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+        next.add_phase(ExecutionPhase {
+            id: "phase_0".to_string(),
+            name: "phase 0".to_string(),
+            execution_mode: PhaseExecutionMode::Parallel,
+            nodes: vec![node("e")],
+            condition: None,
+        });
+        let result = executor
+            .execute_plan(
+                next,
+                Arc::new(tokio::sync::Mutex::new(
+                    flowbuilder_context::FlowContext::default(),
+                )),
+                &RunContext::default(),
+            )
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.stats().successful_tasks, 1);
+        assert_eq!(finished.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_run_context_cancel_and_deadline() {
+        let plan = || {
+            let mut plan = ExecutionPlan::new(
+                "wf".to_string(),
+                "1.0".to_string(),
+                HashMap::new(),
+                HashMap::new(),
+            );
+            let sleep = ExecutionNode::new(
+                "slow".to_string(),
+                "Slow".to_string(),
+                ActionSpec {
+                    action_type: "builtin".to_string(),
+                    parameters: HashMap::from([
+                        ("operation".to_string(), "sleep".into()),
+                        ("duration".to_string(), 5_000.into()),
+                    ]),
+                    outputs: HashMap::new(),
+                },
+            );
+            plan.add_phase(ExecutionPhase {
+                id: "phase_0".to_string(),
+                name: "phase".to_string(),
+                execution_mode: PhaseExecutionMode::Sequential,
+                nodes: vec![sleep],
+                condition: None,
+            });
+            plan
+        };
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        let executor = EnhancedTaskExecutor::new();
+
+        // 取消中断进行中的节点
+        let run = RunContext::default();
+        let cancel = run.cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            cancel.cancel();
+        });
+        let started = Instant::now();
+        let result = executor
+            .execute_plan(plan(), context.clone(), &run)
+            .await
+            .unwrap();
+        assert!(started.elapsed() < Duration::from_secs(2));
+        let node = &result.phase_results[0].node_results[0];
+        assert!(!node.success);
+        assert!(node.error_message.as_deref().unwrap().contains("已取消"));
+
+        // 截止时间早于节点超时
+        let run = RunContext::new().with_timeout(Duration::from_millis(50));
+        let result = executor
+            .execute_plan(plan(), context.clone(), &run)
+            .await
+            .unwrap();
+        let node = &result.phase_results[0].node_results[0];
+        assert!(node.error_message.as_deref().unwrap().contains("截止时间"));
+
+        // 截止时间已过时不再开始执行
+        let result =
+            executor.execute_plan(plan(), context, &run).await.unwrap();
+        assert!(!result.success);
+        assert!(result.error_message.unwrap().contains("截止时间"));
+    }
+
+    /// 条件形如 `<变量名> < <n>`，按整数比较
+    #[derive(Debug)]
+    struct LessThan;
+
+    impl ConditionEvaluator for LessThan {
+        fn evaluate(
+            &self,
+            condition: &str,
+            variables: &flowbuilder_context::VarMap<String>,
+        ) -> Result<bool> {
+            let (key, limit) = condition.split_once(" < ").unwrap();
+            let value: u32 = variables[key].parse()?;
+            Ok(value < limit.parse()?)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unmet_conditions_skip_nodes_and_phases() {
+        let node = |id: &str| {
+            let key = format!("ran_{id}");
+            ExecutionNode::native(
+                id.to_string(),
+                id.to_string(),
+                NativeAction::new(move |ctx| {
+                    let key = key.clone();
+                    Box::pin(async move {
+                        ctx.lock().await.set_variable(key, "1".to_string());
+                        Ok(())
+                    })
+                }),
+            )
+        };
+        let phase =
+            |id: &str, mode, condition: Option<&str>, nodes| ExecutionPhase {
+                id: id.to_string(),
+                name: id.to_string(),
+                execution_mode: mode,
+                nodes,
+                condition: condition.map(str::to_string),
+            };
+        let mut plan = ExecutionPlan::new(
+            "conditions".to_string(),
+            "1.0".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+        plan.add_phase(phase(
+            "phase_0",
+            PhaseExecutionMode::Sequential,
+            Some("count < 10"),
+            vec![
+                node("gated").with_condition("count < 3".to_string()),
+                node("after_gated").add_dependency("gated".to_string()),
+                node("free"),
+            ],
+        ));
+        plan.add_phase(phase(
+            "phase_1",
+            PhaseExecutionMode::Parallel,
+            Some("count < 2"),
+            vec![node("late_a"), node("late_b")],
+        ));
+        plan.add_phase(phase(
+            "phase_2",
+            PhaseExecutionMode::Conditional {
+                condition: "count < 9".to_string(),
+            },
+            None,
+            vec![node("branch")],
+        ));
+
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        context
+            .lock()
+            .await
+            .set_variable("count".to_string(), "5".to_string());
+        let executor = EnhancedTaskExecutor::with_config(ExecutorConfig {
+            condition_evaluator: Some(Arc::new(LessThan)),
+            ..ExecutorConfig::default()
+        });
+        let result = executor
+            .execute_plan(plan, context.clone(), &RunContext::default())
+            .await
+            .unwrap();
+
+        let skipped: Vec<_> = result
+            .phase_results
+            .iter()
+            .flat_map(|p| &p.node_results)
+            .filter(|n| n.skipped)
+            .map(|n| n.node_id.as_str())
+            .collect();
+        assert_eq!(skipped, vec!["gated", "after_gated", "late_a", "late_b"]);
+        assert_eq!(result.stats().skipped_tasks, 4);
+
+        let guard = context.lock().await;
+        assert!(guard.get_variable("ran_gated").is_none());
+        assert!(guard.get_variable("ran_late_a").is_none());
+        assert!(guard.get_variable("ran_free").is_some());
+        assert!(guard.get_variable("ran_branch").is_some());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[tokio::test]
+    async fn test_mutex_group_serializes_parallel_nodes() {
+        use std::sync::atomic::AtomicUsize;
+
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let node = |id: &str| {
+            let running = running.clone();
+            let peak = peak.clone();
+            ExecutionNode::native(
+                id.to_string(),
+                id.to_string(),
+                NativeAction::new(move |_ctx| {
+                    let running = running.clone();
+                    let peak = peak.clone();
+                    Box::pin(async move {
+                        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        running.fetch_sub(1, Ordering::SeqCst);
+                        Ok(())
+                    })
+                }),
+            )
+            .with_mutex_group("device")
+        };
+        let mut plan = ExecutionPlan::new(
+            "exclusive".to_string(),
+            "1.0".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+        plan.add_phase(ExecutionPhase {
+            id: "phase_0".to_string(),
+            name: "phase".to_string(),
+            execution_mode: PhaseExecutionMode::Parallel,
+            nodes: vec![node("flash_a"), node("flash_b")],
+            condition: None,
+        });
+
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        let started = Instant::now();
+        let result = EnhancedTaskExecutor::new()
+            .execute_plan(plan, context, &RunContext::default())
+            .await
+            .unwrap();
+
+        assert_eq!(result.stats().successful_tasks, 2);
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_parallel_phase_starts_nodes_by_priority() {
+        let started = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let node = |id: &'static str, priority: u32| {
+            let started = started.clone();
+            ExecutionNode::native(
+                id.to_string(),
+                id.to_string(),
+                NativeAction::new(move |_ctx| {
+                    let started = started.clone();
+                    Box::pin(async move {
+                        started.lock().unwrap().push(id);
+                        tokio::time::sleep(Duration::from_millis(5)).await;
+                        Ok(())
+                    })
+                }),
+            )
+            .with_priority(priority)
+        };
+        let mut plan = ExecutionPlan::new(
+            "priorities".to_string(),
+            "1.0".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+        plan.add_phase(ExecutionPhase {
+            id: "phase_0".to_string(),
+            name: "phase".to_string(),
+            execution_mode: PhaseExecutionMode::Parallel,
+            nodes: vec![
+                node("low", 200),
+                node("critical", 1),
+                node("normal", 100),
+                node("urgent", 10),
+            ],
+            condition: None,
+        });
+
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        let executor = EnhancedTaskExecutor::with_config(ExecutorConfig {
+            max_concurrent_tasks: 1,
+            ..ExecutorConfig::default()
+        });
+        executor
+            .execute_plan(plan, context, &RunContext::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *started.lock().unwrap(),
+            ["critical", "urgent", "normal", "low"]
+        );
+    }
+
+    /// (span 名称, 父 span 名称)
+    type SpanEdge = (&'static str, Option<&'static str>);
+
+    /// 记录每个 span 的名称及其父 span 名称
+    #[derive(Clone, Default)]
+    struct SpanParents {
+        edges: Arc<std::sync::Mutex<Vec<SpanEdge>>>,
+    }
+
+    impl<S> tracing_subscriber::Layer<S> for SpanParents
+    where
+        S: tracing::Subscriber
+            + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    {
+        fn on_new_span(
+            &self,
+            _: &tracing::span::Attributes<'_>,
+            id: &tracing::span::Id,
+            ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let Some(span) = ctx.span(id) else { return };
+            let parent = span.parent().map(|parent| parent.name());
+            self.edges.lock().unwrap().push((span.name(), parent));
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[tokio::test]
+    async fn test_parallel_node_spans_nest_under_phase_span() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let recorder = SpanParents::default();
+        let _guard = tracing::subscriber::set_default(
+            tracing_subscriber::registry().with(recorder.clone()),
+        );
+
+        let node = |id: &str| {
+            ExecutionNode::native(
+                id.to_string(),
+                id.to_string(),
+                NativeAction::new(|_ctx| Box::pin(async { Ok(()) })),
+            )
+        };
+        let mut plan = ExecutionPlan::new(
+            "spans".to_string(),
+            "1.0".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+        plan.add_phase(ExecutionPhase {
+            id: "phase_0".to_string(),
+            name: "phase".to_string(),
+            execution_mode: PhaseExecutionMode::Parallel,
+            nodes: vec![node("a"), node("b")],
+            condition: None,
+        });
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        EnhancedTaskExecutor::new()
+            .execute_plan(plan, context, &RunContext::default())
+            .await
+            .unwrap();
+
+        let edges = recorder.edges.lock().unwrap().clone();
+        assert!(edges.contains(&("execute_phase", Some("execute_plan"))));
+        assert_eq!(
+            edges
+                .iter()
+                .filter(
+                    |e| **e == ("execute_node_static", Some("execute_phase"))
+                )
+                .count(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_empty_plans_and_phases_succeed() {
+        let context = || {
+            Arc::new(tokio::sync::Mutex::new(
+                flowbuilder_context::FlowContext::default(),
+            ))
+        };
+        let empty_phase = |id: &str, mode| ExecutionPhase {
+            id: id.to_string(),
+            name: id.to_string(),
+            execution_mode: mode,
+            nodes: Vec::new(),
+            condition: None,
+        };
+        let executor = EnhancedTaskExecutor::new();
+
+        // 空计划
+        let plan = flowbuilder_core::EnhancedFlowOrchestrator::new()
+            .create_execution_plan(
+                Vec::new(),
+                HashMap::new(),
+                HashMap::new(),
+                "empty".to_string(),
+                "1.0".to_string(),
+            )
+            .unwrap();
+        assert!(plan.is_empty());
+        let result = executor
+            .execute_plan(plan, context(), &RunContext::default())
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(result.phase_results.is_empty());
+        assert_eq!(result.stats().total_tasks, 0);
+
+        // 空阶段（各执行模式）
+        let mut plan = ExecutionPlan::new(
+            "empty_phases".to_string(),
+            "1.0".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+        plan.add_phase(empty_phase("seq", PhaseExecutionMode::Sequential));
+        plan.add_phase(empty_phase("par", PhaseExecutionMode::Parallel));
+        plan.add_phase(empty_phase(
+            "cond",
+            PhaseExecutionMode::Conditional {
+                condition: "unknown".to_string(),
+            },
+        ));
+        let result = executor
+            .execute_plan(plan, context(), &RunContext::default())
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.phase_results.len(), 3);
+        assert!(result
+            .phase_results
+            .iter()
+            .all(|p| p.success && p.node_results.is_empty()));
+
+        // 全部节点因条件被跳过
+        let mut plan = ExecutionPlan::new(
+            "all_skipped".to_string(),
+            "1.0".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+        plan.add_phase(ExecutionPhase {
+            nodes: vec![
+                ExecutionNode::native(
+                    "a".to_string(),
+                    "a".to_string(),
+                    NativeAction::new(|_ctx| {
+                        Box::pin(async { anyhow::bail!("should not run") })
+                    }),
+                )
+                .with_condition("false".to_string()),
+                ExecutionNode::native(
+                    "b".to_string(),
+                    "b".to_string(),
+                    NativeAction::new(|_ctx| {
+                        Box::pin(async { anyhow::bail!("should not run") })
+                    }),
+                )
+                .add_dependency("a".to_string()),
+            ],
+            ..empty_phase("phase_0", PhaseExecutionMode::Sequential)
+        });
+        let result = executor
+            .execute_plan(plan, context(), &RunContext::default())
+            .await
+            .unwrap();
+        assert!(result.success);
+        let stats = result.stats();
+        assert_eq!((stats.total_tasks, stats.skipped_tasks), (2, 2));
+        assert_eq!(stats.failed_tasks, 0);
+    }
+
+    #[tokio::test]
+    async fn test_summary_counts_mixed_results() {
+        let executor = EnhancedTaskExecutor::new();
+        let mut plan = ExecutionPlan::new(
+            "mixed".to_string(),
+            "1.0".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+        plan.add_phase(ExecutionPhase {
+            nodes: vec![
+                ExecutionNode::native(
+                    "ok".to_string(),
+                    "ok".to_string(),
+                    NativeAction::new(|_ctx| Box::pin(async { Ok(()) })),
+                ),
+                ExecutionNode::native(
+                    "skipped".to_string(),
+                    "skipped".to_string(),
+                    NativeAction::new(|_ctx| Box::pin(async { Ok(()) })),
+                )
+                .with_condition("false".to_string()),
+                ExecutionNode::native(
+                    "fail".to_string(),
+                    "fail".to_string(),
+                    NativeAction::new(|_ctx| {
+                        Box::pin(async { anyhow::bail!("boom") })
+                    }),
+                ),
+            ],
+            id: "phase_0".to_string(),
+            name: "phase_0".to_string(),
+            execution_mode: PhaseExecutionMode::Parallel,
+            condition: None,
+        });
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        let result = executor
+            .execute_plan(plan, context, &RunContext::default())
+            .await
+            .unwrap();
+
+        let summary = result.summary();
+        assert_eq!(
+            (
+                summary.total,
+                summary.succeeded,
+                summary.failed,
+                summary.skipped
+            ),
+            (3, 1, 1, 1)
+        );
+        assert_eq!(summary.total_duration, result.total_duration);
+    }
+
+    /// 把 `key` 参数写入上下文变量 `grpc.called`
+    #[derive(Debug)]
+    struct FakeGrpc;
+
+    impl ActionHandler for FakeGrpc {
+        fn handle<'a>(
+            &'a self,
+            spec: &'a ActionSpec,
+            context: SharedContext,
+        ) -> crate::ActionFuture<'a> {
+            Box::pin(async move {
+                let method = spec
+                    .parameters
+                    .get("method")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string();
+                context
+                    .lock()
+                    .await
+                    .set_variable("grpc.called".to_string(), method);
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registered_action_handler_runs() {
+        let mut executor = EnhancedTaskExecutor::new();
+        executor.register_action("grpc", Arc::new(FakeGrpc));
+        assert!(executor.action_types().contains(&"grpc"));
+        assert!(
+            !EnhancedTaskExecutor::supported_action_types().contains(&"grpc")
+        );
+
+        let mut parameters = HashMap::new();
+        parameters.insert("method".to_string(), "Users/Get".into());
+        let mut plan = ExecutionPlan::new(
+            "custom".to_string(),
+            "1.0".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+        plan.add_phase(ExecutionPhase {
+            id: "phase_0".to_string(),
+            name: "phase".to_string(),
+            execution_mode: PhaseExecutionMode::Sequential,
+            nodes: vec![ExecutionNode::new(
+                "call".to_string(),
+                "Call".to_string(),
+                ActionSpec {
+                    action_type: "grpc".to_string(),
+                    parameters,
+                    outputs: HashMap::new(),
+                },
+            )],
+            condition: None,
+        });
+
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        let result = executor
+            .execute_plan(plan, context.clone(), &RunContext::default())
+            .await
+            .unwrap();
+        assert_eq!(result.stats().successful_tasks, 1);
+        assert_eq!(
+            context.lock().await.get_variable("grpc.called"),
+            Some(&"Users/Get".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_loop_node_iterations() {
+        let config = ExecutorConfig {
+            condition_evaluator: Some(Arc::new(LessThan)),
+            ..ExecutorConfig::default()
+        };
+        let node = ExecutionNode::new(
+            "poll".to_string(),
+            "Poll".to_string(),
+            ActionSpec {
+                action_type: "builtin".to_string(),
+                parameters: HashMap::from([
+                    ("operation".to_string(), "log".into()),
+                    ("message".to_string(), "polling".into()),
+                ]),
+                outputs: HashMap::new(),
+            },
+        );
+
+        // 条件在第 3 次迭代后不再成立
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        let looping = node.clone().with_loop(LoopConfig {
+            condition: "poll.iteration < 3".to_string(),
+            max_iterations: 10,
+        });
+        let result = EnhancedTaskExecutor::execute_node_static(
+            &looping,
+            context.clone(),
+            &config,
+            &RunContext::default(),
+        )
+        .await
+        .unwrap();
+        assert!(result.success);
+        assert_eq!(result.iterations, 3);
+        assert_eq!(
+            context.lock().await.get_variable("poll.iteration").cloned(),
+            Some("3".to_string())
+        );
+
+        // 条件始终成立时受最大迭代次数限制
+        let capped = node.clone().with_loop(LoopConfig {
+            condition: "poll.iteration < 100".to_string(),
+            max_iterations: 5,
+        });
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        let result = EnhancedTaskExecutor::execute_node_static(
+            &capped,
+            context,
+            &config,
+            &RunContext::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.iterations, 5);
+
+        // 普通节点执行一次
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        let result = EnhancedTaskExecutor::execute_node_static(
+            &node,
+            context,
+            &config,
+            &RunContext::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.iterations, 1);
+    }
+
     #[tokio::test]
     async fn test_builtin_set_variable_action() {
         let action_spec = ActionSpec {
@@ -1149,7 +3515,8 @@ mod tests {
 
         let result = EnhancedTaskExecutor::execute_builtin_action(
             &action_spec,
-            context.clone(),
+            &mut ActionContext::new(&action_spec, context.clone()),
+            &ExecutorConfig::default(),
         )
         .await;
         assert!(result.is_ok());
@@ -1161,6 +3528,44 @@ mod tests {
         assert!(stored_value.contains("test_value")); // Just check it contains the value
     }
 
+    #[tokio::test]
+    async fn test_builtin_set_variable_keeps_structure() {
+        let value: serde_yaml::Value =
+            serde_yaml::from_str("{ name: bob, tags: [a, b] }").unwrap();
+        let action_spec = ActionSpec {
+            action_type: "builtin".to_string(),
+            parameters: HashMap::from([
+                ("operation".to_string(), "set_variable".into()),
+                ("key".to_string(), "user".into()),
+                ("value".to_string(), value),
+            ]),
+            outputs: HashMap::new(),
+        };
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+
+        EnhancedTaskExecutor::execute_action_by_type(
+            &action_spec,
+            context.clone(),
+        )
+        .await
+        .unwrap();
+
+        let mut guard = context.lock().await;
+        let expected = serde_json::json!({ "name": "bob", "tags": ["a", "b"] });
+        assert_eq!(guard.get_typed("user"), Some(&expected));
+        assert!(guard.get_variable("user").is_some());
+
+        // 快照同时保存并恢复结构化变量
+        guard
+            .create_snapshot("before".to_string(), String::new())
+            .unwrap();
+        guard.set_typed("user".to_string(), serde_json::Value::Null);
+        guard.rollback_to_snapshot("before").unwrap();
+        assert_eq!(guard.get_typed("user"), Some(&expected));
+    }
+
     #[tokio::test]
     async fn test_builtin_sleep_action() {
         let action_spec = ActionSpec {
@@ -1185,9 +3590,12 @@ mod tests {
         ));
 
         let start = std::time::Instant::now();
-        let result =
-            EnhancedTaskExecutor::execute_builtin_action(&action_spec, context)
-                .await;
+        let result = EnhancedTaskExecutor::execute_builtin_action(
+            &action_spec,
+            &mut ActionContext::new(&action_spec, context),
+            &ExecutorConfig::default(),
+        )
+        .await;
         let elapsed = start.elapsed();
 
         assert!(result.is_ok());
@@ -1222,6 +3630,7 @@ mod tests {
         let result = EnhancedTaskExecutor::execute_cmd_action(
             &action_spec,
             context.clone(),
+            &ExecutorConfig::default(),
         )
         .await;
         assert!(result.is_ok());
@@ -1370,11 +3779,47 @@ mod tests {
         let result = EnhancedTaskExecutor::execute_composite_action(
             &action_spec,
             context,
+            &ExecutorConfig::default(),
         )
         .await;
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_composite_action_skips_unmet_condition() {
+        let actions: serde_yaml::Value = serde_yaml::from_str(
+            r#"
+- type: builtin
+  condition: "false"
+  parameters: { operation: set_variable, key: skipped, value: "1" }
+- type: builtin
+  condition: "true"
+  parameters: { operation: set_variable, key: ran, value: "1" }
+"#,
+        )
+        .unwrap();
+        let action_spec = ActionSpec {
+            action_type: "composite".to_string(),
+            parameters: HashMap::from([("actions".to_string(), actions)]),
+            outputs: HashMap::new(),
+        };
+
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        EnhancedTaskExecutor::execute_composite_action(
+            &action_spec,
+            context.clone(),
+            &ExecutorConfig::default(),
+        )
+        .await
+        .unwrap();
+
+        let guard = context.lock().await;
+        assert!(guard.get_variable("skipped").is_none());
+        assert!(guard.get_variable("ran").is_some());
+    }
+
     #[tokio::test]
     async fn test_unsupported_action_type() {
         let action_spec = ActionSpec {
@@ -1393,4 +3838,522 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("不支持的动作类型"));
     }
+
+    /// 启动本地 HTTP 测试服务，按请求路径返回（状态码，响应体）
+    #[cfg(feature = "http")]
+    async fn spawn_test_server(handler: fn(&str) -> (u16, String)) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut buf = vec![0u8; 8192];
+                    let n = socket.read(&mut buf).await.unwrap_or(0);
+                    let request = String::from_utf8_lossy(&buf[..n]);
+                    let path = request.split_whitespace().nth(1).unwrap_or("/");
+                    let (status, body) = handler(path);
+                    let response = format!(
+                        "HTTP/1.1 {status} OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        format!("http://{addr}")
+    }
+
+    #[cfg(feature = "http")]
+    #[tokio::test]
+    async fn test_http_paginate_action() {
+        let base = spawn_test_server(|path| {
+            let body = match path {
+                "/items" => r#"{"data":{"items":[1,2]},"next":"c2"}"#,
+                "/items?cursor=c2" => r#"{"data":{"items":[3]},"next":"c3"}"#,
+                "/items?cursor=c3" => r#"{"data":{"items":[4]},"next":null}"#,
+                _ => return (404, "{}".to_string()),
+            };
+            (200, body.to_string())
+        })
+        .await;
+
+        let spec = |max_pages: u64| ActionSpec {
+            action_type: "http_paginate".to_string(),
+            parameters: HashMap::from([
+                (
+                    "url".to_string(),
+                    serde_yaml::Value::String(format!("{base}/items")),
+                ),
+                (
+                    "items".to_string(),
+                    serde_yaml::Value::String(".data | .items".to_string()),
+                ),
+                (
+                    "next_cursor".to_string(),
+                    serde_yaml::Value::String(".next".to_string()),
+                ),
+                (
+                    "max_pages".to_string(),
+                    serde_yaml::Value::Number(max_pages.into()),
+                ),
+                (
+                    "output".to_string(),
+                    serde_yaml::Value::String("all_items".to_string()),
+                ),
+            ]),
+            outputs: HashMap::new(),
+        };
+
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        EnhancedTaskExecutor::execute_action_by_type(
+            &spec(10),
+            context.clone(),
+        )
+        .await
+        .unwrap();
+        {
+            let guard = context.lock().await;
+            assert_eq!(guard.get_variable("all_items").unwrap(), "[1,2,3,4]");
+            assert_eq!(guard.get_variable("all_items_pages").unwrap(), "3");
+        }
+
+        // 达到最大页数时停止
+        EnhancedTaskExecutor::execute_action_by_type(&spec(2), context.clone())
+            .await
+            .unwrap();
+        let guard = context.lock().await;
+        assert_eq!(guard.get_variable("all_items").unwrap(), "[1,2,3]");
+        assert_eq!(guard.get_variable("all_items_pages").unwrap(), "2");
+        drop(guard);
+
+        // 路径与 `${jq:...}` 同样校验
+        let mut invalid = spec(1);
+        invalid.parameters.insert(
+            "items".to_string(),
+            serde_yaml::Value::String(".data.items[x]".to_string()),
+        );
+        let err =
+            EnhancedTaskExecutor::execute_action_by_type(&invalid, context)
+                .await
+                .unwrap_err();
+        assert!(err.to_string().contains("jq expression failed"));
+    }
+
+    #[tokio::test]
+    async fn test_cmd_inherits_context_env() {
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        {
+            let mut guard = context.lock().await;
+            guard.set_variable("flow.user".to_string(), "ada".to_string());
+            guard.set_variable("flow.region".to_string(), "eu".to_string());
+            guard.set_variable("secret".to_string(), "hidden".to_string());
+        }
+
+        let action_spec = ActionSpec {
+            action_type: "cmd".to_string(),
+            parameters: serde_yaml::from_str(
+                r#"
+command: sh
+args: ["-c", "echo $FLOW_USER-$FLOW_REGION-${SECRET:-none}"]
+context_env_prefix: "flow."
+env: { FLOW_REGION: "us" }
+"#,
+            )
+            .unwrap(),
+            outputs: HashMap::new(),
+        };
+        EnhancedTaskExecutor::execute_cmd_action(
+            &action_spec,
+            context.clone(),
+            &ExecutorConfig::default(),
+        )
+        .await
+        .unwrap();
+
+        // 仅导出匹配前缀的变量，显式 env 优先
+        let guard = context.lock().await;
+        assert_eq!(guard.get_variable("cmd_stdout").unwrap(), "ada-us-none\n");
+    }
+
+    #[tokio::test]
+    async fn test_cmd_exports_plan_flow_vars_unquoted() {
+        let mut plan = ExecutionPlan::new(
+            "deploy".to_string(),
+            "1.0".to_string(),
+            HashMap::from([("REGION".to_string(), "eu".into())]),
+            HashMap::from([
+                ("name".to_string(), "Deploy".into()),
+                ("replicas".to_string(), 3.into()),
+            ]),
+        );
+        let action_spec = ActionSpec {
+            action_type: "cmd".to_string(),
+            parameters: serde_yaml::from_str(
+                r#"
+command: sh
+args: ["-c", "echo $FLOW_NAME-$FLOW_REPLICAS-$ENV_REGION"]
+inherit_context_env: true
+"#,
+            )
+            .unwrap(),
+            outputs: HashMap::new(),
+        };
+        plan.add_phase(ExecutionPhase {
+            id: "phase_0".to_string(),
+            name: "phase".to_string(),
+            execution_mode: PhaseExecutionMode::Sequential,
+            nodes: vec![ExecutionNode::new(
+                "run".to_string(),
+                "Run".to_string(),
+                action_spec,
+            )],
+            condition: None,
+        });
+
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        let result = EnhancedTaskExecutor::new()
+            .execute_plan(plan, context.clone(), &RunContext::default())
+            .await
+            .unwrap();
+
+        assert!(result.success);
+        let guard = context.lock().await;
+        assert_eq!(guard.get_variable("flow.name").unwrap(), "Deploy");
+        assert_eq!(guard.get_variable("cmd_stdout").unwrap(), "Deploy-3-eu\n");
+    }
+
+    #[test]
+    fn test_stream_lines_flush_long_partial_line() {
+        let mut pending = Vec::new();
+        for _ in 0..16 {
+            pending.extend_from_slice(&[b'#'; 4096]);
+            log_stream_lines(&mut pending, "stdout");
+            assert!(pending.len() < STREAM_LINE_LIMIT);
+        }
+
+        pending.extend_from_slice(b"done\nrest");
+        log_stream_lines(&mut pending, "stdout");
+        assert_eq!(pending, b"rest");
+    }
+
+    #[tokio::test]
+    async fn test_cmd_output_truncated_to_limit() {
+        let mut parameters = HashMap::new();
+        parameters.insert("command".to_string(), "sh".into());
+        parameters.insert(
+            "args".to_string(),
+            serde_yaml::Value::Sequence(vec![
+                "-c".into(),
+                "yes line | head -c 50000; echo oops >&2".into(),
+            ]),
+        );
+        parameters.insert("stream".to_string(), true.into());
+        let action_spec = ActionSpec {
+            action_type: "cmd".to_string(),
+            parameters,
+            outputs: HashMap::new(),
+        };
+        let config = ExecutorConfig {
+            max_output_bytes: Some(100),
+            ..ExecutorConfig::default()
+        };
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+
+        EnhancedTaskExecutor::execute_cmd_action(
+            &action_spec,
+            context.clone(),
+            &config,
+        )
+        .await
+        .unwrap();
+
+        let guard = context.lock().await;
+        let stdout = guard.get_variable("cmd_stdout").unwrap();
+        assert_eq!(stdout.len(), 100 + "...[truncated]".len());
+        assert!(stdout.starts_with("line\nline\n"));
+        assert!(stdout.ends_with("...[truncated]"));
+        assert_eq!(guard.get_variable("cmd_stderr").unwrap(), "oops\n");
+    }
+
+    #[cfg(feature = "http")]
+    #[tokio::test]
+    async fn test_healthcheck_action() {
+        let base = spawn_test_server(|path| match path {
+            "/ready" => (200, r#"{"status":"ok"}"#.to_string()),
+            _ => (503, "starting".to_string()),
+        })
+        .await;
+
+        let spec = |params: &[(&str, serde_yaml::Value)]| ActionSpec {
+            action_type: "healthcheck".to_string(),
+            parameters: params
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect(),
+            outputs: HashMap::new(),
+        };
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+
+        let ready = spec(&[
+            ("url", format!("{base}/ready").into()),
+            ("body_contains", "ok".into()),
+        ]);
+        EnhancedTaskExecutor::execute_action_by_type(&ready, context.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            context
+                .lock()
+                .await
+                .get_variable("healthcheck_attempts")
+                .unwrap(),
+            "1"
+        );
+
+        // TCP 探测
+        let tcp = spec(&[("tcp", base.trim_start_matches("http://").into())]);
+        EnhancedTaskExecutor::execute_action_by_type(&tcp, context.clone())
+            .await
+            .unwrap();
+
+        // 尝试次数用尽后失败，并记录尝试次数
+        let down = spec(&[
+            ("url", format!("{base}/health").into()),
+            ("interval", 10.into()),
+            ("max_attempts", 3.into()),
+        ]);
+        let err = EnhancedTaskExecutor::execute_action_by_type(
+            &down,
+            context.clone(),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("503"), "{err}");
+        let guard = context.lock().await;
+        assert_eq!(guard.get_variable("healthcheck_attempts").unwrap(), "3");
+        assert!(guard.get_variable("healthcheck_latency_ms").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_port_action() {
+        let listener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open_port = listener.local_addr().unwrap().port();
+        // 绑定后随即释放的端口无人监听，用作关闭端口
+        let closed_port = {
+            let probe =
+                tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            probe.local_addr().unwrap().port()
+        };
+
+        let spec = |port: u16| ActionSpec {
+            action_type: "wait_for_port".to_string(),
+            parameters: HashMap::from([
+                ("port".to_string(), serde_yaml::Value::from(port)),
+                ("interval".to_string(), serde_yaml::Value::from(20)),
+                ("timeout".to_string(), serde_yaml::Value::from(200)),
+            ]),
+            outputs: HashMap::new(),
+        };
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+
+        EnhancedTaskExecutor::execute_action_by_type(
+            &spec(open_port),
+            context.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            context
+                .lock()
+                .await
+                .get_variable("wait_for_port_success")
+                .unwrap(),
+            "true"
+        );
+
+        let err = EnhancedTaskExecutor::execute_action_by_type(
+            &spec(closed_port),
+            context.clone(),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("超时"), "{err}");
+        let guard = context.lock().await;
+        assert_eq!(
+            guard.get_variable("wait_for_port_success").unwrap(),
+            "false"
+        );
+        assert!(guard.get_variable("wait_for_port_elapsed_ms").is_some());
+    }
+
+    #[cfg(feature = "http")]
+    #[tokio::test]
+    async fn test_http_download_to_file() {
+        let base = spawn_test_server(|path| match path {
+            "/artifact.bin" => (200, "x".repeat(256 * 1024)),
+            _ => (404, "missing".to_string()),
+        })
+        .await;
+
+        let target = std::env::temp_dir().join(format!(
+            "flowbuilder-download-{}/artifact.bin",
+            std::process::id()
+        ));
+        let spec = |url: String| ActionSpec {
+            action_type: "http".to_string(),
+            parameters: HashMap::from([
+                ("url".to_string(), serde_yaml::Value::String(url)),
+                (
+                    "download_to".to_string(),
+                    serde_yaml::Value::String(
+                        target.to_string_lossy().into_owned(),
+                    ),
+                ),
+            ]),
+            outputs: HashMap::new(),
+        };
+
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        EnhancedTaskExecutor::execute_http_action(
+            &spec(format!("{base}/artifact.bin")),
+            context.clone(),
+        )
+        .await
+        .unwrap();
+
+        {
+            let guard = context.lock().await;
+            assert_eq!(
+                guard.get_variable("http_download_bytes").unwrap(),
+                "262144"
+            );
+            assert!(guard.get_variable("http_response_body").is_none());
+        }
+        assert_eq!(std::fs::metadata(&target).unwrap().len(), 256 * 1024);
+
+        // 非成功状态码报错
+        assert!(EnhancedTaskExecutor::execute_http_action(
+            &spec(format!("{base}/missing")),
+            context,
+        )
+        .await
+        .is_err());
+
+        // 传输中断：保留原文件，不留下临时文件
+        let listener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 8192];
+            let _ = socket.read(&mut buf).await;
+            let _ = socket
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 1024\r\n\r\npartial",
+                )
+                .await;
+        });
+        assert!(EnhancedTaskExecutor::execute_http_action(
+            &spec(format!("http://{addr}/artifact.bin")),
+            Arc::new(tokio::sync::Mutex::new(
+                flowbuilder_context::FlowContext::default(),
+            )),
+        )
+        .await
+        .is_err());
+        assert_eq!(std::fs::metadata(&target).unwrap().len(), 256 * 1024);
+        let mut part = target.clone().into_os_string();
+        part.push(".part");
+        assert!(!std::path::Path::new(&part).exists());
+
+        std::fs::remove_dir_all(target.parent().unwrap()).ok();
+    }
+
+    #[tokio::test]
+    async fn test_artifact_handoff_between_nodes() {
+        let config = ExecutorConfig::default();
+        let context = Arc::new(tokio::sync::Mutex::new(
+            flowbuilder_context::FlowContext::default(),
+        ));
+        let builtin = |params: &[(&str, &str)]| ActionSpec {
+            action_type: "builtin".to_string(),
+            parameters: params
+                .iter()
+                .map(|(k, v)| {
+                    (k.to_string(), serde_yaml::Value::String(v.to_string()))
+                })
+                .collect(),
+            outputs: HashMap::new(),
+        };
+
+        let producer = ExecutionNode::new(
+            "producer".to_string(),
+            "Producer".to_string(),
+            builtin(&[
+                ("operation", "put_artifact"),
+                ("key", "report"),
+                ("content", "large report body"),
+            ]),
+        );
+        let consumer = ExecutionNode::new(
+            "consumer".to_string(),
+            "Consumer".to_string(),
+            builtin(&[
+                ("operation", "get_artifact"),
+                ("key", "report"),
+                ("output", "report_text"),
+            ]),
+        );
+
+        for node in [&producer, &consumer] {
+            let result = EnhancedTaskExecutor::execute_node_static(
+                node,
+                context.clone(),
+                &config,
+                &RunContext::default(),
+            )
+            .await
+            .unwrap();
+            assert!(result.success, "{:?}", result.error_message);
+        }
+
+        // 无共享配置的调用路径明确拒绝制品操作
+        let err = EnhancedTaskExecutor::execute_action_by_type(
+            &producer.action_spec,
+            context.clone(),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("execute_action_with_config"));
+
+        let guard = context.lock().await;
+        let handle = guard.get_variable("report").unwrap();
+        assert!(handle.starts_with("sha256:"));
+        assert!(config
+            .artifact_store
+            .contains(&ArtifactId::parse(handle).unwrap()));
+        assert_eq!(
+            guard.get_variable("report_text").unwrap(),
+            "large report body"
+        );
+    }
 }
@@ -2,34 +2,53 @@
 //!
 //! 高级运行时功能，包括任务调度、流程编排和增强执行器
 
+mod action_context;
+mod action_handler;
+mod artifact;
+mod condition;
 mod enhanced_executor;
-mod enhanced_orchestrator;
+mod parameter;
+mod run_context;
+#[cfg(feature = "sqlx")]
+mod sql;
+mod subflow;
 
 // 重新导出增强组件
-pub use enhanced_orchestrator::{
+pub use action_context::ActionContext;
+pub use action_handler::{ActionFuture, ActionHandler, ActionRegistry};
+
+pub use artifact::{
+    ArtifactId, ArtifactStore, FsArtifactStore, MemoryArtifactStore,
+};
+
+pub use condition::ConditionEvaluator;
+pub use parameter::ParameterResolver;
+
+pub use run_context::RunContext;
+pub use tokio_util::sync::CancellationToken;
+
+pub use subflow::{WorkflowFuture, WorkflowRunner};
+
+// 编排器已移至 core（规划不依赖运行时），此处保留原有导出
+pub use flowbuilder_core::{
     EnhancedFlowOrchestrator, ExecutionComplexity,
-    OrchestratorConfig as EnhancedOrchestratorConfig,
+    OrchestratorConfig as EnhancedOrchestratorConfig, ResourcePacking,
 };
 
 pub use enhanced_executor::{
-    EnhancedTaskExecutor, ExecutionResult, ExecutorConfig, NodeResult,
-    PhaseResult,
+    EnhancedTaskExecutor, ExecutionResult, ExecutionStats, ExecutionSummary,
+    ExecutorConfig, NodeResult, PhaseResult, PlanHandle, TimeoutHandlerResult,
 };
 
-#[cfg(feature = "perf-metrics")]
-pub use enhanced_executor::ExecutionStats;
-
 /// 预导入模块
 pub mod prelude {
     // 增强组件
     pub use crate::{
         EnhancedFlowOrchestrator, EnhancedTaskExecutor, ExecutionComplexity,
-        ExecutionResult, NodeResult, PhaseResult,
+        ExecutionResult, ExecutionStats, ExecutionSummary, NodeResult,
+        PhaseResult, PlanHandle, RunContext,
     };
 
-    #[cfg(feature = "perf-metrics")]
-    pub use crate::ExecutionStats;
-
     // 核心接口
     pub use flowbuilder_core::{
         ExecutionNode, ExecutionPhase, ExecutionPlan, Executor, ExecutorStatus,
@@ -0,0 +1,151 @@
+//! # FlowBuilder Runtime - 参数求值
+//!
+//! 动作参数中的 `${...}` 表达式在执行时由配置层注入的
+//! [`ParameterResolver`] 基于当前上下文变量求值
+
+use anyhow::Result;
+use flowbuilder_context::{SharedContext, VarMap};
+use flowbuilder_core::ActionSpec;
+use std::fmt;
+use std::sync::Arc;
+
+/// 参数求值器
+pub trait ParameterResolver: fmt::Debug + Send + Sync {
+    /// 基于上下文变量求值含表达式的字符串参数
+    ///
+    /// 整个值为单一表达式时应保留其原生类型
+    fn resolve(
+        &self,
+        value: &str,
+        variables: &VarMap<String>,
+    ) -> Result<serde_yaml::Value>;
+}
+
+/// 求值动作参数中的表达式；没有表达式时返回 `None`（无需复制动作）
+///
+/// SQL 语句（`sql`/`sql_stream` 的 `query`）不求值：拼接进语句的变量
+/// 绕过参数绑定，变量应通过 `params: [{ var: <name> }]` 绑定
+pub(crate) async fn resolve_parameters(
+    action_spec: &ActionSpec,
+    context: &SharedContext,
+    resolver: &Arc<dyn ParameterResolver>,
+) -> Result<Option<ActionSpec>> {
+    let resolvable = |key: &str| !is_sql_statement(action_spec, key);
+    if !action_spec
+        .parameters
+        .iter()
+        .any(|(key, value)| resolvable(key) && has_expression(value))
+    {
+        return Ok(None);
+    }
+
+    let variables = context.lock().await.variables.clone();
+    let mut resolved = action_spec.clone();
+    for (key, value) in resolved.parameters.iter_mut() {
+        if !resolvable(key) {
+            continue;
+        }
+        *value = resolve_value(value, resolver.as_ref(), &variables)
+            .map_err(|e| anyhow::anyhow!("参数 {} 求值失败: {}", key, e))?;
+    }
+    Ok(Some(resolved))
+}
+
+/// 参数是否为 SQL 语句
+fn is_sql_statement(action_spec: &ActionSpec, key: &str) -> bool {
+    key == "query"
+        && matches!(action_spec.action_type.as_str(), "sql" | "sql_stream")
+}
+
+/// 值（含嵌套映射/列表）中是否有 `${...}` 表达式
+fn has_expression(value: &serde_yaml::Value) -> bool {
+    match value {
+        serde_yaml::Value::String(s) => s.contains("${"),
+        serde_yaml::Value::Sequence(items) => items.iter().any(has_expression),
+        serde_yaml::Value::Mapping(map) => map.values().any(has_expression),
+        _ => false,
+    }
+}
+
+/// 递归求值字符串参数，其余值原样保留
+fn resolve_value(
+    value: &serde_yaml::Value,
+    resolver: &dyn ParameterResolver,
+    variables: &VarMap<String>,
+) -> Result<serde_yaml::Value> {
+    Ok(match value {
+        serde_yaml::Value::String(s) if s.contains("${") => {
+            resolver.resolve(s, variables)?
+        }
+        serde_yaml::Value::Sequence(items) => serde_yaml::Value::Sequence(
+            items
+                .iter()
+                .map(|item| resolve_value(item, resolver, variables))
+                .collect::<Result<_>>()?,
+        ),
+        serde_yaml::Value::Mapping(map) => {
+            let mut resolved = serde_yaml::Mapping::new();
+            for (key, item) in map {
+                resolved.insert(
+                    key.clone(),
+                    resolve_value(item, resolver, variables)?,
+                );
+            }
+            serde_yaml::Value::Mapping(resolved)
+        }
+        other => other.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flowbuilder_context::FlowContext;
+    use std::collections::HashMap;
+
+    /// 把表达式替换为固定值
+    #[derive(Debug)]
+    struct FixedResolver;
+
+    impl ParameterResolver for FixedResolver {
+        fn resolve(
+            &self,
+            _value: &str,
+            _variables: &VarMap<String>,
+        ) -> Result<serde_yaml::Value> {
+            Ok("resolved".into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sql_query_is_not_resolved() {
+        let context = Arc::new(tokio::sync::Mutex::new(FlowContext::default()));
+        let resolver: Arc<dyn ParameterResolver> = Arc::new(FixedResolver);
+        let query = "SELECT * FROM t WHERE name = '${ctx:name}'";
+        let spec = |action_type: &str| ActionSpec {
+            action_type: action_type.to_string(),
+            parameters: HashMap::from([
+                ("url".to_string(), "${ctx:url}".into()),
+                ("query".to_string(), query.into()),
+            ]),
+            outputs: HashMap::new(),
+        };
+
+        for action_type in ["sql", "sql_stream"] {
+            let resolved =
+                resolve_parameters(&spec(action_type), &context, &resolver)
+                    .await
+                    .unwrap()
+                    .unwrap();
+            assert_eq!(resolved.parameters["url"], "resolved");
+            assert_eq!(resolved.parameters["query"], query);
+        }
+
+        // 其他动作的同名参数照常求值
+        let resolved = resolve_parameters(&spec("http"), &context, &resolver)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(resolved.parameters["query"], "resolved");
+    }
+}
@@ -0,0 +1,117 @@
+//! # FlowBuilder Runtime - 运行上下文
+//!
+//! 汇总一次执行的横切关注点（取消、截止时间、追踪 span），
+//! 沿 `execute_plan` → 阶段 → 节点逐层传递
+
+use anyhow::Result;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// 运行上下文
+#[derive(Debug, Clone)]
+pub struct RunContext {
+    /// 取消令牌，取消后尚未开始的节点不再执行，进行中的节点被中断
+    pub cancel: CancellationToken,
+    /// 截止时间，超过后的行为同取消
+    pub deadline: Option<Instant>,
+    /// 执行计划 span 的父 span
+    pub span: tracing::Span,
+}
+
+impl Default for RunContext {
+    /// 无截止时间，父 span 为当前 span
+    fn default() -> Self {
+        Self {
+            cancel: CancellationToken::new(),
+            deadline: None,
+            span: tracing::Span::current(),
+        }
+    }
+}
+
+impl RunContext {
+    /// 创建运行上下文
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置取消令牌
+    pub fn with_cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// 设置截止时间
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// 设置从现在起的超时
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// 设置父 span
+    pub fn with_span(mut self, span: tracing::Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// 以外部 OpenTelemetry 上下文（如从上游请求头提取）作为根，
+    /// 执行计划、阶段与节点的 span 依次挂在其下
+    #[cfg(feature = "otel")]
+    pub fn with_otel_parent(self, parent: opentelemetry::Context) -> Self {
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+        let span = tracing::info_span!(parent: None, "flowbuilder.run");
+        span.set_parent(parent);
+        self.with_span(span)
+    }
+
+    /// 距截止时间的剩余时间（无截止时间时为 `None`）
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|d| d.saturating_duration_since(Instant::now()))
+    }
+
+    /// 是否已取消或超过截止时间
+    pub fn is_stopped(&self) -> bool {
+        self.cancel.is_cancelled() || self.remaining() == Some(Duration::ZERO)
+    }
+
+    /// 已取消或超过截止时间时返回错误
+    pub fn check(&self) -> Result<()> {
+        if self.cancel.is_cancelled() {
+            return Err(anyhow::anyhow!("执行已取消"));
+        }
+        if self.remaining() == Some(Duration::ZERO) {
+            return Err(anyhow::anyhow!("已超过执行截止时间"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_context_check() {
+        let run = RunContext::default();
+        assert!(run.check().is_ok());
+        assert_eq!(run.remaining(), None);
+
+        run.cancel.cancel();
+        assert!(run.is_stopped());
+        assert!(run.check().unwrap_err().to_string().contains("取消"));
+
+        let expired = RunContext::new().with_deadline(Instant::now());
+        assert!(expired.is_stopped());
+        assert!(expired.check().unwrap_err().to_string().contains("截止"));
+
+        let later = RunContext::new().with_timeout(Duration::from_secs(60));
+        assert!(later.remaining().unwrap() > Duration::from_secs(59));
+        assert!(later.check().is_ok());
+    }
+}
@@ -0,0 +1,238 @@
+//! # FlowBuilder Runtime - 子工作流
+//!
+//! `workflow` 动作把另一个工作流作为嵌套流程执行；加载与编排由配置层注入的
+//! [`WorkflowRunner`] 完成，运行时只负责变量的传入与传出
+
+use anyhow::Result;
+use flowbuilder_context::{FlowContext, SharedContext};
+use flowbuilder_core::ActionSpec;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// 子工作流运行结果的 Future
+pub type WorkflowFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// 子工作流运行器
+pub trait WorkflowRunner: fmt::Debug + Send + Sync {
+    /// 加载、编排并执行 `reference` 指向的工作流
+    fn run_workflow<'a>(
+        &'a self,
+        reference: &'a str,
+        context: SharedContext,
+    ) -> WorkflowFuture<'a>;
+}
+
+/// 执行 `workflow` 动作
+///
+/// 参数：`ref` 子工作流引用；`inputs` 子变量名 → 父变量名；
+/// `outputs` 父变量名 → 子变量名
+pub(crate) async fn execute_workflow_action(
+    action_spec: &ActionSpec,
+    context: SharedContext,
+    runner: Option<&Arc<dyn WorkflowRunner>>,
+) -> Result<()> {
+    let reference = action_spec
+        .parameters
+        .get("ref")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("workflow 动作缺少 'ref' 参数"))?;
+    let runner = runner.ok_or_else(|| {
+        anyhow::anyhow!("未配置子工作流运行器，无法执行 workflow 动作")
+    })?;
+    let inputs = string_mapping(action_spec, "inputs")?;
+    let outputs = string_mapping(action_spec, "outputs")?;
+
+    // 子流程使用独立上下文，沿用父流程的 trace_id
+    let child = {
+        let guard = context.lock().await;
+        let mut child = FlowContext::new_with_trace_id(guard.trace_id.clone());
+        for (child_key, parent_key) in &inputs {
+            let value = guard.get_variable(parent_key).ok_or_else(|| {
+                anyhow::anyhow!("子工作流输入缺少变量: {}", parent_key)
+            })?;
+            child.set_variable(child_key.clone(), value.clone());
+        }
+        Arc::new(Mutex::new(child))
+    };
+
+    tracing::debug!(reference, "执行子工作流");
+    // 子流程在独立任务中运行，嵌套层级不会在同一调用栈上累积；
+    // 父节点超时或被取消时本 Future 被丢弃，子流程随之中止
+    let mut task = AbortOnDrop({
+        let runner = runner.clone();
+        let reference = reference.to_string();
+        let child = child.clone();
+        tokio::spawn(
+            async move { runner.run_workflow(&reference, child).await },
+        )
+    });
+    (&mut task.0)
+        .await
+        .map_err(|e| anyhow::anyhow!("子工作流任务异常退出: {e}"))
+        .and_then(|r| r)
+        // 节点错误只记录顶层信息，这里把子流程的错误链展开
+        .map_err(|e| anyhow::anyhow!("子工作流 {reference} 执行失败: {e:#}"))?;
+
+    let child = child.lock().await;
+    let mut guard = context.lock().await;
+    for (parent_key, child_key) in &outputs {
+        let value = child.get_variable(child_key).ok_or_else(|| {
+            anyhow::anyhow!("子工作流未产生输出变量: {}", child_key)
+        })?;
+        guard.set_variable(parent_key.clone(), value.clone());
+    }
+
+    Ok(())
+}
+
+/// 丢弃时中止任务的句柄
+struct AbortOnDrop<T>(tokio::task::JoinHandle<T>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// 读取字符串到字符串的映射参数（缺省为空）
+fn string_mapping(
+    action_spec: &ActionSpec,
+    name: &str,
+) -> Result<Vec<(String, String)>> {
+    let Some(value) = action_spec.parameters.get(name) else {
+        return Ok(Vec::new());
+    };
+    let mapping = value.as_mapping().ok_or_else(|| {
+        anyhow::anyhow!("workflow 动作的 '{}' 参数必须是映射", name)
+    })?;
+    mapping
+        .iter()
+        .map(|(k, v)| match (k.as_str(), v.as_str()) {
+            (Some(k), Some(v)) => Ok((k.to_string(), v.to_string())),
+            _ => Err(anyhow::anyhow!(
+                "workflow 动作的 '{}' 参数只能包含字符串",
+                name
+            )),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// 把 `in` 变量加上后缀写入 `out`
+    #[derive(Debug)]
+    struct EchoRunner;
+
+    impl WorkflowRunner for EchoRunner {
+        fn run_workflow<'a>(
+            &'a self,
+            reference: &'a str,
+            context: SharedContext,
+        ) -> WorkflowFuture<'a> {
+            Box::pin(async move {
+                let mut guard = context.lock().await;
+                let input =
+                    guard.get_variable("in").cloned().unwrap_or_default();
+                guard
+                    .set_variable("out".into(), format!("{input}@{reference}"));
+                Ok(())
+            })
+        }
+    }
+
+    fn workflow_spec(yaml: &str) -> ActionSpec {
+        ActionSpec {
+            action_type: "workflow".to_string(),
+            parameters: serde_yaml::from_str::<HashMap<_, _>>(yaml).unwrap(),
+            outputs: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_workflow_action_maps_inputs_and_outputs() {
+        let context = Arc::new(Mutex::new(FlowContext::default()));
+        context
+            .lock()
+            .await
+            .set_variable("user".into(), "alice".into());
+
+        let spec = workflow_spec(
+            "ref: child.yaml\ninputs: { in: user }\noutputs: { greeting: out }",
+        );
+        let runner: Arc<dyn WorkflowRunner> = Arc::new(EchoRunner);
+        execute_workflow_action(&spec, context.clone(), Some(&runner))
+            .await
+            .unwrap();
+
+        let guard = context.lock().await;
+        assert_eq!(
+            guard.get_variable("greeting").map(String::as_str),
+            Some("alice@child.yaml")
+        );
+        // 子流程的变量不会泄漏到父上下文
+        assert!(guard.get_variable("out").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_workflow_action_requires_runner_and_inputs() {
+        let context = Arc::new(Mutex::new(FlowContext::default()));
+        let spec = workflow_spec("ref: child.yaml");
+        assert!(execute_workflow_action(&spec, context.clone(), None)
+            .await
+            .is_err());
+
+        let spec = workflow_spec("ref: child.yaml\ninputs: { in: missing }");
+        let runner: Arc<dyn WorkflowRunner> = Arc::new(EchoRunner);
+        let err = execute_workflow_action(&spec, context, Some(&runner))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("missing"));
+    }
+
+    /// 等待一段时间后标记完成
+    #[derive(Debug, Default)]
+    struct SlowRunner {
+        finished: std::sync::atomic::AtomicBool,
+    }
+
+    impl WorkflowRunner for SlowRunner {
+        fn run_workflow<'a>(
+            &'a self,
+            _reference: &'a str,
+            _context: SharedContext,
+        ) -> WorkflowFuture<'a> {
+            Box::pin(async move {
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                self.finished
+                    .store(true, std::sync::atomic::Ordering::SeqCst);
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dropped_workflow_action_aborts_child() {
+        let context = Arc::new(Mutex::new(FlowContext::default()));
+        let slow = Arc::new(SlowRunner::default());
+        let runner: Arc<dyn WorkflowRunner> = slow.clone();
+        let spec = workflow_spec("ref: child.yaml");
+
+        // 父节点超时：动作的 Future 被丢弃
+        let run = execute_workflow_action(&spec, context, Some(&runner));
+        let timed_out =
+            tokio::time::timeout(std::time::Duration::from_millis(20), run)
+                .await;
+        assert!(timed_out.is_err());
+
+        // 子流程已中止，不会继续运行到结束
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        assert!(!slow.finished.load(std::sync::atomic::Ordering::SeqCst));
+    }
+}
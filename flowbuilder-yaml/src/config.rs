@@ -1,14 +1,18 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// 完整的工作流配置结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkflowConfig {
     pub workflow: Workflow,
+    /// 环境配置：名称 → 覆盖到基础配置上的部分配置树
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub profiles: HashMap<String, serde_yaml::Value>,
 }
 
 /// 工作流定义
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "WorkflowShape")]
 pub struct Workflow {
     pub version: String,
     #[serde(default)]
@@ -18,6 +22,93 @@ pub struct Workflow {
     #[serde(default)]
     pub template: Option<Template>,
     pub tasks: Vec<Task>,
+    /// 任务组（组内任务共享默认配置）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<Group>,
+    /// 多动作任务的编排方式
+    #[serde(default, skip_serializing_if = "MultiActionMode::is_composite")]
+    pub multi_action: MultiActionMode,
+    /// 命名模板，供 `${template:<name>}` 渲染（需启用 `tera` 特性）
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub templates: HashMap<String, TemplateSource>,
+}
+
+/// 模板来源：内联字符串或 `{ file: <path> }`（相对文件 Provider 基准目录）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TemplateSource {
+    Inline(String),
+    File { file: String },
+}
+
+/// 多动作任务的编排方式
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum MultiActionMode {
+    /// 合并为一个复合节点
+    #[default]
+    Composite,
+    /// 每个动作一个节点（ID 为 `<task>.<action>`），任务内按顺序依赖，
+    /// 保留各动作的条件、重试与超时配置
+    Sequential,
+}
+
+impl MultiActionMode {
+    fn is_composite(&self) -> bool {
+        *self == MultiActionMode::Composite
+    }
+}
+
+/// 反序列化用的工作流结构：`tasks` 只在定义了任务组时可省略
+#[derive(Deserialize)]
+struct WorkflowShape {
+    version: String,
+    #[serde(default)]
+    env: HashMap<String, String>,
+    #[serde(default)]
+    vars: HashMap<String, serde_yaml::Value>,
+    #[serde(default)]
+    template: Option<Template>,
+    tasks: Option<Vec<Task>>,
+    #[serde(default)]
+    groups: Vec<Group>,
+    #[serde(default)]
+    multi_action: MultiActionMode,
+    #[serde(default)]
+    templates: HashMap<String, TemplateSource>,
+}
+
+impl TryFrom<WorkflowShape> for Workflow {
+    type Error = String;
+
+    fn try_from(shape: WorkflowShape) -> Result<Self, Self::Error> {
+        if shape.tasks.is_none() && shape.groups.is_empty() {
+            return Err("missing field `tasks`".to_string());
+        }
+        Ok(Self {
+            version: shape.version,
+            env: shape.env,
+            vars: shape.vars,
+            template: shape.template,
+            tasks: shape.tasks.unwrap_or_default(),
+            groups: shape.groups,
+            multi_action: shape.multi_action,
+            templates: shape.templates,
+        })
+    }
+}
+
+impl Workflow {
+    /// 所有任务定义（顶层任务在前，任务组成员在后）
+    pub fn all_tasks(&self) -> impl Iterator<Item = &TaskDefinition> {
+        self.tasks.iter().map(|t| &t.task).chain(
+            self.groups
+                .iter()
+                .flat_map(|g| g.group.tasks.iter().map(|t| &t.task)),
+        )
+    }
 }
 
 /// 模板定义
@@ -35,12 +126,55 @@ pub struct Task {
     pub task: TaskDefinition,
 }
 
+/// 任务组
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Group {
+    pub group: GroupDefinition,
+}
+
+/// 任务组定义详情
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupDefinition {
+    pub id: String,
+    #[serde(default)]
+    pub defaults: GroupDefaults,
+    pub tasks: Vec<Task>,
+}
+
+/// 任务组默认配置
+///
+/// 合并规则：任务（或其动作）显式设置的值优先，未设置时使用组默认值；
+/// `tags` 整体覆盖，不做合并
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GroupDefaults {
+    #[serde(default)]
+    pub timeout: Option<TimeoutConfig>,
+    #[serde(default)]
+    pub retry: Option<RetryConfig>,
+    #[serde(default)]
+    pub on_error: Option<String>,
+    #[serde(default)]
+    pub on_timeout: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
 /// 任务定义详情
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskDefinition {
     pub id: String,
     pub name: String,
     pub description: String,
+    /// 参数化展开：每组取值生成一个任务，字符串中的 `${matrix.<key>}`
+    /// 替换为对应取值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matrix: Option<BTreeMap<String, Vec<serde_yaml::Value>>>,
+    /// 任务标签
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// 显式阶段：按首次出现的顺序形成屏障，之前阶段的任务全部完成后才执行
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<String>,
     #[serde(default)]
     pub actions: Vec<Action>,
 }
@@ -65,6 +199,9 @@ pub struct ActionDefinition {
     pub action_type: ActionType,
     #[serde(default)]
     pub parameters: HashMap<String, Parameter>,
+    /// 参数约束（默认值与必填），解析时应用
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub param_schema: HashMap<String, ParamSchema>,
 }
 
 /// 流程控制
@@ -115,6 +252,50 @@ pub enum ActionType {
     #[default]
     Builtin,
     Wasm,
+    /// 游标分页 HTTP 请求
+    #[serde(rename = "http_paginate")]
+    HttpPaginate,
+    /// 子工作流（`ref` 指向另一个工作流文件）
+    Workflow,
+    /// 健康检查（轮询 URL 或 TCP 端点直到就绪）
+    Healthcheck,
+    /// 等待 TCP 端口可连接
+    #[serde(rename = "wait_for_port")]
+    WaitForPort,
+    /// 参数化 SQL 查询（需启用 `sqlx` 特性）
+    Sql,
+    /// 流式 SQL 查询，逐行执行模板动作（需启用 `sqlx` 特性）
+    #[serde(rename = "sql_stream")]
+    SqlStream,
+}
+
+impl ActionType {
+    /// 执行器分派使用的类型名（与配置中的写法一致，不随变体重命名而变化）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActionType::Cmd => "cmd",
+            ActionType::Http => "http",
+            ActionType::Builtin => "builtin",
+            ActionType::Wasm => "wasm",
+            ActionType::HttpPaginate => "http_paginate",
+            ActionType::Workflow => "workflow",
+            ActionType::Healthcheck => "healthcheck",
+            ActionType::WaitForPort => "wait_for_port",
+            ActionType::Sql => "sql",
+            ActionType::SqlStream => "sql_stream",
+        }
+    }
+}
+
+/// 参数约束
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ParamSchema {
+    /// 是否必填（未提供且无默认值时解析失败）
+    #[serde(default)]
+    pub required: bool,
+    /// 未提供时注入的默认值
+    #[serde(default)]
+    pub default: Option<serde_yaml::Value>,
 }
 
 /// 参数定义
@@ -124,3 +305,31 @@ pub struct Parameter {
     #[serde(default)]
     pub required: bool,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_type_as_str() {
+        let cases = [
+            (ActionType::Cmd, "cmd"),
+            (ActionType::Http, "http"),
+            (ActionType::Builtin, "builtin"),
+            (ActionType::Wasm, "wasm"),
+            (ActionType::HttpPaginate, "http_paginate"),
+            (ActionType::Workflow, "workflow"),
+            (ActionType::Healthcheck, "healthcheck"),
+            (ActionType::WaitForPort, "wait_for_port"),
+            (ActionType::Sql, "sql"),
+            (ActionType::SqlStream, "sql_stream"),
+        ];
+        for (action_type, expected) in cases {
+            assert_eq!(action_type.as_str(), expected);
+            // 配置中的写法与分派类型名一致
+            let parsed: ActionType =
+                serde_yaml::from_str(expected).expect("known action type");
+            assert_eq!(parsed.as_str(), expected);
+        }
+    }
+}
@@ -2,59 +2,165 @@
 //!
 //! 从YAML配置解析生成执行节点
 
-use crate::config::{ActionDefinition, TaskDefinition, WorkflowConfig};
+use crate::config::{
+    ActionDefinition, GroupDefaults, MultiActionMode, TaskDefinition,
+    WorkflowConfig,
+};
 use anyhow::Result;
 use flowbuilder_core::{
-    ActionSpec, ConfigParser, ExecutionNode, NodeType, RetryConfig,
-    RetryStrategy, TimeoutConfig,
+    ActionSpec, ConfigParser, ExecutionNode, LoopConfig, NodeType,
+    OrchestratorConfig, RetryConfig, RetryStrategy, TimeoutConfig,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// YAML配置解析器
 pub struct YamlConfigParser {
     config: WorkflowConfig,
+    /// 展开后允许的最大任务数
+    max_nodes: usize,
 }
 
 impl YamlConfigParser {
     /// 创建新的配置解析器
     pub fn new(config: WorkflowConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            max_nodes: OrchestratorConfig::DEFAULT_MAX_NODES,
+        }
+    }
+
+    /// 设置展开后允许的最大任务数（matrix 展开超过时报错）
+    pub fn with_max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = max_nodes;
+        self
     }
 
     /// 解析配置，生成执行节点列表
     pub fn parse(&self) -> Result<Vec<ExecutionNode>> {
+        // 作为 on_timeout 处理目标的任务只在超时时执行，不参与正常编排
+        let handlers = self.timeout_handler_targets();
+        let tasks: Vec<_> = self
+            .expand_tasks()?
+            .into_iter()
+            .filter(|t| !handlers.contains(&t.origin_id))
+            .collect();
         let mut nodes = Vec::new();
 
-        for task_wrapper in &self.config.workflow.tasks {
-            let task = &task_wrapper.task;
-
-            // 为每个任务创建执行节点
-            let node = self.create_execution_node(task)?;
-            nodes.push(node);
+        for task in &tasks {
+            if self.is_sequential(&task.task) {
+                // 每个动作一个节点
+                nodes.extend(self.create_action_nodes(task, &tasks)?);
+            } else {
+                // 为每个任务创建执行节点
+                let node = self.create_execution_node(task, &tasks)?;
+                nodes.push(node);
+            }
         }
 
         Ok(nodes)
     }
 
+    /// 合并任务组默认配置并展开 matrix 任务，得到实际参与编排的任务列表
+    pub(crate) fn expand_tasks(&self) -> Result<Vec<ExpandedTask>> {
+        let workflow = &self.config.workflow;
+        let top_level = workflow.tasks.iter().map(|t| t.task.clone());
+        let grouped = workflow.groups.iter().flat_map(|g| {
+            g.group
+                .tasks
+                .iter()
+                .map(|t| apply_group_defaults(&t.task, &g.group.defaults))
+        });
+
+        let mut tasks = Vec::new();
+        for task in top_level.chain(grouped) {
+            let remaining = self.max_nodes.saturating_sub(tasks.len());
+            if remaining == 0 {
+                return Err(anyhow::anyhow!(
+                    "任务数超过节点上限 {}（展开到任务 {} 时）",
+                    self.max_nodes,
+                    task.id
+                ));
+            }
+            for expanded in expand_matrix(&task, remaining)? {
+                tasks.push(ExpandedTask {
+                    origin_id: task.id.clone(),
+                    task: expanded,
+                });
+            }
+        }
+        Ok(tasks)
+    }
+
     /// 创建执行节点
     fn create_execution_node(
         &self,
-        task: &TaskDefinition,
+        expanded: &ExpandedTask,
+        tasks: &[ExpandedTask],
     ) -> Result<ExecutionNode> {
+        let task = &expanded.task;
+
         // 合并所有动作为一个节点
         let action_spec = self.merge_task_actions(task)?;
 
         let mut node =
             ExecutionNode::new(task.id.clone(), task.name.clone(), action_spec);
 
-        // 设置节点类型
-        node.node_type = self.determine_node_type(task);
-
         // 提取依赖关系
-        node.dependencies = self.extract_dependencies(task)?;
+        node.dependencies = self.extract_dependencies(expanded, tasks)?;
+        node.edge_conditions = self.extract_edge_conditions(expanded, tasks);
+
+        self.configure_node(&mut node, task)?;
+        Ok(node)
+    }
 
-        // 提取执行条件
-        node.condition = self.extract_condition(task)?;
+    /// 为多动作任务的每个动作创建执行节点，任务内按顺序依赖
+    fn create_action_nodes(
+        &self,
+        expanded: &ExpandedTask,
+        tasks: &[ExpandedTask],
+    ) -> Result<Vec<ExecutionNode>> {
+        let task = &expanded.task;
+        let mut nodes: Vec<ExecutionNode> = Vec::new();
+
+        for action_wrapper in &task.actions {
+            let action = &action_wrapper.action;
+            let mut node = ExecutionNode::new(
+                action_node_id(task, action),
+                action.name.clone(),
+                self.convert_action_to_spec(action)?,
+            );
+
+            // 首个动作继承任务的依赖，其余动作依赖前一个动作
+            match nodes.last() {
+                Some(prev) => node.dependencies = vec![prev.id.clone()],
+                None => {
+                    node.dependencies =
+                        self.extract_dependencies(expanded, tasks)?;
+                    node.edge_conditions =
+                        self.extract_edge_conditions(expanded, tasks);
+                }
+            }
+
+            // 仅包含当前动作的任务视图，使条件、重试与超时取自该动作
+            let single = TaskDefinition {
+                actions: vec![action_wrapper.clone()],
+                ..task.clone()
+            };
+            self.configure_node(&mut node, &single)?;
+            nodes.push(node);
+        }
+
+        Ok(nodes)
+    }
+
+    /// 根据任务定义设置节点类型、条件、优先级、重试、超时、标签与显式阶段
+    fn configure_node(
+        &self,
+        node: &mut ExecutionNode,
+        task: &TaskDefinition,
+    ) -> Result<()> {
+        // 设置节点类型
+        node.node_type = self.determine_node_type(task);
 
         // 设置优先级
         node.priority = self.determine_priority(task)?;
@@ -69,7 +175,17 @@ impl YamlConfigParser {
             node.timeout_config = Some(timeout_config);
         }
 
-        Ok(node)
+        node.loop_config = self.extract_loop_config(task);
+        node.tags = task.tags.clone();
+        node.phase = task.phase.clone();
+
+        Ok(())
+    }
+
+    /// 多动作任务是否按动作拆分为多个节点
+    fn is_sequential(&self, task: &TaskDefinition) -> bool {
+        self.config.workflow.multi_action == MultiActionMode::Sequential
+            && task.actions.len() > 1
     }
 
     /// 合并任务中的所有动作
@@ -86,22 +202,43 @@ impl YamlConfigParser {
         // 多个动作时，创建一个复合动作
         let mut parameters = HashMap::new();
         let mut outputs = HashMap::new();
+        let mut sub_actions = Vec::new();
 
         for (index, action_wrapper) in task.actions.iter().enumerate() {
             let action = &action_wrapper.action;
+            let resolved = self.resolve_parameters(action)?;
 
             // 为每个动作添加前缀
             let prefix = format!("action_{index}");
 
-            for (key, value) in &action.parameters {
-                parameters
-                    .insert(format!("{prefix}_{key}"), value.value.clone());
+            for (key, value) in &resolved {
+                parameters.insert(format!("{prefix}_{key}"), value.clone());
             }
 
             for (key, value) in &action.outputs {
                 outputs.insert(format!("{prefix}_{key}"), value.clone());
             }
+
+            // 子动作结构供执行器按顺序执行，并保留各自的条件
+            let mut sub_action = serde_yaml::Mapping::new();
+            sub_action.insert("id".into(), action.id.as_str().into());
+            sub_action
+                .insert("type".into(), action.action_type.as_str().into());
+            sub_action.insert("parameters".into(), to_mapping(resolved).into());
+            sub_action.insert(
+                "outputs".into(),
+                to_mapping(action.outputs.clone()).into(),
+            );
+            if let Some(condition) = &action.flow.next_if {
+                sub_action
+                    .insert("condition".into(), condition.as_str().into());
+            }
+            sub_actions.push(serde_yaml::Value::Mapping(sub_action));
         }
+        parameters.insert(
+            "actions".to_string(),
+            serde_yaml::Value::Sequence(sub_actions),
+        );
 
         Ok(ActionSpec {
             action_type: "composite".to_string(),
@@ -115,18 +252,48 @@ impl YamlConfigParser {
         &self,
         action: &ActionDefinition,
     ) -> Result<ActionSpec> {
-        let mut parameters = HashMap::new();
-        for (key, param) in &action.parameters {
-            parameters.insert(key.clone(), param.value.clone());
-        }
+        let parameters = self.resolve_parameters(action)?;
 
         Ok(ActionSpec {
-            action_type: format!("{:?}", action.action_type).to_lowercase(),
+            action_type: action.action_type.as_str().to_string(),
             parameters,
             outputs: action.outputs.clone(),
         })
     }
 
+    /// 按 param_schema 补全默认值并检查必填参数
+    fn resolve_parameters(
+        &self,
+        action: &ActionDefinition,
+    ) -> Result<HashMap<String, serde_yaml::Value>> {
+        let mut parameters: HashMap<_, _> = action
+            .parameters
+            .iter()
+            .map(|(key, param)| (key.clone(), param.value.clone()))
+            .collect();
+
+        for (name, schema) in &action.param_schema {
+            if parameters.contains_key(name) {
+                continue;
+            }
+            match &schema.default {
+                Some(default) => {
+                    parameters.insert(name.clone(), default.clone());
+                }
+                None if schema.required => {
+                    return Err(anyhow::anyhow!(
+                        "动作 {} 缺少必填参数: {}",
+                        action.id,
+                        name
+                    ));
+                }
+                None => {}
+            }
+        }
+
+        Ok(parameters)
+    }
+
     /// 确定节点类型
     fn determine_node_type(&self, task: &TaskDefinition) -> NodeType {
         // 检查是否有条件逻辑
@@ -158,15 +325,65 @@ impl YamlConfigParser {
     ///
     /// 新语义：当 A.next = B 时，意味着 B 依赖于 A（即 A 执行完后执行 B）
     /// 因此需要查找所有指向当前任务的其他任务，将它们作为当前任务的依赖
+    /// 指向 matrix 任务原始 ID 的 next 会扇出到它的每个展开任务；
+    /// 按动作拆分的任务以设置了 next 的那个动作节点作为依赖
     fn extract_dependencies(
         &self,
-        task: &TaskDefinition,
+        expanded: &ExpandedTask,
+        tasks: &[ExpandedTask],
     ) -> Result<Vec<String>> {
-        let mut deps = Vec::new();
+        let mut deps: Vec<String> = self
+            .incoming_edges(expanded, tasks)
+            .into_iter()
+            .map(|(dep, _)| dep)
+            .collect();
+
+        // 移除重复的依赖
+        deps.sort();
+        deps.dedup();
+
+        Ok(deps)
+    }
+
+    /// 提取入边条件：指向当前任务的动作上的 `next_if`
+    ///
+    /// 同一依赖存在无条件的 next 时不设条件
+    fn extract_edge_conditions(
+        &self,
+        expanded: &ExpandedTask,
+        tasks: &[ExpandedTask],
+    ) -> HashMap<String, String> {
+        let mut conditions: HashMap<String, Option<String>> = HashMap::new();
+        for (dep, condition) in self.incoming_edges(expanded, tasks) {
+            match conditions.get_mut(&dep) {
+                Some(existing) => {
+                    if condition.is_none() {
+                        *existing = None;
+                    }
+                }
+                None => {
+                    conditions.insert(dep, condition);
+                }
+            }
+        }
+        conditions
+            .into_iter()
+            .filter_map(|(dep, condition)| Some((dep, condition?)))
+            .collect()
+    }
+
+    /// 查找指向当前任务的所有 next：(依赖节点ID, next_if)
+    fn incoming_edges(
+        &self,
+        expanded: &ExpandedTask,
+        tasks: &[ExpandedTask],
+    ) -> Vec<(String, Option<String>)> {
+        let task = &expanded.task;
+        let mut edges = Vec::new();
 
         // 遍历所有任务，查找哪些任务的 flow.next 指向当前任务
-        for task_wrapper in &self.config.workflow.tasks {
-            let other_task = &task_wrapper.task;
+        for other in tasks {
+            let other_task = &other.task;
 
             // 跳过当前任务自身
             if other_task.id == task.id {
@@ -178,33 +395,20 @@ impl YamlConfigParser {
                 let action = &action_wrapper.action;
 
                 if let Some(next) = &action.flow.next {
-                    if next == &task.id {
+                    if next == &task.id || next == &expanded.origin_id {
                         // 找到指向当前任务的任务，将其作为依赖
-                        deps.push(other_task.id.clone());
-                        break; // 每个任务最多添加一次依赖
+                        let dep = if self.is_sequential(other_task) {
+                            action_node_id(other_task, action)
+                        } else {
+                            other_task.id.clone()
+                        };
+                        edges.push((dep, action.flow.next_if.clone()));
                     }
                 }
             }
         }
 
-        // 移除重复的依赖
-        deps.sort();
-        deps.dedup();
-
-        Ok(deps)
-    }
-
-    /// 提取执行条件
-    fn extract_condition(
-        &self,
-        task: &TaskDefinition,
-    ) -> Result<Option<String>> {
-        // 从第一个动作的条件中提取
-        if let Some(action_wrapper) = task.actions.first() {
-            Ok(action_wrapper.action.flow.next_if.clone())
-        } else {
-            Ok(None)
-        }
+        edges
     }
 
     /// 确定优先级
@@ -260,9 +464,15 @@ impl YamlConfigParser {
         // 从第一个动作的超时配置中提取
         if let Some(action_wrapper) = task.actions.first() {
             if let Some(timeout) = &action_wrapper.action.flow.timeout {
+                let on_timeout = action_wrapper.action.flow.on_timeout.clone();
+                let on_timeout_action = match &on_timeout {
+                    Some(target) => self.resolve_timeout_handler(target)?,
+                    None => None,
+                };
                 return Ok(Some(TimeoutConfig {
                     duration: timeout.duration,
-                    on_timeout: action_wrapper.action.flow.on_timeout.clone(),
+                    on_timeout,
+                    on_timeout_action,
                 }));
             }
         }
@@ -270,6 +480,47 @@ impl YamlConfigParser {
         Ok(None)
     }
 
+    /// 提取循环配置
+    fn extract_loop_config(&self, task: &TaskDefinition) -> Option<LoopConfig> {
+        // 与节点类型判断一致：取第一个设置了 while_util 的动作
+        task.actions.iter().find_map(|a| {
+            a.action.flow.while_util.as_ref().map(|w| LoopConfig {
+                condition: w.condition.clone(),
+                max_iterations: w.max_iterations,
+            })
+        })
+    }
+
+    /// 所有 on_timeout 指向的已有任务ID
+    fn timeout_handler_targets(&self) -> HashSet<String> {
+        let workflow = &self.config.workflow;
+        let task_ids: HashSet<&str> =
+            workflow.all_tasks().map(|t| t.id.as_str()).collect();
+        let group_targets = workflow
+            .groups
+            .iter()
+            .filter_map(|g| g.group.defaults.on_timeout.as_deref());
+        workflow
+            .all_tasks()
+            .flat_map(|t| &t.actions)
+            .filter_map(|a| a.action.flow.on_timeout.as_deref())
+            .chain(group_targets)
+            .filter(|target| task_ids.contains(target))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// 解析超时处理动作：`on_timeout` 指向的任务（不存在时返回 `None`）
+    fn resolve_timeout_handler(
+        &self,
+        target: &str,
+    ) -> Result<Option<ActionSpec>> {
+        match self.config.workflow.all_tasks().find(|t| t.id == target) {
+            Some(task) => Ok(Some(self.merge_task_actions(task)?)),
+            None => Ok(None),
+        }
+    }
+
     /// 获取环境变量
     pub fn get_env_vars(&self) -> HashMap<String, String> {
         self.config.workflow.env.clone()
@@ -298,12 +549,14 @@ impl YamlConfigParser {
 
     /// 验证配置的有效性
     pub fn validate(&self) -> Result<()> {
-        if self.config.workflow.tasks.is_empty() {
+        let tasks = self.expand_tasks()?;
+        if tasks.is_empty() {
             return Err(anyhow::anyhow!("工作流没有任务"));
         }
 
-        for task_wrapper in &self.config.workflow.tasks {
-            let task = &task_wrapper.task;
+        let mut task_ids = std::collections::HashSet::new();
+        for expanded in &tasks {
+            let task = &expanded.task;
 
             if task.id.is_empty() {
                 return Err(anyhow::anyhow!("任务ID不能为空"));
@@ -317,6 +570,10 @@ impl YamlConfigParser {
                 return Err(anyhow::anyhow!("任务 {} 没有动作", task.id));
             }
 
+            if !task_ids.insert(task.id.as_str()) {
+                return Err(anyhow::anyhow!("任务ID重复: {}", task.id));
+            }
+
             // 验证每个动作
             for action_wrapper in &task.actions {
                 let action = &action_wrapper.action;
@@ -328,6 +585,8 @@ impl YamlConfigParser {
                 if action.name.is_empty() {
                     return Err(anyhow::anyhow!("动作名称不能为空"));
                 }
+
+                self.resolve_parameters(action)?;
             }
         }
 
@@ -335,6 +594,157 @@ impl YamlConfigParser {
     }
 }
 
+/// 展开后的任务
+pub(crate) struct ExpandedTask {
+    /// 展开前的任务ID（非 matrix 任务与 `task.id` 相同）
+    pub(crate) origin_id: String,
+    pub(crate) task: TaskDefinition,
+}
+
+/// 按键排序转换为 YAML 映射（保证复合动作参数稳定）
+fn to_mapping(map: HashMap<String, serde_yaml::Value>) -> serde_yaml::Mapping {
+    let mut entries: Vec<_> = map.into_iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries.into_iter().map(|(k, v)| (k.into(), v)).collect()
+}
+
+/// 按动作拆分时的节点ID
+fn action_node_id(task: &TaskDefinition, action: &ActionDefinition) -> String {
+    format!("{}.{}", task.id, action.id)
+}
+
+/// 将任务组默认配置合并到任务（任务及其动作上显式设置的值优先）
+fn apply_group_defaults(
+    task: &TaskDefinition,
+    defaults: &GroupDefaults,
+) -> TaskDefinition {
+    let mut task = task.clone();
+    if task.tags.is_empty() {
+        task.tags = defaults.tags.clone();
+    }
+    for action_wrapper in &mut task.actions {
+        let flow = &mut action_wrapper.action.flow;
+        if flow.timeout.is_none() {
+            flow.timeout = defaults.timeout.clone();
+        }
+        if flow.retry.is_none() {
+            flow.retry = defaults.retry.clone();
+        }
+        if flow.on_error.is_none() {
+            flow.on_error = defaults.on_error.clone();
+        }
+        if flow.on_timeout.is_none() {
+            flow.on_timeout = defaults.on_timeout.clone();
+        }
+    }
+    task
+}
+
+/// 按 matrix 的笛卡尔积展开任务
+///
+/// 变量按名称排序组合；ID 中未引用 matrix 变量时，以 `-<取值>` 追加后缀。
+/// 组合数超过 `limit` 时在展开前报错
+fn expand_matrix(
+    task: &TaskDefinition,
+    limit: usize,
+) -> Result<Vec<TaskDefinition>> {
+    let Some(matrix) = &task.matrix else {
+        return Ok(vec![task.clone()]);
+    };
+    if matrix.is_empty() {
+        return Err(anyhow::anyhow!("任务 {} 的 matrix 为空", task.id));
+    }
+    let total = matrix
+        .values()
+        .try_fold(1usize, |acc, values| acc.checked_mul(values.len()));
+    if total.is_none_or(|total| total > limit) {
+        return Err(anyhow::anyhow!(
+            "任务 {} 的 matrix 展开为 {} 个任务，超过剩余节点上限 {}",
+            task.id,
+            total.map_or_else(|| "过多".to_string(), |t| t.to_string()),
+            limit
+        ));
+    }
+
+    let mut combinations: Vec<Vec<(&str, String)>> = vec![Vec::new()];
+    for (key, values) in matrix {
+        if values.is_empty() {
+            return Err(anyhow::anyhow!(
+                "任务 {} 的 matrix 变量 {} 没有取值",
+                task.id,
+                key
+            ));
+        }
+        let values = values
+            .iter()
+            .map(|v| match v {
+                serde_yaml::Value::String(s) => Ok(s.clone()),
+                serde_yaml::Value::Number(n) => Ok(n.to_string()),
+                serde_yaml::Value::Bool(b) => Ok(b.to_string()),
+                _ => Err(anyhow::anyhow!(
+                    "任务 {} 的 matrix 变量 {} 只能取标量值",
+                    task.id,
+                    key
+                )),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        combinations = combinations
+            .into_iter()
+            .flat_map(|combo| {
+                values.iter().map(move |value| {
+                    let mut combo = combo.clone();
+                    combo.push((key.as_str(), value.clone()));
+                    combo
+                })
+            })
+            .collect();
+    }
+
+    let mut template = task.clone();
+    template.matrix = None;
+    let template = serde_yaml::to_value(&template)?;
+
+    combinations
+        .into_iter()
+        .map(|bindings| {
+            let mut value = template.clone();
+            bind_matrix_vars(&mut value, &bindings);
+            let mut expanded: TaskDefinition = serde_yaml::from_value(value)?;
+            if expanded.id == task.id {
+                let suffix: Vec<_> =
+                    bindings.iter().map(|(_, v)| v.as_str()).collect();
+                expanded.id = format!("{}-{}", task.id, suffix.join("-"));
+            }
+            Ok(expanded)
+        })
+        .collect()
+}
+
+/// 递归替换字符串中的 `${matrix.<key>}`
+fn bind_matrix_vars(
+    value: &mut serde_yaml::Value,
+    bindings: &[(&str, String)],
+) {
+    match value {
+        serde_yaml::Value::String(s) => {
+            for (key, v) in bindings {
+                *s = s.replace(&format!("${{matrix.{key}}}"), v);
+            }
+        }
+        serde_yaml::Value::Sequence(seq) => {
+            for item in seq {
+                bind_matrix_vars(item, bindings);
+            }
+        }
+        serde_yaml::Value::Mapping(map) => {
+            for (_, item) in map.iter_mut() {
+                bind_matrix_vars(item, bindings);
+            }
+        }
+        _ => {}
+    }
+}
+
 impl ConfigParser<WorkflowConfig> for YamlConfigParser {
     type Output = Vec<ExecutionNode>;
     type Error = anyhow::Error;
@@ -632,4 +1042,499 @@ workflow:
             );
         }
     }
+
+    #[test]
+    fn test_http_paginate_action_type() {
+        let yaml_content = r#"
+workflow:
+  version: "1.0"
+  tasks:
+    - task:
+        id: "fetch_all"
+        name: "Fetch All"
+        description: "Page through the API"
+        actions:
+          - action:
+              id: "paginate"
+              name: "Paginate"
+              description: "Cursor pagination"
+              type: "http_paginate"
+              flow:
+                next: null
+              outputs: {}
+              parameters:
+                url:
+                  value: "https://api.example.com/items"
+                items:
+                  value: ".data"
+                next_cursor:
+                  value: ".next"
+"#;
+
+        let config = WorkflowLoader::from_yaml_str(yaml_content).unwrap();
+        let nodes = YamlConfigParser::new(config).parse().unwrap();
+        assert_eq!(nodes[0].action_spec.action_type, "http_paginate");
+    }
+
+    #[test]
+    fn test_param_schema_defaults_and_required() {
+        let yaml_content = r#"
+workflow:
+  version: "1.0"
+  tasks:
+    - task:
+        id: "fetch"
+        name: "Fetch"
+        description: "Fetch with defaults"
+        actions:
+          - action:
+              id: "request"
+              name: "Request"
+              description: "HTTP request"
+              type: "http"
+              flow:
+                next: null
+              outputs: {}
+              param_schema:
+                url: { required: true }
+                method: { default: "GET" }
+                timeout: { required: true, default: 30 }
+              parameters:
+                url:
+                  value: "https://api.example.com"
+                timeout:
+                  value: 5
+"#;
+
+        let config = WorkflowLoader::from_yaml_str(yaml_content).unwrap();
+        let nodes = YamlConfigParser::new(config.clone()).parse().unwrap();
+        let params = &nodes[0].action_spec.parameters;
+        assert_eq!(params["method"].as_str(), Some("GET"));
+        // 显式提供的参数优先于默认值
+        assert_eq!(params["timeout"].as_u64(), Some(5));
+
+        // 缺少无默认值的必填参数时，校验阶段即失败
+        let missing = yaml_content.replace(
+            "                url:\n                  value: \"https://api.example.com\"\n",
+            "",
+        );
+        let config = WorkflowLoader::from_yaml_str(&missing).unwrap();
+        let err = YamlConfigParser::new(config).validate().unwrap_err();
+        assert!(err.to_string().contains("request"));
+        assert!(err.to_string().contains("url"));
+    }
+
+    #[test]
+    fn test_matrix_expansion_fans_out_dependencies() {
+        let yaml_content = r#"
+workflow:
+  version: "1.0"
+  tasks:
+    - task:
+        id: "build"
+        name: "Build"
+        description: "Build once"
+        actions:
+          - action:
+              id: "build_action"
+              name: "Build"
+              description: "Build artifacts"
+              type: "builtin"
+              flow:
+                next: "deploy"
+              outputs: {}
+              parameters: {}
+    - task:
+        id: "deploy"
+        name: "Deploy ${matrix.env}"
+        description: "Deploy to one environment"
+        matrix:
+          env: [dev, staging, prod]
+        actions:
+          - action:
+              id: "deploy_action"
+              name: "Deploy"
+              description: "Deploy artifacts"
+              type: "cmd"
+              flow:
+                next: "notify"
+              outputs: {}
+              parameters:
+                target:
+                  value: "https://${matrix.env}.example.com"
+    - task:
+        id: "notify"
+        name: "Notify"
+        description: "Notify after all deployments"
+        actions:
+          - action:
+              id: "notify_action"
+              name: "Notify"
+              description: "Send notification"
+              type: "builtin"
+              flow:
+                next: null
+              outputs: {}
+              parameters: {}
+"#;
+
+        let config = WorkflowLoader::from_yaml_str(yaml_content).unwrap();
+        let parser = YamlConfigParser::new(config);
+        assert!(parser.validate().is_ok());
+        let nodes = parser.parse().unwrap();
+
+        let ids: Vec<_> = nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "build",
+                "deploy-dev",
+                "deploy-staging",
+                "deploy-prod",
+                "notify"
+            ]
+        );
+
+        let staging = nodes.iter().find(|n| n.id == "deploy-staging").unwrap();
+        assert_eq!(staging.name, "Deploy staging");
+        assert_eq!(staging.dependencies, vec!["build".to_string()]);
+        assert_eq!(
+            staging.action_spec.parameters["target"].as_str(),
+            Some("https://staging.example.com")
+        );
+
+        let notify = nodes.iter().find(|n| n.id == "notify").unwrap();
+        assert_eq!(
+            notify.dependencies,
+            vec!["deploy-dev", "deploy-prod", "deploy-staging"]
+        );
+    }
+
+    #[test]
+    fn test_group_defaults_merge_precedence() {
+        let yaml_content = r#"
+workflow:
+  version: "1.0"
+  groups:
+    - group:
+        id: "ingest"
+        defaults:
+          timeout: { duration: 5000 }
+          retry: { max_retries: 3, delay: 100 }
+          on_timeout: "alert"
+          tags: [ingest, nightly]
+        tasks:
+          - task:
+              id: "fetch"
+              name: "Fetch"
+              description: "Inherits group defaults"
+              actions:
+                - action:
+                    id: "fetch_action"
+                    name: "Fetch"
+                    description: "Fetch data"
+                    type: "builtin"
+                    flow:
+                      next: "store"
+                    outputs: {}
+                    parameters: {}
+          - task:
+              id: "store"
+              name: "Store"
+              description: "Overrides group defaults"
+              tags: [storage]
+              actions:
+                - action:
+                    id: "store_action"
+                    name: "Store"
+                    description: "Store data"
+                    type: "builtin"
+                    flow:
+                      next: null
+                      timeout: { duration: 60000 }
+                    outputs: {}
+                    parameters: {}
+"#;
+
+        let config = WorkflowLoader::from_yaml_str(yaml_content).unwrap();
+        assert!(WorkflowLoader::validate(&config).is_ok());
+        let parser = YamlConfigParser::new(config);
+        assert!(parser.validate().is_ok());
+        let nodes = parser.parse().unwrap();
+        assert_eq!(nodes.len(), 2);
+
+        // 未设置的值全部继承组默认值
+        let fetch = &nodes[0];
+        assert_eq!(fetch.timeout_config.as_ref().unwrap().duration, 5000);
+        assert_eq!(
+            fetch.timeout_config.as_ref().unwrap().on_timeout.as_deref(),
+            Some("alert")
+        );
+        assert_eq!(fetch.retry_config.as_ref().unwrap().max_retries, 3);
+        assert_eq!(fetch.tags, vec!["ingest", "nightly"]);
+
+        // 任务显式设置的值覆盖组默认值，其余仍继承
+        let store = &nodes[1];
+        assert_eq!(store.timeout_config.as_ref().unwrap().duration, 60000);
+        assert_eq!(store.retry_config.as_ref().unwrap().max_retries, 3);
+        assert_eq!(store.tags, vec!["storage"]);
+        assert_eq!(store.dependencies, vec!["fetch".to_string()]);
+    }
+
+    #[test]
+    fn test_sequential_multi_action_nodes() {
+        let yaml_content = r#"
+workflow:
+  version: "1.0"
+  multi_action: sequential
+  tasks:
+    - task:
+        id: "prepare"
+        name: "Prepare"
+        description: "Two actions, split into nodes"
+        actions:
+          - action:
+              id: "fetch"
+              name: "Fetch"
+              description: "Fetch data"
+              type: "http"
+              flow:
+                retry: { max_retries: 3, delay: 100 }
+              outputs: {}
+              parameters:
+                url: { value: "https://example.com" }
+          - action:
+              id: "store"
+              name: "Store"
+              description: "Store data"
+              type: "builtin"
+              flow:
+                next: "report"
+                next_if: "${ctx:vars.ok} == true"
+                timeout: { duration: 2000 }
+              outputs: {}
+              parameters:
+                operation: { value: "log" }
+    - task:
+        id: "report"
+        name: "Report"
+        description: "Single action stays one node"
+        actions:
+          - action:
+              id: "send"
+              name: "Send"
+              description: "Send report"
+              type: "builtin"
+              flow:
+                next: null
+              outputs: {}
+              parameters:
+                operation: { value: "log" }
+"#;
+
+        let config = WorkflowLoader::from_yaml_str(yaml_content).unwrap();
+        let nodes = YamlConfigParser::new(config.clone()).parse().unwrap();
+        let ids: Vec<_> = nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, vec!["prepare.fetch", "prepare.store", "report"]);
+
+        let fetch = &nodes[0];
+        assert_eq!(fetch.action_spec.action_type, "http");
+        assert!(fetch.dependencies.is_empty());
+        assert_eq!(fetch.retry_config.as_ref().unwrap().max_retries, 3);
+        assert!(fetch.timeout_config.is_none());
+
+        let store = &nodes[1];
+        assert_eq!(store.dependencies, vec!["prepare.fetch"]);
+        assert!(matches!(store.node_type, NodeType::Condition));
+        assert_eq!(store.timeout_config.as_ref().unwrap().duration, 2000);
+        assert!(store.retry_config.is_none());
+
+        assert_eq!(nodes[2].dependencies, vec!["prepare.store"]);
+        assert_eq!(
+            nodes[2].edge_conditions["prepare.store"],
+            "${ctx:vars.ok} == true"
+        );
+
+        // 默认仍合并为复合节点
+        let mut config = config;
+        config.workflow.multi_action = MultiActionMode::Composite;
+        let nodes = YamlConfigParser::new(config).parse().unwrap();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].action_spec.action_type, "composite");
+        assert_eq!(nodes[1].dependencies, vec!["prepare"]);
+    }
+
+    #[test]
+    fn test_composite_carries_sub_action_conditions() {
+        let yaml_content = r#"
+workflow:
+  version: "1.0"
+  tasks:
+    - task:
+        id: "notify"
+        name: "Notify"
+        description: "Conditional sub-actions"
+        actions:
+          - action:
+              id: "always"
+              name: "Always"
+              description: "Unconditional"
+              type: "builtin"
+              outputs: {}
+              parameters:
+                operation: { value: "log" }
+          - action:
+              id: "on_failure"
+              name: "On failure"
+              description: "Only when the build failed"
+              type: "http"
+              flow:
+                next_if: "${ctx:vars.failed} == true"
+              outputs: {}
+              parameters:
+                url: { value: "https://hooks.example.com" }
+"#;
+
+        let config = WorkflowLoader::from_yaml_str(yaml_content).unwrap();
+        let nodes = YamlConfigParser::new(config).parse().unwrap();
+        let spec = &nodes[0].action_spec;
+        assert_eq!(spec.action_type, "composite");
+
+        let actions = spec.parameters["actions"].as_sequence().unwrap();
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[0]["type"].as_str(), Some("builtin"));
+        assert!(actions[0].get("condition").is_none());
+        assert_eq!(actions[1]["id"].as_str(), Some("on_failure"));
+        assert_eq!(actions[1]["type"].as_str(), Some("http"));
+        assert_eq!(
+            actions[1]["condition"].as_str(),
+            Some("${ctx:vars.failed} == true")
+        );
+        assert_eq!(
+            actions[1]["parameters"]["url"].as_str(),
+            Some("https://hooks.example.com")
+        );
+    }
+
+    #[test]
+    fn test_explicit_phases_force_barriers() {
+        let task = |id: &str, phase: &str, next: &str| {
+            format!(
+                r#"
+    - task:
+        id: "{id}"
+        name: "{id}"
+        description: "{id}"
+        phase: "{phase}"
+        actions:
+          - action:
+              id: "run"
+              name: "Run"
+              description: "Run"
+              type: "builtin"
+              flow:
+                next: {next}
+              outputs: {{}}
+              parameters:
+                operation: {{ value: "log" }}"#
+            )
+        };
+        let workflow = |tasks: String| {
+            format!("workflow:\n  version: \"1.0\"\n  tasks:{tasks}\n")
+        };
+        let plan_for = |yaml: String| {
+            let config = WorkflowLoader::from_yaml_str(&yaml).unwrap();
+            let nodes = YamlConfigParser::new(config).parse().unwrap();
+            flowbuilder_core::EnhancedFlowOrchestrator::new()
+                .create_execution_plan(
+                    nodes,
+                    HashMap::new(),
+                    HashMap::new(),
+                    "wf".to_string(),
+                    "1.0".to_string(),
+                )
+        };
+
+        // 无数据依赖的任务也按阶段分隔
+        let plan = plan_for(workflow(
+            task("migrate", "schema", "null")
+                + &task("seed", "data", "null")
+                + &task("backfill", "schema", "null"),
+        ))
+        .unwrap();
+        let layers: Vec<Vec<&str>> = plan
+            .phases
+            .iter()
+            .map(|p| p.nodes.iter().map(|n| n.id.as_str()).collect())
+            .collect();
+        assert_eq!(layers, vec![vec!["migrate", "backfill"], vec!["seed"]]);
+
+        // 先声明的阶段依赖后续阶段的任务时报错
+        let error = plan_for(workflow(
+            task("load", "extract", "null")
+                + &task("check", "verify", "\"publish\"")
+                + &task("publish", "extract", "null"),
+        ))
+        .unwrap_err();
+        assert!(error.to_string().contains("与数据依赖冲突"), "{error}");
+    }
+
+    #[test]
+    fn test_max_nodes_rejects_matrix_explosion() {
+        let yaml_content = r#"
+workflow:
+  version: "1.0"
+  tasks:
+    - task:
+        id: "setup"
+        name: "Setup"
+        description: "Plain task"
+        actions:
+          - action:
+              id: "run"
+              name: "Run"
+              description: "Run"
+              type: "builtin"
+              flow:
+                next: null
+              outputs: {}
+              parameters:
+                operation: { value: "log" }
+    - task:
+        id: "shard"
+        name: "Shard"
+        description: "Fan out over regions and shards"
+        matrix:
+          region: ["eu", "us", "ap"]
+          shard: [1, 2, 3, 4]
+        actions:
+          - action:
+              id: "run"
+              name: "Run"
+              description: "Run"
+              type: "builtin"
+              flow:
+                next: null
+              outputs: {}
+              parameters:
+                operation: { value: "log" }
+"#;
+        let config = WorkflowLoader::from_yaml_str(yaml_content).unwrap();
+        assert_eq!(
+            YamlConfigParser::new(config.clone())
+                .with_max_nodes(13)
+                .parse()
+                .unwrap()
+                .len(),
+            13
+        );
+
+        let error = YamlConfigParser::new(config)
+            .with_max_nodes(12)
+            .parse()
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("shard"), "{error}");
+        assert!(error.contains("12 个任务"), "{error}");
+    }
 }
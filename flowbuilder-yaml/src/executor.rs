@@ -3,20 +3,30 @@
 //! 实现新的分层架构：配置解析器 → 流程编排器 → 任务执行器
 
 use crate::config::WorkflowConfig;
+use crate::config_parser::ParseResult;
 use crate::config_parser::YamlConfigParser;
 use crate::expression::ExpressionEvaluator;
-use anyhow::{Context, Result};
-use flowbuilder_context::SharedContext;
 #[cfg(feature = "runtime")]
-use flowbuilder_core::ExecutionPlan;
-use flowbuilder_core::{Executor, ExecutorStatus};
+use crate::expression::{ContextConditionEvaluator, ContextParameterResolver};
+use crate::lint::LintReport;
+use anyhow::{Context, Result};
+use flowbuilder_context::{FlowContext, SharedContext};
+use flowbuilder_core::{
+    EnhancedFlowOrchestrator, ExecutionComplexity, ExecutionNode,
+    ExecutionPlan, Executor, ExecutorStatus, OrchestratorConfig,
+};
 #[cfg(all(feature = "runtime", feature = "perf-metrics"))]
 use flowbuilder_runtime::ExecutionStats;
 #[cfg(feature = "runtime")]
 use flowbuilder_runtime::{
-    EnhancedFlowOrchestrator, EnhancedTaskExecutor, ExecutionComplexity,
-    ExecutionResult, ExecutorConfig,
+    EnhancedTaskExecutor, ExecutionResult, ExecutorConfig, RunContext,
 };
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, OnceLock};
+#[cfg(feature = "runtime")]
+use std::time::{Duration, Instant};
 #[cfg(feature = "runtime")]
 use tracing::{debug, info};
 
@@ -24,7 +34,9 @@ use tracing::{debug, info};
 #[derive(Debug, Clone)]
 pub struct ExecutionResultPlaceholder;
 
-#[cfg(all(test, feature = "runtime"))]
+#[cfg(feature = "runtime")]
+use crate::subflow::FileWorkflowRunner;
+#[cfg(feature = "runtime")]
 use std::sync::Arc;
 
 /// 统一的动态流程执行器
@@ -34,7 +46,6 @@ pub struct DynamicFlowExecutor {
     /// 配置解析器
     parser: YamlConfigParser,
     /// 流程编排器
-    #[cfg(feature = "runtime")]
     orchestrator: EnhancedFlowOrchestrator,
     /// 任务执行器
     #[cfg(feature = "runtime")]
@@ -44,6 +55,20 @@ pub struct DynamicFlowExecutor {
     /// 是否在执行前打印执行计划
     #[cfg(feature = "runtime")]
     print_plan: bool,
+    /// `execute` 的总超时（覆盖解析、规划、执行三个阶段）
+    #[cfg(feature = "runtime")]
+    overall_timeout: Option<Duration>,
+    /// 创建时传入的执行器配置（替换工作流配置时据此重建执行器）
+    #[cfg(feature = "runtime")]
+    executor_config: ExecutorConfig,
+    /// 解析结果缓存（首次解析时填充，替换配置时清空）
+    parse_cache: OnceLock<ParseResult>,
+    /// 当前配置的内容哈希（无法序列化时为 `None`，不缓存计划）
+    config_hash: Option<u64>,
+    /// 是否缓存执行计划
+    plan_cache_enabled: bool,
+    /// 执行计划缓存：配置哈希 → 计划
+    plan_cache: Mutex<HashMap<u64, ExecutionPlan>>,
 }
 
 impl DynamicFlowExecutor {
@@ -55,27 +80,34 @@ impl DynamicFlowExecutor {
         // 验证配置
         parser.validate().context("配置验证失败")?;
 
+        // 创建表达式评估器
+        let evaluator = workflow_evaluator(&config);
+
         // 创建流程编排器
-        #[cfg(feature = "runtime")]
         let orchestrator = EnhancedFlowOrchestrator::new();
         #[cfg(feature = "runtime")]
-        let executor = EnhancedTaskExecutor::new();
-
-        // 创建表达式评估器
-        let mut evaluator = ExpressionEvaluator::new();
-        evaluator.set_env_vars(config.workflow.env.clone());
-        evaluator.set_flow_vars(config.workflow.vars.clone());
+        let executor_config = ExecutorConfig::default();
+        #[cfg(feature = "runtime")]
+        let executor = build_executor(executor_config.clone(), &evaluator);
+        let config_hash = config_hash(&config);
 
         Ok(Self {
             config,
             parser,
-            #[cfg(feature = "runtime")]
             orchestrator,
             #[cfg(feature = "runtime")]
             executor,
             evaluator,
             #[cfg(feature = "runtime")]
             print_plan: false,
+            #[cfg(feature = "runtime")]
+            overall_timeout: None,
+            #[cfg(feature = "runtime")]
+            executor_config,
+            parse_cache: OnceLock::new(),
+            config_hash,
+            plan_cache_enabled: false,
+            plan_cache: Mutex::new(HashMap::new()),
         })
     }
 
@@ -88,28 +120,59 @@ impl DynamicFlowExecutor {
         let parser = YamlConfigParser::new(config.clone());
         parser.validate().context("配置验证失败")?;
 
-        #[cfg(feature = "runtime")]
-        let orchestrator = EnhancedFlowOrchestrator::new();
-        #[cfg(feature = "runtime")]
-        let executor = EnhancedTaskExecutor::with_config(executor_config);
+        let evaluator = workflow_evaluator(&config);
 
-        let mut evaluator = ExpressionEvaluator::new();
-        evaluator.set_env_vars(config.workflow.env.clone());
-        evaluator.set_flow_vars(config.workflow.vars.clone());
+        let orchestrator = EnhancedFlowOrchestrator::new();
+        let executor = build_executor(executor_config.clone(), &evaluator);
+        let config_hash = config_hash(&config);
 
         Ok(Self {
             config,
             parser,
-            #[cfg(feature = "runtime")]
             orchestrator,
-            #[cfg(feature = "runtime")]
             executor,
             evaluator,
-            #[cfg(feature = "runtime")]
             print_plan: false,
+            overall_timeout: None,
+            executor_config,
+            parse_cache: OnceLock::new(),
+            config_hash,
+            plan_cache_enabled: false,
+            plan_cache: Mutex::new(HashMap::new()),
         })
     }
 
+    /// 替换工作流配置
+    ///
+    /// 重新校验配置并重建表达式评估器与执行器，已缓存的解析结果随之失效；
+    /// 执行计划缓存按配置哈希区分，切换回先前的配置时仍可复用
+    pub fn set_config(&mut self, config: WorkflowConfig) -> Result<()> {
+        let parser = YamlConfigParser::new(config.clone())
+            .with_max_nodes(self.orchestrator.config().max_nodes);
+        parser.validate().context("配置验证失败")?;
+
+        self.evaluator = workflow_evaluator(&config);
+        #[cfg(feature = "runtime")]
+        {
+            self.executor =
+                build_executor(self.executor_config.clone(), &self.evaluator);
+        }
+        self.parse_cache = OnceLock::new();
+        self.config_hash = config_hash(&config);
+        self.parser = parser;
+        self.config = config;
+        Ok(())
+    }
+
+    /// 解析配置（首次解析后缓存，`set_config` 时失效）
+    fn parse_cached(&self) -> Result<ParseResult> {
+        if let Some(parsed) = self.parse_cache.get() {
+            return Ok(parsed.clone());
+        }
+        let parsed = self.parser.parse_full().context("配置解析失败")?;
+        Ok(self.parse_cache.get_or_init(|| parsed).clone())
+    }
+
     /// 执行工作流 - 新的分层架构实现
     #[cfg(feature = "runtime")]
     pub async fn execute(
@@ -117,39 +180,196 @@ impl DynamicFlowExecutor {
         context: SharedContext,
     ) -> Result<ExecutionResult> {
         info!("开始执行工作流，使用新的分层架构");
+        self.execute_scoped(PlanScope::Full, context).await
+    }
 
-        // 第1步：解析配置，生成执行节点
-        let parse_result = self.parser.parse_full().context("配置解析失败")?;
+    /// 在总超时的约束下执行指定范围的计划
+    #[cfg(feature = "runtime")]
+    async fn execute_scoped(
+        &mut self,
+        scope: PlanScope<'_>,
+        context: SharedContext,
+    ) -> Result<ExecutionResult> {
+        let stage = std::sync::Mutex::new(PipelineStage::Parse);
+        let Some(limit) = self.overall_timeout else {
+            return self.execute_pipeline(scope, context, None, &stage).await;
+        };
 
-        info!("配置解析完成");
-        info!(workflow_name = %parse_result.workflow_name, workflow_version = %parse_result.workflow_version, node_count = parse_result.nodes.len());
+        let deadline = Instant::now() + limit;
+        let pipeline =
+            self.execute_pipeline(scope, context, Some(deadline), &stage);
+        match tokio::time::timeout(limit, pipeline).await {
+            Ok(result) => result,
+            Err(_) => {
+                let stage = *stage.lock().unwrap_or_else(|e| e.into_inner());
+                Err(overall_timeout_error(limit, stage))
+            }
+        }
+    }
 
-        // 第2步：流程编排，生成执行计划
-        let env_vars = parse_result
-            .env_vars
-            .into_iter()
-            .map(|(k, v)| (k, serde_yaml::Value::String(v)))
-            .collect();
+    /// 依次执行解析 → 规划 → 执行，并记录当前所处阶段
+    ///
+    /// 解析与规划是同步的，超时只能在阶段切换时发现；
+    /// 执行阶段通过运行上下文的截止时间中断节点
+    #[cfg(feature = "runtime")]
+    async fn execute_pipeline(
+        &mut self,
+        scope: PlanScope<'_>,
+        context: SharedContext,
+        deadline: Option<Instant>,
+        stage: &std::sync::Mutex<PipelineStage>,
+    ) -> Result<ExecutionResult> {
+        let limit = self.overall_timeout.unwrap_or_default();
+        let enter = |next: PipelineStage| -> Result<()> {
+            let mut current = stage.lock().unwrap_or_else(|e| e.into_inner());
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                return Err(overall_timeout_error(limit, *current));
+            }
+            *current = next;
+            Ok(())
+        };
 
-        #[cfg(feature = "runtime")]
-        let execution_plan = self
-            .orchestrator
-            .create_execution_plan(
-                parse_result.nodes,
-                env_vars,
-                parse_result.flow_vars,
-                parse_result.workflow_name,
-                parse_result.workflow_version,
-            )
-            .context("执行计划创建失败")?;
+        let execution_plan = match (scope, self.lookup_plan()) {
+            (PlanScope::From(task_id), _) => {
+                let parse_result = self.parse_from(task_id, &context).await?;
+                enter(PipelineStage::Plan)?;
+                self.create_plan(parse_result).context("执行计划创建失败")?
+            }
+            (PlanScope::Until(task_id), _) => {
+                let parse_result = self.parse_until(task_id)?;
+                enter(PipelineStage::Plan)?;
+                self.create_plan(parse_result).context("执行计划创建失败")?
+            }
+            (PlanScope::Full, Some(plan)) => {
+                debug!(plan_id = %plan.metadata.plan_id, "复用缓存的执行计划");
+                plan
+            }
+            (PlanScope::Full, None) => {
+                // 第1步：解析配置，生成执行节点
+                let parse_result = self.parse_cached()?;
+
+                info!("配置解析完成");
+                info!(workflow_name = %parse_result.workflow_name, workflow_version = %parse_result.workflow_version, node_count = parse_result.nodes.len());
+
+                // 第2步：流程编排，生成执行计划
+                enter(PipelineStage::Plan)?;
+                let plan = self
+                    .create_plan(parse_result)
+                    .context("执行计划创建失败")?;
+                self.store_plan(&plan);
+                plan
+            }
+        };
+
+        enter(PipelineStage::Execute)?;
+        let run = match deadline {
+            Some(deadline) => RunContext::new().with_deadline(deadline),
+            None => RunContext::default(),
+        };
+        let result = self.run_plan(execution_plan, context, &run).await?;
+        // 截止时间已过时节点已被中断，结果不完整
+        if run.is_stopped() {
+            return Err(overall_timeout_error(limit, PipelineStage::Execute));
+        }
+        Ok(result)
+    }
+
+    /// 从指定任务开始执行（包含该任务及其全部下游任务）
+    ///
+    /// 被裁剪掉的上游任务的输出需要由 `context` 预先提供，缺失时返回错误
+    #[cfg(feature = "runtime")]
+    pub async fn execute_from(
+        &mut self,
+        task_id: &str,
+        context: SharedContext,
+    ) -> Result<ExecutionResult> {
+        info!(task_id, "从指定任务开始执行工作流");
+        self.execute_scoped(PlanScope::From(task_id), context).await
+    }
+
+    /// 执行到指定任务为止（包含该任务及其全部传递依赖）
+    ///
+    /// 下游任务全部跳过，执行结束后上下文保留供检查
+    #[cfg(feature = "runtime")]
+    pub async fn execute_until(
+        &mut self,
+        task_id: &str,
+        context: SharedContext,
+    ) -> Result<ExecutionResult> {
+        info!(task_id, "执行工作流至指定任务");
+        self.execute_scoped(PlanScope::Until(task_id), context)
+            .await
+    }
 
-        #[cfg(not(feature = "runtime"))]
-        let execution_plan: ExecutionPlan = {
+    /// 解析配置并裁剪为指定任务及其下游任务
+    #[cfg(feature = "runtime")]
+    async fn parse_from(
+        &self,
+        task_id: &str,
+        context: &SharedContext,
+    ) -> Result<ParseResult> {
+        let mut parse_result = self.parse_cached()?;
+        let nodes = std::mem::take(&mut parse_result.nodes);
+        let keep = downstream_closure(&nodes, task_id)?;
+
+        // 检查被裁剪的上游任务输出是否已由上下文提供
+        let mut missing = Vec::new();
+        {
+            let guard = context.lock().await;
+            for node in nodes.iter().filter(|n| keep.contains(&n.id)) {
+                for dep in &node.dependencies {
+                    if keep.contains(dep) {
+                        continue;
+                    }
+                    let Some(upstream) = nodes.iter().find(|n| &n.id == dep)
+                    else {
+                        continue;
+                    };
+                    for key in upstream.action_spec.outputs.keys() {
+                        if guard.get_variable(key).is_none() {
+                            missing.push(format!("{dep}.{key}"));
+                        }
+                    }
+                }
+            }
+        }
+        if !missing.is_empty() {
+            missing.sort();
+            missing.dedup();
             return Err(anyhow::anyhow!(
-                "运行时未启用: 请开启 feature 'runtime' 后使用执行功能"
+                "缺少上游任务的输入: {}",
+                missing.join(", ")
             ));
-        };
+        }
 
+        parse_result.nodes = nodes
+            .into_iter()
+            .filter(|n| keep.contains(&n.id))
+            .map(|mut n| {
+                n.dependencies.retain(|d| keep.contains(d));
+                n
+            })
+            .collect();
+        Ok(parse_result)
+    }
+
+    /// 解析配置并裁剪为指定任务及其传递依赖
+    #[cfg(feature = "runtime")]
+    fn parse_until(&self, task_id: &str) -> Result<ParseResult> {
+        let mut parse_result = self.parse_cached()?;
+        let keep = upstream_closure(&parse_result.nodes, task_id)?;
+        parse_result.nodes.retain(|n| keep.contains(&n.id));
+        Ok(parse_result)
+    }
+
+    /// 执行编排好的计划
+    #[cfg(feature = "runtime")]
+    async fn run_plan(
+        &mut self,
+        execution_plan: ExecutionPlan,
+        context: SharedContext,
+        run: &RunContext,
+    ) -> Result<ExecutionResult> {
         info!("执行计划生成完成");
         info!(phases = execution_plan.phases.len(), total_nodes = execution_plan.metadata.total_nodes, est_duration_ms = ?execution_plan.estimated_duration());
 
@@ -160,24 +380,20 @@ impl DynamicFlowExecutor {
         }
 
         // 第3步：分析执行复杂度
-        #[cfg(feature = "runtime")]
         let complexity = self.orchestrator.analyze_complexity(&execution_plan);
-        #[cfg(feature = "runtime")]
         info!("执行复杂度分析");
-        #[cfg(feature = "runtime")]
-        {
-            info!(
-                score = complexity.complexity_score,
-                max_parallel = complexity.max_parallel_nodes,
-                conditional_nodes = complexity.conditional_nodes
-            );
-        }
+        info!(
+            score = complexity.complexity_score,
+            max_parallel = complexity.max_parallel_nodes,
+            conditional_nodes = complexity.conditional_nodes,
+            critical_path = complexity.critical_path_length,
+            critical_path_ms = ?complexity.critical_path_duration
+        );
 
         // 第4步：执行任务
-        #[cfg(feature = "runtime")]
         let result = self
             .executor
-            .execute_plan(execution_plan, context)
+            .execute_plan(execution_plan, context, run)
             .await
             .context("任务执行失败")?;
 
@@ -185,7 +401,7 @@ impl DynamicFlowExecutor {
         info!(success = result.success, total_duration_ms = ?result.total_duration, phases = result.phase_results.len());
 
         // 打印执行统计
-        #[cfg(all(feature = "runtime", feature = "perf-metrics"))]
+        #[cfg(feature = "perf-metrics")]
         {
             let stats = self.executor.get_stats();
             info!("执行统计");
@@ -195,11 +411,8 @@ impl DynamicFlowExecutor {
         Ok(result)
     }
 
-    /// 获取执行计划预览（不执行）
-    #[cfg(feature = "runtime")]
-    pub fn get_execution_plan_preview(&self) -> Result<ExecutionPlan> {
-        let parse_result = self.parser.parse_full().context("配置解析失败")?;
-
+    /// 根据解析结果生成执行计划
+    fn create_plan(&self, parse_result: ParseResult) -> Result<ExecutionPlan> {
         let env_vars = parse_result
             .env_vars
             .into_iter()
@@ -215,15 +428,182 @@ impl DynamicFlowExecutor {
         )
     }
 
+    /// 获取执行计划预览（不执行）
+    pub fn get_execution_plan_preview(&self) -> Result<ExecutionPlan> {
+        if let Some(plan) = self.lookup_plan() {
+            return Ok(plan);
+        }
+        let plan = self.create_plan(self.parse_cached()?)?;
+        self.store_plan(&plan);
+        Ok(plan)
+    }
+
+    /// 按配置哈希查找缓存的执行计划
+    fn lookup_plan(&self) -> Option<ExecutionPlan> {
+        let key = self.config_hash.filter(|_| self.plan_cache_enabled)?;
+        let cache = self.plan_cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache.get(&key).cloned()
+    }
+
+    /// 按配置哈希缓存执行计划
+    fn store_plan(&self, plan: &ExecutionPlan) {
+        if let Some(key) = self.config_hash.filter(|_| self.plan_cache_enabled)
+        {
+            self.plan_cache
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(key, plan.clone());
+        }
+    }
+
+    /// 设置是否缓存执行计划（默认关闭）
+    ///
+    /// 缓存以配置内容的哈希为键，同一配置重复执行时跳过解析与编排。
+    /// 缓存的计划沿用首次生成的计划ID，每次执行不再有唯一的计划ID，
+    /// 宜配合 [`set_deterministic_ids`](Self::set_deterministic_ids) 使用
+    pub fn set_plan_cache(&mut self, enabled: bool) {
+        self.plan_cache_enabled = enabled;
+    }
+
+    /// 清空执行计划缓存
+    pub fn clear_plan_cache(&self) {
+        self.plan_cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// 模拟执行（不执行任何动作）
+    ///
+    /// 基于给定上下文求值阶段条件、`next_if` 分支条件与节点条件，逐阶段给出
+    /// 将执行与将跳过的节点；依赖被跳过的节点同样视为跳过
+    pub fn simulate(&self, context: &FlowContext) -> Result<SimulationReport> {
+        let plan = self.get_execution_plan_preview()?;
+
+        let mut evaluator = self.evaluator.clone();
+        for (key, value) in &context.variables {
+            evaluator
+                .set_context_var(key, serde_yaml::Value::String(value.clone()));
+        }
+
+        let mut skipped: HashSet<String> = HashSet::new();
+        let mut phases = Vec::with_capacity(plan.phases.len());
+
+        for phase in &plan.phases {
+            let phase_condition = match &phase.condition {
+                Some(cond) => Some((
+                    cond,
+                    evaluator.evaluate_condition(cond).with_context(|| {
+                        format!("阶段 {} 条件求值失败", phase.id)
+                    })?,
+                )),
+                None => None,
+            };
+
+            let mut simulated = SimulatedPhase {
+                phase_id: phase.id.clone(),
+                phase_name: phase.name.clone(),
+                would_run: Vec::new(),
+                would_skip: Vec::new(),
+            };
+
+            for node in &phase.nodes {
+                let reason = if let Some((cond, false)) = phase_condition {
+                    Some(format!("阶段条件不满足: {cond}"))
+                } else if let Some(dep) =
+                    node.dependencies.iter().find(|d| skipped.contains(*d))
+                {
+                    Some(format!("依赖任务被跳过: {dep}"))
+                } else if let Some((dep, cond)) =
+                    first_unmet_edge(&evaluator, node)?
+                {
+                    Some(format!("{dep} 的分支条件不满足: {cond}"))
+                } else if let Some(cond) = &node.condition {
+                    let met = evaluator.evaluate_condition(cond).with_context(
+                        || format!("节点 {} 条件求值失败", node.id),
+                    )?;
+                    (!met).then(|| format!("节点条件不满足: {cond}"))
+                } else {
+                    None
+                };
+
+                match reason {
+                    Some(reason) => {
+                        skipped.insert(node.id.clone());
+                        simulated.would_skip.push(SkippedNode {
+                            node_id: node.id.clone(),
+                            reason,
+                        });
+                    }
+                    None => simulated.would_run.push(node.id.clone()),
+                }
+            }
+
+            phases.push(simulated);
+        }
+
+        Ok(SimulationReport {
+            workflow_name: plan.metadata.workflow_name,
+            phases,
+        })
+    }
+
+    /// 演练执行：按执行计划求值每个将执行节点的动作参数并记录，不执行任何动作
+    ///
+    /// 跳过规则与 [`Self::simulate`] 相同；参数基于上下文当前变量求值，
+    /// 不会启动进程、发出网络请求或修改上下文
+    pub async fn execute_dry_run(
+        &self,
+        context: SharedContext,
+    ) -> Result<DryRunReport> {
+        let snapshot = context.lock().await.clone();
+        let simulation = self.simulate(&snapshot)?;
+        let plan = self.get_execution_plan_preview()?;
+        let evaluator =
+            self.evaluator.with_context_variables(&snapshot.variables);
+
+        let would_run: HashSet<&str> =
+            simulation.would_run().into_iter().collect();
+        let mut actions = Vec::new();
+        for node in plan.phases.iter().flat_map(|p| &p.nodes) {
+            if !would_run.contains(node.id.as_str()) {
+                continue;
+            }
+            let spec = &node.action_spec;
+            let mut resolved_parameters = BTreeMap::new();
+            for (key, value) in &spec.parameters {
+                let value =
+                    evaluator.evaluate_value(value).with_context(|| {
+                        format!("节点 {} 的参数 {} 求值失败", node.id, key)
+                    })?;
+                resolved_parameters.insert(key.clone(), value);
+            }
+            actions.push(DryRunAction {
+                node_id: node.id.clone(),
+                action_type: spec.action_type.clone(),
+                resolved_parameters,
+            });
+        }
+
+        let skipped = simulation
+            .phases
+            .into_iter()
+            .flat_map(|p| p.would_skip)
+            .collect();
+        Ok(DryRunReport {
+            workflow_name: simulation.workflow_name,
+            actions,
+            skipped,
+        })
+    }
+
     /// 分析工作流复杂度
-    #[cfg(feature = "runtime")]
     pub fn analyze_workflow_complexity(&self) -> Result<ExecutionComplexity> {
         let execution_plan = self.get_execution_plan_preview()?;
         Ok(self.orchestrator.analyze_complexity(&execution_plan))
     }
 
     /// 生成并返回“可读”的执行计划字符串（不执行）
-    #[cfg(feature = "runtime")]
     pub fn print_execution_plan(&self) -> Result<String> {
         let plan = self.get_execution_plan_preview()?;
         Ok(plan.to_pretty_string())
@@ -235,8 +615,40 @@ impl DynamicFlowExecutor {
         self.print_plan = enabled;
     }
 
-    /// 验证工作流配置
+    /// 设置 `execute` 的总超时（默认不限制）
+    ///
+    /// 超时后返回的错误会指明进行中的阶段（配置解析、执行计划创建或任务执行）
     #[cfg(feature = "runtime")]
+    pub fn set_overall_timeout(&mut self, timeout: Option<Duration>) {
+        self.overall_timeout = timeout;
+    }
+
+    /// 设置是否按计划内容生成稳定的计划ID（默认随机）
+    pub fn set_deterministic_ids(&mut self, enabled: bool) {
+        self.orchestrator =
+            EnhancedFlowOrchestrator::with_config(OrchestratorConfig {
+                deterministic_ids: enabled,
+                ..self.orchestrator.config().clone()
+            });
+        self.clear_plan_cache();
+    }
+
+    /// 设置计划允许的最大节点数（默认
+    /// [`OrchestratorConfig::DEFAULT_MAX_NODES`]），matrix 展开与计划创建
+    /// 超过时报错
+    pub fn set_max_nodes(&mut self, max_nodes: usize) {
+        self.orchestrator =
+            EnhancedFlowOrchestrator::with_config(OrchestratorConfig {
+                max_nodes,
+                ..self.orchestrator.config().clone()
+            });
+        self.parser = YamlConfigParser::new(self.config.clone())
+            .with_max_nodes(max_nodes);
+        self.parse_cache = OnceLock::new();
+        self.clear_plan_cache();
+    }
+
+    /// 验证工作流配置
     pub fn validate_workflow(&self) -> Result<()> {
         // 验证配置
         self.parser.validate()?;
@@ -247,12 +659,33 @@ impl DynamicFlowExecutor {
             .validate()
             .map_err(|e| anyhow::anyhow!("执行计划验证失败: {}", e))?;
 
+        // 验证动作类型在当前构建中可用（无运行时时不检查）
+        #[cfg(feature = "runtime")]
+        let supported = self.executor.action_types();
+        #[cfg(feature = "runtime")]
+        for node in execution_plan.phases.iter().flat_map(|p| &p.nodes) {
+            let action_type = node.action_spec.action_type.as_str();
+            if !supported.contains(&action_type) {
+                return Err(anyhow::anyhow!(
+                    "节点 {} 的动作类型 {} 不可用，支持的类型: {}",
+                    node.id,
+                    action_type,
+                    supported.join(", ")
+                ));
+            }
+        }
+
         Ok(())
     }
 
+    /// 一次性检查配置问题（结构校验、循环依赖、无效任务、变量引用），不执行
+    pub fn lint_report(&self) -> LintReport {
+        LintReport::from_config(&self.config)
+    }
+
     /// 获取执行统计信息
     #[cfg(all(feature = "runtime", feature = "perf-metrics"))]
-    pub fn get_stats(&self) -> &ExecutionStats {
+    pub fn get_stats(&self) -> ExecutionStats {
         self.executor.get_stats()
     }
 
@@ -261,7 +694,7 @@ impl DynamicFlowExecutor {
         WorkflowInfo {
             name: self.parser.get_workflow_name(),
             version: self.parser.get_workflow_version(),
-            task_count: self.config.workflow.tasks.len(),
+            task_count: self.config.workflow.all_tasks().count(),
             env_var_count: self.config.workflow.env.len(),
             flow_var_count: self.config.workflow.vars.len(),
         }
@@ -298,6 +731,256 @@ impl DynamicFlowExecutor {
     }
 }
 
+/// 以工作流的环境变量、流程变量与模板创建表达式求值器
+fn workflow_evaluator(config: &WorkflowConfig) -> ExpressionEvaluator {
+    let mut evaluator = ExpressionEvaluator::new();
+    evaluator.set_env_vars(config.workflow.env.clone());
+    evaluator.set_flow_vars(config.workflow.vars.clone());
+    evaluator.set_templates(config.workflow.templates.clone());
+    evaluator
+}
+
+/// 按执行器配置创建任务执行器
+///
+/// 未指定子工作流运行器时，按当前目录解析 `workflow` 动作的引用，
+/// 子工作流沿用 `executor_config`；
+/// 未指定条件求值器与参数求值器时，使用工作流的表达式求值器
+#[cfg(feature = "runtime")]
+fn build_executor(
+    executor_config: ExecutorConfig,
+    evaluator: &ExpressionEvaluator,
+) -> EnhancedTaskExecutor {
+    EnhancedTaskExecutor::with_config(ExecutorConfig {
+        workflow_runner: executor_config.workflow_runner.clone().or_else(
+            || {
+                let runner = FileWorkflowRunner::default()
+                    .with_executor_config(executor_config.clone());
+                Some(Arc::new(runner) as _)
+            },
+        ),
+        condition_evaluator: executor_config
+            .condition_evaluator
+            .clone()
+            .or_else(|| {
+                Some(
+                    Arc::new(ContextConditionEvaluator::new(evaluator.clone()))
+                        as _,
+                )
+            }),
+        parameter_resolver: executor_config.parameter_resolver.clone().or_else(
+            || {
+                Some(Arc::new(ContextParameterResolver::new(evaluator.clone()))
+                    as _)
+            },
+        ),
+        ..executor_config
+    })
+}
+
+/// 计算配置内容的哈希（对象键排序，与 `HashMap` 的迭代顺序无关）
+fn config_hash(config: &WorkflowConfig) -> Option<u64> {
+    fn canonical(value: serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::Object(map) => {
+                let sorted: std::collections::BTreeMap<_, _> =
+                    map.into_iter().map(|(k, v)| (k, canonical(v))).collect();
+                serde_json::Value::Object(sorted.into_iter().collect())
+            }
+            serde_json::Value::Array(items) => serde_json::Value::Array(
+                items.into_iter().map(canonical).collect(),
+            ),
+            other => other,
+        }
+    }
+
+    let value = serde_json::to_value(config).ok()?;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    canonical(value).to_string().hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+/// 本次执行覆盖的任务范围
+#[cfg(feature = "runtime")]
+#[derive(Debug, Clone, Copy)]
+enum PlanScope<'a> {
+    /// 全部任务（可复用缓存的计划）
+    Full,
+    /// 指定任务及其下游任务
+    From(&'a str),
+    /// 指定任务及其传递依赖
+    Until(&'a str),
+}
+
+/// `execute` 所处的阶段（用于总超时的错误信息）
+#[cfg(feature = "runtime")]
+#[derive(Debug, Clone, Copy)]
+enum PipelineStage {
+    Parse,
+    Plan,
+    Execute,
+}
+
+#[cfg(feature = "runtime")]
+impl PipelineStage {
+    fn as_str(self) -> &'static str {
+        match self {
+            PipelineStage::Parse => "配置解析",
+            PipelineStage::Plan => "执行计划创建",
+            PipelineStage::Execute => "任务执行",
+        }
+    }
+}
+
+#[cfg(feature = "runtime")]
+fn overall_timeout_error(
+    limit: Duration,
+    stage: PipelineStage,
+) -> anyhow::Error {
+    anyhow::anyhow!(
+        "工作流执行超过总超时 {} 毫秒（进行中阶段: {}）",
+        limit.as_millis(),
+        stage.as_str()
+    )
+}
+
+/// 计算指定任务及其全部下游任务的集合
+#[cfg(feature = "runtime")]
+fn downstream_closure(
+    nodes: &[ExecutionNode],
+    task_id: &str,
+) -> Result<HashSet<String>> {
+    if !nodes.iter().any(|n| n.id == task_id) {
+        return Err(anyhow::anyhow!("任务不存在: {}", task_id));
+    }
+
+    let mut keep = HashSet::from([task_id.to_string()]);
+    let mut stack = vec![task_id.to_string()];
+    while let Some(current) = stack.pop() {
+        for node in nodes {
+            if node.dependencies.contains(&current)
+                && keep.insert(node.id.clone())
+            {
+                stack.push(node.id.clone());
+            }
+        }
+    }
+    Ok(keep)
+}
+
+/// 计算指定任务及其全部传递依赖的集合
+#[cfg(feature = "runtime")]
+fn upstream_closure(
+    nodes: &[ExecutionNode],
+    task_id: &str,
+) -> Result<HashSet<String>> {
+    let by_id: HashMap<&str, &ExecutionNode> =
+        nodes.iter().map(|n| (n.id.as_str(), n)).collect();
+    if !by_id.contains_key(task_id) {
+        return Err(anyhow::anyhow!("任务不存在: {}", task_id));
+    }
+
+    let mut keep = HashSet::from([task_id.to_string()]);
+    let mut stack = vec![task_id];
+    while let Some(current) = stack.pop() {
+        for dep in &by_id[current].dependencies {
+            if by_id.contains_key(dep.as_str()) && keep.insert(dep.clone()) {
+                stack.push(dep.as_str());
+            }
+        }
+    }
+    Ok(keep)
+}
+
+/// 按依赖顺序查找第一个不成立的入边条件
+fn first_unmet_edge<'a>(
+    evaluator: &ExpressionEvaluator,
+    node: &'a ExecutionNode,
+) -> Result<Option<(&'a str, &'a str)>> {
+    for dep in &node.dependencies {
+        let Some(cond) = node.edge_conditions.get(dep) else {
+            continue;
+        };
+        let met = evaluator
+            .evaluate_condition(cond)
+            .with_context(|| format!("节点 {} 入边条件求值失败", node.id))?;
+        if !met {
+            return Ok(Some((dep, cond)));
+        }
+    }
+    Ok(None)
+}
+
+/// 模拟执行报告
+#[derive(Debug, Clone)]
+pub struct SimulationReport {
+    /// 工作流名称
+    pub workflow_name: String,
+    /// 各阶段模拟结果
+    pub phases: Vec<SimulatedPhase>,
+}
+
+impl SimulationReport {
+    /// 按执行顺序列出将执行的节点
+    pub fn would_run(&self) -> Vec<&str> {
+        self.phases
+            .iter()
+            .flat_map(|p| p.would_run.iter().map(String::as_str))
+            .collect()
+    }
+
+    /// 列出将跳过的节点
+    pub fn would_skip(&self) -> Vec<&str> {
+        self.phases
+            .iter()
+            .flat_map(|p| p.would_skip.iter().map(|n| n.node_id.as_str()))
+            .collect()
+    }
+}
+
+/// 单个阶段的模拟结果
+#[derive(Debug, Clone)]
+pub struct SimulatedPhase {
+    /// 阶段ID
+    pub phase_id: String,
+    /// 阶段名称
+    pub phase_name: String,
+    /// 将执行的节点
+    pub would_run: Vec<String>,
+    /// 将跳过的节点
+    pub would_skip: Vec<SkippedNode>,
+}
+
+/// 演练执行报告
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunReport {
+    /// 工作流名称
+    pub workflow_name: String,
+    /// 将执行的动作（按执行顺序）
+    pub actions: Vec<DryRunAction>,
+    /// 将跳过的节点
+    pub skipped: Vec<SkippedNode>,
+}
+
+/// 演练中记录的动作
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunAction {
+    /// 节点ID
+    pub node_id: String,
+    /// 动作类型
+    pub action_type: String,
+    /// 求值后的参数
+    pub resolved_parameters: BTreeMap<String, serde_yaml::Value>,
+}
+
+/// 被跳过的节点
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedNode {
+    /// 节点ID
+    pub node_id: String,
+    /// 跳过原因
+    pub reason: String,
+}
+
 /// 工作流信息
 #[derive(Debug, Clone)]
 pub struct WorkflowInfo {
@@ -363,8 +1046,6 @@ impl Executor for DynamicFlowExecutor {
 mod tests {
     use super::*;
     use crate::loader::WorkflowLoader;
-    #[cfg(feature = "runtime")]
-    use flowbuilder_context::FlowContext;
 
     #[cfg(feature = "runtime")]
     #[tokio::test]
@@ -424,77 +1105,217 @@ workflow:
 
     #[cfg(feature = "runtime")]
     #[tokio::test]
-    async fn test_execution_plan_preview() {
+    async fn test_action_parameters_resolved_at_execution() {
         let yaml_content = r#"
 workflow:
   version: "1.0"
-  env:
-    TEST_ENV: "preview"
-  vars:
-    name: "Preview Test"
   tasks:
     - task:
-        id: "preview_task"
-        name: "Preview Task"
-        description: "Task for preview testing"
+        id: "greet"
+        name: "Greet"
+        description: "Builds a message from a context variable"
         actions:
           - action:
-              id: "preview_action"
-              name: "Preview Action"
-              description: "Action for preview"
+              id: "compose"
+              name: "Compose"
+              description: "Reference another context variable"
               type: "builtin"
               flow:
                 next: null
               outputs: {}
-              parameters: {}
+              parameters:
+                operation: { value: "set_variable" }
+                key: { value: "message" }
+                value: { value: "${ctx:greeting} world" }
 "#;
 
         let config = WorkflowLoader::from_yaml_str(yaml_content).unwrap();
-        let executor = DynamicFlowExecutor::new(config).unwrap();
+        let mut executor = DynamicFlowExecutor::new(config).unwrap();
+        let context = Arc::new(tokio::sync::Mutex::new(FlowContext::default()));
+        context
+            .lock()
+            .await
+            .set_variable("greeting".to_string(), "hello".to_string());
 
-        let plan = executor.get_execution_plan_preview().unwrap();
-        assert_eq!(plan.phases.len(), 1);
-        assert_eq!(plan.metadata.total_nodes, 1);
-        assert_eq!(plan.metadata.workflow_name, "Preview Test");
+        let result = executor.execute(context.clone()).await.unwrap();
+        assert!(result.success);
+        let guard = context.lock().await;
+        let message = guard.get_variable("message").unwrap();
+        assert!(message.contains("hello world"), "{message}");
     }
 
-    #[cfg(feature = "runtime")]
     #[tokio::test]
-    async fn test_workflow_complexity_analysis() {
+    async fn test_dry_run_resolves_without_side_effects() {
         let yaml_content = r#"
 workflow:
   version: "1.0"
-  env:
-    TEST_ENV: "complexity"
   vars:
-    name: "Complexity Test"
+    greeting: "hello"
   tasks:
     - task:
-        id: "complex_task"
-        name: "Complex Task"
-        description: "A complex task for analysis"
+        id: "touch"
+        name: "Touch"
+        description: "Would create a marker file"
         actions:
           - action:
-              id: "complex_action"
-              name: "Complex Action"
-              description: "A complex action"
-              type: "builtin"
+              id: "touch_marker"
+              name: "Touch marker"
+              description: "Command with context references"
+              type: "cmd"
               flow:
-                next_if: "true"
                 next: null
               outputs: {}
-              parameters: {}
+              parameters:
+                command: { value: "touch ${ctx:marker}" }
+                args: { value: ["${{ vars.greeting }}"] }
 "#;
 
+        let marker = std::env::temp_dir()
+            .join(format!("flowbuilder-dry-run-{}", std::process::id()));
         let config = WorkflowLoader::from_yaml_str(yaml_content).unwrap();
         let executor = DynamicFlowExecutor::new(config).unwrap();
+        let context = std::sync::Arc::new(tokio::sync::Mutex::new(
+            FlowContext::default(),
+        ));
+        context
+            .lock()
+            .await
+            .set_variable("marker".to_string(), marker.display().to_string());
+
+        let report = executor.execute_dry_run(context.clone()).await.unwrap();
+        assert_eq!(report.actions.len(), 1);
+        let action = &report.actions[0];
+        assert_eq!(
+            (action.node_id.as_str(), action.action_type.as_str()),
+            ("touch", "cmd")
+        );
+        assert_eq!(
+            action.resolved_parameters["command"],
+            serde_yaml::Value::String(format!("touch {}", marker.display()))
+        );
+        assert_eq!(
+            action.resolved_parameters["args"],
+            serde_yaml::Value::Sequence(vec!["hello".into()])
+        );
+        assert!(!marker.exists());
+        assert!(serde_json::to_string(&report).unwrap().contains("touch"));
+        assert!(context.lock().await.step_logs.is_empty());
+    }
+
+    #[test]
+    fn test_validate_workflow_reports_cycle() {
+        let yaml_content = r#"
+workflow:
+  version: "1.0"
+  tasks:
+    - task:
+        id: "a"
+        name: "A"
+        description: "Points at b"
+        actions:
+          - action:
+              id: "a1"
+              name: "A1"
+              description: "A1"
+              type: "builtin"
+              flow:
+                next: "b"
+              outputs: {}
+              parameters: {}
+    - task:
+        id: "b"
+        name: "B"
+        description: "Points back at a"
+        actions:
+          - action:
+              id: "b1"
+              name: "B1"
+              description: "B1"
+              type: "builtin"
+              flow:
+                next: "a"
+              outputs: {}
+              parameters: {}
+"#;
+
+        let config = WorkflowLoader::from_yaml_str(yaml_content).unwrap();
+        let executor = DynamicFlowExecutor::new(config).unwrap();
+        let error = format!("{:#}", executor.validate_workflow().unwrap_err());
+        assert!(error.contains("循环依赖"), "{error}");
+        assert!(error.contains("a -> b -> a"), "{error}");
+    }
+
+    #[tokio::test]
+    async fn test_execution_plan_preview() {
+        let yaml_content = r#"
+workflow:
+  version: "1.0"
+  env:
+    TEST_ENV: "preview"
+  vars:
+    name: "Preview Test"
+  tasks:
+    - task:
+        id: "preview_task"
+        name: "Preview Task"
+        description: "Task for preview testing"
+        actions:
+          - action:
+              id: "preview_action"
+              name: "Preview Action"
+              description: "Action for preview"
+              type: "builtin"
+              flow:
+                next: null
+              outputs: {}
+              parameters: {}
+"#;
+
+        let config = WorkflowLoader::from_yaml_str(yaml_content).unwrap();
+        let executor = DynamicFlowExecutor::new(config).unwrap();
+
+        let plan = executor.get_execution_plan_preview().unwrap();
+        assert_eq!(plan.phases.len(), 1);
+        assert_eq!(plan.metadata.total_nodes, 1);
+        assert_eq!(plan.metadata.workflow_name, "Preview Test");
+    }
+
+    #[tokio::test]
+    async fn test_workflow_complexity_analysis() {
+        let yaml_content = r#"
+workflow:
+  version: "1.0"
+  env:
+    TEST_ENV: "complexity"
+  vars:
+    name: "Complexity Test"
+  tasks:
+    - task:
+        id: "complex_task"
+        name: "Complex Task"
+        description: "A complex task for analysis"
+        actions:
+          - action:
+              id: "complex_action"
+              name: "Complex Action"
+              description: "A complex action"
+              type: "builtin"
+              flow:
+                next_if: "true"
+                next: null
+              outputs: {}
+              parameters: {}
+"#;
+
+        let config = WorkflowLoader::from_yaml_str(yaml_content).unwrap();
+        let executor = DynamicFlowExecutor::new(config).unwrap();
+
+        let complexity = executor.analyze_workflow_complexity().unwrap();
+        assert_eq!(complexity.total_nodes, 1);
+        assert_eq!(complexity.conditional_nodes, 1);
+        assert!(complexity.complexity_score > 0.0);
+    }
 
-        let complexity = executor.analyze_workflow_complexity().unwrap();
-        assert_eq!(complexity.total_nodes, 1);
-        assert_eq!(complexity.conditional_nodes, 1);
-        assert!(complexity.complexity_score > 0.0);
-    }
-
     #[test]
     fn test_workflow_info() {
         let yaml_content = r#"
@@ -534,6 +1355,44 @@ workflow:
         assert_eq!(info.flow_var_count, 2);
     }
 
+    #[cfg(feature = "runtime")]
+    #[test]
+    fn test_validate_rejects_unavailable_action_type() {
+        let yaml_content = r#"
+workflow:
+  version: "2.0"
+  tasks:
+    - task:
+        id: "query"
+        name: "Query"
+        description: "Query task"
+        actions:
+          - action:
+              id: "select"
+              name: "Select"
+              description: "Select one"
+              type: "sql"
+              flow:
+                next: null
+              outputs: {}
+              parameters:
+                url: { value: "sqlite::memory:", required: true }
+                query: { value: "SELECT 1", required: true }
+"#;
+
+        let config = WorkflowLoader::from_yaml_str(yaml_content).unwrap();
+        let executor = DynamicFlowExecutor::new(config).unwrap();
+
+        let result = executor.validate_workflow();
+        if cfg!(feature = "sqlx") {
+            assert!(result.is_ok(), "{result:?}");
+        } else {
+            let message = result.unwrap_err().to_string();
+            assert!(message.contains("sql"));
+            assert!(message.contains("builtin"));
+        }
+    }
+
     #[cfg(feature = "runtime")]
     #[tokio::test]
     async fn test_execution_plan_dependency_phases() {
@@ -642,4 +1501,528 @@ workflow:
             "process_task should depend on notification_task"
         );
     }
+
+    #[cfg(feature = "runtime")]
+    const PIPELINE_YAML: &str = r#"
+workflow:
+  version: "1.0"
+  env: {}
+  vars:
+    name: "Partial Execution Test"
+  tasks:
+    - task:
+        id: "extract"
+        name: "Extract"
+        description: "Extract data"
+        actions:
+          - action:
+              id: "extract_action"
+              name: "Extract Action"
+              description: "Extract action"
+              type: "builtin"
+              flow:
+                next: "transform"
+              outputs:
+                raw_data: "extracted"
+              parameters:
+                operation:
+                  value: "log"
+                message:
+                  value: "running"
+    - task:
+        id: "transform"
+        name: "Transform"
+        description: "Transform data"
+        actions:
+          - action:
+              id: "transform_action"
+              name: "Transform Action"
+              description: "Transform action"
+              type: "builtin"
+              flow:
+                next: "load"
+              outputs:
+                clean_data: "transformed"
+              parameters:
+                operation:
+                  value: "log"
+                message:
+                  value: "running"
+    - task:
+        id: "load"
+        name: "Load"
+        description: "Load data"
+        actions:
+          - action:
+              id: "load_action"
+              name: "Load Action"
+              description: "Load action"
+              type: "builtin"
+              flow:
+                next: null
+              outputs:
+                loaded: "done"
+              parameters:
+                operation:
+                  value: "log"
+                message:
+                  value: "running"
+"#;
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_execute_from_task() {
+        let config = WorkflowLoader::from_yaml_str(PIPELINE_YAML).unwrap();
+        let mut executor = DynamicFlowExecutor::new(config).unwrap();
+
+        // 未提供上游输出时报错
+        let context = Arc::new(tokio::sync::Mutex::new(FlowContext::default()));
+        let err = executor
+            .execute_from("transform", context)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("extract.raw_data"));
+
+        // 未知任务报错
+        let context = Arc::new(tokio::sync::Mutex::new(FlowContext::default()));
+        assert!(executor.execute_from("missing", context).await.is_err());
+
+        // 由上下文提供上游输出
+        let mut seeded = FlowContext::default();
+        seeded.set_variable("raw_data".to_string(), "seeded".to_string());
+        let context = Arc::new(tokio::sync::Mutex::new(seeded));
+        let result = executor
+            .execute_from("transform", context.clone())
+            .await
+            .unwrap();
+
+        assert!(result.success);
+        let executed: Vec<_> = result
+            .phase_results
+            .iter()
+            .flat_map(|p| p.node_results.iter().map(|n| n.node_id.as_str()))
+            .collect();
+        assert_eq!(executed, vec!["transform", "load"]);
+
+        let guard = context.lock().await;
+        assert_eq!(guard.get_variable("raw_data"), Some(&"seeded".to_string()));
+        assert!(guard.get_variable("loaded").is_some());
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_execute_until_task() {
+        let config = WorkflowLoader::from_yaml_str(PIPELINE_YAML).unwrap();
+        let mut executor = DynamicFlowExecutor::new(config).unwrap();
+
+        let context = Arc::new(tokio::sync::Mutex::new(FlowContext::default()));
+        let result = executor
+            .execute_until("transform", context.clone())
+            .await
+            .unwrap();
+
+        assert!(result.success);
+        let executed: Vec<_> = result
+            .phase_results
+            .iter()
+            .flat_map(|p| p.node_results.iter().map(|n| n.node_id.as_str()))
+            .collect();
+        assert_eq!(executed, vec!["extract", "transform"]);
+
+        // 下游任务未执行，中间状态可供检查
+        let guard = context.lock().await;
+        assert!(guard.get_variable("raw_data").is_some());
+        assert!(guard.get_variable("clean_data").is_some());
+        assert!(guard.get_variable("loaded").is_none());
+        drop(guard);
+
+        let context = Arc::new(tokio::sync::Mutex::new(FlowContext::default()));
+        assert!(executor.execute_until("missing", context).await.is_err());
+    }
+
+    #[test]
+    fn test_simulate_resolves_conditions() {
+        let yaml_content = r#"
+workflow:
+  version: "1.0"
+  env: {}
+  vars:
+    name: "Simulation Test"
+  tasks:
+    - task:
+        id: "check"
+        name: "Check"
+        description: "Always runs"
+        actions:
+          - action:
+              id: "check_action"
+              name: "Check Action"
+              description: "Check action"
+              type: "builtin"
+              flow:
+                next: "deploy"
+              outputs: {}
+              parameters: {}
+    - task:
+        id: "deploy"
+        name: "Deploy"
+        description: "Runs only in prod"
+        actions:
+          - action:
+              id: "deploy_action"
+              name: "Deploy Action"
+              description: "Deploy action"
+              type: "builtin"
+              flow:
+                next_if: "${ctx:stage} == prod"
+                next: "notify"
+              outputs: {}
+              parameters: {}
+    - task:
+        id: "notify"
+        name: "Notify"
+        description: "Runs after deploy"
+        actions:
+          - action:
+              id: "notify_action"
+              name: "Notify Action"
+              description: "Notify action"
+              type: "builtin"
+              flow:
+                next: null
+              outputs: {}
+              parameters: {}
+"#;
+
+        let config = WorkflowLoader::from_yaml_str(yaml_content).unwrap();
+        let executor = DynamicFlowExecutor::new(config).unwrap();
+
+        let mut context = FlowContext::default();
+        context.set_variable("stage".to_string(), "prod".to_string());
+        let report = executor.simulate(&context).unwrap();
+        assert_eq!(report.phases.len(), 3);
+        assert_eq!(report.would_run(), vec!["check", "deploy", "notify"]);
+        assert!(report.would_skip().is_empty());
+
+        // next_if 不满足时，该分支指向的下游被跳过
+        context.set_variable("stage".to_string(), "dev".to_string());
+        let report = executor.simulate(&context).unwrap();
+        assert_eq!(report.would_run(), vec!["check", "deploy"]);
+        assert_eq!(report.would_skip(), vec!["notify"]);
+        assert!(report.phases[2].would_skip[0].reason.contains("deploy"));
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_next_if_routing_skips_false_branch() {
+        let yaml_content = r#"
+workflow:
+  version: "1.0"
+  vars:
+    deploy: false
+  tasks:
+    - task:
+        id: "build"
+        name: "Build"
+        description: "Routes to deploy only when enabled"
+        actions:
+          - action:
+              id: "compile"
+              name: "Compile"
+              description: "Compile"
+              type: "builtin"
+              flow:
+                next: "deploy"
+                next_if: "${ctx:vars.deploy} == true"
+              outputs: {}
+              parameters:
+                operation: { value: "log" }
+                message: { value: "build" }
+          - action:
+              id: "report"
+              name: "Report"
+              description: "Always report"
+              type: "builtin"
+              flow:
+                next: "notify"
+              outputs: {}
+              parameters:
+                operation: { value: "log" }
+                message: { value: "report" }
+    - task:
+        id: "deploy"
+        name: "Deploy"
+        description: "False branch"
+        actions:
+          - action:
+              id: "push"
+              name: "Push"
+              description: "Push"
+              type: "builtin"
+              flow:
+                next: "verify"
+              outputs: {}
+              parameters:
+                operation: { value: "log" }
+                message: { value: "deploy" }
+    - task:
+        id: "verify"
+        name: "Verify"
+        description: "Downstream of the false branch"
+        actions:
+          - action:
+              id: "check"
+              name: "Check"
+              description: "Check"
+              type: "builtin"
+              outputs: {}
+              parameters:
+                operation: { value: "log" }
+                message: { value: "verify" }
+    - task:
+        id: "notify"
+        name: "Notify"
+        description: "Unconditional successor"
+        actions:
+          - action:
+              id: "send"
+              name: "Send"
+              description: "Send"
+              type: "builtin"
+              outputs: {}
+              parameters:
+                operation: { value: "log" }
+                message: { value: "notify" }
+"#;
+
+        let config = WorkflowLoader::from_yaml_str(yaml_content).unwrap();
+        let mut executor = DynamicFlowExecutor::new(config).unwrap();
+        let context = Arc::new(tokio::sync::Mutex::new(FlowContext::default()));
+        let result = executor.execute(context).await.unwrap();
+
+        let skipped: HashMap<_, _> = result
+            .phase_results
+            .iter()
+            .flat_map(|p| &p.node_results)
+            .map(|n| (n.node_id.as_str(), n.skipped))
+            .collect();
+        assert_eq!(skipped.len(), 4);
+        assert!(!skipped["build"]);
+        assert!(skipped["deploy"]);
+        assert!(skipped["verify"]);
+        assert!(!skipped["notify"]);
+
+        // 模拟执行给出相同的路由结果
+        let report = executor.simulate(&FlowContext::default()).unwrap();
+        let would_skip: Vec<_> = report
+            .phases
+            .iter()
+            .flat_map(|p| &p.would_skip)
+            .map(|s| s.node_id.as_str())
+            .collect();
+        assert_eq!(would_skip, vec!["deploy", "verify"]);
+    }
+
+    #[test]
+    fn test_parse_cache_shared_and_invalidated() {
+        let yaml = |task_id: &str| {
+            format!(
+                r#"
+workflow:
+  version: "1.0"
+  tasks:
+    - task:
+        id: "{task_id}"
+        name: "Task"
+        description: "Cached"
+        actions:
+          - action:
+              id: "noop"
+              name: "Noop"
+              description: "Noop"
+              type: "builtin"
+              outputs: {{}}
+              parameters: {{}}
+"#
+            )
+        };
+
+        let config = WorkflowLoader::from_yaml_str(&yaml("first")).unwrap();
+        let mut executor = DynamicFlowExecutor::new(config).unwrap();
+        assert!(executor.parse_cache.get().is_none());
+
+        executor.get_execution_plan_preview().unwrap();
+        assert!(executor.parse_cache.get().is_some());
+        executor.analyze_workflow_complexity().unwrap();
+
+        let config = WorkflowLoader::from_yaml_str(&yaml("second")).unwrap();
+        executor.set_config(config).unwrap();
+        assert!(executor.parse_cache.get().is_none());
+        let plan = executor.get_execution_plan_preview().unwrap();
+        assert_eq!(plan.phases[0].nodes[0].id, "second");
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_plan_cache_reuses_plan_for_same_config() {
+        let yaml_content = r#"
+workflow:
+  version: "1.0"
+  tasks:
+    - task:
+        id: "only"
+        name: "Only"
+        description: "Cached plan"
+        actions:
+          - action:
+              id: "noop"
+              name: "Noop"
+              description: "Noop"
+              type: "builtin"
+              outputs: {}
+              parameters: {}
+"#;
+
+        let config = WorkflowLoader::from_yaml_str(yaml_content).unwrap();
+        let mut executor = DynamicFlowExecutor::new(config.clone()).unwrap();
+        let context =
+            || Arc::new(tokio::sync::Mutex::new(FlowContext::default()));
+
+        // 默认不缓存：每次执行的计划ID唯一
+        let first = executor.execute(context()).await.unwrap();
+        let second = executor.execute(context()).await.unwrap();
+        assert_ne!(first.plan_id, second.plan_id);
+
+        executor.set_plan_cache(true);
+        let first = executor.execute(context()).await.unwrap();
+        let second = executor.execute(context()).await.unwrap();
+        assert_eq!(first.plan_id, second.plan_id);
+
+        // 同内容的配置命中同一缓存项
+        executor.set_config(config).unwrap();
+        let preview = executor.get_execution_plan_preview().unwrap();
+        assert_eq!(preview.metadata.plan_id, first.plan_id);
+
+        executor.clear_plan_cache();
+        let rebuilt = executor.get_execution_plan_preview().unwrap();
+        assert_ne!(rebuilt.metadata.plan_id, first.plan_id);
+
+        executor.set_plan_cache(false);
+        let a = executor.get_execution_plan_preview().unwrap();
+        let b = executor.get_execution_plan_preview().unwrap();
+        assert_ne!(a.metadata.plan_id, b.metadata.plan_id);
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_overall_timeout_reports_stage() {
+        let yaml_content = r#"
+workflow:
+  version: "1.0"
+  tasks:
+    - task:
+        id: "slow"
+        name: "Slow"
+        description: "Sleeps past the overall timeout"
+        actions:
+          - action:
+              id: "wait"
+              name: "Wait"
+              description: "Sleep"
+              type: "builtin"
+              outputs: {}
+              parameters:
+                operation: { value: "sleep" }
+                duration: { value: 5000 }
+"#;
+
+        let config = WorkflowLoader::from_yaml_str(yaml_content).unwrap();
+        let mut executor = DynamicFlowExecutor::new(config).unwrap();
+        executor.set_overall_timeout(Some(Duration::from_millis(100)));
+        let context = Arc::new(tokio::sync::Mutex::new(FlowContext::default()));
+
+        let started = Instant::now();
+        let err = executor.execute(context).await.unwrap_err().to_string();
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(err.contains("总超时 100 毫秒"), "{err}");
+        assert!(err.contains("任务执行"), "{err}");
+
+        // 部分执行同样受总超时约束
+        for from in [true, false] {
+            let context =
+                Arc::new(tokio::sync::Mutex::new(FlowContext::default()));
+            let started = Instant::now();
+            let result = if from {
+                executor.execute_from("slow", context).await
+            } else {
+                executor.execute_until("slow", context).await
+            };
+            let err = result.unwrap_err().to_string();
+            assert!(started.elapsed() < Duration::from_secs(2));
+            assert!(err.contains("总超时 100 毫秒"), "{err}");
+        }
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_on_timeout_runs_handler_task() {
+        let yaml_content = r#"
+workflow:
+  version: "1.0"
+  tasks:
+    - task:
+        id: "fetch"
+        name: "Fetch"
+        description: "Times out"
+        actions:
+          - action:
+              id: "wait"
+              name: "Wait"
+              description: "Sleeps past the timeout"
+              type: "builtin"
+              flow:
+                timeout: { duration: 20 }
+                on_timeout: "cleanup"
+              outputs: {}
+              parameters:
+                operation: { value: "sleep" }
+                duration: { value: 500 }
+    - task:
+        id: "cleanup"
+        name: "Cleanup"
+        description: "Runs only on timeout"
+        actions:
+          - action:
+              id: "mark"
+              name: "Mark"
+              description: "Record cleanup"
+              type: "builtin"
+              outputs: {}
+              parameters:
+                operation: { value: "set_variable" }
+                key: { value: "cleaned" }
+                value: { value: "yes" }
+"#;
+
+        let config = WorkflowLoader::from_yaml_str(yaml_content).unwrap();
+        let mut executor = DynamicFlowExecutor::new(config).unwrap();
+        let context = Arc::new(tokio::sync::Mutex::new(FlowContext::default()));
+        let result = executor.execute(context.clone()).await.unwrap();
+
+        // 处理任务不作为普通节点执行
+        let nodes: Vec<_> = result
+            .phase_results
+            .iter()
+            .flat_map(|p| &p.node_results)
+            .collect();
+        assert_eq!(nodes.len(), 1);
+
+        let fetch = nodes[0];
+        assert!(!fetch.success);
+        assert!(fetch.error_message.as_deref().unwrap().contains("超时"));
+        let handler = fetch.timeout_handler.as_ref().unwrap();
+        assert_eq!(handler.target, "cleanup");
+        assert!(handler.success, "{:?}", handler.error_message);
+        assert!(context.lock().await.get_variable("cleaned").is_some());
+    }
 }
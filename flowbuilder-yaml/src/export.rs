@@ -0,0 +1,225 @@
+//! # FlowBuilder YAML - 配置导出
+//!
+//! 将代码构建的流程导出为可共享的工作流配置
+
+use crate::config::{
+    Action, ActionDefinition, ActionType, FlowControl, Parameter, RetryConfig,
+    Task, TaskDefinition, TimeoutConfig, Workflow, WorkflowConfig,
+};
+use flowbuilder_core::{FlowBuilder, StepDescriptor, StepKind};
+use std::collections::{HashMap, HashSet};
+use tracing::warn;
+
+/// 导出为工作流配置
+pub trait ToWorkflowConfig {
+    /// 生成等价的工作流配置，流程含无法用配置表示的步骤时返回 `None`
+    fn to_workflow_config(&self) -> Option<WorkflowConfig>;
+}
+
+impl ToWorkflowConfig for FlowBuilder {
+    fn to_workflow_config(&self) -> Option<WorkflowConfig> {
+        WorkflowConfig::from_descriptors(&self.describe())
+    }
+}
+
+impl WorkflowConfig {
+    /// 由步骤描述生成配置
+    ///
+    /// 每个普通步骤对应一个同名任务（单个记录步骤名的 builtin `log`
+    /// 动作，作为可运行的占位），按顺序以 `next` 串联，重试与超时设置
+    /// 随之导出。条件、等待、并行等依赖闭包的步骤无法表示；任务 ID 取自
+    /// 步骤名，未命名（`step_<index>`）或重名的步骤也无法导出。遇到这些
+    /// 情况时记录警告并返回 `None`
+    pub fn from_descriptors(steps: &[StepDescriptor]) -> Option<Self> {
+        if steps.is_empty() {
+            warn!("流程没有步骤，无法导出为配置");
+            return None;
+        }
+        let mut tasks = Vec::with_capacity(steps.len());
+        let mut names = HashSet::new();
+        for (i, step) in steps.iter().enumerate() {
+            if step.kind != StepKind::Step {
+                warn!(
+                    step = %step.name,
+                    kind = ?step.kind,
+                    "步骤无法导出为配置"
+                );
+                return None;
+            }
+            if step.name == format!("step_{}", step.index) {
+                warn!(step = %step.name, "未命名的步骤无法导出为配置");
+                return None;
+            }
+            if !names.insert(step.name.as_str()) {
+                warn!(step = %step.name, "步骤重名，无法导出为配置");
+                return None;
+            }
+            let next = steps.get(i + 1).map(|s| s.name.clone());
+            tasks.push(export_step(step, next));
+        }
+
+        Some(WorkflowConfig {
+            workflow: Workflow {
+                version: "1.0".to_string(),
+                env: HashMap::new(),
+                vars: HashMap::new(),
+                template: None,
+                tasks,
+                groups: Vec::new(),
+                multi_action: Default::default(),
+                templates: HashMap::new(),
+            },
+            profiles: HashMap::new(),
+        })
+    }
+}
+
+/// 将普通步骤导出为单动作任务（builtin `log` 占位动作）
+fn export_step(step: &StepDescriptor, next: Option<String>) -> Task {
+    let millis = |key: &str| step.metadata.get(key)?.parse::<u64>().ok();
+    let retry = step
+        .metadata
+        .get("max_retries")
+        .and_then(|n| n.parse().ok())
+        .map(|max_retries| RetryConfig {
+            max_retries,
+            delay: millis("delay_ms").unwrap_or(0),
+        });
+    let timeout =
+        millis("timeout_ms").map(|duration| TimeoutConfig { duration });
+
+    Task {
+        task: TaskDefinition {
+            id: step.name.clone(),
+            name: step.name.clone(),
+            description: String::new(),
+            matrix: None,
+            tags: Vec::new(),
+            phase: None,
+            actions: vec![Action {
+                action: ActionDefinition {
+                    id: step.name.clone(),
+                    name: step.name.clone(),
+                    description: String::new(),
+                    flow: FlowControl {
+                        next,
+                        retry,
+                        timeout,
+                        ..Default::default()
+                    },
+                    outputs: HashMap::new(),
+                    action_type: ActionType::Builtin,
+                    parameters: HashMap::from([
+                        ("operation".to_string(), parameter("log")),
+                        ("message".to_string(), parameter(&step.name)),
+                    ]),
+                    param_schema: HashMap::new(),
+                },
+            }],
+        },
+    }
+}
+
+fn parameter(value: &str) -> Parameter {
+    Parameter {
+        value: serde_yaml::Value::String(value.to_string()),
+        required: true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_parser::YamlConfigParser;
+    use std::time::Duration;
+
+    #[test]
+    fn test_export_sequential_steps() {
+        let flow = FlowBuilder::new()
+            .named_step("fetch", |_ctx| async move { Ok(()) })
+            .step_with_retry(
+                "store",
+                3,
+                Duration::from_millis(50),
+                |_ctx| async move { Ok(()) },
+            )
+            .step_with_timeout(
+                "notify",
+                Duration::from_secs(2),
+                |_ctx| async move { Ok(()) },
+            );
+
+        let config = flow.to_workflow_config().expect("exportable flow");
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        let config: WorkflowConfig = serde_yaml::from_str(&yaml).unwrap();
+
+        let nodes = YamlConfigParser::new(config.clone()).parse().unwrap();
+        let ids: Vec<_> = nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, ["fetch", "store", "notify"]);
+        assert_eq!(nodes[1].dependencies, ["fetch"]);
+        assert_eq!(nodes[2].dependencies, ["store"]);
+
+        let flow_control =
+            &config.workflow.tasks[1].task.actions[0].action.flow;
+        let retry = flow_control.retry.as_ref().unwrap();
+        assert_eq!((retry.max_retries, retry.delay), (3, 50));
+        let flow_control =
+            &config.workflow.tasks[2].task.actions[0].action.flow;
+        assert_eq!(flow_control.timeout.as_ref().unwrap().duration, 2000);
+    }
+
+    #[test]
+    fn test_export_rejects_closure_conditions() {
+        let flow = FlowBuilder::new()
+            .named_step("fetch", |_ctx| async move { Ok(()) })
+            .step_if(|_ctx| true, |_ctx| async move { Ok(()) });
+        assert!(flow.to_workflow_config().is_none());
+    }
+
+    #[test]
+    fn test_export_rejects_unusable_task_ids() {
+        let duplicated = FlowBuilder::new()
+            .named_step("fetch", |_ctx| async move { Ok(()) })
+            .named_step("fetch", |_ctx| async move { Ok(()) });
+        assert!(duplicated.to_workflow_config().is_none());
+
+        let unnamed = FlowBuilder::new()
+            .named_step("fetch", |_ctx| async move { Ok(()) })
+            .step(|_ctx| async move { Ok(()) });
+        assert!(unnamed.to_workflow_config().is_none());
+
+        assert!(FlowBuilder::new().to_workflow_config().is_none());
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_exported_workflow_runs() {
+        use crate::executor::DynamicFlowExecutor;
+        use flowbuilder_context::FlowContext;
+        use std::sync::Arc;
+
+        let config = FlowBuilder::new()
+            .named_step("fetch", |_ctx| async move { Ok(()) })
+            .step_with_retry(
+                "store",
+                1,
+                Duration::from_millis(10),
+                |_ctx| async move { Ok(()) },
+            )
+            .to_workflow_config()
+            .expect("exportable flow");
+        YamlConfigParser::new(config.clone()).validate().unwrap();
+
+        let mut executor = DynamicFlowExecutor::new(config).unwrap();
+        let context = Arc::new(tokio::sync::Mutex::new(FlowContext::default()));
+        let result = executor.execute(context).await.unwrap();
+        assert!(result.success, "{:?}", result.error_message);
+        let nodes: Vec<_> = result
+            .phase_results
+            .iter()
+            .flat_map(|phase| &phase.node_results)
+            .collect();
+        assert_eq!(nodes.len(), 2);
+        assert!(nodes.iter().all(|node| node.success), "{nodes:?}");
+    }
+}
@@ -1,7 +1,86 @@
+use crate::config::TemplateSource;
+#[cfg(feature = "jq")]
+use crate::jq::JqCache;
 use anyhow::{Context, Result};
+use chrono::{
+    DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc,
+};
+use flowbuilder_context::VarMap;
 use regex::Regex;
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+#[cfg(feature = "jq")]
+use std::sync::{Arc, Mutex};
+
+/// 文件 Provider：在基准目录内读取文件内容
+///
+/// 路径相对基准目录解析，禁止越出基准目录（防止路径穿越）
+#[derive(Debug, Clone)]
+pub struct FileProvider {
+    base_dir: PathBuf,
+}
+
+impl FileProvider {
+    /// 以指定目录为基准创建
+    pub fn new<P: Into<PathBuf>>(base_dir: P) -> Self {
+        Self {
+            base_dir: base_dir.into(),
+        }
+    }
+
+    /// 获取基准目录
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    /// 解析并校验文件路径
+    pub fn resolve(&self, path: &str) -> Result<PathBuf> {
+        let base = self.base_dir.canonicalize().with_context(|| {
+            format!("File provider base dir not found: {:?}", self.base_dir)
+        })?;
+        let full = base
+            .join(path)
+            .canonicalize()
+            .with_context(|| format!("File not found: {path}"))?;
+        if !full.starts_with(&base) {
+            return Err(anyhow::anyhow!(
+                "File path escapes base dir: {}",
+                path
+            ));
+        }
+        Ok(full)
+    }
+
+    /// 读取文件原始内容
+    pub fn read(&self, path: &str) -> Result<JsonValue> {
+        let full = self.resolve(path)?;
+        let content = std::fs::read_to_string(&full)
+            .with_context(|| format!("Failed to read file: {path}"))?;
+        Ok(JsonValue::String(content))
+    }
+
+    /// 读取并解析文件（`.yaml`/`.yml` 按 YAML，其余按 JSON）
+    pub fn read_parsed(&self, path: &str) -> Result<JsonValue> {
+        let full = self.resolve(path)?;
+        let content = std::fs::read_to_string(&full)
+            .with_context(|| format!("Failed to read file: {path}"))?;
+        match full.extension().and_then(|e| e.to_str()) {
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&content)
+                .with_context(|| format!("Failed to parse YAML file: {path}")),
+            _ => serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse JSON file: {path}")),
+        }
+    }
+}
+
+impl Default for FileProvider {
+    /// 默认以当前工作目录为基准
+    fn default() -> Self {
+        Self::new(".")
+    }
+}
 
 /// 表达式求值器，用于处理工作流中的变量和表达式
 #[derive(Clone)]
@@ -9,6 +88,17 @@ pub struct ExpressionEvaluator {
     env_vars: HashMap<String, String>,
     flow_vars: HashMap<String, serde_yaml::Value>,
     context_vars: HashMap<String, serde_yaml::Value>,
+    file_provider: FileProvider,
+    /// `${template:<name>}` 使用的命名模板
+    #[cfg_attr(not(feature = "tera"), allow(dead_code))]
+    templates: HashMap<String, TemplateSource>,
+    /// 固定时钟（用于测试与可复现执行），为空时使用系统时间
+    fixed_now: Option<DateTime<Utc>>,
+    /// 日期函数使用的时区
+    timezone: FixedOffset,
+    /// `${jq:...}` 编译缓存（克隆间共享）
+    #[cfg(feature = "jq")]
+    jq_cache: Arc<Mutex<JqCache>>,
 }
 
 impl ExpressionEvaluator {
@@ -18,9 +108,38 @@ impl ExpressionEvaluator {
             env_vars: HashMap::new(),
             flow_vars: HashMap::new(),
             context_vars: HashMap::new(),
+            file_provider: FileProvider::default(),
+            templates: HashMap::new(),
+            fixed_now: None,
+            timezone: FixedOffset::east_opt(0).expect("UTC offset"),
+            #[cfg(feature = "jq")]
+            jq_cache: Arc::new(Mutex::new(JqCache::default())),
         }
     }
 
+    /// 设置固定时钟（`None` 表示使用系统时间）
+    pub fn set_fixed_now(&mut self, now: Option<DateTime<Utc>>) {
+        self.fixed_now = now;
+    }
+
+    /// 设置日期函数使用的时区（默认 UTC）
+    pub fn set_timezone(&mut self, timezone: FixedOffset) {
+        self.timezone = timezone;
+    }
+
+    /// 设置文件 Provider 的基准目录
+    pub fn set_file_base_dir<P: Into<PathBuf>>(&mut self, base_dir: P) {
+        self.file_provider = FileProvider::new(base_dir);
+    }
+
+    /// 设置命名模板
+    pub fn set_templates(
+        &mut self,
+        templates: HashMap<String, TemplateSource>,
+    ) {
+        self.templates = templates;
+    }
+
     /// 设置环境变量
     pub fn set_env_vars(&mut self, env_vars: HashMap<String, String>) {
         self.env_vars = env_vars;
@@ -51,6 +170,47 @@ impl ExpressionEvaluator {
         self.context_vars.get(key.as_ref())
     }
 
+    /// 复制求值器并叠加执行上下文中的变量
+    pub(crate) fn with_context_variables(
+        &self,
+        variables: &VarMap<String>,
+    ) -> Self {
+        let mut evaluator = self.clone();
+        for (key, value) in variables {
+            // 上下文变量以字符串保存，尽量还原为数字/布尔等原生类型
+            let value = serde_yaml::from_str(value)
+                .unwrap_or_else(|_| serde_yaml::Value::String(value.clone()));
+            evaluator.set_context_var(key, value);
+        }
+        evaluator
+    }
+
+    /// 递归求值值中含 `${...}` 的字符串（含嵌套映射/列表），其余值原样保留
+    pub fn evaluate_value(
+        &self,
+        value: &serde_yaml::Value,
+    ) -> Result<serde_yaml::Value> {
+        Ok(match value {
+            serde_yaml::Value::String(s) if s.contains("${") => {
+                self.evaluate(s)?
+            }
+            serde_yaml::Value::Sequence(items) => serde_yaml::Value::Sequence(
+                items
+                    .iter()
+                    .map(|item| self.evaluate_value(item))
+                    .collect::<Result<_>>()?,
+            ),
+            serde_yaml::Value::Mapping(map) => {
+                let mut resolved = serde_yaml::Mapping::new();
+                for (key, item) in map {
+                    resolved.insert(key.clone(), self.evaluate_value(item)?);
+                }
+                serde_yaml::Value::Mapping(resolved)
+            }
+            other => other.clone(),
+        })
+    }
+
     /// 求值表达式字符串（支持统一 Provider 语法与旧语法，带类型保真与插值）
     pub fn evaluate(&self, expression: &str) -> Result<serde_yaml::Value> {
         let expr = expression.trim();
@@ -75,37 +235,83 @@ impl ExpressionEvaluator {
     }
 
     /// 求值条件表达式，返回布尔值
+    ///
+    /// 优先级从低到高：`||`、`&&`、比较（`==` `!=` `<` `<=` `>` `>=`）。
+    /// 比较两侧先各自求值，均为数字时按数值比较，否则按字符串比较。
     pub fn evaluate_condition(&self, condition: &str) -> Result<bool> {
-        let result = self.evaluate(condition)?;
-
-        match result {
-            serde_yaml::Value::Bool(b) => Ok(b),
-            serde_yaml::Value::String(s) => {
-                // 简单的条件解析
-                if s.contains("==") {
-                    self.evaluate_equality(&s)
-                } else if s.contains("!=") {
-                    self.evaluate_inequality(&s)
-                } else if s.contains("&&") {
-                    self.evaluate_and(&s)
-                } else if s.contains("||") {
-                    self.evaluate_or(&s)
-                } else {
-                    // 非空字符串视为 true
-                    Ok(!s.is_empty())
+        let condition = condition.trim();
+
+        let any = split_top_level(condition, "||");
+        if any.len() > 1 {
+            for part in any {
+                if self.evaluate_condition(part)? {
+                    return Ok(true);
+                }
+            }
+            return Ok(false);
+        }
+
+        let all = split_top_level(condition, "&&");
+        if all.len() > 1 {
+            for part in all {
+                if !self.evaluate_condition(part)? {
+                    return Ok(false);
                 }
             }
+            return Ok(true);
+        }
+
+        if let Some((left, op, right)) = split_comparison(condition) {
+            let left = self.evaluate(left)?;
+            let right = self.evaluate(right)?;
+            return Ok(self.compare(&left, op, &right));
+        }
+
+        Ok(match self.evaluate(condition)? {
+            serde_yaml::Value::Bool(b) => b,
+            // 非空字符串视为 true
+            serde_yaml::Value::String(s) => !s.is_empty(),
             serde_yaml::Value::Number(n) => {
                 if let Some(i) = n.as_i64() {
-                    Ok(i != 0)
+                    i != 0
                 } else if let Some(f) = n.as_f64() {
-                    Ok(f != 0.0)
+                    f != 0.0
                 } else {
-                    Ok(false)
+                    false
                 }
             }
-            serde_yaml::Value::Null => Ok(false),
-            _ => Ok(true),
+            serde_yaml::Value::Null => false,
+            _ => true,
+        })
+    }
+
+    /// 比较两个已求值的操作数
+    fn compare(
+        &self,
+        left: &serde_yaml::Value,
+        op: &str,
+        right: &serde_yaml::Value,
+    ) -> bool {
+        let left_text = self.yaml_value_to_string(left);
+        let right_text = self.yaml_value_to_string(right);
+        let ordering = match (
+            left_text.trim().parse::<f64>(),
+            right_text.trim().parse::<f64>(),
+        ) {
+            (Ok(l), Ok(r)) => l.partial_cmp(&r),
+            _ => Some(left_text.cmp(&right_text)),
+        };
+        let Some(ordering) = ordering else {
+            // NaN 参与比较时仅 `!=` 成立
+            return op == "!=";
+        };
+        match op {
+            "==" => ordering.is_eq(),
+            "!=" => ordering.is_ne(),
+            "<" => ordering.is_lt(),
+            "<=" => ordering.is_le(),
+            ">" => ordering.is_gt(),
+            _ => ordering.is_ge(),
         }
     }
 
@@ -116,9 +322,16 @@ impl ExpressionEvaluator {
         &self,
         s: &'a str,
     ) -> Result<Option<(&'a str, &'a str)>> {
+        // 无参数的 ${now}
+        if s == "${now}" {
+            return Ok(Some(("now", "")));
+        }
         // 简单正则：不支持大括号/嵌套，覆盖常见用法
-        let re = Regex::new(r"^\$\{([a-zA-Z_][\w\-]*):([^}]*)\}$")
-            .context("compile provider single regex")?;
+        static RE: OnceLock<Regex> = OnceLock::new();
+        let re = RE.get_or_init(|| {
+            Regex::new(r"^\$\{([a-zA-Z_][\w\-]*):([^}]*)\}$")
+                .expect("provider single regex")
+        });
         if let Some(c) = re.captures(s) {
             let provider = c.get(1).unwrap().as_str();
             let body = c.get(2).unwrap().as_str();
@@ -129,8 +342,11 @@ impl ExpressionEvaluator {
 
     /// 字符串内插值统一 Provider
     fn interpolate_providers(&self, s: &str) -> Result<String> {
-        let re = Regex::new(r"\$\{([a-zA-Z_][\w\-]*):([^}]*)\}")
-            .context("compile provider inline regex")?;
+        static RE: OnceLock<Regex> = OnceLock::new();
+        let re = RE.get_or_init(|| {
+            Regex::new(r"\$\{([a-zA-Z_][\w\-]*):([^}]*)\}")
+                .expect("provider inline regex")
+        });
         let mut out = String::from(s);
         // 为避免重叠替换，先收集所有匹配再逐个替换
         let caps: Vec<(String, String)> = re
@@ -147,19 +363,318 @@ impl ExpressionEvaluator {
         for (full, repl) in caps {
             out = out.replace(&full, &repl);
         }
+        // 无参数的 ${now}
+        if out.contains("${now}") {
+            let now = self.eval_now_provider("")?;
+            out = out.replace(
+                "${now}",
+                &self.yaml_value_to_string(&self.json_to_yaml(now)),
+            );
+        }
         Ok(out)
     }
 
+    /// 支持的统一 Provider 名称（与 `eval_provider` 保持一致）
+    pub(crate) const PROVIDERS: &'static [&'static str] = &[
+        "env",
+        "ctx",
+        "jq",
+        "file",
+        "file_json",
+        "now",
+        "date_add",
+        "add",
+        "mul",
+        "min",
+        "max",
+        "round",
+        #[cfg(feature = "tera")]
+        "template",
+    ];
+
     /// 统一 Provider 求值
     fn eval_provider(&self, provider: &str, body: &str) -> Result<JsonValue> {
         match provider {
             "env" => self.eval_env_provider(body),
             "ctx" => self.eval_ctx_provider(body),
             "jq" => self.eval_jq_provider(body),
+            "file" => self.file_provider.read(body),
+            "file_json" => self.file_provider.read_parsed(body),
+            "now" => self.eval_now_provider(body),
+            "date_add" => self.eval_date_add_provider(body),
+            "add" | "mul" | "min" | "max" => {
+                self.eval_math_provider(provider, body)
+            }
+            "round" => self.eval_round_provider(body),
+            #[cfg(feature = "tera")]
+            "template" => self.eval_template_provider(body),
             _ => Err(anyhow::anyhow!("Unknown provider: {}", provider)),
         }
     }
 
+    /// 渲染命名模板，渲染作用域为 `vars`/`env` 及上下文变量
+    #[cfg(feature = "tera")]
+    fn eval_template_provider(&self, name: &str) -> Result<JsonValue> {
+        let source = match self.templates.get(name) {
+            Some(TemplateSource::Inline(source)) => source.clone(),
+            Some(TemplateSource::File { file }) => {
+                let full = self.file_provider.resolve(file)?;
+                std::fs::read_to_string(&full).with_context(|| {
+                    format!("Failed to read template file: {file}")
+                })?
+            }
+            None => {
+                return Err(anyhow::anyhow!("Template not found: {}", name))
+            }
+        };
+        let scope = tera::Context::from_value(self.build_ctx_root_json())
+            .context("build template context")?;
+        let rendered =
+            tera::Tera::one_off(&source, &scope, false).map_err(|e| {
+                // tera 的错误原因在 source 链中
+                let mut message = e.to_string();
+                let mut cause = std::error::Error::source(&e);
+                while let Some(inner) = cause {
+                    message = format!("{message}: {inner}");
+                    cause = inner.source();
+                }
+                anyhow::anyhow!(
+                    "Failed to render template '{}': {}",
+                    name,
+                    message
+                )
+            })?;
+        Ok(JsonValue::String(rendered))
+    }
+
+    /// 当前时间：无格式时返回 RFC3339 字符串；`unix`/`unix_ms` 返回数字；
+    /// 其余按 strftime 格式化
+    fn eval_now_provider(&self, format: &str) -> Result<JsonValue> {
+        let now = self.fixed_now.unwrap_or_else(Utc::now);
+        self.format_datetime(now.with_timezone(&self.timezone), format)
+    }
+
+    /// 时间运算：`base|duration[|format]`，base 为 `now` 或日期时间字符串，
+    /// duration 形如 `1d`、`-2h`、`1h30m`（单位 w/d/h/m/s/ms）
+    fn eval_date_add_provider(&self, body: &str) -> Result<JsonValue> {
+        let parts: Vec<&str> = body.split('|').map(|p| p.trim()).collect();
+        if parts.len() < 2 || parts.len() > 3 {
+            return Err(anyhow::anyhow!(
+                "date_add expects 'base|duration[|format]', got: {}",
+                body
+            ));
+        }
+
+        let base = self.parse_datetime(parts[0])?;
+        let duration = Self::parse_duration(parts[1])?;
+        let result = base
+            .checked_add_signed(duration)
+            .ok_or_else(|| anyhow::anyhow!("date_add overflow: {}", body))?;
+        self.format_datetime(result, parts.get(2).copied().unwrap_or(""))
+    }
+
+    fn format_datetime(
+        &self,
+        dt: DateTime<FixedOffset>,
+        format: &str,
+    ) -> Result<JsonValue> {
+        match format {
+            "" => Ok(JsonValue::String(dt.to_rfc3339())),
+            "unix" => Ok(JsonValue::from(dt.timestamp())),
+            "unix_ms" => Ok(JsonValue::from(dt.timestamp_millis())),
+            fmt => {
+                use std::fmt::Write;
+                let mut out = String::new();
+                write!(out, "{}", dt.format(fmt)).map_err(|_| {
+                    anyhow::anyhow!("Invalid date format: {}", fmt)
+                })?;
+                Ok(JsonValue::String(out))
+            }
+        }
+    }
+
+    /// 解析日期时间：`now`、RFC3339、`YYYY-MM-DD[ HH:MM:SS]` 或 Unix 秒
+    fn parse_datetime(&self, s: &str) -> Result<DateTime<FixedOffset>> {
+        if s == "now" {
+            let now = self.fixed_now.unwrap_or_else(Utc::now);
+            return Ok(now.with_timezone(&self.timezone));
+        }
+        if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+            return Ok(dt.with_timezone(&self.timezone));
+        }
+        if let Ok(secs) = s.parse::<i64>() {
+            return DateTime::from_timestamp(secs, 0)
+                .map(|dt| dt.with_timezone(&self.timezone))
+                .ok_or_else(|| anyhow::anyhow!("Invalid timestamp: {}", s));
+        }
+        let naive = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+            .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S"))
+            .or_else(|_| {
+                NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                    .map(|d| d.and_hms_opt(0, 0, 0).expect("midnight"))
+            })
+            .map_err(|_| anyhow::anyhow!("Invalid datetime: {}", s))?;
+        self.timezone
+            .from_local_datetime(&naive)
+            .single()
+            .ok_or_else(|| anyhow::anyhow!("Ambiguous datetime: {}", s))
+    }
+
+    /// 解析时长：如 `1d`、`-2h`、`1h30m`、`500ms`
+    fn parse_duration(s: &str) -> Result<Duration> {
+        let (negative, rest) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let re = Regex::new(r"(\d+)(ms|w|d|h|m|s)")
+            .context("compile duration regex")?;
+
+        let mut total = Duration::zero();
+        let mut consumed = 0;
+        for c in re.captures_iter(rest) {
+            let m = c.get(0).unwrap();
+            if m.start() != consumed {
+                break;
+            }
+            consumed = m.end();
+            // 超出范围的数值返回错误而不是 panic
+            let invalid = || anyhow::anyhow!("Invalid duration: {}", s);
+            let n: i64 = c[1].parse().map_err(|_| invalid())?;
+            let part = match &c[2] {
+                "w" => Duration::try_weeks(n),
+                "d" => Duration::try_days(n),
+                "h" => Duration::try_hours(n),
+                "m" => Duration::try_minutes(n),
+                "s" => Duration::try_seconds(n),
+                _ => Duration::try_milliseconds(n),
+            };
+            total = part
+                .and_then(|part| total.checked_add(&part))
+                .ok_or_else(invalid)?;
+        }
+        if rest.is_empty() || consumed != rest.len() {
+            return Err(anyhow::anyhow!("Invalid duration: {}", s));
+        }
+        Ok(if negative { -total } else { total })
+    }
+
+    /// 算术函数：`add`/`mul`/`min`/`max`，操作数以 `|` 分隔，
+    /// 可为数字字面量或上下文路径（如 `vars.x`）
+    fn eval_math_provider(&self, op: &str, body: &str) -> Result<JsonValue> {
+        let operands = body
+            .split('|')
+            .map(|p| self.eval_numeric_operand(op, p.trim()))
+            .collect::<Result<Vec<_>>>()?;
+        if operands.len() < 2 {
+            return Err(anyhow::anyhow!(
+                "{} expects at least 2 operands, got: {}",
+                op,
+                body
+            ));
+        }
+
+        // 全为整数时按整数运算，溢出或含小数时退化为浮点
+        let ints: Option<Vec<i64>> =
+            operands.iter().map(|n| n.as_i64()).collect();
+        if let Some(ints) = ints {
+            let result = match op {
+                "add" => {
+                    ints.iter().try_fold(0i64, |acc, n| acc.checked_add(*n))
+                }
+                "mul" => {
+                    ints.iter().try_fold(1i64, |acc, n| acc.checked_mul(*n))
+                }
+                "min" => ints.iter().min().copied(),
+                _ => ints.iter().max().copied(),
+            };
+            if let Some(result) = result {
+                return Ok(JsonValue::from(result));
+            }
+        }
+
+        let floats = operands.iter().filter_map(|n| n.as_f64());
+        let result = match op {
+            "add" => floats.sum(),
+            "mul" => floats.product(),
+            "min" => floats.fold(f64::INFINITY, f64::min),
+            _ => floats.fold(f64::NEG_INFINITY, f64::max),
+        };
+        Self::float_to_json(result)
+    }
+
+    /// 四舍五入：`x|digits`，digits 缺省为 0（返回整数）
+    fn eval_round_provider(&self, body: &str) -> Result<JsonValue> {
+        let mut parts = body.split('|').map(|p| p.trim());
+        let value = self
+            .eval_numeric_operand("round", parts.next().unwrap_or(""))?
+            .as_f64()
+            .unwrap_or_default();
+        let digits = match parts.next() {
+            Some(d) => d.parse::<u32>().map_err(|_| {
+                anyhow::anyhow!(
+                    "round digits must be a non-negative integer: {}",
+                    d
+                )
+            })?,
+            None => 0,
+        };
+        if parts.next().is_some() {
+            return Err(anyhow::anyhow!(
+                "round expects 'x|digits', got: {}",
+                body
+            ));
+        }
+
+        if digits == 0 {
+            return Ok(JsonValue::from(value.round() as i64));
+        }
+        let digits = i32::try_from(digits).map_err(|_| {
+            anyhow::anyhow!("round digits out of range: {}", digits)
+        })?;
+        let factor = 10f64.powi(digits);
+        Self::float_to_json((value * factor).round() / factor)
+    }
+
+    /// 解析数值操作数：数字字面量或上下文路径
+    fn eval_numeric_operand(
+        &self,
+        op: &str,
+        operand: &str,
+    ) -> Result<serde_json::Number> {
+        if let Ok(i) = operand.parse::<i64>() {
+            return Ok(i.into());
+        }
+        if let Ok(f) = operand.parse::<f64>() {
+            return serde_json::Number::from_f64(f).ok_or_else(|| {
+                anyhow::anyhow!("{}: non-finite operand: {}", op, operand)
+            });
+        }
+
+        let root = self.build_ctx_root_json();
+        match self.get_by_dot_path(&root, operand) {
+            Some(JsonValue::Number(n)) => Ok(n),
+            // 上下文中的数值常以字符串形式保存
+            Some(JsonValue::String(s)) if s.trim().parse::<f64>().is_ok() => {
+                self.eval_numeric_operand(op, s.trim())
+            }
+            Some(other) => Err(anyhow::anyhow!(
+                "{}: non-numeric operand '{}': {}",
+                op,
+                operand,
+                other
+            )),
+            None => {
+                Err(anyhow::anyhow!("{}: non-numeric operand: {}", op, operand))
+            }
+        }
+    }
+
+    fn float_to_json(value: f64) -> Result<JsonValue> {
+        serde_json::Number::from_f64(value)
+            .map(JsonValue::Number)
+            .ok_or_else(|| anyhow::anyhow!("Non-finite result: {}", value))
+    }
+
     fn eval_env_provider(&self, key: &str) -> Result<JsonValue> {
         if let Some(v) = self.env_vars.get(key) {
             Ok(JsonValue::String(v.clone()))
@@ -177,27 +692,21 @@ impl ExpressionEvaluator {
     }
 
     /// 极简 jq 风格解析器：支持 '.' 根路径、管道 '|'、数组索引 '[n]' 与点路径
+    #[cfg(feature = "jq")]
     fn eval_jq_provider(&self, expr: &str) -> Result<JsonValue> {
-        let root = self.build_ctx_root_json();
-        // 管道拆分
-        let stages: Vec<&str> = expr.split('|').map(|s| s.trim()).collect();
-        let mut cur = root;
-        for stage in stages {
-            if stage.is_empty() {
-                continue;
-            }
-            let next = if let Some(path) = stage.strip_prefix('.') {
-                // 从当前值相对路径
-                self.get_by_dot_path(&cur, path)
-            } else {
-                // 从全局根访问（如 vars.config）
-                self.get_by_dot_path(&cur, stage)
-            };
-            cur = next.ok_or_else(|| {
-                anyhow::anyhow!("jq expression failed at: {}", stage)
-            })?;
-        }
-        Ok(cur)
+        let program = self
+            .jq_cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_or_compile(expr)?;
+        program.run(&self.build_ctx_root_json())
+    }
+
+    #[cfg(not(feature = "jq"))]
+    fn eval_jq_provider(&self, _expr: &str) -> Result<JsonValue> {
+        Err(anyhow::anyhow!(
+            "jq provider not enabled (enable the `jq` feature of flowbuilder-yaml)"
+        ))
     }
 
     /// 构建供 ctx/jq 使用的 JSON 根对象：包含 vars、env 以及 context_vars（按点号嵌套）
@@ -341,10 +850,7 @@ impl ExpressionEvaluator {
                 } else if let Some(u) = n.as_u64() {
                     serde_yaml::Value::Number(serde_yaml::Number::from(u))
                 } else if let Some(f) = n.as_f64() {
-                    // serde_yaml::Number 没有 from_f64，降级为字符串表示
-                    serde_yaml::Value::Number(serde_yaml::Number::from(
-                        f as i64,
-                    ))
+                    serde_yaml::Value::Number(serde_yaml::Number::from(f))
                 } else {
                     serde_yaml::Value::Null
                 }
@@ -475,48 +981,59 @@ impl ExpressionEvaluator {
         }
         Ok(out)
     }
+}
 
-    fn evaluate_equality(&self, expr: &str) -> Result<bool> {
-        let parts: Vec<&str> = expr.splitn(2, "==").collect();
-        if parts.len() != 2 {
-            return Ok(false);
+/// 在 `${...}` 与引号之外按 `token` 拆分表达式
+fn split_top_level<'a>(expr: &'a str, token: &str) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for index in top_level_positions(expr) {
+        if index >= start && expr[index..].starts_with(token) {
+            parts.push(expr[start..index].trim());
+            start = index + token.len();
         }
-
-        let left = parts[0].trim();
-        let right = parts[1].trim();
-        Ok(left == right)
     }
+    parts.push(expr[start..].trim());
+    parts
+}
 
-    fn evaluate_inequality(&self, expr: &str) -> Result<bool> {
-        let parts: Vec<&str> = expr.splitn(2, "!=").collect();
-        if parts.len() != 2 {
-            return Ok(false);
-        }
-
-        let left = parts[0].trim();
-        let right = parts[1].trim();
-        Ok(left != right)
-    }
+/// 找到首个顶层比较运算符，返回 (左操作数, 运算符, 右操作数)
+fn split_comparison(expr: &str) -> Option<(&str, &str, &str)> {
+    const OPERATORS: [&str; 6] = ["==", "!=", "<=", ">=", "<", ">"];
+    top_level_positions(expr).find_map(|index| {
+        let op = OPERATORS.iter().find(|op| expr[index..].starts_with(*op))?;
+        Some((expr[..index].trim(), *op, expr[index + op.len()..].trim()))
+    })
+}
 
-    fn evaluate_and(&self, expr: &str) -> Result<bool> {
-        let parts: Vec<&str> = expr.split("&&").collect();
-        for part in parts {
-            if !self.evaluate_condition(part.trim())? {
-                return Ok(false);
+/// 遍历不在 `${...}`（含嵌套大括号）和引号内的字符位置
+fn top_level_positions(expr: &str) -> impl Iterator<Item = usize> + '_ {
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    expr.char_indices().filter_map(move |(index, c)| {
+        if let Some(q) = quote {
+            if c == q {
+                quote = None;
             }
+            return None;
         }
-        Ok(true)
-    }
-
-    fn evaluate_or(&self, expr: &str) -> Result<bool> {
-        let parts: Vec<&str> = expr.split("||").collect();
-        for part in parts {
-            if self.evaluate_condition(part.trim())? {
-                return Ok(true);
+        let top_level = match c {
+            '\'' | '"' if depth == 0 => {
+                quote = Some(c);
+                false
             }
-        }
-        Ok(false)
-    }
+            '{' if depth > 0 || expr[..index].ends_with('$') => {
+                depth += 1;
+                false
+            }
+            '}' if depth > 0 => {
+                depth -= 1;
+                false
+            }
+            _ => depth == 0,
+        };
+        top_level.then_some(index)
+    })
 }
 
 impl Default for ExpressionEvaluator {
@@ -525,6 +1042,76 @@ impl Default for ExpressionEvaluator {
     }
 }
 
+/// 运行时条件求值器：在工作流求值器的基础上叠加执行上下文中的变量
+#[cfg(feature = "runtime")]
+#[derive(Clone)]
+pub struct ContextConditionEvaluator {
+    base: ExpressionEvaluator,
+}
+
+#[cfg(feature = "runtime")]
+impl ContextConditionEvaluator {
+    /// 以已配置环境变量与流程变量的求值器为基础创建
+    pub fn new(base: ExpressionEvaluator) -> Self {
+        Self { base }
+    }
+}
+
+#[cfg(feature = "runtime")]
+impl std::fmt::Debug for ContextConditionEvaluator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContextConditionEvaluator")
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "runtime")]
+impl flowbuilder_runtime::ConditionEvaluator for ContextConditionEvaluator {
+    fn evaluate(
+        &self,
+        condition: &str,
+        variables: &VarMap<String>,
+    ) -> Result<bool> {
+        self.base
+            .with_context_variables(variables)
+            .evaluate_condition(condition)
+    }
+}
+
+/// 运行时参数求值器：在工作流求值器的基础上叠加执行上下文中的变量
+#[cfg(feature = "runtime")]
+#[derive(Clone)]
+pub struct ContextParameterResolver {
+    base: ExpressionEvaluator,
+}
+
+#[cfg(feature = "runtime")]
+impl ContextParameterResolver {
+    /// 以已配置环境变量与流程变量的求值器为基础创建
+    pub fn new(base: ExpressionEvaluator) -> Self {
+        Self { base }
+    }
+}
+
+#[cfg(feature = "runtime")]
+impl std::fmt::Debug for ContextParameterResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContextParameterResolver")
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "runtime")]
+impl flowbuilder_runtime::ParameterResolver for ContextParameterResolver {
+    fn resolve(
+        &self,
+        value: &str,
+        variables: &VarMap<String>,
+    ) -> Result<serde_yaml::Value> {
+        self.base.with_context_variables(variables).evaluate(value)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -567,6 +1154,37 @@ mod tests {
         assert!(!evaluator.evaluate_condition("test != test").unwrap());
     }
 
+    #[test]
+    fn test_condition_comparison_operators() {
+        let mut evaluator = ExpressionEvaluator::new();
+        let mut flow_vars = HashMap::new();
+        flow_vars.insert("count".into(), serde_yaml::Value::from(12));
+        flow_vars.insert("stage".into(), serde_yaml::Value::from("prod"));
+        evaluator.set_flow_vars(flow_vars);
+
+        // 数值比较，而不是字符串比较（"5" > "10" 按字符串成立）
+        assert!(evaluator.evaluate_condition("5 > 3").unwrap());
+        assert!(!evaluator.evaluate_condition("5 > 10").unwrap());
+        assert!(evaluator.evaluate_condition("2.0 == 2").unwrap());
+        assert!(evaluator
+            .evaluate_condition("${ctx:vars.count} >= 10")
+            .unwrap());
+        assert!(!evaluator
+            .evaluate_condition("${ctx:vars.count} < 12")
+            .unwrap());
+
+        // Provider 解析后再比较
+        assert!(evaluator
+            .evaluate_condition("${ctx:vars.stage} == prod")
+            .unwrap());
+        assert!(evaluator
+            .evaluate_condition("${ctx:vars.stage} != dev && 5 > 3")
+            .unwrap());
+        assert!(evaluator
+            .evaluate_condition("${ctx:vars.count} < 3 || b > a")
+            .unwrap());
+    }
+
     #[test]
     fn test_unified_env() {
         let mut evaluator = ExpressionEvaluator::new();
@@ -621,6 +1239,7 @@ mod tests {
         assert_eq!(v, serde_yaml::Value::String("abc123".into()));
     }
 
+    #[cfg(feature = "jq")]
     #[test]
     fn test_unified_jq_vars_array() {
         use serde_yaml::{Mapping, Value};
@@ -655,6 +1274,34 @@ mod tests {
         assert_eq!(v2, serde_yaml::Value::String("user".into()));
     }
 
+    #[cfg(feature = "jq")]
+    #[test]
+    fn test_jq_repeated_expression_uses_cache() {
+        let mut evaluator = ExpressionEvaluator::new();
+        let items: Vec<serde_yaml::Value> =
+            (0..100).map(serde_yaml::Value::from).collect();
+        let mut flow_vars = HashMap::new();
+        flow_vars.insert("items".into(), serde_yaml::Value::Sequence(items));
+        evaluator.set_flow_vars(flow_vars);
+
+        let start = std::time::Instant::now();
+        for _ in 0..1000 {
+            let v = evaluator.evaluate("${jq:vars.items|.[42]}").unwrap();
+            assert_eq!(v, serde_yaml::Value::from(42));
+        }
+        assert!(start.elapsed() < std::time::Duration::from_secs(2));
+        assert_eq!(evaluator.jq_cache.lock().unwrap().len(), 1);
+    }
+
+    #[cfg(not(feature = "jq"))]
+    #[test]
+    fn test_jq_provider_disabled() {
+        let err = ExpressionEvaluator::new()
+            .evaluate("${jq:vars.items}")
+            .unwrap_err();
+        assert!(err.to_string().contains("jq provider not enabled"));
+    }
+
     #[test]
     fn test_string_interpolation_mixed() {
         let mut evaluator = ExpressionEvaluator::new();
@@ -676,4 +1323,237 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn test_file_provider() {
+        let base = std::env::temp_dir()
+            .join(format!("flowbuilder-file-provider-{}", std::process::id()));
+        std::fs::create_dir_all(base.join("data")).unwrap();
+        std::fs::write(base.join("data/note.txt"), "hello").unwrap();
+        std::fs::write(base.join("data/config.json"), r#"{"retries": 3}"#)
+            .unwrap();
+        std::fs::write(base.join("data/config.yaml"), "mode: fast\n").unwrap();
+
+        let mut evaluator = ExpressionEvaluator::new();
+        evaluator.set_file_base_dir(base.join("data"));
+
+        let v = evaluator.evaluate("${file:note.txt}").unwrap();
+        assert_eq!(v, serde_yaml::Value::String("hello".into()));
+
+        let v = evaluator.evaluate("${file_json:config.json}").unwrap();
+        assert_eq!(v["retries"], serde_yaml::Value::Number(3.into()));
+
+        let v = evaluator.evaluate("${file_json:config.yaml}").unwrap();
+        assert_eq!(v["mode"], serde_yaml::Value::String("fast".into()));
+
+        let v = evaluator.evaluate("Say ${file:note.txt}").unwrap();
+        assert_eq!(v, serde_yaml::Value::String("Say hello".into()));
+
+        // 禁止越出基准目录
+        std::fs::write(base.join("secret.txt"), "top secret").unwrap();
+        assert!(evaluator.evaluate("${file:../secret.txt}").is_err());
+        assert!(evaluator
+            .evaluate(&format!(
+                "${{file:{}}}",
+                base.join("secret.txt").display()
+            ))
+            .is_err());
+
+        std::fs::remove_dir_all(&base).ok();
+    }
+
+    #[test]
+    fn test_date_functions_with_fixed_clock() {
+        let mut evaluator = ExpressionEvaluator::new();
+        let now = DateTime::parse_from_rfc3339("2024-03-15T10:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        evaluator.set_fixed_now(Some(now));
+
+        let v = evaluator.evaluate("${now}").unwrap();
+        assert_eq!(
+            v,
+            serde_yaml::Value::String("2024-03-15T10:30:00+00:00".into())
+        );
+
+        let v = evaluator.evaluate("${now:%Y-%m-%d}").unwrap();
+        assert_eq!(v, serde_yaml::Value::String("2024-03-15".into()));
+
+        let v = evaluator.evaluate("${now:unix}").unwrap();
+        assert_eq!(v, serde_yaml::Value::Number(1710498600.into()));
+
+        let v = evaluator.evaluate("${date_add:now|-1d|%Y-%m-%d}").unwrap();
+        assert_eq!(v, serde_yaml::Value::String("2024-03-14".into()));
+
+        let v = evaluator
+            .evaluate("${date_add:2024-02-28|1d12h|%Y-%m-%d %H:%M}")
+            .unwrap();
+        assert_eq!(v, serde_yaml::Value::String("2024-02-29 12:00".into()));
+
+        let v = evaluator
+            .evaluate("out/${now:%Y%m%d}/since-${date_add:now|-1w|%m-%d}.csv")
+            .unwrap();
+        assert_eq!(
+            v,
+            serde_yaml::Value::String("out/20240315/since-03-08.csv".into())
+        );
+
+        assert!(evaluator.evaluate("${date_add:now|1x}").is_err());
+        assert!(evaluator.evaluate("${date_add:yesterday|1d}").is_err());
+        // 超出范围的时长
+        for expr in [
+            "${date_add:now|9999999999999w}",
+            "${date_add:now|99999999999999999999s}",
+            "${date_add:now|100000000000d100000000000d}",
+        ] {
+            let error = evaluator.evaluate(expr).unwrap_err();
+            assert!(
+                format!("{error:#}").contains("Invalid duration"),
+                "{expr}"
+            );
+        }
+
+        // 时区
+        evaluator.set_timezone(FixedOffset::east_opt(8 * 3600).unwrap());
+        let v = evaluator.evaluate("${now}").unwrap();
+        assert_eq!(
+            v,
+            serde_yaml::Value::String("2024-03-15T18:30:00+08:00".into())
+        );
+        let v = evaluator.evaluate("${now:%H:%M}").unwrap();
+        assert_eq!(v, serde_yaml::Value::String("18:30".into()));
+    }
+
+    #[test]
+    fn test_math_functions() {
+        let mut evaluator = ExpressionEvaluator::new();
+        let mut flow_vars = HashMap::new();
+        flow_vars.insert("x".into(), serde_yaml::Value::Number(8.into()));
+        flow_vars.insert("name".into(), serde_yaml::Value::String("a".into()));
+        evaluator.set_flow_vars(flow_vars);
+        evaluator.set_context_var(
+            "page.outputs.size",
+            serde_yaml::Value::String("25".into()),
+        );
+
+        let num = |v: i64| serde_yaml::Value::Number(v.into());
+        let float = |v: f64| serde_yaml::Value::Number(v.into());
+
+        assert_eq!(evaluator.evaluate("${add:vars.x|2}").unwrap(), num(10));
+        assert_eq!(evaluator.evaluate("${add:1|2|3.5}").unwrap(), float(6.5));
+        assert_eq!(
+            evaluator.evaluate("${mul:page.outputs.size|4}").unwrap(),
+            num(100)
+        );
+        assert_eq!(evaluator.evaluate("${min:vars.x|3|5}").unwrap(), num(3));
+        assert_eq!(evaluator.evaluate("${max:vars.x|-3}").unwrap(), num(8));
+        assert_eq!(
+            evaluator.evaluate("${round:2.71828|2}").unwrap(),
+            float(2.72)
+        );
+        assert_eq!(evaluator.evaluate("${round:2.5}").unwrap(), num(3));
+
+        // 插值组合
+        assert_eq!(
+            evaluator.evaluate("limit=${mul:vars.x|10}").unwrap(),
+            serde_yaml::Value::String("limit=80".into())
+        );
+
+        // 非数值操作数给出明确错误
+        let err = evaluator.evaluate("${add:vars.name|1}").unwrap_err();
+        assert!(err.to_string().contains("non-numeric operand"));
+        assert!(evaluator.evaluate("${add:vars.missing|1}").is_err());
+        assert!(evaluator.evaluate("${add:1}").is_err());
+        assert!(evaluator.evaluate("${round:1.5|x}").is_err());
+        let err = evaluator.evaluate("${round:1.5|4294967295}").unwrap_err();
+        assert!(format!("{err:#}").contains("out of range"), "{err:#}");
+
+        // 组合进条件
+        assert!(evaluator.evaluate_condition("${add:vars.x|2} > 9").unwrap());
+        assert!(!evaluator
+            .evaluate_condition("${add:vars.x|2} > 10")
+            .unwrap());
+        assert!(evaluator
+            .evaluate_condition("${mul:vars.x|2} >= ${add:vars.x|8}")
+            .unwrap());
+    }
+
+    #[cfg(feature = "tera")]
+    #[test]
+    fn test_template_provider() {
+        let base = std::env::temp_dir()
+            .join(format!("flowbuilder-template-{}", std::process::id()));
+        std::fs::create_dir_all(&base).unwrap();
+        std::fs::write(
+            base.join("notify.txt"),
+            "{% for s in vars.services %}{{ s }}{% if not loop.last %},{% endif %}{% endfor %}",
+        )
+        .unwrap();
+
+        let mut evaluator = ExpressionEvaluator::new();
+        evaluator.set_file_base_dir(&base);
+        evaluator.set_env_vars(HashMap::from([(
+            "STAGE".to_string(),
+            "prod".to_string(),
+        )]));
+        evaluator.set_flow_vars(HashMap::from([(
+            "services".to_string(),
+            serde_yaml::from_str("[api, web]").unwrap(),
+        )]));
+        evaluator.set_context_var(
+            "build.outputs.status",
+            serde_yaml::Value::String("ok".into()),
+        );
+        evaluator.set_templates(HashMap::from([
+            (
+                "summary".to_string(),
+                TemplateSource::Inline(
+                    "{{ env.STAGE }}: {% if build.outputs.status == 'ok' %}passed{% else %}failed{% endif %}"
+                        .to_string(),
+                ),
+            ),
+            (
+                "services".to_string(),
+                TemplateSource::File {
+                    file: "notify.txt".to_string(),
+                },
+            ),
+        ]));
+
+        assert_eq!(
+            evaluator.evaluate("${template:summary}").unwrap(),
+            serde_yaml::Value::String("prod: passed".into())
+        );
+        assert_eq!(
+            evaluator.evaluate("${template:services}").unwrap(),
+            serde_yaml::Value::String("api,web".into())
+        );
+        assert!(evaluator.evaluate("${template:missing}").is_err());
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[cfg(feature = "runtime")]
+    #[test]
+    fn test_context_condition_evaluator() {
+        use flowbuilder_runtime::ConditionEvaluator;
+
+        let mut base = ExpressionEvaluator::new();
+        base.set_flow_vars(HashMap::from([(
+            "threshold".to_string(),
+            serde_yaml::Value::from(10),
+        )]));
+        let evaluator = ContextConditionEvaluator::new(base);
+
+        let variables: VarMap<String> =
+            [("retries".to_string(), "3".to_string())]
+                .into_iter()
+                .collect();
+        assert!(evaluator
+            .evaluate("${ctx:retries} == 3", &variables)
+            .unwrap());
+        assert!(!evaluator
+            .evaluate("${ctx:retries} == ${ctx:vars.threshold}", &variables)
+            .unwrap());
+    }
 }
@@ -0,0 +1,93 @@
+//! # FlowBuilder YAML - jq Provider
+//!
+//! `${jq:...}` 表达式的预编译与缓存
+
+use anyhow::Result;
+use flowbuilder_core::JqProgram;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+/// 默认缓存容量
+const DEFAULT_CAPACITY: usize = 256;
+
+/// 以表达式字符串为键的 LRU 编译缓存
+#[derive(Debug)]
+pub(crate) struct JqCache {
+    capacity: usize,
+    programs: HashMap<String, Arc<JqProgram>>,
+    order: VecDeque<String>,
+}
+
+impl Default for JqCache {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+}
+
+impl JqCache {
+    /// 创建指定容量的缓存
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            programs: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// 命中则返回缓存的程序，否则编译并写入（超出容量时淘汰最久未用项）
+    pub(crate) fn get_or_compile(
+        &mut self,
+        expr: &str,
+    ) -> Result<Arc<JqProgram>> {
+        if let Some(program) = self.programs.get(expr).cloned() {
+            self.touch(expr);
+            return Ok(program);
+        }
+        let program = Arc::new(JqProgram::compile(expr)?);
+        if self.programs.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.programs.remove(&oldest);
+            }
+        }
+        self.programs.insert(expr.to_string(), program.clone());
+        self.order.push_back(expr.to_string());
+        Ok(program)
+    }
+
+    fn touch(&mut self, expr: &str) {
+        if let Some(pos) = self.order.iter().position(|e| e == expr) {
+            if let Some(key) = self.order.remove(pos) {
+                self.order.push_back(key);
+            }
+        }
+    }
+
+    /// 当前缓存的表达式数量
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.programs.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let mut cache = JqCache::with_capacity(2);
+        let first = cache.get_or_compile("vars.a").unwrap();
+        cache.get_or_compile("vars.b").unwrap();
+        // 访问 a 后淘汰 b
+        assert!(Arc::ptr_eq(
+            &first,
+            &cache.get_or_compile("vars.a").unwrap()
+        ));
+        cache.get_or_compile("vars.c").unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(Arc::ptr_eq(
+            &first,
+            &cache.get_or_compile("vars.a").unwrap()
+        ));
+    }
+}
@@ -5,23 +5,37 @@
 mod config;
 mod config_parser;
 mod executor;
+mod export;
 mod expression;
+#[cfg(feature = "jq")]
+mod jq;
+mod lint;
 mod loader;
 mod parser;
+#[cfg(feature = "runtime")]
+mod subflow;
+#[cfg(feature = "notify")]
+mod watcher;
 
 // 重新导出主要类型
 pub use config::*;
 pub use config_parser::*;
 pub use executor::*;
+pub use export::*;
 pub use expression::*;
+pub use lint::*;
 pub use loader::*;
 pub use parser::*;
+#[cfg(feature = "runtime")]
+pub use subflow::*;
+#[cfg(feature = "notify")]
+pub use watcher::*;
 
 /// 预导入模块
 pub mod prelude {
     pub use crate::{
-        DynamicFlowExecutor, WorkflowConfig, WorkflowInfo, WorkflowLoader,
-        YamlConfigParser,
+        DynamicFlowExecutor, ToWorkflowConfig, WorkflowConfig, WorkflowInfo,
+        WorkflowLoader, YamlConfigParser,
     };
     // 重命名 ExpressionEvaluator 以避免与 core 包冲突
     pub use crate::ExpressionEvaluator as YamlExpressionEvaluator;
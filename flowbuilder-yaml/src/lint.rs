@@ -0,0 +1,518 @@
+//! # FlowBuilder YAML - 配置检查
+//!
+//! 不执行工作流，一次性汇总配置校验、循环依赖、无效任务与变量引用问题
+
+use crate::config::WorkflowConfig;
+use crate::config_parser::YamlConfigParser;
+use crate::expression::ExpressionEvaluator;
+use crate::loader::WorkflowLoader;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// 问题严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// 执行必然失败或行为错误
+    Error,
+    /// 可能不符合预期
+    Warning,
+    /// 提示信息
+    Info,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        })
+    }
+}
+
+/// 单条检查问题
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintIssue {
+    pub severity: Severity,
+    pub task_id: Option<String>,
+    pub action_id: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", self.severity)?;
+        match (&self.task_id, &self.action_id) {
+            (Some(task), Some(action)) => write!(f, " {task}.{action}:")?,
+            (Some(task), None) => write!(f, " {task}:")?,
+            _ => {}
+        }
+        write!(f, " {}", self.message)
+    }
+}
+
+/// 配置检查报告
+#[derive(Debug, Clone, Default)]
+pub struct LintReport {
+    pub issues: Vec<LintIssue>,
+}
+
+impl LintReport {
+    /// 检查工作流配置（不执行）
+    pub fn from_config(config: &WorkflowConfig) -> Self {
+        let mut linter = Linter {
+            config,
+            report: LintReport::default(),
+        };
+        linter.check_schema();
+        linter.check_graph();
+        linter.check_references();
+        linter.report.issues.sort_by_key(|i| i.severity);
+        linter.report
+    }
+
+    /// 是否存在错误
+    pub fn has_errors(&self) -> bool {
+        self.issues.iter().any(|i| i.severity == Severity::Error)
+    }
+
+    /// 没有任何问题
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// 按严重程度筛选
+    pub fn with_severity(
+        &self,
+        severity: Severity,
+    ) -> impl Iterator<Item = &LintIssue> {
+        self.issues.iter().filter(move |i| i.severity == severity)
+    }
+}
+
+impl fmt::Display for LintReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "{issue}")?;
+        }
+        let count = |s| self.with_severity(s).count();
+        write!(
+            f,
+            "{} error(s), {} warning(s), {} info",
+            count(Severity::Error),
+            count(Severity::Warning),
+            count(Severity::Info)
+        )
+    }
+}
+
+struct Linter<'a> {
+    config: &'a WorkflowConfig,
+    report: LintReport,
+}
+
+impl Linter<'_> {
+    fn push(
+        &mut self,
+        severity: Severity,
+        task_id: Option<&str>,
+        action_id: Option<&str>,
+        message: String,
+    ) {
+        self.report.issues.push(LintIssue {
+            severity,
+            task_id: task_id.map(str::to_string),
+            action_id: action_id.map(str::to_string),
+            message,
+        });
+    }
+
+    /// 配置结构校验
+    fn check_schema(&mut self) {
+        if let Err(e) = WorkflowLoader::validate(self.config) {
+            self.push(Severity::Error, None, None, format!("{e:#}"));
+        }
+        let parser = YamlConfigParser::new(self.config.clone());
+        if let Err(e) = parser.validate() {
+            self.push(Severity::Error, None, None, format!("{e:#}"));
+        }
+    }
+
+    /// 依赖图检查：悬空引用、自引用、循环依赖、恒不执行的任务
+    fn check_graph(&mut self) {
+        let parser = YamlConfigParser::new(self.config.clone());
+        let Ok(tasks) = parser.expand_tasks() else {
+            return;
+        };
+        let known: HashSet<&str> = tasks
+            .iter()
+            .flat_map(|t| [t.task.id.as_str(), t.origin_id.as_str()])
+            .collect();
+
+        for expanded in &tasks {
+            let task = &expanded.task;
+            for action_wrapper in &task.actions {
+                let action = &action_wrapper.action;
+                let flow = &action.flow;
+                if let Some(next) = flow.next.as_deref() {
+                    if next == task.id || next == expanded.origin_id {
+                        self.push(
+                            Severity::Warning,
+                            Some(&task.id),
+                            Some(&action.id),
+                            "next 指向任务自身，将被忽略".to_string(),
+                        );
+                    } else if next != "null" && !known.contains(next) {
+                        self.push(
+                            Severity::Error,
+                            Some(&task.id),
+                            Some(&action.id),
+                            format!("next 指向不存在的任务: {next}"),
+                        );
+                    }
+                }
+
+                // 不含变量的条件可静态求值
+                if let Some(cond) = flow.next_if.as_deref() {
+                    if !cond.contains("${") {
+                        let evaluator = ExpressionEvaluator::new();
+                        if let Ok(false) = evaluator.evaluate_condition(cond) {
+                            self.push(
+                                Severity::Warning,
+                                Some(&task.id),
+                                Some(&action.id),
+                                format!("条件恒为假，任务不会执行: {cond}"),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Ok(nodes) = parser.parse() {
+            let graph: HashMap<&str, Vec<&str>> = nodes
+                .iter()
+                .map(|n| {
+                    (
+                        n.id.as_str(),
+                        n.dependencies.iter().map(String::as_str).collect(),
+                    )
+                })
+                .collect();
+            let order: Vec<&str> =
+                nodes.iter().map(|n| n.id.as_str()).collect();
+            for cycle in find_cycles(&order, &graph) {
+                self.push(
+                    Severity::Error,
+                    Some(cycle[0]),
+                    None,
+                    format!("检测到循环依赖: {}", cycle.join(" -> ")),
+                );
+            }
+        }
+    }
+
+    /// 表达式中的 Provider 与变量引用检查
+    fn check_references(&mut self) {
+        let workflow = &self.config.workflow;
+        let provider_re =
+            Regex::new(r"\$\{([a-zA-Z_][\w\-]*):([^}]*)\}").expect("regex");
+        let legacy_re =
+            Regex::new(r"\$\{\{\s*(env|vars)\.(\w+)\s*\}\}").expect("regex");
+        let outputs_re =
+            Regex::new(r"\$\{([\w\-]+)\.([\w\-]+)\.outputs\.").expect("regex");
+
+        let task_actions: HashMap<&str, HashSet<&str>> = workflow
+            .all_tasks()
+            .map(|t| {
+                (
+                    t.id.as_str(),
+                    t.actions.iter().map(|a| a.action.id.as_str()).collect(),
+                )
+            })
+            .collect();
+
+        let mut issues = Vec::new();
+        for task in workflow.all_tasks() {
+            for action_wrapper in &task.actions {
+                let action = &action_wrapper.action;
+                let mut strings = Vec::new();
+                for param in action.parameters.values() {
+                    collect_strings(&param.value, &mut strings);
+                }
+                for output in action.outputs.values() {
+                    collect_strings(output, &mut strings);
+                }
+                strings.extend(action.flow.next_if.clone());
+                if let Some(w) = &action.flow.while_util {
+                    strings.push(w.condition.clone());
+                }
+
+                let mut report = |severity, message| {
+                    issues.push((
+                        severity,
+                        task.id.clone(),
+                        action.id.clone(),
+                        message,
+                    ))
+                };
+                for s in &strings {
+                    for c in provider_re.captures_iter(s) {
+                        let (provider, body) = (&c[1], c[2].trim());
+                        if !ExpressionEvaluator::PROVIDERS.contains(&provider) {
+                            report(
+                                Severity::Error,
+                                format!("未知的 Provider: {provider}"),
+                            );
+                        } else if provider == "env"
+                            && !workflow.env.contains_key(body)
+                        {
+                            report(
+                                Severity::Error,
+                                format!("引用了未定义的环境变量: {body}"),
+                            );
+                        } else if provider == "ctx" {
+                            if let Some(name) = body.strip_prefix("vars.") {
+                                let name = name.split('.').next().unwrap_or("");
+                                if !workflow.vars.contains_key(name) {
+                                    report(
+                                        Severity::Error,
+                                        format!(
+                                            "引用了未定义的流程变量: {name}"
+                                        ),
+                                    );
+                                }
+                            }
+                        }
+                    }
+                    for c in legacy_re.captures_iter(s) {
+                        let defined = match &c[1] {
+                            "env" => workflow.env.contains_key(&c[2]),
+                            _ => workflow.vars.contains_key(&c[2]),
+                        };
+                        if !defined {
+                            report(
+                                Severity::Error,
+                                format!(
+                                    "引用了未定义的变量: {}.{}",
+                                    &c[1], &c[2]
+                                ),
+                            );
+                        }
+                    }
+                    for c in outputs_re.captures_iter(s) {
+                        let (t, a) = (&c[1], &c[2]);
+                        let found = task_actions
+                            .get(t)
+                            .is_some_and(|actions| actions.contains(a));
+                        if !found {
+                            report(
+                                Severity::Warning,
+                                format!("引用了不存在的动作输出: {t}.{a}"),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        for (severity, task, action, message) in issues {
+            self.push(severity, Some(&task), Some(&action), message);
+        }
+    }
+}
+
+/// 收集 YAML 值中的所有字符串
+fn collect_strings(value: &serde_yaml::Value, out: &mut Vec<String>) {
+    match value {
+        serde_yaml::Value::String(s) => out.push(s.clone()),
+        serde_yaml::Value::Sequence(seq) => {
+            seq.iter().for_each(|v| collect_strings(v, out))
+        }
+        serde_yaml::Value::Mapping(map) => {
+            map.values().for_each(|v| collect_strings(v, out))
+        }
+        _ => {}
+    }
+}
+
+/// 在依赖图中查找循环（每个循环只报告一次）
+fn find_cycles<'a>(
+    order: &[&'a str],
+    graph: &HashMap<&'a str, Vec<&'a str>>,
+) -> Vec<Vec<&'a str>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        Visiting,
+        Done,
+    }
+
+    fn visit<'a>(
+        node: &'a str,
+        graph: &HashMap<&'a str, Vec<&'a str>>,
+        marks: &mut HashMap<&'a str, Mark>,
+        stack: &mut Vec<&'a str>,
+        cycles: &mut Vec<Vec<&'a str>>,
+    ) {
+        match marks.get(node) {
+            Some(Mark::Done) => return,
+            Some(Mark::Visiting) => {
+                let start = stack.iter().position(|n| *n == node).unwrap_or(0);
+                let mut cycle = stack[start..].to_vec();
+                cycle.push(node);
+                cycles.push(cycle);
+                return;
+            }
+            None => {}
+        }
+        marks.insert(node, Mark::Visiting);
+        stack.push(node);
+        for dep in graph.get(node).into_iter().flatten() {
+            visit(dep, graph, marks, stack, cycles);
+        }
+        stack.pop();
+        marks.insert(node, Mark::Done);
+    }
+
+    let mut marks = HashMap::new();
+    let mut cycles = Vec::new();
+    for node in order {
+        visit(node, graph, &mut marks, &mut Vec::new(), &mut cycles);
+    }
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(yaml: &str) -> LintReport {
+        LintReport::from_config(&WorkflowLoader::from_yaml_str(yaml).unwrap())
+    }
+
+    #[test]
+    fn test_lint_clean_workflow() {
+        let report = lint(
+            r#"
+workflow:
+  version: "1.0"
+  env:
+    API_URL: "https://api.example.com"
+  tasks:
+    - task:
+        id: "fetch"
+        name: "Fetch"
+        description: "Fetch data"
+        actions:
+          - action:
+              id: "get"
+              name: "Get"
+              description: "HTTP GET"
+              type: "http"
+              flow:
+                next: "store"
+              outputs: {}
+              parameters:
+                url: { value: "${env:API_URL}/items" }
+    - task:
+        id: "store"
+        name: "Store"
+        description: "Store data"
+        actions:
+          - action:
+              id: "save"
+              name: "Save"
+              description: "Save result"
+              type: "builtin"
+              flow:
+                next: null
+              outputs: {}
+              parameters:
+                body: { value: "${fetch.get.outputs.body}" }
+"#,
+        );
+        assert!(report.is_clean(), "{report}");
+    }
+
+    #[test]
+    fn test_lint_reports_all_issue_kinds() {
+        let report = lint(
+            r#"
+workflow:
+  version: "1.0"
+  tasks:
+    - task:
+        id: "a"
+        name: "A"
+        description: "Part of a cycle"
+        actions:
+          - action:
+              id: "a1"
+              name: "A1"
+              description: "A1"
+              type: "builtin"
+              flow:
+                next: "b"
+              outputs: {}
+              parameters:
+                token: { value: "${env:TOKEN}" }
+                when: { value: "${clock:now}" }
+    - task:
+        id: "b"
+        name: "B"
+        description: "Part of a cycle"
+        actions:
+          - action:
+              id: "b1"
+              name: "B1"
+              description: "B1"
+              type: "builtin"
+              flow:
+                next: "a"
+              outputs: {}
+              parameters:
+                prev: { value: "${ghost.run.outputs.value}" }
+    - task:
+        id: "c"
+        name: "C"
+        description: "Never runs"
+        actions:
+          - action:
+              id: "c1"
+              name: "C1"
+              description: "C1"
+              type: "builtin"
+              flow:
+                next: "missing"
+                next_if: "false"
+              outputs: {}
+              parameters: {}
+"#,
+        );
+
+        assert!(report.has_errors());
+        let messages: Vec<_> =
+            report.issues.iter().map(|i| i.to_string()).collect();
+        let has = |needle: &str| messages.iter().any(|m| m.contains(needle));
+        assert!(has("循环依赖"), "{report}");
+        assert!(
+            has("[error] a.a1: 引用了未定义的环境变量: TOKEN"),
+            "{report}"
+        );
+        assert!(has("未知的 Provider: clock"), "{report}");
+        assert!(has("[warning] b.b1: 引用了不存在的动作输出: ghost.run"));
+        assert!(has("[error] c.c1: next 指向不存在的任务: missing"));
+        assert!(has("[warning] c.c1: 条件恒为假"), "{report}");
+
+        // 错误排在警告之前
+        let first_warning = report
+            .issues
+            .iter()
+            .position(|i| i.severity == Severity::Warning)
+            .unwrap();
+        assert!(report.issues[..first_warning]
+            .iter()
+            .all(|i| i.severity == Severity::Error));
+    }
+}
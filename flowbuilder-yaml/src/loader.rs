@@ -1,5 +1,6 @@
 use crate::config::WorkflowConfig;
 use anyhow::{Context, Result};
+use serde::Deserialize;
 use std::fs;
 use std::path::Path;
 
@@ -21,6 +22,62 @@ impl WorkflowLoader {
             .with_context(|| "Failed to parse YAML content")
     }
 
+    /// 从 YAML 字符串加载工作流配置，并应用指定的环境配置
+    ///
+    /// 环境配置按深度合并覆盖基础配置：映射逐键合并，其余值（包括列表）
+    /// 整体替换；环境名不存在时返回错误
+    pub fn from_yaml_str_with_profile(
+        content: &str,
+        profile: &str,
+    ) -> Result<WorkflowConfig> {
+        let mut base: serde_yaml::Value = serde_yaml::from_str(content)
+            .with_context(|| "Failed to parse YAML content")?;
+        let profiles = base
+            .as_mapping_mut()
+            .and_then(|m| m.remove("profiles"))
+            .unwrap_or(serde_yaml::Value::Null);
+
+        let overrides = profiles.get(profile).cloned().ok_or_else(|| {
+            let mut available: Vec<_> = profiles
+                .as_mapping()
+                .map(|m| m.keys().filter_map(|k| k.as_str()).collect())
+                .unwrap_or_default();
+            available.sort_unstable();
+            anyhow::anyhow!(
+                "Unknown profile: {} (available: {})",
+                profile,
+                available.join(", ")
+            )
+        })?;
+
+        deep_merge(&mut base, overrides);
+        serde_yaml::from_value(base).with_context(|| {
+            format!("Failed to apply profile '{profile}' to workflow config")
+        })
+    }
+
+    /// 从多文档 YAML 字符串（以 `---` 分隔）加载多个工作流配置
+    ///
+    /// 空文档会被跳过；解析失败时错误中包含文档下标（从 0 开始）
+    pub fn from_yaml_str_multi(content: &str) -> Result<Vec<WorkflowConfig>> {
+        let mut configs = Vec::new();
+        for (index, document) in
+            serde_yaml::Deserializer::from_str(content).enumerate()
+        {
+            let value = serde_yaml::Value::deserialize(document).with_context(
+                || format!("Failed to parse YAML document at index {index}"),
+            )?;
+            if value.is_null() {
+                continue;
+            }
+            let config = serde_yaml::from_value(value).with_context(|| {
+                format!("Invalid workflow in YAML document at index {index}")
+            })?;
+            configs.push(config);
+        }
+        Ok(configs)
+    }
+
     /// 从 JSON 文件加载工作流配置
     pub fn from_json_file<P: AsRef<Path>>(path: P) -> Result<WorkflowConfig> {
         let content = fs::read_to_string(&path).with_context(|| {
@@ -35,6 +92,47 @@ impl WorkflowLoader {
             .with_context(|| "Failed to parse JSON content")
     }
 
+    /// 从 TOML 文件加载工作流配置
+    #[cfg(feature = "toml")]
+    pub fn from_toml_file<P: AsRef<Path>>(path: P) -> Result<WorkflowConfig> {
+        let content = fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read TOML file: {:?}", path.as_ref())
+        })?;
+        Self::from_toml_str(&content)
+    }
+
+    /// 从 TOML 字符串加载工作流配置
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(content: &str) -> Result<WorkflowConfig> {
+        toml::from_str(content).with_context(|| "Failed to parse TOML content")
+    }
+
+    /// 按扩展名（`.yaml`/`.yml`/`.json`/`.toml`）选择格式加载工作流配置
+    ///
+    /// 各格式反序列化到同一个 [`WorkflowConfig`]，`.toml` 需启用 `toml` 特性
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<WorkflowConfig> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("yaml" | "yml") => Self::from_yaml_file(path),
+            Some("json") => Self::from_json_file(path),
+            #[cfg(feature = "toml")]
+            Some("toml") => Self::from_toml_file(path),
+            #[cfg(not(feature = "toml"))]
+            Some("toml") => anyhow::bail!(
+                "Loading {:?} requires the `toml` feature",
+                path
+            ),
+            _ => anyhow::bail!(
+                "Unsupported workflow file extension: {:?} (expected .yaml, .yml, .json or .toml)",
+                path
+            ),
+        }
+    }
+
     /// 保存工作流配置到 YAML 文件
     pub fn save_to_yaml<P: AsRef<Path>>(
         config: &WorkflowConfig,
@@ -75,7 +173,7 @@ impl WorkflowLoader {
         }
 
         // 检查任务定义
-        if workflow.tasks.is_empty() {
+        if workflow.all_tasks().next().is_none() {
             return Err(anyhow::anyhow!(
                 "Workflow must contain at least one task"
             ));
@@ -83,21 +181,18 @@ impl WorkflowLoader {
 
         // 检查任务 ID 的唯一性
         let mut task_ids = std::collections::HashSet::new();
-        for task in &workflow.tasks {
-            if !task_ids.insert(&task.task.id) {
-                return Err(anyhow::anyhow!(
-                    "Duplicate task ID: {}",
-                    task.task.id
-                ));
+        for task in workflow.all_tasks() {
+            if !task_ids.insert(&task.id) {
+                return Err(anyhow::anyhow!("Duplicate task ID: {}", task.id));
             }
         }
 
         // 检查动作 ID 的唯一性
         let mut action_ids = std::collections::HashSet::new();
-        for task in &workflow.tasks {
-            for action in &task.task.actions {
+        for task in workflow.all_tasks() {
+            for action in &task.actions {
                 let full_action_id =
-                    format!("{}.{}", task.task.id, action.action.id);
+                    format!("{}.{}", task.id, action.action.id);
                 if !action_ids.insert(full_action_id.clone()) {
                     return Err(anyhow::anyhow!(
                         "Duplicate action ID: {}",
@@ -122,10 +217,26 @@ impl WorkflowLoader {
     /// 快速执行工作流文件（使用 runtime 功能）
     #[cfg(feature = "runtime")]
     pub async fn execute_workflow_file<P: AsRef<Path>>(path: P) -> Result<()> {
+        let path = path.as_ref();
         let config = Self::from_yaml_file(path)?;
         Self::validate(&config)?;
 
-        let mut executor = Self::create_runtime_executor(config)?;
+        // 子工作流引用相对于该文件所在目录解析
+        let base_dir = match path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p,
+            _ => Path::new("."),
+        };
+        let executor_config = flowbuilder_runtime::ExecutorConfig {
+            workflow_runner: Some(std::sync::Arc::new(
+                crate::subflow::FileWorkflowRunner::new(base_dir),
+            )),
+            ..Default::default()
+        };
+        let mut executor =
+            crate::executor::DynamicFlowExecutor::with_executor_config(
+                config,
+                executor_config,
+            )?;
         let context = std::sync::Arc::new(tokio::sync::Mutex::new(
             flowbuilder_context::FlowContext::default(),
         ));
@@ -158,6 +269,26 @@ impl WorkflowLoader {
     }
 }
 
+/// 将 `overlay` 深度合并到 `base`
+fn deep_merge(base: &mut serde_yaml::Value, overlay: serde_yaml::Value) {
+    match (base, overlay) {
+        (
+            serde_yaml::Value::Mapping(base),
+            serde_yaml::Value::Mapping(overlay),
+        ) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => deep_merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -227,4 +358,238 @@ workflow:
         let config = WorkflowLoader::from_yaml_str(yaml_content).unwrap();
         assert!(WorkflowLoader::validate(&config).is_err());
     }
+
+    #[test]
+    fn test_load_multi_document_yaml() {
+        let yaml_content = r#"
+workflow:
+  version: "1.0"
+  tasks:
+    - task:
+        id: "extract"
+        name: "Extract"
+        description: "First pipeline"
+        actions: []
+---
+workflow:
+  version: "2.0"
+  tasks:
+    - task:
+        id: "report"
+        name: "Report"
+        description: "Second pipeline"
+        actions: []
+---
+"#;
+
+        let configs =
+            WorkflowLoader::from_yaml_str_multi(yaml_content).unwrap();
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[0].workflow.tasks[0].task.id, "extract");
+        assert_eq!(configs[1].workflow.version, "2.0");
+
+        // 第二个文档缺少 tasks，错误应指明文档下标
+        let broken = "workflow:\n  version: \"1.0\"\n  tasks: []\n---\nworkflow:\n  version: \"1.0\"\n";
+        let err = WorkflowLoader::from_yaml_str_multi(broken).unwrap_err();
+        assert!(err.to_string().contains("index 1"), "{err}");
+        assert!(format!("{err:#}").contains("tasks"), "{err:#}");
+
+        // 只有任务组时可省略 tasks
+        let grouped = "workflow:\n  version: \"1.0\"\n  tasks: []\n---\nworkflow:\n  version: \"1.0\"\n  groups:\n    - group: { id: g, tasks: [] }\n";
+        let configs = WorkflowLoader::from_yaml_str_multi(grouped).unwrap();
+        assert_eq!(configs[1].workflow.groups[0].group.id, "g");
+        assert!(configs[1].workflow.tasks.is_empty());
+    }
+
+    #[test]
+    fn test_load_with_profile() {
+        let yaml_content = r#"
+workflow:
+  version: "1.0"
+  env:
+    API_URL: "http://localhost:8080"
+    LOG_LEVEL: "debug"
+  vars:
+    name: "Deploy"
+    limits: { cpu: 1, memory: 512 }
+  tasks:
+    - task:
+        id: "deploy"
+        name: "Deploy"
+        description: "Deploy service"
+        actions: []
+profiles:
+  prod:
+    workflow:
+      env:
+        API_URL: "https://api.example.com"
+      vars:
+        limits: { cpu: 4 }
+"#;
+
+        let config =
+            WorkflowLoader::from_yaml_str_with_profile(yaml_content, "prod")
+                .unwrap();
+        let env = &config.workflow.env;
+        assert_eq!(env["API_URL"], "https://api.example.com");
+        // 未覆盖的键保持基础配置
+        assert_eq!(env["LOG_LEVEL"], "debug");
+        let limits = &config.workflow.vars["limits"];
+        assert_eq!(limits["cpu"].as_u64(), Some(4));
+        assert_eq!(limits["memory"].as_u64(), Some(512));
+        assert!(config.profiles.is_empty());
+
+        let err =
+            WorkflowLoader::from_yaml_str_with_profile(yaml_content, "staging")
+                .unwrap_err();
+        assert!(err.to_string().contains("staging"));
+        assert!(err.to_string().contains("prod"));
+    }
+
+    const FORMAT_YAML: &str = r#"
+workflow:
+  version: "1.0"
+  vars:
+    retries: 3
+  tasks:
+    - task:
+        id: "fetch"
+        name: "Fetch"
+        description: "Fetch data"
+        actions:
+          - action:
+              id: "get"
+              name: "Get"
+              description: "HTTP GET"
+              type: "http"
+              flow:
+                next: "store"
+                retry: { max_retries: 2, delay: 100 }
+              parameters:
+                url: { value: "https://example.com", required: true }
+                headers: { value: { accept: "json", limit: 10 } }
+    - task:
+        id: "store"
+        name: "Store"
+        description: "Store data"
+        actions:
+          - action:
+              id: "save"
+              name: "Save"
+              description: "Save rows"
+              type: "builtin"
+"#;
+
+    const FORMAT_JSON: &str = r#"{
+  "workflow": {
+    "version": "1.0",
+    "vars": { "retries": 3 },
+    "tasks": [
+      { "task": {
+        "id": "fetch", "name": "Fetch", "description": "Fetch data",
+        "actions": [ { "action": {
+          "id": "get", "name": "Get", "description": "HTTP GET",
+          "type": "http",
+          "flow": {
+            "next": "store",
+            "retry": { "max_retries": 2, "delay": 100 }
+          },
+          "parameters": {
+            "url": { "value": "https://example.com", "required": true },
+            "headers": { "value": { "accept": "json", "limit": 10 } }
+          }
+        } } ]
+      } },
+      { "task": {
+        "id": "store", "name": "Store", "description": "Store data",
+        "actions": [ { "action": {
+          "id": "save", "name": "Save", "description": "Save rows",
+          "type": "builtin"
+        } } ]
+      } }
+    ]
+  }
+}"#;
+
+    #[cfg(feature = "toml")]
+    const FORMAT_TOML: &str = r#"
+[workflow]
+version = "1.0"
+vars = { retries = 3 }
+
+[[workflow.tasks]]
+[workflow.tasks.task]
+id = "fetch"
+name = "Fetch"
+description = "Fetch data"
+
+[[workflow.tasks.task.actions]]
+[workflow.tasks.task.actions.action]
+id = "get"
+name = "Get"
+description = "HTTP GET"
+type = "http"
+flow = { next = "store", retry = { max_retries = 2, delay = 100 } }
+
+[workflow.tasks.task.actions.action.parameters]
+url = { value = "https://example.com", required = true }
+headers = { value = { accept = "json", limit = 10 } }
+
+[[workflow.tasks]]
+[workflow.tasks.task]
+id = "store"
+name = "Store"
+description = "Store data"
+
+[[workflow.tasks.task.actions]]
+action = { id = "save", name = "Save", description = "Save rows", type = "builtin" }
+"#;
+
+    #[test]
+    fn test_load_same_workflow_from_all_formats() {
+        use crate::config_parser::YamlConfigParser;
+
+        let dir = std::env::temp_dir()
+            .join(format!("flowbuilder-loader-formats-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let sources = [
+            ("workflow.yml", FORMAT_YAML),
+            ("workflow.json", FORMAT_JSON),
+            #[cfg(feature = "toml")]
+            ("workflow.toml", FORMAT_TOML),
+        ];
+
+        let configs: Vec<_> = sources
+            .iter()
+            .map(|(file, content)| {
+                let path = dir.join(file);
+                fs::write(&path, content).unwrap();
+                WorkflowLoader::from_path(&path).unwrap()
+            })
+            .collect();
+        fs::remove_dir_all(&dir).ok();
+
+        let expected = serde_json::to_value(&configs[0]).unwrap();
+        let expected_nodes =
+            YamlConfigParser::new(configs[0].clone()).parse().unwrap();
+        for config in &configs[1..] {
+            assert_eq!(serde_json::to_value(config).unwrap(), expected);
+            let nodes = YamlConfigParser::new(config.clone()).parse().unwrap();
+            assert_eq!(nodes.len(), expected_nodes.len());
+            for (node, expected) in nodes.iter().zip(&expected_nodes) {
+                assert_eq!(node.id, expected.id);
+                assert_eq!(node.dependencies, expected.dependencies);
+                assert_eq!(
+                    node.action_spec.parameters,
+                    expected.action_spec.parameters
+                );
+            }
+        }
+        let headers = &expected_nodes[0].action_spec.parameters["headers"];
+        assert_eq!(headers["limit"].as_u64(), Some(10));
+
+        let err =
+            WorkflowLoader::from_path(dir.join("workflow.ini")).unwrap_err();
+        assert!(err.to_string().contains("Unsupported"));
+    }
 }
@@ -34,6 +34,7 @@ impl YamlFlowBuilder {
         // 设置环境变量和流程变量
         evaluator.set_env_vars(config.workflow.env.clone());
         evaluator.set_flow_vars(config.workflow.vars.clone());
+        evaluator.set_templates(config.workflow.templates.clone());
 
         Ok(Self { config, evaluator })
     }
@@ -43,8 +44,8 @@ impl YamlFlowBuilder {
         let mut flow_builder = FlowBuilder::new();
 
         // 按顺序添加任务中的动作
-        for task in &self.config.workflow.tasks {
-            for action in &task.task.actions {
+        for task in self.config.workflow.all_tasks() {
+            for action in &task.actions {
                 let step_closure =
                     self.create_step_closure_from_action(&action.action)?;
                 flow_builder = flow_builder.step(step_closure);
@@ -91,7 +92,7 @@ impl YamlFlowBuilder {
                             debug!(param = %param_name, value = ?evaluated_value);
                         }
                     }
-                    ActionType::Http => {
+                    ActionType::Http | ActionType::HttpPaginate => {
                         info!(action_id = %action.id, "执行HTTP动作");
                         // 模拟HTTP请求
                         tokio::time::sleep(std::time::Duration::from_millis(
@@ -107,6 +108,30 @@ impl YamlFlowBuilder {
                         ))
                         .await;
                     }
+                    ActionType::Workflow => {
+                        return Err(anyhow::anyhow!(
+                            "子工作流动作 {} 需通过 DynamicFlowExecutor 执行",
+                            action.id
+                        ));
+                    }
+                    ActionType::Sql | ActionType::SqlStream => {
+                        return Err(anyhow::anyhow!(
+                            "SQL 动作 {} 需通过 DynamicFlowExecutor 执行",
+                            action.id
+                        ));
+                    }
+                    ActionType::Healthcheck => {
+                        return Err(anyhow::anyhow!(
+                            "健康检查动作 {} 需通过 DynamicFlowExecutor 执行",
+                            action.id
+                        ));
+                    }
+                    ActionType::WaitForPort => {
+                        return Err(anyhow::anyhow!(
+                            "端口等待动作 {} 需通过 DynamicFlowExecutor 执行",
+                            action.id
+                        ));
+                    }
                 }
                 Ok(())
             });
@@ -123,8 +148,26 @@ impl YamlFlowBuilder {
         match action.action_type {
             ActionType::Builtin => self.create_builtin_step(action),
             ActionType::Cmd => self.create_cmd_step(action),
-            ActionType::Http => self.create_http_step(action),
+            ActionType::Http | ActionType::HttpPaginate => {
+                self.create_http_step(action)
+            }
             ActionType::Wasm => self.create_wasm_step(action),
+            ActionType::Workflow => Err(anyhow::anyhow!(
+                "子工作流动作 {} 需通过 DynamicFlowExecutor 执行",
+                action.id
+            )),
+            ActionType::Sql | ActionType::SqlStream => Err(anyhow::anyhow!(
+                "SQL 动作 {} 需通过 DynamicFlowExecutor 执行",
+                action.id
+            )),
+            ActionType::Healthcheck => Err(anyhow::anyhow!(
+                "健康检查动作 {} 需通过 DynamicFlowExecutor 执行",
+                action.id
+            )),
+            ActionType::WaitForPort => Err(anyhow::anyhow!(
+                "端口等待动作 {} 需通过 DynamicFlowExecutor 执行",
+                action.id
+            )),
         }
     }
 
@@ -369,4 +412,41 @@ workflow:
         let result = flow.execute().await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_probe_actions_require_dynamic_executor() {
+        for (action_type, parameters) in [
+            ("healthcheck", "url: { value: \"http://127.0.0.1:1\" }"),
+            ("wait_for_port", "port: { value: 1 }"),
+        ] {
+            let yaml_content = format!(
+                r#"
+workflow:
+  version: "1.0"
+  tasks:
+    - task:
+        id: "task1"
+        name: "Probe"
+        description: "Readiness gate"
+        actions:
+          - action:
+              id: "probe"
+              name: "Probe"
+              description: "Probe"
+              type: "{action_type}"
+              outputs: {{}}
+              parameters: {{ {parameters} }}
+"#
+            );
+
+            let config = WorkflowLoader::from_yaml_str(&yaml_content).unwrap();
+            let flow = YamlFlowBuilder::new(config).unwrap().build().unwrap();
+            // 探测动作不能以模拟步骤“通过”
+            let err = flow.build().execute().await.unwrap_err();
+            assert!(
+                format!("{err:#}").contains("需通过 DynamicFlowExecutor"),
+                "{action_type}: {err:#}"
+            );
+        }
+    }
 }
@@ -0,0 +1,348 @@
+//! # FlowBuilder YAML - 子工作流
+//!
+//! 按文件引用加载子工作流，并以嵌套执行计划的方式运行
+
+use crate::executor::DynamicFlowExecutor;
+use crate::loader::WorkflowLoader;
+use anyhow::{Context, Result};
+use flowbuilder_context::SharedContext;
+use flowbuilder_runtime::{ExecutorConfig, WorkflowFuture, WorkflowRunner};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// 子工作流最大嵌套深度（防止循环引用）
+pub const MAX_WORKFLOW_DEPTH: usize = 16;
+
+/// 基于文件的子工作流运行器
+///
+/// `ref` 为相对路径时相对于引用方所在目录解析；按扩展名选择格式加载
+/// （见 [`WorkflowLoader::from_path`]）。子工作流的执行器沿用
+/// [`Self::with_executor_config`] 设置的配置（制品存储、自定义动作等）
+#[derive(Debug, Clone)]
+pub struct FileWorkflowRunner {
+    /// 解析相对引用的基准目录
+    base_dir: PathBuf,
+    /// 当前嵌套深度
+    depth: usize,
+    /// 子工作流执行器的配置（`workflow_runner` 由运行器自行设置）
+    executor_config: Arc<ExecutorConfig>,
+}
+
+impl FileWorkflowRunner {
+    /// 创建运行器
+    pub fn new<P: AsRef<Path>>(base_dir: P) -> Self {
+        Self {
+            base_dir: base_dir.as_ref().to_path_buf(),
+            depth: 0,
+            executor_config: Arc::new(ExecutorConfig::default()),
+        }
+    }
+
+    /// 子工作流沿用父工作流的执行器配置
+    ///
+    /// 应传入父执行器使用的配置，使制品句柄、自定义动作、输出上限等在
+    /// 子工作流中保持一致；其中的 `workflow_runner` 被忽略
+    pub fn with_executor_config(mut self, config: ExecutorConfig) -> Self {
+        self.executor_config = Arc::new(ExecutorConfig {
+            workflow_runner: None,
+            ..config
+        });
+        self
+    }
+
+    /// 解析相对引用的基准目录
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    /// 解析子工作流路径
+    pub fn resolve(&self, reference: &str) -> PathBuf {
+        self.base_dir.join(reference)
+    }
+
+    async fn run(&self, reference: &str, context: SharedContext) -> Result<()> {
+        if self.depth >= MAX_WORKFLOW_DEPTH {
+            return Err(anyhow::anyhow!(
+                "子工作流嵌套超过 {} 层，可能存在循环引用: {}",
+                MAX_WORKFLOW_DEPTH,
+                reference
+            ));
+        }
+
+        let path = self.resolve(reference);
+        let config = WorkflowLoader::from_path(&path)?;
+        WorkflowLoader::validate(&config)
+            .with_context(|| format!("子工作流配置无效: {path:?}"))?;
+
+        // 子工作流中的引用相对于其自身所在目录
+        let child_runner = Self {
+            base_dir: path
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| self.base_dir.clone()),
+            depth: self.depth + 1,
+            executor_config: self.executor_config.clone(),
+        };
+        let executor_config = ExecutorConfig {
+            workflow_runner: Some(Arc::new(child_runner)),
+            ..(*self.executor_config).clone()
+        };
+        let mut executor =
+            DynamicFlowExecutor::with_executor_config(config, executor_config)?;
+        let result = executor.execute(context).await?;
+
+        // 节点失败不会中断计划，这里汇总为子工作流的失败
+        let failed: Vec<_> = result
+            .phase_results
+            .iter()
+            .flat_map(|p| &p.node_results)
+            .filter(|n| !n.success)
+            .map(|n| {
+                format!(
+                    "{}: {}",
+                    n.node_id,
+                    n.error_message.as_deref().unwrap_or("未知错误")
+                )
+            })
+            .collect();
+        if !failed.is_empty() {
+            return Err(anyhow::anyhow!(
+                "子工作流节点执行失败: {}",
+                failed.join("; ")
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for FileWorkflowRunner {
+    fn default() -> Self {
+        Self::new(".")
+    }
+}
+
+impl WorkflowRunner for FileWorkflowRunner {
+    fn run_workflow<'a>(
+        &'a self,
+        reference: &'a str,
+        context: SharedContext,
+    ) -> WorkflowFuture<'a> {
+        Box::pin(self.run(reference, context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flowbuilder_context::FlowContext;
+    use tokio::sync::Mutex;
+
+    const CHILD_YAML: &str = r#"
+workflow:
+  version: "1.0"
+  tasks:
+    - task:
+        id: "greet"
+        name: "Greet"
+        description: "Child task"
+        actions:
+          - action:
+              id: "greet_action"
+              name: "Greet"
+              description: "Set greeting"
+              type: "builtin"
+              flow:
+                next: null
+              outputs:
+                greeting: "hello"
+              parameters:
+                operation: { value: "log", required: true }
+                message: { value: "greeting", required: false }
+"#;
+
+    const PARENT_YAML: &str = r#"
+workflow:
+  version: "1.0"
+  tasks:
+    - task:
+        id: "call_child"
+        name: "Call child"
+        description: "Run nested workflow"
+        actions:
+          - action:
+              id: "call_child_action"
+              name: "Call child"
+              description: "Nested pipeline"
+              type: "workflow"
+              flow:
+                next: null
+              outputs: {}
+              parameters:
+                ref: { value: "child.yaml", required: true }
+                inputs: { value: { user: "name" }, required: false }
+                outputs: { value: { child_greeting: "greeting" }, required: false }
+"#;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "flowbuilder-subflow-{}-{}",
+            name,
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_workflow_action_runs_nested_plan() {
+        let dir = temp_dir("nested");
+        std::fs::write(dir.join("child.yaml"), CHILD_YAML).unwrap();
+
+        let config = WorkflowLoader::from_yaml_str(PARENT_YAML).unwrap();
+        let executor_config = ExecutorConfig {
+            workflow_runner: Some(Arc::new(FileWorkflowRunner::new(&dir))),
+            ..ExecutorConfig::default()
+        };
+        let mut executor =
+            DynamicFlowExecutor::with_executor_config(config, executor_config)
+                .unwrap();
+
+        let context = Arc::new(Mutex::new(FlowContext::default()));
+        context
+            .lock()
+            .await
+            .set_variable("name".into(), "alice".into());
+        let result = executor.execute(context.clone()).await.unwrap();
+        assert!(result.phase_results[0].node_results[0].success);
+
+        let guard = context.lock().await;
+        assert_eq!(
+            guard.get_variable("child_greeting").map(String::as_str),
+            Some("String(\"hello\")")
+        );
+        assert!(guard.get_variable("greeting").is_none());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    /// 替换 builtin 动作：写入变量 `greeting = "handled"`
+    #[derive(Debug)]
+    struct HandledBuiltin;
+
+    impl flowbuilder_runtime::ActionHandler for HandledBuiltin {
+        fn handle<'a>(
+            &'a self,
+            _spec: &'a flowbuilder_core::ActionSpec,
+            context: SharedContext,
+        ) -> flowbuilder_runtime::ActionFuture<'a> {
+            Box::pin(async move {
+                context
+                    .lock()
+                    .await
+                    .set_variable("greeting".into(), "handled".into());
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_nested_workflow_inherits_executor_config() {
+        let dir = temp_dir("inherit");
+        std::fs::write(dir.join("child.yaml"), CHILD_YAML).unwrap();
+
+        let mut parent_config = ExecutorConfig::default();
+        parent_config
+            .action_handlers
+            .register("builtin", Arc::new(HandledBuiltin));
+        let executor_config = ExecutorConfig {
+            workflow_runner: Some(Arc::new(
+                FileWorkflowRunner::new(&dir)
+                    .with_executor_config(parent_config.clone()),
+            )),
+            ..parent_config
+        };
+        let config = WorkflowLoader::from_yaml_str(PARENT_YAML).unwrap();
+        let mut executor =
+            DynamicFlowExecutor::with_executor_config(config, executor_config)
+                .unwrap();
+
+        let context = Arc::new(Mutex::new(FlowContext::default()));
+        context
+            .lock()
+            .await
+            .set_variable("name".into(), "alice".into());
+        let result = executor.execute(context.clone()).await.unwrap();
+        let node = &result.phase_results[0].node_results[0];
+        assert!(node.success, "{:?}", node.error_message);
+
+        // 子工作流的 builtin 动作由父配置注册的处理器执行
+        let guard = context.lock().await;
+        assert_eq!(
+            guard.get_variable("child_greeting").map(String::as_str),
+            Some("handled")
+        );
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(feature = "toml")]
+    #[tokio::test]
+    async fn test_toml_sub_workflow() {
+        let dir = temp_dir("toml");
+        let child = WorkflowLoader::from_yaml_str(CHILD_YAML).unwrap();
+        std::fs::write(
+            dir.join("child.toml"),
+            toml::to_string(&child).unwrap(),
+        )
+        .unwrap();
+
+        let config = WorkflowLoader::from_yaml_str(
+            &PARENT_YAML.replace("child.yaml", "child.toml"),
+        )
+        .unwrap();
+        let executor_config = ExecutorConfig {
+            workflow_runner: Some(Arc::new(FileWorkflowRunner::new(&dir))),
+            ..ExecutorConfig::default()
+        };
+        let mut executor =
+            DynamicFlowExecutor::with_executor_config(config, executor_config)
+                .unwrap();
+
+        let context = Arc::new(Mutex::new(FlowContext::default()));
+        context
+            .lock()
+            .await
+            .set_variable("name".into(), "alice".into());
+        let result = executor.execute(context.clone()).await.unwrap();
+        let node = &result.phase_results[0].node_results[0];
+        assert!(node.success, "{:?}", node.error_message);
+        assert!(context
+            .lock()
+            .await
+            .get_variable("child_greeting")
+            .is_some());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_recursive_workflow_is_rejected() {
+        let dir = temp_dir("recursive");
+        std::fs::write(
+            dir.join("loop.yaml"),
+            PARENT_YAML.replace("child.yaml", "loop.yaml").replace(
+                "inputs: { value: { user: \"name\" }, required: false }",
+                "",
+            ),
+        )
+        .unwrap();
+
+        let runner = FileWorkflowRunner::new(&dir);
+        let context = Arc::new(Mutex::new(FlowContext::default()));
+        let err = runner.run_workflow("loop.yaml", context).await.unwrap_err();
+        assert!(format!("{err:#}").contains("嵌套超过"), "{err:#}");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,263 @@
+//! # FlowBuilder YAML - 配置热加载
+//!
+//! 监听工作流配置文件，变更后重新解析并校验，再通过回调通知调用方
+
+use crate::config::WorkflowConfig;
+use crate::loader::WorkflowLoader;
+use anyhow::{Context, Result};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// 工作流配置监听器
+///
+/// 监听器被 drop 时停止监听
+pub struct WorkflowWatcher {
+    /// 被监听的配置文件
+    path: PathBuf,
+    /// 底层文件系统监听器
+    _watcher: RecommendedWatcher,
+}
+
+impl WorkflowWatcher {
+    /// 监听配置文件变化
+    ///
+    /// 每次变更都会重新解析并校验配置：校验通过时调用 `on_change`，
+    /// 解析或校验失败时调用 `on_error` 并跳过本次变更（同一份无效内容只报告一次，
+    /// 内容再次变化后才会重新报告）
+    pub fn watch<P, F, E>(path: P, on_change: F, on_error: E) -> Result<Self>
+    where
+        P: AsRef<Path>,
+        F: Fn(WorkflowConfig) + Send + 'static,
+        E: Fn(anyhow::Error) + Send + 'static,
+    {
+        let path = path.as_ref().to_path_buf();
+        let file_name =
+            path.file_name().map(|n| n.to_os_string()).ok_or_else(|| {
+                anyhow::anyhow!("Invalid config path: {:?}", path)
+            })?;
+        // 监听所在目录：编辑器常以"写临时文件再重命名"的方式保存
+        let dir = match path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        let target = path.clone();
+        let mut last_content: Option<String> = None;
+        let mut last_failed: Option<u64> = None;
+        let mut watcher = notify::recommended_watcher(
+            move |res: notify::Result<notify::Event>| {
+                let event = match res {
+                    Ok(event) => event,
+                    Err(e) => {
+                        on_error(anyhow::anyhow!("Watch error: {}", e));
+                        return;
+                    }
+                };
+
+                if !matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_)
+                ) {
+                    return;
+                }
+                if !event
+                    .paths
+                    .iter()
+                    .any(|p| p.file_name() == Some(file_name.as_os_str()))
+                {
+                    return;
+                }
+
+                // 同一次保存可能触发多个事件，内容未变化时不重复通知
+                let content = match std::fs::read_to_string(&target) {
+                    Ok(content) => content,
+                    Err(e) => {
+                        on_error(anyhow::anyhow!(
+                            "Failed to read config file {:?}: {}",
+                            target,
+                            e
+                        ));
+                        return;
+                    }
+                };
+                if last_content.as_deref() == Some(content.as_str()) {
+                    return;
+                }
+
+                let mut hasher = DefaultHasher::new();
+                content.hash(&mut hasher);
+                let hash = hasher.finish();
+                if last_failed == Some(hash) {
+                    return;
+                }
+
+                match Self::parse_and_validate(&target, &content) {
+                    Ok(config) => {
+                        tracing::info!(path = ?target, "工作流配置已重新加载");
+                        last_content = Some(content);
+                        last_failed = None;
+                        on_change(config);
+                    }
+                    Err(e) => {
+                        tracing::warn!(path = ?target, error = %e, "工作流配置无效，已跳过");
+                        last_failed = Some(hash);
+                        on_error(e);
+                    }
+                }
+            },
+        )
+        .context("Failed to create file watcher")?;
+
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch directory: {dir:?}"))?;
+
+        Ok(Self {
+            path,
+            _watcher: watcher,
+        })
+    }
+
+    /// 读取、解析并校验配置文件（`.json` 按 JSON 解析，其余按 YAML）
+    pub fn reload<P: AsRef<Path>>(path: P) -> Result<WorkflowConfig> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file: {path:?}"))?;
+        Self::parse_and_validate(path, &content)
+    }
+
+    /// 获取被监听的配置文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn parse_and_validate(
+        path: &Path,
+        content: &str,
+    ) -> Result<WorkflowConfig> {
+        let config = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => WorkflowLoader::from_json_str(content)?,
+            _ => WorkflowLoader::from_yaml_str(content)?,
+        };
+        WorkflowLoader::validate(&config)?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    const VALID_YAML: &str = r#"
+workflow:
+  version: "1.0"
+  tasks:
+    - task:
+        id: "task1"
+        name: "Test Task"
+        description: "A test task"
+        actions: []
+"#;
+
+    fn temp_config_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "flowbuilder-watcher-{}-{}",
+            name,
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// 以"写临时文件再重命名"的方式替换文件内容，避免读到写了一半的文件
+    fn replace_file(path: &Path, content: &str) {
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, content).unwrap();
+        std::fs::rename(&tmp, path).unwrap();
+    }
+
+    #[test]
+    fn test_reload_validates_config() {
+        let dir = temp_config_dir("reload");
+        let path = dir.join("workflow.yaml");
+
+        std::fs::write(&path, VALID_YAML).unwrap();
+        let config = WorkflowWatcher::reload(&path).unwrap();
+        assert_eq!(config.workflow.tasks[0].task.id, "task1");
+
+        // 没有任务的配置无法通过校验
+        std::fs::write(&path, "workflow:\n  version: \"1.0\"\n  tasks: []\n")
+            .unwrap();
+        assert!(WorkflowWatcher::reload(&path).is_err());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_watch_emits_on_change_and_skips_invalid() {
+        let dir = temp_config_dir("watch");
+        let path = dir.join("workflow.yaml");
+        std::fs::write(&path, VALID_YAML).unwrap();
+
+        let (ok_tx, ok_rx) = mpsc::channel();
+        let (err_tx, err_rx) = mpsc::channel();
+        let watcher = WorkflowWatcher::watch(
+            &path,
+            move |config| {
+                ok_tx.send(config.workflow.version).ok();
+            },
+            move |e| {
+                err_tx.send(e.to_string()).ok();
+            },
+        )
+        .unwrap();
+        assert_eq!(watcher.path(), path.as_path());
+
+        // 无效修改：触发错误回调
+        std::fs::write(&path, "workflow: [").unwrap();
+        assert!(err_rx.recv_timeout(Duration::from_secs(5)).is_ok());
+
+        // 有效修改：触发变更回调
+        std::fs::write(&path, VALID_YAML.replace("1.0", "2.0")).unwrap();
+        let version = ok_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(version, "2.0");
+
+        drop(watcher);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_watch_reports_same_invalid_content_once() {
+        let dir = temp_config_dir("invalid-once");
+        let path = dir.join("workflow.yaml");
+        std::fs::write(&path, VALID_YAML).unwrap();
+
+        let (err_tx, err_rx) = mpsc::channel();
+        let watcher = WorkflowWatcher::watch(
+            &path,
+            |_| {},
+            move |e| {
+                err_tx.send(e.to_string()).ok();
+            },
+        )
+        .unwrap();
+
+        replace_file(&path, "workflow: [");
+        assert!(err_rx.recv_timeout(Duration::from_secs(5)).is_ok());
+
+        // 内容未变的后续事件不再报告
+        replace_file(&path, "workflow: [");
+        assert!(err_rx.recv_timeout(Duration::from_millis(500)).is_err());
+
+        // 内容变化后重新报告
+        replace_file(&path, "workflow: {");
+        assert!(err_rx.recv_timeout(Duration::from_secs(5)).is_ok());
+
+        drop(watcher);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
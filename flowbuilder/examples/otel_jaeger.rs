@@ -0,0 +1,73 @@
+//! Export plan/phase/node spans to Jaeger as one trace waterfall
+//!
+//! Start a Jaeger all-in-one instance (agent on 6831/udp, UI on 16686):
+//!
+//! ```text
+//! docker run -d -p 6831:6831/udp -p 16686:16686 jaegertracing/all-in-one
+//! cargo run -p flowbuilder --example otel_jaeger --features otel
+//! ```
+//!
+//! Then open http://localhost:16686 and look for the `flowbuilder-demo`
+//! service.
+
+use flowbuilder_core::FlowBuilder;
+use flowbuilder_runtime::{EnhancedTaskExecutor, RunContext};
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let tracer = opentelemetry_jaeger::new_agent_pipeline()
+        .with_service_name("flowbuilder-demo")
+        .install_batch(opentelemetry_sdk::runtime::Tokio)?;
+    tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .with(flowbuilder::logging::layer())
+        .init();
+
+    // The flow continues a trace started upstream, e.g. an incoming HTTP
+    // request carrying a W3C `traceparent` header
+    let headers = HashMap::from([(
+        "traceparent".to_string(),
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_string(),
+    )]);
+    let parent = TraceContextPropagator::new().extract(&headers);
+
+    let plan = FlowBuilder::new()
+        .named_step("fetch", |_ctx| async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(())
+        })
+        .named_step("transform", |ctx| async move {
+            ctx.lock()
+                .await
+                .set_variable("rows".to_string(), "42".to_string());
+            Ok(())
+        })
+        .named_step("store", |_ctx| async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok(())
+        })
+        .compile();
+
+    let context = Arc::new(tokio::sync::Mutex::new(
+        flowbuilder_context::FlowContext::default(),
+    ));
+    let run = RunContext::new().with_otel_parent(parent);
+    let result = EnhancedTaskExecutor::new()
+        .execute_plan(plan, context, &run)
+        .await?;
+    println!(
+        "plan {} finished in {:?}",
+        result.plan_id, result.total_duration
+    );
+
+    // Flush pending spans before exiting
+    opentelemetry::global::shutdown_tracer_provider();
+    Ok(())
+}
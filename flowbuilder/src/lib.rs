@@ -52,7 +52,7 @@ pub use flowbuilder_yaml as yaml;
 
 /// Prelude module for easy imports
 pub mod prelude {
-    pub use flowbuilder_context::{FlowContext, SharedContext};
+    pub use flowbuilder_context::{FlowContext, FlowEvent, SharedContext};
     pub use flowbuilder_core::prelude::*;
 
     #[cfg(feature = "runtime")]
@@ -60,7 +60,7 @@ pub mod prelude {
     pub use flowbuilder_runtime::{
         EnhancedFlowOrchestrator, EnhancedOrchestratorConfig,
         EnhancedTaskExecutor, ExecutionComplexity, ExecutionResult,
-        ExecutorConfig, NodeResult, PhaseResult,
+        ExecutionSummary, ExecutorConfig, NodeResult, PhaseResult,
     };
 
     // 细粒度子特性透传（仅当 runtime 启用且对应子特性启用）
@@ -74,35 +74,185 @@ pub mod prelude {
 
 /// 统一日志初始化工具
 pub mod logging {
-    use tracing_subscriber::EnvFilter;
+    use flowbuilder_context::FlowContext;
+    use std::fmt::Display;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use tracing::dispatcher::DefaultGuard;
+    use tracing::{Dispatch, Subscriber};
+    use tracing_subscriber::fmt::MakeWriter;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::util::SubscriberInitExt;
+    use tracing_subscriber::{EnvFilter, Layer, Registry};
 
-    /// 初始化 tracing，支持 RUST_LOG 与 FB_LOG_FORMAT（pretty|compact|json）
-    pub fn init() {
+    /// 装箱的日志层
+    pub type BoxedLayer<S> = Box<dyn Layer<S> + Send + Sync + 'static>;
+
+    /// 绑定步骤的日志器：每条日志自动附带 `trace_id` 与 `step` 字段
+    #[derive(Debug, Clone)]
+    pub struct StepLogger {
+        trace_id: String,
+        step: String,
+    }
+
+    impl StepLogger {
+        /// 为步骤创建日志器（`trace_id` 取自上下文）
+        pub fn for_step(
+            context: &FlowContext,
+            step_name: impl Into<String>,
+        ) -> Self {
+            Self {
+                trace_id: context.trace_id.clone(),
+                step: step_name.into(),
+            }
+        }
+
+        /// 输出 debug 日志
+        pub fn debug(&self, message: impl Display) {
+            tracing::debug!(trace_id = %self.trace_id, step = %self.step, "{message}");
+        }
+
+        /// 输出 info 日志
+        pub fn info(&self, message: impl Display) {
+            tracing::info!(trace_id = %self.trace_id, step = %self.step, "{message}");
+        }
+
+        /// 输出 warn 日志
+        pub fn warn(&self, message: impl Display) {
+            tracing::warn!(trace_id = %self.trace_id, step = %self.step, "{message}");
+        }
+
+        /// 输出 error 日志
+        pub fn error(&self, message: impl Display) {
+            tracing::error!(trace_id = %self.trace_id, step = %self.step, "{message}");
+        }
+    }
+
+    /// 捕获模式下的内存输出缓冲
+    #[derive(Debug, Clone, Default)]
+    struct CaptureBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for CaptureBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for CaptureBuffer {
+        type Writer = CaptureBuffer;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    /// 捕获句柄：存活期间当前线程的 tracing 输出写入内存缓冲
+    ///
+    /// 订阅器仅作用于当前线程（`tracing::dispatcher::set_default`），
+    /// 不会与其他测试的全局订阅器冲突；多线程运行时中其他工作线程的日志不会被捕获。
+    #[derive(Debug)]
+    pub struct CaptureHandle {
+        buffer: CaptureBuffer,
+        _guard: DefaultGuard,
+    }
+
+    impl CaptureHandle {
+        /// 已捕获的全部输出
+        pub fn contents(&self) -> String {
+            let bytes = self.buffer.0.lock().unwrap_or_else(|e| e.into_inner());
+            String::from_utf8_lossy(&bytes).into_owned()
+        }
+
+        /// 清空已捕获的输出
+        pub fn clear(&self) {
+            self.buffer
+                .0
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clear();
+        }
+    }
+
+    /// 按 RUST_LOG 与 FB_LOG_FORMAT 构建输出层（自带过滤器）
+    fn fmt_layer<S, W>(writer: W, ansi: bool) -> BoxedLayer<S>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+        W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+    {
         let env_filter = EnvFilter::try_from_default_env()
             .unwrap_or_else(|_| EnvFilter::new("info"));
+        let layer = tracing_subscriber::fmt::layer()
+            .with_writer(writer)
+            .with_ansi(ansi);
 
         match std::env::var("FB_LOG_FORMAT").as_deref() {
-            Ok("json") => {
-                tracing_subscriber::fmt()
-                    .with_env_filter(env_filter)
-                    .json()
-                    .with_target(true)
-                    .with_file(true)
-                    .with_line_number(true)
-                    .init();
-            }
-            Ok("compact") => {
-                tracing_subscriber::fmt()
-                    .with_env_filter(env_filter)
-                    .compact()
-                    .init();
-            }
-            _ => {
-                tracing_subscriber::fmt()
-                    .with_env_filter(env_filter)
-                    .pretty()
-                    .init();
-            }
+            Ok("json") => layer
+                .json()
+                .with_target(true)
+                .with_file(true)
+                .with_line_number(true)
+                .with_filter(env_filter)
+                .boxed(),
+            Ok("compact") => layer.compact().with_filter(env_filter).boxed(),
+            _ => layer.pretty().with_filter(env_filter).boxed(),
+        }
+    }
+
+    /// 可组合的日志层（输出到 stdout，遵循 RUST_LOG 与 FB_LOG_FORMAT）
+    ///
+    /// 适合已有订阅器的应用：`registry().with(logging::layer())`
+    pub fn layer<S>() -> BoxedLayer<S>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fmt_layer(io::stdout, true)
+    }
+
+    /// 配置好的订阅器，不做全局安装
+    ///
+    /// 可交给 `tracing::subscriber::with_default` / `set_default` 作为线程局部默认值，
+    /// 适合库嵌入等不宜全局初始化的场景
+    ///
+    /// ```rust
+    /// let subscriber = flowbuilder::logging::subscriber();
+    /// tracing::subscriber::with_default(subscriber, || {
+    ///     tracing::info!("scoped to this closure");
+    /// });
+    /// ```
+    pub fn subscriber() -> impl Subscriber + Send + Sync + 'static {
+        Registry::default().with(layer())
+    }
+
+    /// 初始化 tracing，支持 RUST_LOG 与 FB_LOG_FORMAT（pretty|compact|json）
+    pub fn init() {
+        subscriber().init();
+    }
+
+    /// 初始化捕获模式：输出写入内存缓冲而非 stdout，格式同样遵循 FB_LOG_FORMAT
+    ///
+    /// ```rust
+    /// let capture = flowbuilder::logging::init_captured();
+    /// tracing::info!("hello capture");
+    /// assert!(capture.contents().contains("hello capture"));
+    /// ```
+    pub fn init_captured() -> CaptureHandle {
+        let buffer = CaptureBuffer::default();
+        let dispatch = Dispatch::new(
+            Registry::default().with(fmt_layer(buffer.clone(), false)),
+        );
+        let guard = tracing::dispatcher::set_default(&dispatch);
+        CaptureHandle {
+            buffer,
+            _guard: guard,
         }
     }
 }